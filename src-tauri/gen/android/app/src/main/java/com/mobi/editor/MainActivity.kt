@@ -0,0 +1,75 @@
+package com.mobi.editor
+
+import android.content.Intent
+import android.net.Uri
+import android.os.Build
+import android.os.Bundle
+import android.provider.OpenableColumns
+import androidx.activity.enableEdgeToEdge
+
+// 其他应用的"打开方式"（ACTION_VIEW）和分享（ACTION_SEND）经 JNI 交给 src/intents.rs，保存到分享目录后在编辑器中打开
+class MainActivity : TauriActivity() {
+  override fun onCreate(savedInstanceState: Bundle?) {
+    enableEdgeToEdge()
+    super.onCreate(savedInstanceState)
+    // 重建 Activity 时 intent 还是上次那个，不再重复处理
+    if (savedInstanceState == null) {
+      handleIntent(intent)
+    }
+  }
+
+  // launchMode 为 singleTask，应用已在运行时新的 intent 从这里进来
+  override fun onNewIntent(intent: Intent) {
+    super.onNewIntent(intent)
+    handleIntent(intent)
+  }
+
+  private fun handleIntent(intent: Intent?) {
+    when (intent?.action) {
+      Intent.ACTION_VIEW -> intent.data?.let { receiveUri(it) }
+      Intent.ACTION_SEND -> {
+        val text = intent.getStringExtra(Intent.EXTRA_TEXT)
+        if (text != null) {
+          receiveShared(text, intent.getStringExtra(Intent.EXTRA_SUBJECT), null)
+        } else {
+          streamOf(intent)?.let { receiveUri(it) }
+        }
+      }
+    }
+  }
+
+  private fun streamOf(intent: Intent): Uri? =
+    if (Build.VERSION.SDK_INT >= Build.VERSION_CODES.TIRAMISU) {
+      intent.getParcelableExtra(Intent.EXTRA_STREAM, Uri::class.java)
+    } else {
+      @Suppress("DEPRECATION")
+      intent.getParcelableExtra(Intent.EXTRA_STREAM)
+    }
+
+  // content:// 没有可写的文件路径，读出文字交给 Rust 另存
+  private fun receiveUri(uri: Uri) {
+    val content = try {
+      contentResolver.openInputStream(uri)?.use { it.bufferedReader().readText() }
+    } catch (e: Exception) {
+      null
+    } ?: return
+    receiveFile(displayName(uri), content)
+  }
+
+  private fun displayName(uri: Uri): String {
+    try {
+      contentResolver.query(uri, arrayOf(OpenableColumns.DISPLAY_NAME), null, null, null)?.use { cursor ->
+        if (cursor.moveToFirst()) {
+          cursor.getString(0)?.let { return it }
+        }
+      }
+    } catch (e: Exception) {
+      // file:// 等不支持查询的地址取路径最后一段
+    }
+    return uri.lastPathSegment?.substringAfterLast('/') ?: ""
+  }
+
+  private external fun receiveShared(text: String, title: String?, url: String?)
+
+  private external fun receiveFile(name: String, content: String)
+}
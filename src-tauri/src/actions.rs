@@ -0,0 +1,591 @@
+// 动作注册表：核心模块、前端插件和用户脚本在这里登记命令（id、标题、参数说明），命令面板、菜单、托盘和 mobi://action 链接都经 list_actions / run_action 调用
+// 后端动作直接执行；界面动作和插件动作发出 run-action 事件交给窗口执行；用户脚本保存在设置的 actions 节中，作为外部程序运行
+
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tauri::{AppHandle, Emitter, Manager, State, Window};
+
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+use crate::error;
+use crate::error::{MobiError, MutexExt};
+use crate::export::presets;
+use crate::preview::{self, PreviewState};
+use crate::{clipboard, repair, settings, trash, workspaces};
+
+// 事件载荷：{id, args}
+const EVENT: &str = "run-action";
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum ArgKind {
+    #[default]
+    String,
+    Number,
+    Boolean,
+    // 文件或文件夹的绝对路径
+    Path,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ArgSpec {
+    name: String,
+    title: String,
+    kind: ArgKind,
+    required: bool,
+    // 没有传入时使用
+    default: Option<Value>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum ActionSource {
+    #[default]
+    Core,
+    Plugin,
+    Script,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ActionInfo {
+    id: String,
+    title: String,
+    // 命令面板中的分组
+    category: String,
+    args: Vec<ArgSpec>,
+    source: ActionSource,
+    // 允许通过 mobi://action 链接从外部触发；会删除数据或运行程序的动作不允许
+    external: bool,
+}
+
+// 用户脚本：program 和 args 中的 {参数名} 替换为传入的参数
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+struct UserScript {
+    id: String,
+    title: String,
+    category: String,
+    program: String,
+    args: Vec<String>,
+    // 工作目录，同样可以使用 {参数名}，留空为当前目录
+    cwd: String,
+    params: Vec<ArgSpec>,
+    timeout_secs: u64,
+}
+
+impl Default for UserScript {
+    fn default() -> Self {
+        UserScript {
+            id: String::new(),
+            title: String::new(),
+            category: "脚本".into(),
+            program: String::new(),
+            args: Vec::new(),
+            cwd: String::new(),
+            params: Vec::new(),
+            timeout_secs: 30,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct ActionSettings {
+    scripts: Vec<UserScript>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct ActionEvent {
+    id: String,
+    args: Map<String, Value>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ScriptOutput {
+    code: Option<i32>,
+    stdout: String,
+    stderr: String,
+    timed_out: bool,
+}
+
+type CoreHandler = fn(&AppHandle, &Map<String, Value>) -> Result<Value, String>;
+
+enum Handler {
+    // 在后端执行
+    Core(CoreHandler),
+    // 发给窗口执行，None 为当前聚焦的窗口
+    Window(Option<String>),
+    Script(UserScript),
+}
+
+// 前端插件登记的动作：(动作, 登记它的窗口)
+#[derive(Default)]
+pub struct ActionState {
+    registered: Mutex<Vec<(ActionInfo, String)>>,
+}
+
+fn arg(name: &str, title: &str, kind: ArgKind, required: bool) -> ArgSpec {
+    ArgSpec {
+        name: name.into(),
+        title: title.into(),
+        kind,
+        required,
+        default: None,
+    }
+}
+
+fn info(id: &str, title: &str, category: &str, args: Vec<ArgSpec>, external: bool) -> ActionInfo {
+    ActionInfo {
+        id: id.into(),
+        title: title.into(),
+        category: category.into(),
+        args,
+        source: ActionSource::Core,
+        external,
+    }
+}
+
+fn str_arg(args: &Map<String, Value>, name: &str) -> String {
+    args.get(name)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn to_value<T: Serialize>(value: T) -> Result<Value, String> {
+    serde_json::to_value(value).map_err(|e| e.to_string())
+}
+
+// 核心动作；界面上的动作由 MainLayout 响应 run-action 事件
+fn core_actions() -> Vec<(ActionInfo, Handler)> {
+    vec![
+        (
+            info("file.new", "新建文件", "文件", vec![], true),
+            Handler::Window(None),
+        ),
+        (
+            info("file.open", "打开文件", "文件", vec![], true),
+            Handler::Window(None),
+        ),
+        (
+            info("file.save", "保存", "文件", vec![], false),
+            Handler::Window(None),
+        ),
+        (
+            info("file.saveAs", "另存为", "文件", vec![], false),
+            Handler::Window(None),
+        ),
+        (
+            info("file.print", "打印", "文件", vec![], false),
+            Handler::Window(None),
+        ),
+        (
+            info(
+                "view.toggleSidebar",
+                "显示/隐藏侧边栏",
+                "视图",
+                vec![],
+                true,
+            ),
+            Handler::Window(None),
+        ),
+        (
+            info(
+                "workspace.openWindow",
+                "在新窗口中打开工作区",
+                "工作区",
+                vec![arg("id", "工作区", ArgKind::String, true)],
+                true,
+            ),
+            Handler::Core(|app, args| {
+                to_value(workspaces::open_workspace_window(
+                    app.clone(),
+                    str_arg(args, "id"),
+                )?)
+            }),
+        ),
+        (
+            info(
+                "links.repair",
+                "修复失效链接",
+                "工作区",
+                vec![
+                    arg("workspace", "工作区", ArgKind::Path, true),
+                    arg("apply", "直接修复", ArgKind::Boolean, false),
+                ],
+                false,
+            ),
+            Handler::Core(|_, args| {
+                let report = tauri::async_runtime::block_on(repair::repair_broken_links(
+                    str_arg(args, "workspace"),
+                    args.get("apply").and_then(Value::as_bool),
+                ))?;
+                to_value(report)
+            }),
+        ),
+        (
+            info(
+                "trash.empty",
+                "清空回收站",
+                "工作区",
+                vec![arg("workspace", "工作区", ArgKind::Path, true)],
+                false,
+            ),
+            Handler::Core(|_, args| {
+                trash::purge_trashed(str_arg(args, "workspace"), None)?;
+                Ok(Value::Null)
+            }),
+        ),
+        (
+            info(
+                "export.runPreset",
+                "按预设导出",
+                "导出",
+                vec![
+                    arg("name", "预设", ArgKind::String, true),
+                    arg("path", "笔记", ArgKind::Path, true),
+                    arg("workspace", "工作区", ArgKind::Path, false),
+                ],
+                false,
+            ),
+            Handler::Core(|app, args| {
+                let workspace = Some(str_arg(args, "workspace")).filter(|w| !w.is_empty());
+                let result = tauri::async_runtime::block_on(presets::run_export_preset(
+                    app.clone(),
+                    str_arg(args, "name"),
+                    str_arg(args, "path"),
+                    workspace,
+                ))?;
+                to_value(result)
+            }),
+        ),
+        (
+            info(
+                "preview.start",
+                "启动实时预览",
+                "预览",
+                vec![arg("lan", "允许局域网访问", ArgKind::Boolean, false)],
+                false,
+            ),
+            Handler::Core(|app, args| {
+                let state = app.state::<PreviewState>();
+                let lan = args.get("lan").and_then(Value::as_bool);
+                let label = preview::default_label(&state);
+                to_value(preview::start(app.clone(), &state, &label, None, lan)?)
+            }),
+        ),
+        (
+            info("preview.stop", "停止实时预览", "预览", vec![], true),
+            Handler::Core(|app, _| {
+                preview::stop_preview_server(app.state::<PreviewState>());
+                Ok(Value::Null)
+            }),
+        ),
+        (
+            info(
+                "clipboard.clearHistory",
+                "清空剪贴板历史",
+                "编辑",
+                vec![],
+                false,
+            ),
+            Handler::Core(|app, _| {
+                clipboard::clear_clipboard_history(app.state::<clipboard::ClipboardState>());
+                Ok(Value::Null)
+            }),
+        ),
+    ]
+}
+
+fn scripts(app: &AppHandle) -> Vec<UserScript> {
+    let config: ActionSettings = settings::load(app, "actions");
+    config.scripts
+}
+
+fn script_info(script: &UserScript) -> ActionInfo {
+    ActionInfo {
+        id: script.id.clone(),
+        title: script.title.clone(),
+        category: script.category.clone(),
+        args: script.params.clone(),
+        source: ActionSource::Script,
+        external: false,
+    }
+}
+
+fn find(app: &AppHandle, state: &ActionState, id: &str) -> Option<(ActionInfo, Handler)> {
+    if let Some(found) = core_actions().into_iter().find(|(info, _)| info.id == id) {
+        return Some(found);
+    }
+    let registered = state.registered.locked();
+    if let Some((info, window)) = registered.iter().find(|(info, _)| info.id == id) {
+        return Some((info.clone(), Handler::Window(Some(window.clone()))));
+    }
+    scripts(app)
+        .into_iter()
+        .find(|s| s.id == id)
+        .map(|script| (script_info(&script), Handler::Script(script)))
+}
+
+// 按参数说明检查并补全参数；链接传来的参数都是字符串，数字和布尔值在这里转换
+fn check_args(spec: &[ArgSpec], args: Option<Value>) -> Result<Map<String, Value>, String> {
+    let mut args = match args {
+        None | Some(Value::Null) => Map::new(),
+        Some(Value::Object(map)) => map,
+        Some(_) => return Err("参数必须是对象".into()),
+    };
+    for param in spec {
+        let value = match args.remove(&param.name) {
+            Some(Value::Null) | None => match &param.default {
+                Some(default) => default.clone(),
+                None if param.required => {
+                    return Err(format!("缺少参数: {}", param.name));
+                }
+                None => continue,
+            },
+            Some(value) => value,
+        };
+        let value = match (param.kind, value) {
+            (ArgKind::String | ArgKind::Path, Value::String(s)) => Value::String(s),
+            (ArgKind::Number, Value::Number(n)) => Value::Number(n),
+            (ArgKind::Number, Value::String(s)) => s
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .and_then(serde_json::Number::from_f64)
+                .map(Value::Number)
+                .ok_or_else(|| format!("参数 {} 应为数字", param.name))?,
+            (ArgKind::Boolean, Value::Bool(b)) => Value::Bool(b),
+            (ArgKind::Boolean, Value::String(s)) => match s.trim() {
+                "true" | "1" | "yes" => Value::Bool(true),
+                "false" | "0" | "no" | "" => Value::Bool(false),
+                _ => return Err(format!("参数 {} 应为布尔值", param.name)),
+            },
+            _ => {
+                return Err(format!("参数 {} 的类型不对", param.name));
+            }
+        };
+        args.insert(param.name.clone(), value);
+    }
+    Ok(args)
+}
+
+fn substitute(template: &str, args: &Map<String, Value>) -> String {
+    args.iter()
+        .fold(template.to_string(), |text, (name, value)| {
+            let value = match value {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            text.replace(&format!("{{{}}}", name), &value)
+        })
+}
+
+fn run_script(script: &UserScript, args: &Map<String, Value>) -> Result<ScriptOutput, String> {
+    let mut command = Command::new(substitute(&script.program, args));
+    command
+        .args(script.args.iter().map(|a| substitute(a, args)))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if !script.cwd.trim().is_empty() {
+        command.current_dir(substitute(&script.cwd, args));
+    }
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("启动 {} 失败: {}", script.program, e))?;
+    let read = |pipe: Option<Box<dyn Read + Send>>| {
+        thread::spawn(move || {
+            let mut text = String::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_string(&mut text);
+            }
+            text
+        })
+    };
+    let stdout = read(
+        child
+            .stdout
+            .take()
+            .map(|p| Box::new(p) as Box<dyn Read + Send>),
+    );
+    let stderr = read(
+        child
+            .stderr
+            .take()
+            .map(|p| Box::new(p) as Box<dyn Read + Send>),
+    );
+
+    let timeout = Duration::from_secs(script.timeout_secs.max(1));
+    let started = Instant::now();
+    let mut timed_out = false;
+    let status = loop {
+        if let Ok(Some(status)) = child.try_wait() {
+            break Some(status);
+        }
+        if started.elapsed() >= timeout {
+            timed_out = true;
+            let _ = child.kill();
+            break child.wait().ok();
+        }
+        thread::sleep(Duration::from_millis(50));
+    };
+    Ok(ScriptOutput {
+        code: status.and_then(|s| s.code()),
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+        timed_out,
+    })
+}
+
+// 界面动作发给当前聚焦的窗口，没有时发给主窗口
+fn emit_to_window(
+    app: &AppHandle,
+    window: Option<String>,
+    event: ActionEvent,
+) -> Result<(), String> {
+    let label = window
+        .or_else(|| {
+            app.webview_windows()
+                .into_iter()
+                .find(|(_, w)| w.is_focused().unwrap_or(false))
+                .map(|(label, _)| label)
+        })
+        .unwrap_or_else(|| "main".into());
+    app.emit_to(label.as_str(), EVENT, event)
+        .map_err(|e| format!("发送动作失败: {}", e))
+}
+
+async fn dispatch(
+    app: AppHandle,
+    info: ActionInfo,
+    handler: Handler,
+    args: Option<Value>,
+) -> Result<Value, String> {
+    let args = check_args(&info.args, args)?;
+    match handler {
+        Handler::Window(window) => {
+            emit_to_window(&app, window, ActionEvent { id: info.id, args })?;
+            Ok(Value::Null)
+        }
+        Handler::Core(run) => tauri::async_runtime::spawn_blocking(move || run(&app, &args))
+            .await
+            .map_err(|e| e.to_string())?,
+        Handler::Script(script) => {
+            let output = tauri::async_runtime::spawn_blocking(move || run_script(&script, &args))
+                .await
+                .map_err(|e| e.to_string())??;
+            to_value(output)
+        }
+    }
+}
+
+// mobi://action/{id}?参数=值 打开的动作；只执行允许外部触发的
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+pub fn run_external(app: &AppHandle, id: &str, args: Map<String, Value>) {
+    let state = app.state::<ActionState>();
+    let Some((info, handler)) = find(app, &state, id) else {
+        error::report(app, "执行动作失败", format!("没有该动作: {}", id));
+        return;
+    };
+    if !info.external {
+        error::report(app, "执行动作失败", format!("动作 {} 不允许从链接触发", id));
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = dispatch(app.clone(), info, handler, Some(Value::Object(args))).await {
+            error::report(&app, "执行动作失败", e);
+        }
+    });
+}
+
+// 窗口关闭后去掉它登记的动作
+pub fn forget_window(app: &AppHandle, label: &str) {
+    if let Some(state) = app.try_state::<ActionState>() {
+        state.registered.locked().retain(|(_, w)| w != label);
+    }
+}
+
+// 核心动作在前，其次是插件和用户脚本
+#[tauri::command]
+pub fn list_actions(app: AppHandle, state: State<ActionState>) -> Vec<ActionInfo> {
+    let mut actions: Vec<ActionInfo> = core_actions().into_iter().map(|(info, _)| info).collect();
+    for (info, _) in state.registered.locked().iter() {
+        if !actions.iter().any(|a| a.id == info.id) {
+            actions.push(info.clone());
+        }
+    }
+    for script in scripts(&app) {
+        if !actions.iter().any(|a| a.id == script.id) {
+            actions.push(script_info(&script));
+        }
+    }
+    actions
+}
+
+// 前端插件登记动作，执行时发给登记它的窗口；同一窗口重复登记会覆盖
+#[tauri::command]
+pub fn register_action(
+    window: Window,
+    state: State<ActionState>,
+    action: ActionInfo,
+) -> Result<(), MobiError> {
+    if action.id.trim().is_empty() {
+        return Err("动作 id 不能为空".into());
+    }
+    if core_actions().iter().any(|(info, _)| info.id == action.id) {
+        return Err(MobiError::AlreadyExists(format!(
+            "动作 {} 已被内置动作使用",
+            action.id
+        )));
+    }
+    let mut registered = state.registered.locked();
+    if registered
+        .iter()
+        .any(|(info, w)| info.id == action.id && w != window.label())
+    {
+        return Err(MobiError::AlreadyExists(format!(
+            "动作 {} 已被其他窗口登记",
+            action.id
+        )));
+    }
+    registered.retain(|(info, _)| info.id != action.id);
+    registered.push((
+        ActionInfo {
+            source: ActionSource::Plugin,
+            ..action
+        },
+        window.label().to_string(),
+    ));
+    Ok(())
+}
+
+#[tauri::command]
+pub fn unregister_action(window: Window, state: State<ActionState>, id: String) {
+    state
+        .registered
+        .locked()
+        .retain(|(info, w)| !(info.id == id && w == window.label()));
+}
+
+// 执行动作，返回它的结果；发给窗口的动作返回 null
+#[tauri::command]
+pub async fn run_action(
+    app: AppHandle,
+    state: State<'_, ActionState>,
+    id: String,
+    args: Option<Value>,
+) -> Result<Value, MobiError> {
+    let (info, handler) = find(&app, &state, &id).ok_or_else(|| format!("没有该动作: {}", id))?;
+    Ok(dispatch(app.clone(), info, handler, args).await?)
+}
@@ -0,0 +1,316 @@
+// AI 助手：在后端代理 OpenAI 兼容的 chat/completions 接口，流式结果通过事件推送给前端
+// API Key 通过 secrets 模块保存在系统钥匙串中，不会进入 webview
+
+pub mod ollama;
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::error::{MobiError, MutexExt};
+use crate::{network, secrets, settings};
+
+#[derive(Serialize, Deserialize, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+enum AiProvider {
+    // OpenAI 兼容的云端接口
+    #[default]
+    OpenAi,
+    // 本地 Ollama，笔记内容不出本机
+    Ollama,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct AiSettings {
+    provider: AiProvider,
+    base_url: String,
+    model: String,
+    temperature: f32,
+    // 仅用于 AI 的代理，如 http://127.0.0.1:7890；为空时使用全局代理设置
+    proxy: String,
+    ollama_url: String,
+    ollama_model: String,
+    // 语义搜索使用的嵌入模型
+    embedding_model: String,
+}
+
+impl Default for AiSettings {
+    fn default() -> Self {
+        AiSettings {
+            provider: AiProvider::OpenAi,
+            base_url: "https://api.openai.com/v1".into(),
+            model: "gpt-4o-mini".into(),
+            temperature: 0.7,
+            proxy: String::new(),
+            ollama_url: "http://127.0.0.1:11434".into(),
+            ollama_model: "qwen2.5:7b".into(),
+            embedding_model: "nomic-embed-text".into(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ChatMessage {
+    role: String,
+    content: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptTemplate {
+    id: &'static str,
+    name: &'static str,
+    #[serde(skip)]
+    system: &'static str,
+}
+
+const TEMPLATES: &[PromptTemplate] = &[
+    PromptTemplate {
+        id: "continue",
+        name: "续写",
+        system: "你是一名写作助手。请紧接着用户提供的 Markdown 文本继续写作，保持原文的语言、语气和格式，只输出续写的内容。",
+    },
+    PromptTemplate {
+        id: "summarize",
+        name: "总结",
+        system: "你是一名写作助手。请用与原文相同的语言，为用户提供的 Markdown 文本写一段简洁的摘要，只输出摘要内容。",
+    },
+    PromptTemplate {
+        id: "fix-grammar",
+        name: "修正语法",
+        system: "你是一名校对编辑。请修正用户提供的 Markdown 文本中的错别字、语法和标点问题，保留原有的 Markdown 格式和意思，只输出修正后的全文。",
+    },
+];
+
+// 发给前端的 ai-stream 事件
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StreamEvent {
+    request_id: String,
+    delta: String,
+    done: bool,
+    error: Option<String>,
+}
+
+#[derive(Default)]
+pub struct AiState {
+    tasks: Mutex<HashMap<String, JoinHandle<()>>>,
+}
+
+fn api_key() -> Result<String, String> {
+    secrets::get_secret("ai", "api-key")?.ok_or_else(|| "请先设置 AI 服务的 API Key".into())
+}
+
+// AI 设置中单独填写的代理优先，否则使用全局代理设置
+fn http_client(app: &AppHandle, config: &AiSettings) -> Result<reqwest::Client, String> {
+    let mut builder = network::client_builder(app)?;
+    if config.provider == AiProvider::Ollama {
+        builder = builder.no_proxy();
+    } else if !config.proxy.is_empty() {
+        let proxy =
+            reqwest::Proxy::all(&config.proxy).map_err(|e| format!("代理地址无效: {}", e))?;
+        builder = builder.proxy(proxy);
+    }
+    builder.build().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn set_ai_api_key(key: String) -> Result<(), MobiError> {
+    secrets::set_secret("ai".into(), "api-key".into(), key)
+}
+
+#[tauri::command]
+pub fn has_ai_api_key() -> bool {
+    api_key().is_ok()
+}
+
+#[tauri::command]
+pub fn list_ai_templates() -> &'static [PromptTemplate] {
+    TEMPLATES
+}
+
+#[tauri::command]
+pub fn ai_chat(
+    app: AppHandle,
+    state: State<AiState>,
+    request_id: String,
+    messages: Vec<ChatMessage>,
+) -> Result<(), MobiError> {
+    Ok(start_stream(&app, &state, request_id, messages)?)
+}
+
+#[tauri::command]
+pub fn ai_run_template(
+    app: AppHandle,
+    state: State<AiState>,
+    request_id: String,
+    template: String,
+    text: String,
+) -> Result<(), MobiError> {
+    let template = TEMPLATES
+        .iter()
+        .find(|t| t.id == template)
+        .ok_or_else(|| format!("未知的模板: {}", template))?;
+    let messages = vec![
+        ChatMessage {
+            role: "system".into(),
+            content: template.system.into(),
+        },
+        ChatMessage {
+            role: "user".into(),
+            content: text,
+        },
+    ];
+    Ok(start_stream(&app, &state, request_id, messages)?)
+}
+
+#[tauri::command]
+pub fn ai_cancel(state: State<AiState>, request_id: String) {
+    if let Some(task) = state.tasks.locked().remove(&request_id) {
+        task.abort();
+    }
+}
+
+fn start_stream(
+    app: &AppHandle,
+    state: &AiState,
+    request_id: String,
+    messages: Vec<ChatMessage>,
+) -> Result<(), String> {
+    let config: AiSettings = settings::load(app, "ai");
+    // 本地模型不需要 API Key
+    let key = match config.provider {
+        AiProvider::OpenAi => api_key()?,
+        AiProvider::Ollama => String::new(),
+    };
+    let client = http_client(app, &config)?;
+
+    let handle = app.clone();
+    let id = request_id.clone();
+    let task = tauri::async_runtime::spawn(async move {
+        let result = match config.provider {
+            AiProvider::OpenAi => {
+                stream_completion(&handle, &client, &config, &key, &id, messages).await
+            }
+            AiProvider::Ollama => {
+                ollama::stream_chat(&handle, &client, &config, &id, messages).await
+            }
+        };
+        let _ = handle.emit(
+            "ai-stream",
+            StreamEvent {
+                request_id: id.clone(),
+                delta: String::new(),
+                done: true,
+                error: result.err(),
+            },
+        );
+        handle.state::<AiState>().tasks.locked().remove(&id);
+    });
+
+    if let Some(old) = state.tasks.locked().insert(request_id, task) {
+        old.abort();
+    }
+    Ok(())
+}
+
+fn emit_delta(app: &AppHandle, request_id: &str, delta: &str) {
+    if delta.is_empty() {
+        return;
+    }
+    let _ = app.emit(
+        "ai-stream",
+        StreamEvent {
+            request_id: request_id.to_string(),
+            delta: delta.to_string(),
+            done: false,
+            error: None,
+        },
+    );
+}
+
+// 逐行读取流式响应，数据块可能在任意位置被截断；回调返回 false 时提前结束
+async fn for_each_line(
+    response: reqwest::Response,
+    mut handle_line: impl FnMut(&str) -> Result<bool, String>,
+) -> Result<(), String> {
+    let mut stream = response.bytes_stream();
+    let mut buffer: Vec<u8> = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| format!("读取 AI 响应失败: {}", e))?;
+        buffer.extend_from_slice(&chunk);
+
+        while let Some(pos) = buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim();
+            if !line.is_empty() && !handle_line(line)? {
+                return Ok(());
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn check_status(response: reqwest::Response) -> Result<reqwest::Response, String> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+    let status = response.status();
+    let text = response.text().await.unwrap_or_default();
+    Err(format!("AI 服务返回错误 {}: {}", status, text))
+}
+
+async fn stream_completion(
+    app: &AppHandle,
+    client: &reqwest::Client,
+    config: &AiSettings,
+    key: &str,
+    request_id: &str,
+    messages: Vec<ChatMessage>,
+) -> Result<(), String> {
+    let url = format!("{}/chat/completions", config.base_url.trim_end_matches('/'));
+    let body = json!({
+        "model": config.model,
+        "messages": messages,
+        "temperature": config.temperature,
+        "stream": true,
+    });
+
+    let response = client
+        .post(url)
+        .bearer_auth(key)
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| format!("请求 AI 服务失败: {}", e))?;
+    let response = check_status(response).await?;
+
+    // SSE：只关心 data: 字段
+    for_each_line(response, |line| {
+        let Some(data) = line.strip_prefix("data:") else {
+            return Ok(true);
+        };
+        let data = data.trim();
+        if data == "[DONE]" {
+            return Ok(false);
+        }
+        let Ok(value) = serde_json::from_str::<Value>(data) else {
+            return Ok(true);
+        };
+        if let Some(message) = value["error"]["message"].as_str() {
+            return Err(message.to_string());
+        }
+        if let Some(delta) = value["choices"][0]["delta"]["content"].as_str() {
+            emit_delta(app, request_id, delta);
+        }
+        Ok(true)
+    })
+    .await
+}
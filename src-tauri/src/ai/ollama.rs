@@ -0,0 +1,221 @@
+// 本地模型：通过 Ollama 离线完成总结、改写等操作，并管理模型下载
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use super::{check_status, emit_delta, for_each_line, AiSettings, AiState, ChatMessage};
+use crate::error::{MobiError, MutexExt};
+use crate::settings;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalModel {
+    name: String,
+    size: u64,
+    modified_at: String,
+}
+
+// 发给前端的 ai-model-pull 事件
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PullProgress {
+    model: String,
+    status: String,
+    completed: u64,
+    total: u64,
+    done: bool,
+    error: Option<String>,
+}
+
+fn endpoint(config: &AiSettings, path: &str) -> String {
+    format!("{}{}", config.ollama_url.trim_end_matches('/'), path)
+}
+
+fn local_client() -> Result<reqwest::Client, String> {
+    reqwest::Client::builder()
+        .no_proxy()
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+fn unreachable(e: reqwest::Error) -> String {
+    format!("无法连接 Ollama，请确认已启动: {}", e)
+}
+
+pub(super) async fn stream_chat(
+    app: &AppHandle,
+    client: &reqwest::Client,
+    config: &AiSettings,
+    request_id: &str,
+    messages: Vec<ChatMessage>,
+) -> Result<(), String> {
+    let body = json!({
+        "model": config.ollama_model,
+        "messages": messages,
+        "stream": true,
+        "options": { "temperature": config.temperature },
+    });
+    let response = client
+        .post(endpoint(config, "/api/chat"))
+        .json(&body)
+        .send()
+        .await
+        .map_err(unreachable)?;
+    let response = check_status(response).await?;
+
+    // Ollama 以 NDJSON 逐行返回
+    for_each_line(response, |line| {
+        let value: Value = serde_json::from_str(line).map_err(|e| e.to_string())?;
+        if let Some(error) = value["error"].as_str() {
+            return Err(error.to_string());
+        }
+        if let Some(delta) = value["message"]["content"].as_str() {
+            emit_delta(app, request_id, delta);
+        }
+        Ok(!value["done"].as_bool().unwrap_or(false))
+    })
+    .await
+}
+
+pub fn embedding_model(app: &AppHandle) -> String {
+    let config: AiSettings = settings::load(app, "ai");
+    config.embedding_model
+}
+
+// 批量计算文本向量
+pub async fn embed(app: &AppHandle, inputs: &[String]) -> Result<Vec<Vec<f32>>, String> {
+    #[derive(Deserialize)]
+    struct Embeddings {
+        embeddings: Vec<Vec<f32>>,
+    }
+
+    if inputs.is_empty() {
+        return Ok(Vec::new());
+    }
+    let config: AiSettings = settings::load(app, "ai");
+    let response = local_client()?
+        .post(endpoint(&config, "/api/embed"))
+        .json(&json!({ "model": config.embedding_model, "input": inputs }))
+        .send()
+        .await
+        .map_err(unreachable)?;
+    let result: Embeddings = check_status(response)
+        .await?
+        .json()
+        .await
+        .map_err(|e| format!("解析向量结果失败: {}", e))?;
+    if result.embeddings.len() != inputs.len() {
+        return Err("嵌入模型返回的向量数量不符".into());
+    }
+    Ok(result.embeddings)
+}
+
+#[tauri::command]
+pub async fn ollama_list_models(app: AppHandle) -> Result<Vec<LocalModel>, MobiError> {
+    #[derive(Deserialize)]
+    struct Tags {
+        models: Vec<Tag>,
+    }
+    #[derive(Deserialize)]
+    struct Tag {
+        name: String,
+        #[serde(default)]
+        size: u64,
+        #[serde(default)]
+        modified_at: String,
+    }
+
+    let config: AiSettings = settings::load(&app, "ai");
+    let response = local_client()?
+        .get(endpoint(&config, "/api/tags"))
+        .send()
+        .await
+        .map_err(unreachable)?;
+    let tags: Tags = check_status(response)
+        .await?
+        .json()
+        .await
+        .map_err(|e| format!("解析模型列表失败: {}", e))?;
+
+    Ok(tags
+        .models
+        .into_iter()
+        .map(|t| LocalModel {
+            name: t.name,
+            size: t.size,
+            modified_at: t.modified_at,
+        })
+        .collect())
+}
+
+// 后台下载模型，进度通过 ai-model-pull 推送；可用 ai_cancel("pull:模型名") 取消
+#[tauri::command]
+pub fn ollama_pull_model(app: AppHandle, state: State<AiState>, model: String) {
+    let task_id = format!("pull:{}", model);
+    let handle = app.clone();
+    let id = task_id.clone();
+    let task = tauri::async_runtime::spawn(async move {
+        let result = pull(&handle, &model).await;
+        let _ = handle.emit(
+            "ai-model-pull",
+            PullProgress {
+                model,
+                status: if result.is_ok() { "success" } else { "error" }.into(),
+                completed: 0,
+                total: 0,
+                done: true,
+                error: result.err(),
+            },
+        );
+        handle.state::<AiState>().tasks.locked().remove(&id);
+    });
+
+    if let Some(old) = state.tasks.locked().insert(task_id, task) {
+        old.abort();
+    }
+}
+
+async fn pull(app: &AppHandle, model: &str) -> Result<(), String> {
+    let config: AiSettings = settings::load(app, "ai");
+    let response = local_client()?
+        .post(endpoint(&config, "/api/pull"))
+        .json(&json!({ "model": model, "stream": true }))
+        .send()
+        .await
+        .map_err(unreachable)?;
+    let response = check_status(response).await?;
+
+    for_each_line(response, |line| {
+        let value: Value = serde_json::from_str(line).map_err(|e| e.to_string())?;
+        if let Some(error) = value["error"].as_str() {
+            return Err(error.to_string());
+        }
+        let _ = app.emit(
+            "ai-model-pull",
+            PullProgress {
+                model: model.to_string(),
+                status: value["status"].as_str().unwrap_or_default().to_string(),
+                completed: value["completed"].as_u64().unwrap_or(0),
+                total: value["total"].as_u64().unwrap_or(0),
+                done: false,
+                error: None,
+            },
+        );
+        Ok(true)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn ollama_delete_model(app: AppHandle, model: String) -> Result<(), MobiError> {
+    let config: AiSettings = settings::load(&app, "ai");
+    let response = local_client()?
+        .delete(endpoint(&config, "/api/delete"))
+        .json(&json!({ "model": model }))
+        .send()
+        .await
+        .map_err(unreachable)?;
+    check_status(response).await?;
+    Ok(())
+}
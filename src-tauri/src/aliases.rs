@@ -0,0 +1,416 @@
+// 笔记别名与 wiki 链接：front matter 的 aliases（或 alias）和文件名一样是笔记的名字，[[名字#标题|显示文字]] 按名字解析
+// 别名缓存在工作区索引库中，按修改时间增量更新；两篇笔记声明同一个别名，或别名与另一篇笔记的文件名相同时视为冲突
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use pulldown_cmark::Options;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::error::MobiError;
+use crate::{encryption, index, markdown, search, workspace};
+
+// 快速切换默认返回的条数
+const DEFAULT_LIMIT: usize = 20;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NoteAliases {
+    path: String,
+    // 文件名（不含扩展名）
+    name: String,
+    aliases: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AliasConflict {
+    alias: String,
+    paths: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AliasReport {
+    notes: Vec<NoteAliases>,
+    conflicts: Vec<AliasConflict>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WikiTarget {
+    path: String,
+    heading: Option<String>,
+    // 经由别名解析时为匹配到的别名
+    alias: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NameMatch {
+    path: String,
+    // 匹配到的名字：文件名或别名
+    name: String,
+    alias: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Backlink {
+    path: String,
+    // 行号从 0 开始
+    line: usize,
+    text: String,
+}
+
+fn ensure_schema(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS note_aliases (
+            path TEXT PRIMARY KEY,
+            mtime INTEGER NOT NULL,
+            aliases TEXT NOT NULL
+        );",
+    )
+    .map_err(|e| format!("初始化别名索引失败: {}", e))
+}
+
+// Obsidian 用 aliases，部分工具用单数的 alias
+fn note_aliases(content: &str) -> Vec<String> {
+    if encryption::is_envelope(content) {
+        return Vec::new();
+    }
+    let mut aliases = markdown::front_matter_list(content, "aliases");
+    aliases.extend(markdown::front_matter_list(content, "alias"));
+    let mut seen = HashSet::new();
+    aliases.retain(|a| seen.insert(key(a)));
+    aliases
+}
+
+// 增量更新别名缓存，返回 (相对路径, 别名)
+fn refresh(root: &Path) -> Result<Vec<(String, Vec<String>)>, String> {
+    let conn = index::open(root)?;
+    ensure_schema(&conn)?;
+
+    let mut existing: HashMap<String, (i64, String)> = HashMap::new();
+    {
+        let mut stmt = conn
+            .prepare("SELECT path, mtime, aliases FROM note_aliases")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |r| Ok((r.get(0)?, (r.get(1)?, r.get(2)?))))
+            .map_err(|e| e.to_string())?;
+        existing.extend(rows.flatten());
+    }
+
+    let mut notes = Vec::new();
+    for file in workspace::note_files(root) {
+        let relative = workspace::relative_path(root, &file);
+        let mtime = workspace::modified_secs(&file);
+        let aliases = match existing.remove(&relative) {
+            Some((m, stored)) if m == mtime => serde_json::from_str(&stored).unwrap_or_default(),
+            _ => {
+                let aliases = note_aliases(&fs::read_to_string(&file).unwrap_or_default());
+                conn.execute(
+                    "INSERT OR REPLACE INTO note_aliases (path, mtime, aliases) VALUES (?1, ?2, ?3)",
+                    params![
+                        relative,
+                        mtime,
+                        serde_json::to_string(&aliases).map_err(|e| e.to_string())?
+                    ],
+                )
+                .map_err(|e| e.to_string())?;
+                aliases
+            }
+        };
+        notes.push((relative, aliases));
+    }
+    // 剩下的是已删除的笔记
+    for path in existing.keys() {
+        conn.execute("DELETE FROM note_aliases WHERE path = ?1", [path])
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(notes)
+}
+
+// 名字比较不区分大小写
+fn key(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+fn stem(relative: &str) -> &str {
+    let name = relative.rsplit('/').next().unwrap_or(relative);
+    name.rsplit_once('.').map_or(name, |(stem, _)| stem)
+}
+
+struct Entry {
+    relative: String,
+    name: String,
+    alias: bool,
+}
+
+// 工作区中所有笔记的名字：每篇笔记的文件名和别名各一项
+pub struct Names {
+    root: PathBuf,
+    entries: Vec<Entry>,
+    by_key: HashMap<String, Vec<usize>>,
+}
+
+impl Names {
+    pub fn load(root: &Path) -> Result<Self, String> {
+        let mut entries = Vec::new();
+        for (relative, aliases) in refresh(root)? {
+            entries.push(Entry {
+                name: stem(&relative).to_string(),
+                relative: relative.clone(),
+                alias: false,
+            });
+            entries.extend(aliases.into_iter().map(|name| Entry {
+                relative: relative.clone(),
+                name,
+                alias: true,
+            }));
+        }
+        let mut by_key: HashMap<String, Vec<usize>> = HashMap::new();
+        for (i, entry) in entries.iter().enumerate() {
+            by_key.entry(key(&entry.name)).or_default().push(i);
+        }
+        Ok(Names {
+            root: root.to_path_buf(),
+            entries,
+            by_key,
+        })
+    }
+
+    // 多个候选时取与 from 共同目录最深的，其次路径最短的
+    fn nearest<'a>(
+        &self,
+        candidates: impl Iterator<Item = &'a Entry>,
+        from: Option<&str>,
+    ) -> Option<&'a Entry> {
+        let from_dir: Vec<&str> = from
+            .map(|f| f.split('/').collect::<Vec<_>>())
+            .map(|parts| parts[..parts.len().saturating_sub(1)].to_vec())
+            .unwrap_or_default();
+        candidates.min_by_key(|entry| {
+            let shared = entry
+                .relative
+                .split('/')
+                .zip(&from_dir)
+                .take_while(|(a, b)| a == *b)
+                .count();
+            (std::cmp::Reverse(shared), entry.relative.len())
+        })
+    }
+
+    // target 为 [[ ]] 中 # 之前的部分：带 / 或扩展名时按路径匹配，否则先匹配文件名再匹配别名
+    fn resolve(&self, target: &str, from: Option<&Path>) -> Option<&Entry> {
+        let target = target.trim().trim_start_matches('/');
+        if target.is_empty() {
+            return None;
+        }
+        let from = from.map(|f| workspace::relative_path(&self.root, f));
+        let lower = key(target);
+        if target.contains('/') || workspace::is_note(Path::new(target)) {
+            let with_ext = if workspace::is_note(Path::new(target)) {
+                lower.clone()
+            } else {
+                format!("{}.md", lower)
+            };
+            let suffix = format!("/{}", with_ext);
+            let matches = self.entries.iter().filter(|e| {
+                let relative = e.relative.to_lowercase();
+                !e.alias && (relative == with_ext || relative.ends_with(&suffix))
+            });
+            return self.nearest(matches, from.as_deref());
+        }
+        let candidates = self.by_key.get(&lower)?;
+        let entries = || candidates.iter().map(|&i| &self.entries[i]);
+        self.nearest(entries().filter(|e| !e.alias), from.as_deref())
+            .or_else(|| self.nearest(entries().filter(|e| e.alias), from.as_deref()))
+    }
+
+    // wiki 链接的原文（可带 #标题 和 |显示文字）指向的笔记
+    pub fn resolve_link(&self, target: &str, from: Option<&Path>) -> Option<PathBuf> {
+        self.resolve(split_target(target).0, from)
+            .map(|entry| self.root.join(&entry.relative))
+    }
+
+    // 两篇以上笔记共用、且其中至少有一个是别名的名字
+    fn conflicts(&self) -> Vec<AliasConflict> {
+        let mut conflicts: Vec<AliasConflict> = self
+            .by_key
+            .values()
+            .filter_map(|indices| {
+                let entries: Vec<&Entry> = indices.iter().map(|&i| &self.entries[i]).collect();
+                let alias = entries.iter().find(|e| e.alias)?;
+                let mut paths: Vec<String> =
+                    entries.iter().map(|e| self.absolute(&e.relative)).collect();
+                paths.sort();
+                paths.dedup();
+                (paths.len() > 1).then(|| AliasConflict {
+                    alias: alias.name.clone(),
+                    paths,
+                })
+            })
+            .collect();
+        conflicts.sort_by(|a, b| a.alias.cmp(&b.alias));
+        conflicts
+    }
+
+    fn absolute(&self, relative: &str) -> String {
+        self.root.join(relative).to_string_lossy().to_string()
+    }
+}
+
+pub fn wiki_options() -> Options {
+    markdown::parser_options() | Options::ENABLE_WIKILINKS
+}
+
+// [[目标#标题|显示文字]] 拆成 (目标, 标题)
+fn split_target(target: &str) -> (&str, Option<&str>) {
+    let target = target.split('|').next().unwrap_or(target);
+    match target.split_once('#') {
+        Some((name, heading)) => (name, Some(heading.trim()).filter(|h| !h.is_empty())),
+        None => (target, None),
+    }
+}
+
+// 列出工作区中的别名，给出 path 时只返回这篇笔记及涉及它的冲突
+#[tauri::command]
+pub async fn get_aliases(
+    workspace: String,
+    path: Option<String>,
+) -> Result<AliasReport, MobiError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let names = Names::load(Path::new(&workspace))?;
+        let mut notes: Vec<NoteAliases> = Vec::new();
+        for entry in &names.entries {
+            let absolute = names.absolute(&entry.relative);
+            match notes.last_mut() {
+                Some(note) if note.path == absolute => note.aliases.push(entry.name.clone()),
+                _ => notes.push(NoteAliases {
+                    path: absolute,
+                    name: entry.name.clone(),
+                    aliases: Vec::new(),
+                }),
+            }
+        }
+        let mut conflicts = names.conflicts();
+        if let Some(path) = path {
+            notes.retain(|n| n.path == path);
+            conflicts.retain(|c| c.paths.contains(&path));
+        } else {
+            notes.retain(|n| !n.aliases.is_empty());
+        }
+        Ok(AliasReport { notes, conflicts })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// 解析 [[ ]] 中的目标，from 为链接所在的笔记，同名笔记有多篇时取离它最近的
+#[tauri::command]
+pub async fn resolve_wiki_link(
+    workspace: String,
+    target: String,
+    from: Option<String>,
+) -> Result<Option<WikiTarget>, MobiError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let names = Names::load(Path::new(&workspace))?;
+        let (name, heading) = split_target(&target);
+        Ok(names
+            .resolve(name, from.as_deref().map(Path::new))
+            .map(|entry| WikiTarget {
+                path: names.absolute(&entry.relative),
+                heading: heading.map(str::to_string),
+                alias: entry.alias.then(|| entry.name.clone()),
+            }))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// 快速切换：按文件名和别名匹配，完全相同的在前，其次是前缀匹配、包含匹配；每篇笔记只保留最好的一项
+#[tauri::command]
+pub async fn find_notes_by_name(
+    workspace: String,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<NameMatch>, MobiError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = PathBuf::from(&workspace);
+        let names = Names::load(&root)?;
+        let config = workspace::settings(&root);
+        let query = key(&query);
+        let mut scored: Vec<(u8, &Entry)> = names
+            .entries
+            .iter()
+            .filter(|e| !config.hides(&e.relative))
+            .filter_map(|e| {
+                let name = key(&e.name);
+                let score = if name == query {
+                    0
+                } else if name.starts_with(&query) {
+                    1
+                } else if name.contains(&query) {
+                    2
+                } else {
+                    return None;
+                };
+                Some((score, e))
+            })
+            .collect();
+        scored.sort_by(|(a, x), (b, y)| {
+            a.cmp(b)
+                .then(x.alias.cmp(&y.alias))
+                .then(x.name.len().cmp(&y.name.len()))
+                .then(x.relative.cmp(&y.relative))
+        });
+        let mut seen = HashSet::new();
+        Ok(scored
+            .into_iter()
+            .filter(|(_, e)| seen.insert(e.relative.as_str()))
+            .take(limit.unwrap_or(DEFAULT_LIMIT))
+            .map(|(_, e)| NameMatch {
+                path: names.absolute(&e.relative),
+                name: e.name.clone(),
+                alias: e.alias,
+            })
+            .collect())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// 指向 path 的链接：Markdown 链接按路径解析，wiki 链接和嵌入按文件名或别名解析；链接取自持久化的搜索索引，只重新解析改过的笔记
+#[tauri::command]
+pub async fn get_backlinks(workspace: String, path: String) -> Result<Vec<Backlink>, MobiError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = PathBuf::from(&workspace);
+        let target = workspace::relative_path(&root, Path::new(&path));
+        let names = Names::load(&root)?;
+        let mut backlinks = Vec::new();
+        let mut current: Option<(String, Vec<String>)> = None;
+        for (source, line) in search::backlinks(&root, &names, &target)? {
+            let note = root.join(&source);
+            if current.as_ref().is_none_or(|(path, _)| *path != source) {
+                let content = fs::read_to_string(&note).unwrap_or_default();
+                current = Some((source, content.lines().map(str::to_string).collect()));
+            }
+            let lines = current.as_ref().map(|(_, lines)| lines);
+            backlinks.push(Backlink {
+                path: note.to_string_lossy().to_string(),
+                line,
+                text: lines
+                    .and_then(|lines| lines.get(line))
+                    .map(|text| text.trim().to_string())
+                    .unwrap_or_default(),
+            });
+        }
+        Ok(backlinks)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
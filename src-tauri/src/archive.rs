@@ -0,0 +1,279 @@
+// 工作区归档：把笔记、附件和 .mobi 中的设置打包成一个 zip，用于换机迁移或分享完整项目
+// 包内附带清单 mobi-archive.json，记录每个文件的大小和 SHA-256；导入时先逐一校验，全部通过才解压
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::error::MobiError;
+use crate::workspace;
+
+const MANIFEST: &str = "mobi-archive.json";
+const FORMAT_VERSION: u32 = 1;
+
+// .mobi 中可以重建的缓存不打包
+const SKIPPED_DATA: &[&str] = &["index.db", "index.db-wal", "index.db-shm"];
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ManifestFile {
+    // 相对工作区的路径，/ 分隔
+    path: String,
+    size: u64,
+    sha256: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Manifest {
+    version: u32,
+    // 原工作区文件夹名
+    name: String,
+    created_at: String,
+    files: Vec<ManifestFile>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveSummary {
+    path: String,
+    files: usize,
+    bytes: u64,
+}
+
+// 工作区设置和数据：.mobiignore 以及 .mobi 下除缓存以外的文件
+fn data_files(root: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let ignore_file = root.join(".mobiignore");
+    if ignore_file.is_file() {
+        files.push(ignore_file);
+    }
+    let mut dirs = vec![root.join(".mobi")];
+    while let Some(dir) = dirs.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if !SKIPPED_DATA.contains(&name.as_str()) {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+// 边写入压缩包边计算摘要，大文件不必整个读入内存
+fn add_file(zip: &mut ZipWriter<File>, name: &str, path: &Path) -> Result<ManifestFile, String> {
+    let mut file = File::open(path).map_err(|e| format!("读取 {} 失败: {}", name, e))?;
+    let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .large_file(size > u32::MAX as u64);
+    zip.start_file(name, options)
+        .map_err(|e| format!("写入归档失败: {}", e))?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0; 64 * 1024];
+    let mut written = 0;
+    loop {
+        let n = file
+            .read(&mut buf)
+            .map_err(|e| format!("读取 {} 失败: {}", name, e))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        zip.write_all(&buf[..n])
+            .map_err(|e| format!("写入归档失败: {}", e))?;
+        written += n as u64;
+    }
+    Ok(ManifestFile {
+        path: name.to_string(),
+        size: written,
+        sha256: format!("{:x}", hasher.finalize()),
+    })
+}
+
+fn write_archive(root: &Path, output: &Path) -> Result<ArchiveSummary, String> {
+    let mut paths = workspace::all_files(root);
+    paths.extend(data_files(root));
+    // 归档保存在工作区内时不把上一次的归档再打包进去
+    let previous = fs::canonicalize(output).ok();
+    paths.retain(|path| previous.is_none() || fs::canonicalize(path).ok() != previous);
+
+    // 先写到临时文件，完成后再替换，失败时不留下残缺的归档
+    let temp = output.with_extension("zip.part");
+    let file = File::create(&temp).map_err(|e| format!("创建归档失败: {}", e))?;
+    let mut zip = ZipWriter::new(file);
+    let mut files = Vec::new();
+    let result = (|| {
+        for path in &paths {
+            let name = workspace::relative_path(root, path);
+            if name == MANIFEST {
+                continue;
+            }
+            files.push(add_file(&mut zip, &name, path)?);
+        }
+        let manifest = Manifest {
+            version: FORMAT_VERSION,
+            name: root
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            created_at: chrono::Local::now().to_rfc3339(),
+            files,
+        };
+        zip.start_file(MANIFEST, SimpleFileOptions::default())
+            .and_then(|_| {
+                let json = serde_json::to_vec_pretty(&manifest).map_err(io::Error::other)?;
+                zip.write_all(&json).map_err(Into::into)
+            })
+            .map_err(|e| format!("写入归档失败: {}", e))?;
+        zip.finish().map_err(|e| format!("写入归档失败: {}", e))?;
+        Ok(manifest)
+    })();
+    let manifest = match result {
+        Ok(manifest) => manifest,
+        Err(error) => {
+            let _ = fs::remove_file(&temp);
+            return Err(error);
+        }
+    };
+    fs::rename(&temp, output).map_err(|e| format!("保存归档失败: {}", e))?;
+    Ok(ArchiveSummary {
+        path: output.to_string_lossy().to_string(),
+        files: manifest.files.len(),
+        bytes: manifest.files.iter().map(|f| f.size).sum(),
+    })
+}
+
+// 清单中的路径必须是相对路径且不能跳出目标目录
+fn safe_relative(path: &str) -> Option<PathBuf> {
+    let path = Path::new(path);
+    path.components()
+        .all(|c| matches!(c, Component::Normal(_)))
+        .then(|| path.to_path_buf())
+}
+
+fn read_manifest(archive: &mut ZipArchive<File>) -> Result<Manifest, String> {
+    let mut entry = archive
+        .by_name(MANIFEST)
+        .map_err(|_| "不是墨笔工作区归档：缺少清单")?;
+    let mut json = String::new();
+    entry
+        .read_to_string(&mut json)
+        .map_err(|e| format!("读取清单失败: {}", e))?;
+    let manifest: Manifest =
+        serde_json::from_str(&json).map_err(|e| format!("清单格式错误: {}", e))?;
+    if manifest.version > FORMAT_VERSION {
+        return Err("归档由更新版本的墨笔创建，请先升级".into());
+    }
+    Ok(manifest)
+}
+
+// 逐一校验清单中的文件；包内有清单以外的文件也视为损坏
+fn verify(archive: &mut ZipArchive<File>, manifest: &Manifest) -> Result<(), String> {
+    let expected: HashMap<&str, &ManifestFile> = manifest
+        .files
+        .iter()
+        .map(|file| (file.path.as_str(), file))
+        .collect();
+    for i in 0..archive.len() {
+        let mut entry = archive
+            .by_index(i)
+            .map_err(|e| format!("读取归档失败: {}", e))?;
+        if entry.is_dir() || entry.name() == MANIFEST {
+            continue;
+        }
+        let name = entry.name().to_string();
+        let Some(file) = expected.get(name.as_str()) else {
+            return Err(format!("归档已损坏：清单中没有 {}", name));
+        };
+        let mut hasher = Sha256::new();
+        let size =
+            io::copy(&mut entry, &mut hasher).map_err(|e| format!("读取 {} 失败: {}", name, e))?;
+        if size != file.size || format!("{:x}", hasher.finalize()) != file.sha256 {
+            return Err(format!("归档已损坏：{} 校验失败", name));
+        }
+    }
+    for file in &manifest.files {
+        if archive.index_for_name(&file.path).is_none() {
+            return Err(format!("归档不完整：缺少 {}", file.path));
+        }
+        if safe_relative(&file.path).is_none() {
+            return Err(format!("归档包含不安全的路径: {}", file.path));
+        }
+    }
+    Ok(())
+}
+
+fn extract(
+    archive: &mut ZipArchive<File>,
+    manifest: &Manifest,
+    target: &Path,
+) -> Result<(), String> {
+    for file in &manifest.files {
+        let Some(relative) = safe_relative(&file.path) else {
+            continue;
+        };
+        let dest = target.join(relative);
+        if let Some(dir) = dest.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("创建目录失败: {}", e))?;
+        }
+        let mut entry = archive
+            .by_name(&file.path)
+            .map_err(|e| format!("读取归档失败: {}", e))?;
+        let mut out = File::create(&dest).map_err(|e| format!("写入 {} 失败: {}", file.path, e))?;
+        io::copy(&mut entry, &mut out).map_err(|e| format!("写入 {} 失败: {}", file.path, e))?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn export_workspace_archive(
+    workspace: String,
+    output: String,
+) -> Result<ArchiveSummary, MobiError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        Ok(write_archive(Path::new(&workspace), Path::new(&output))?)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// 解压到 target_dir（不存在或为空的目录），返回导入后的工作区路径
+#[tauri::command]
+pub async fn import_workspace_archive(
+    archive: String,
+    target_dir: String,
+) -> Result<ArchiveSummary, MobiError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let target = PathBuf::from(&target_dir);
+        if fs::read_dir(&target).is_ok_and(|mut entries| entries.next().is_some()) {
+            return Err(MobiError::InvalidInput("目标目录不为空".into()));
+        }
+        let file = File::open(&archive).map_err(|e| MobiError::io("打开归档失败", e))?;
+        let mut zip = ZipArchive::new(file).map_err(|e| format!("不是有效的 zip 文件: {}", e))?;
+        let manifest = read_manifest(&mut zip)?;
+        verify(&mut zip, &manifest)?;
+        fs::create_dir_all(&target).map_err(|e| format!("创建目标目录失败: {}", e))?;
+        extract(&mut zip, &manifest, &target)?;
+        Ok(ArchiveSummary {
+            path: target_dir,
+            files: manifest.files.len(),
+            bytes: manifest.files.iter().map(|f| f.size).sum(),
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
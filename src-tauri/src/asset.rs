@@ -0,0 +1,255 @@
+// 本地资源协议 mobi-asset://：预览中的本地图片、音视频和附件经由此协议读取，只提供当前工作区（含跟随的目录链接）和位于登记工作区中的当前文档所在目录中的文件
+// 路径先规范化（解析 .. 和符号链接）再检查范围，.mobi 数据目录和隐藏文件不对外提供；前端用 convertFileSrc(path, 'mobi-asset') 生成地址
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+
+use tauri::http::response::Builder;
+use tauri::http::{header, HeaderValue, Request, Response, StatusCode};
+use tauri::{AppHandle, Manager, State, Window};
+use tauri_plugin_fs::FsExt;
+
+use crate::error::{MobiError, MutexExt};
+use crate::{preview, workspace, workspaces};
+
+pub const SCHEME: &str = "mobi-asset";
+// Range 请求一次最多返回这么多，其余部分由 webview 继续请求
+const MAX_CHUNK: u64 = 8 * 1024 * 1024;
+
+#[derive(Default)]
+pub struct AssetState {
+    // 按窗口标签区分，每个窗口只能读取自己的工作区和文档
+    scopes: Mutex<HashMap<String, Scope>>,
+}
+
+#[derive(Default)]
+struct Scope {
+    workspace: Option<String>,
+    // 工作区及其跟随的目录链接，切换文档时不必重新遍历
+    workspace_roots: Vec<PathBuf>,
+    // 不在工作区中的当前文档所在目录
+    document_dir: Option<PathBuf>,
+}
+
+// relative 为相对根目录的路径：.mobi 数据目录以及以 . 开头的隐藏文件和目录不对外提供
+pub fn is_private(relative: &Path) -> bool {
+    relative
+        .components()
+        .any(|c| matches!(c, Component::Normal(name) if name.to_string_lossy().starts_with('.')))
+}
+
+// 请求路径为百分号编码的绝对路径：mobi-asset://localhost/%2FUsers%2F...（Windows 上为 http://mobi-asset.localhost/C%3A%5C...）
+fn requested_path(request: &Request<Vec<u8>>) -> Option<PathBuf> {
+    let raw = request.uri().path().trim_start_matches('/');
+    let decoded = urlencoding::decode(raw).ok()?;
+    if decoded.is_empty() {
+        return None;
+    }
+    let path = PathBuf::from(decoded.as_ref());
+    if path.is_absolute() {
+        Some(path)
+    } else {
+        // 部分平台会去掉开头的 /
+        Some(Path::new("/").join(path))
+    }
+}
+
+// real 需已规范化
+fn in_window_scope(app: &AppHandle, label: &str, real: &Path) -> bool {
+    let state = app.state::<AssetState>();
+    let scopes = state.scopes.locked();
+    scopes.get(label).is_some_and(|scope| {
+        scope
+            .workspace_roots
+            .iter()
+            .chain(&scope.document_dir)
+            .any(|root| {
+                real.strip_prefix(root)
+                    .is_ok_and(|relative| !is_private(relative))
+            })
+    })
+}
+
+fn allowed(app: &AppHandle, label: &str, path: &Path) -> Option<PathBuf> {
+    let real = path.canonicalize().ok()?;
+    (real.is_file() && in_window_scope(app, label, &real)).then_some(real)
+}
+
+// 规范化上级目录（尚不存在的部分逐级向上找到已有的目录），最后一段保持原样，删除和重命名符号链接时作用于链接本身
+fn resolve(path: &Path) -> Option<PathBuf> {
+    let name = path.file_name()?;
+    let parent = path.parent()?;
+    let parent = match parent.canonicalize() {
+        Ok(parent) => parent,
+        Err(_) => resolve(parent)?,
+    };
+    Some(parent.join(name))
+}
+
+// 前端的文件读写（files.rs）也只限于该窗口的范围，已存在的符号链接还要求其指向的位置同在范围内
+// 另外允许用户在对话框中选中的和系统交给应用的文件（fs 插件的范围）
+pub fn check_path(app: &AppHandle, label: &str, path: &Path) -> Result<PathBuf, MobiError> {
+    let resolved = resolve(path).ok_or_else(|| format!("无效的路径: {}", path.display()))?;
+    let target = resolved.canonicalize().ok();
+    let in_scope = in_window_scope(app, label, &resolved)
+        && target
+            .as_deref()
+            .is_none_or(|target| in_window_scope(app, label, target));
+    if in_scope || app.fs_scope().is_allowed(path) {
+        Ok(resolved)
+    } else {
+        Err(format!("不允许访问当前工作区以外的文件: {}", path.display()).into())
+    }
+}
+
+// bytes=start-end / bytes=start- / bytes=-suffix
+fn parse_range(value: &str, len: u64) -> Option<(u64, u64)> {
+    let spec = value
+        .trim()
+        .strip_prefix("bytes=")?
+        .split(',')
+        .next()?
+        .trim();
+    let (start, end) = spec.split_once('-')?;
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => {
+            let suffix: u64 = suffix.parse().ok()?;
+            (len.saturating_sub(suffix), len.checked_sub(1)?)
+        }
+        (start, "") => (start.parse().ok()?, len.checked_sub(1)?),
+        (start, end) => (
+            start.parse().ok()?,
+            end.parse::<u64>().ok()?.min(len.checked_sub(1)?),
+        ),
+    };
+    (start <= end && start < len).then_some((start, end))
+}
+
+fn status(code: StatusCode) -> Response<Vec<u8>> {
+    let reason = code.canonical_reason().unwrap_or_default();
+    let mut response = Response::new(reason.as_bytes().to_vec());
+    *response.status_mut() = code;
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("text/plain; charset=utf-8"),
+    );
+    response
+}
+
+// 响应头无效时 builder 会出错，改为返回 500
+fn finish(builder: Builder, body: Vec<u8>) -> Response<Vec<u8>> {
+    builder
+        .body(body)
+        .unwrap_or_else(|_| status(StatusCode::INTERNAL_SERVER_ERROR))
+}
+
+fn read_range(path: &Path, start: u64, len: u64) -> std::io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(start))?;
+    let mut bytes = Vec::with_capacity(len as usize);
+    file.take(len).read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+// label 为发出请求的窗口
+pub fn respond(app: &AppHandle, label: &str, request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+    let Some(path) = requested_path(request) else {
+        return status(StatusCode::BAD_REQUEST);
+    };
+    // 范围外的文件与不存在的文件同样返回 404，不透露文件是否存在
+    let Some(path) = allowed(app, label, &path) else {
+        return status(StatusCode::NOT_FOUND);
+    };
+    let Ok(len) = path.metadata().map(|m| m.len()) else {
+        return status(StatusCode::NOT_FOUND);
+    };
+
+    let range = request
+        .headers()
+        .get(header::RANGE)
+        .and_then(|v| v.to_str().ok());
+    let builder = Response::builder()
+        .header(header::CONTENT_TYPE, preview::content_type(&path))
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::CACHE_CONTROL, "no-cache");
+    let (builder, start, end) = match range {
+        Some(range) => match parse_range(range, len) {
+            Some((start, end)) => {
+                let end = end.min(start + MAX_CHUNK - 1);
+                let builder = builder.status(StatusCode::PARTIAL_CONTENT).header(
+                    header::CONTENT_RANGE,
+                    format!("bytes {}-{}/{}", start, end, len),
+                );
+                (builder, start, end)
+            }
+            None => {
+                return finish(
+                    Response::builder()
+                        .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                        .header(header::CONTENT_RANGE, format!("bytes */{}", len)),
+                    Vec::new(),
+                )
+            }
+        },
+        None if len == 0 => return finish(builder, Vec::new()),
+        None => (builder.status(StatusCode::OK), 0, len - 1),
+    };
+    match read_range(&path, start, end - start + 1) {
+        Ok(bytes) => finish(builder, bytes),
+        Err(_) => status(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+// 打开或切换工作区、文档时调用，替换该窗口之前的范围；两者都为空时不提供任何文件
+// 未登记在工作区列表中的工作区不受理，文档所在目录也只在位于登记过的工作区中时才加入
+#[tauri::command]
+pub fn set_asset_scope(
+    app: AppHandle,
+    window: Window,
+    state: State<AssetState>,
+    workspace: Option<String>,
+    document: Option<String>,
+) {
+    let mut scopes = state.scopes.locked();
+    let scope = scopes.entry(window.label().to_string()).or_default();
+    let workspace = workspace.filter(|w| !w.is_empty() && workspaces::is_registered(&app, w));
+    if scope.workspace != workspace {
+        scope.workspace_roots = match &workspace {
+            Some(root) => {
+                let root = PathBuf::from(root);
+                root.canonicalize()
+                    .ok()
+                    .into_iter()
+                    // 跟随的目录链接指向工作区外，单独加入
+                    .chain(
+                        workspace::linked_dirs(&root)
+                            .into_iter()
+                            .filter_map(|(_, target)| target.canonicalize().ok()),
+                    )
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+        scope.workspace = workspace;
+    }
+    let document_dir = document
+        .filter(|d| !d.is_empty())
+        .and_then(|d| Path::new(&d).parent().and_then(|p| p.canonicalize().ok()));
+    scope.document_dir = document_dir.filter(|dir| {
+        !scope
+            .workspace_roots
+            .iter()
+            .any(|root| dir.starts_with(root))
+            && workspaces::in_registered(&app, dir)
+    });
+}
+
+// 窗口关闭时丢弃它的范围
+pub fn forget_window(app: &AppHandle, label: &str) {
+    if let Some(state) = app.try_state::<AssetState>() {
+        state.scopes.locked().remove(label);
+    }
+}
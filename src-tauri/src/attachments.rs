@@ -0,0 +1,221 @@
+// 附件：PDF、表格、压缩包等非图片文件复制到笔记自己的附件目录（工作区设置 attachmentDir，默认 attachments/{note}），插入带类型图标的相对链接
+// 附件列表合并附件目录中的文件和笔记中链接到的其他本地文件；打开附件交给系统默认程序
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tauri_plugin_opener::OpenerExt;
+
+use crate::error::MobiError;
+use crate::{markdown, workspace};
+
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "webp", "bmp", "svg", "avif"];
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum AttachMode {
+    Copy,
+    Move,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Attachment {
+    path: String,
+    name: String,
+    // 字节，文件不存在时为 0
+    size: u64,
+    // pdf、spreadsheet、document、presentation、archive、audio、video、text、other
+    kind: &'static str,
+    // 链接文字前的图标
+    icon: &'static str,
+    // 插入笔记的链接
+    markdown: String,
+    // 在笔记的附件目录中
+    in_folder: bool,
+    // 笔记中有链接指向它
+    linked: bool,
+    missing: bool,
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+fn kind_of(path: &Path) -> (&'static str, &'static str) {
+    match extension(path).as_str() {
+        "pdf" => ("pdf", "📕"),
+        "xls" | "xlsx" | "ods" | "csv" | "tsv" | "numbers" => ("spreadsheet", "📊"),
+        "doc" | "docx" | "odt" | "rtf" | "pages" | "epub" => ("document", "📄"),
+        "ppt" | "pptx" | "odp" | "key" => ("presentation", "📽️"),
+        "zip" | "7z" | "rar" | "tar" | "gz" | "tgz" | "bz2" | "xz" => ("archive", "📦"),
+        "mp3" | "wav" | "m4a" | "flac" | "ogg" | "aac" => ("audio", "🎵"),
+        "mp4" | "mov" | "mkv" | "webm" | "avi" => ("video", "🎬"),
+        "txt" | "json" | "xml" | "yaml" | "yml" | "log" | "html" => ("text", "📝"),
+        _ => ("other", "📎"),
+    }
+}
+
+// 笔记的附件目录：{note} 替换为笔记文件名（不含扩展名），相对笔记所在目录
+fn folder(workspace: Option<&str>, note: &Path) -> PathBuf {
+    let template = workspace
+        .filter(|w| !w.is_empty())
+        .map(|w| workspace::settings(Path::new(w)).attachment_dir)
+        .unwrap_or_else(|| "attachments/{note}".into());
+    let stem = workspace::sanitize_file_name(
+        &note
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default(),
+    );
+    let relative = template.replace("{note}", &stem);
+    note.parent()
+        .unwrap_or(Path::new("."))
+        .join(relative.trim_matches('/'))
+}
+
+fn describe(note: &Path, path: &Path, in_folder: bool, linked: bool) -> Attachment {
+    let (kind, icon) = kind_of(path);
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let dir = note.parent().unwrap_or(Path::new("."));
+    let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    Attachment {
+        markdown: format!(
+            "[{} {}]({})",
+            icon,
+            name.replace('[', "\\[").replace(']', "\\]"),
+            markdown::link_destination(&workspace::relative_to(dir, path))
+        ),
+        path: path.to_string_lossy().to_string(),
+        name,
+        size,
+        kind,
+        icon,
+        in_folder,
+        linked,
+        missing: !path.is_file(),
+    }
+}
+
+// 把文件复制（或移动）到笔记的附件目录，同名时加序号；返回的 markdown 可直接插入笔记
+#[tauri::command]
+pub fn attach_files(
+    workspace: Option<String>,
+    note: String,
+    files: Vec<String>,
+    mode: Option<AttachMode>,
+) -> Result<Vec<Attachment>, MobiError> {
+    let note = PathBuf::from(&note);
+    let dir = folder(workspace.as_deref(), &note);
+    fs::create_dir_all(&dir).map_err(|e| format!("创建附件目录失败: {}", e))?;
+
+    let mut attached = Vec::new();
+    for source in files.iter().map(PathBuf::from) {
+        if !source.is_file() {
+            return Err(MobiError::InvalidInput(format!(
+                "{} 不是文件",
+                source.display()
+            )));
+        }
+        // 已在附件目录中的文件直接链接
+        let target = if source.parent() == Some(dir.as_path()) {
+            source.clone()
+        } else {
+            let stem = source
+                .file_stem()
+                .map(|s| workspace::sanitize_file_name(&s.to_string_lossy()))
+                .unwrap_or_default();
+            let target = workspace::unique_path(&dir, &stem, &extension(&source));
+            match mode.unwrap_or(AttachMode::Copy) {
+                AttachMode::Copy => {
+                    fs::copy(&source, &target).map_err(|e| format!("复制附件失败: {}", e))?;
+                }
+                AttachMode::Move => {
+                    if fs::rename(&source, &target).is_err() {
+                        fs::copy(&source, &target).map_err(|e| format!("移动附件失败: {}", e))?;
+                        fs::remove_file(&source).map_err(|e| format!("删除原文件失败: {}", e))?;
+                    }
+                }
+            }
+            target
+        };
+        attached.push(describe(&note, &target, true, false));
+    }
+    Ok(attached)
+}
+
+// 笔记的附件：附件目录中的文件和正文链接到的非笔记、非图片本地文件
+#[tauri::command]
+pub fn list_attachments(
+    workspace: Option<String>,
+    note: String,
+) -> Result<Vec<Attachment>, MobiError> {
+    let note = PathBuf::from(&note);
+    let root = workspace
+        .as_deref()
+        .filter(|w| !w.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| note.parent().map(Path::to_path_buf))
+        .unwrap_or_default();
+    let dir = folder(workspace.as_deref(), &note);
+    let content = fs::read_to_string(&note).unwrap_or_default();
+
+    let mut linked: Vec<PathBuf> = Vec::new();
+    markdown::rewrite_link_destinations(&content, |destination| {
+        if let Some(target) = workspace::resolve_note_link(&root, &note, destination) {
+            let ext = extension(&target);
+            if !workspace::is_note(&target)
+                && !IMAGE_EXTENSIONS.contains(&ext.as_str())
+                && !linked.contains(&target)
+            {
+                linked.push(target);
+            }
+        }
+        None
+    });
+
+    let mut in_folder: Vec<PathBuf> = fs::read_dir(&dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.is_file())
+                .filter(|p| {
+                    !p.file_name()
+                        .is_some_and(|n| n.to_string_lossy().starts_with('.'))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    in_folder.sort();
+
+    let mut attachments: Vec<Attachment> = in_folder
+        .iter()
+        .map(|path| describe(&note, path, true, linked.contains(path)))
+        .collect();
+    attachments.extend(
+        linked
+            .iter()
+            .filter(|path| !in_folder.contains(path))
+            .map(|path| describe(&note, path, false, true)),
+    );
+    Ok(attachments)
+}
+
+// 用系统默认程序打开附件
+#[tauri::command]
+pub fn open_attachment(app: AppHandle, path: String) -> Result<(), MobiError> {
+    if !Path::new(&path).is_file() {
+        return Err("附件不存在".into());
+    }
+    app.opener()
+        .open_path(path, None::<&str>)
+        .map_err(|e| format!("打开附件失败: {}", e).into())
+}
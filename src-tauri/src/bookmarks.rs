@@ -0,0 +1,293 @@
+// 书签：收藏的笔记（只有文件）和保存的位置（文件 + 标题或偏移），存在 .mobi/bookmarks.json，随工作区同步
+// 有标题的书签跳转时按标题文本重新定位，笔记编辑后仍能落到正确位置；偏移为 UTF-16，与编辑器一致
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{MobiError, MutexExt};
+use crate::templates::utf16_offset;
+use crate::{markdown, workspace};
+
+// 串行化书签文件的读写
+static LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Bookmark {
+    id: String,
+    // 相对工作区的路径，用 / 分隔
+    path: String,
+    #[serde(default)]
+    heading: Option<String>,
+    #[serde(default)]
+    offset: Option<usize>,
+    #[serde(default)]
+    label: String,
+    // 毫秒时间戳
+    #[serde(default)]
+    created: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BookmarkInfo {
+    #[serde(flatten)]
+    bookmark: Bookmark,
+    // star 为收藏的笔记，location 为笔记中的位置
+    kind: &'static str,
+    // 文件已被删除或移走
+    missing: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BookmarkTarget {
+    path: String,
+    // UTF-16 偏移
+    offset: usize,
+    // 行号从 0 开始
+    line: usize,
+    // 有标题但在当前内容中找不到，已退回保存的偏移
+    stale: bool,
+}
+
+fn bookmarks_file(workspace: &str) -> Result<PathBuf, String> {
+    Ok(workspace::data_dir(Path::new(workspace))?.join("bookmarks.json"))
+}
+
+fn load(workspace: &str) -> Vec<Bookmark> {
+    bookmarks_file(workspace)
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+fn save(workspace: &str, bookmarks: &[Bookmark]) -> Result<(), String> {
+    let text = serde_json::to_string_pretty(bookmarks).map_err(|e| e.to_string())?;
+    fs::write(bookmarks_file(workspace)?, text).map_err(|e| format!("保存书签失败: {}", e))
+}
+
+fn kind(bookmark: &Bookmark) -> &'static str {
+    if bookmark.heading.is_none() && bookmark.offset.is_none() {
+        "star"
+    } else {
+        "location"
+    }
+}
+
+fn heading_text(line: &str) -> Option<&str> {
+    let trimmed = line.trim_start();
+    let level = trimmed.chars().take_while(|c| *c == '#').count();
+    let title = &trimmed[level..];
+    (level > 0 && level <= 6 && (title.is_empty() || title.starts_with(' ')))
+        .then(|| title.trim().trim_end_matches('#').trim())
+}
+
+// 正文中所有标题的（字节偏移, 行号, 文本），跳过 front matter 和代码块
+fn headings(content: &str) -> Vec<(usize, usize, &str)> {
+    let body = markdown::strip_front_matter(content);
+    let body_start = content.len() - body.len();
+    let skip_lines = content[..body_start].lines().count();
+    let fences = markdown::fenced_blocks(body);
+    let mut found = Vec::new();
+    let mut offset = body_start;
+    for (i, line) in body.split_inclusive('\n').enumerate() {
+        let fenced = fences
+            .iter()
+            .any(|f| (f.start_line..=f.end_line).contains(&i));
+        if !fenced {
+            if let Some(text) = heading_text(line) {
+                found.push((offset, skip_lines + i, text));
+            }
+        }
+        offset += line.len();
+    }
+    found
+}
+
+// UTF-16 偏移对应的字节偏移，超出文末时取文末
+fn byte_offset(text: &str, utf16: usize) -> usize {
+    let mut units = 0;
+    for (i, c) in text.char_indices() {
+        if units >= utf16 {
+            return i;
+        }
+        units += c.len_utf16();
+    }
+    text.len()
+}
+
+fn line_of(text: &str, byte: usize) -> usize {
+    text[..byte].matches('\n').count()
+}
+
+fn resolve(content: &str, bookmark: &Bookmark) -> (usize, bool) {
+    let saved = bookmark.offset.map(|o| byte_offset(content, o));
+    if let Some(heading) = &bookmark.heading {
+        // 同名标题有多个时取离保存的偏移最近的
+        let target = saved.unwrap_or(0);
+        if let Some((byte, _, _)) = headings(content)
+            .into_iter()
+            .filter(|(_, _, text)| text == heading)
+            .min_by_key(|(byte, _, _)| byte.abs_diff(target))
+        {
+            return (byte, false);
+        }
+        return (saved.unwrap_or(0), true);
+    }
+    (saved.unwrap_or(0), false)
+}
+
+fn read_note(workspace: &str, path: &str) -> Result<String, String> {
+    fs::read_to_string(Path::new(workspace).join(path)).map_err(|e| format!("读取笔记失败: {}", e))
+}
+
+// 笔记移动后更新指向它的书签，from / to 为相对工作区的路径
+pub fn moved(workspace: &str, from: &str, to: &str) -> Result<(), String> {
+    let _guard = LOCK.locked();
+    let mut bookmarks = load(workspace);
+    let mut changed = false;
+    for bookmark in bookmarks.iter_mut().filter(|b| b.path == from) {
+        bookmark.path = to.to_string();
+        changed = true;
+    }
+    if changed {
+        save(workspace, &bookmarks)?;
+    }
+    Ok(())
+}
+
+// 笔记被永久删除时去掉它的书签
+pub fn removed(workspace: &str, path: &str) -> Result<(), String> {
+    let _guard = LOCK.locked();
+    let mut bookmarks = load(workspace);
+    let before = bookmarks.len();
+    bookmarks.retain(|b| b.path != path);
+    if bookmarks.len() != before {
+        save(workspace, &bookmarks)?;
+    }
+    Ok(())
+}
+
+// 只给 path 时收藏整篇笔记；给出 heading 或 offset 时保存位置。已有相同的书签时直接返回它
+#[tauri::command]
+pub fn add_bookmark(
+    workspace: String,
+    path: String,
+    heading: Option<String>,
+    offset: Option<usize>,
+    label: Option<String>,
+) -> Result<Bookmark, MobiError> {
+    let root = Path::new(&workspace);
+    let file = root.join(&path);
+    if !file.is_file() {
+        return Err("笔记不存在".into());
+    }
+    let relative = workspace::relative_path(root, &file);
+    let heading = heading
+        .map(|h| h.trim().trim_start_matches('#').trim().to_string())
+        .filter(|h| !h.is_empty());
+
+    // 按标题保存时记下标题当前的位置，同名标题据此区分
+    let offset = match (&heading, offset) {
+        (Some(heading), None) => {
+            let content = read_note(&workspace, &relative)?;
+            let (byte, _, _) = headings(&content)
+                .into_iter()
+                .find(|(_, _, text)| text == heading)
+                .ok_or_else(|| format!("笔记中没有标题「{}」", heading))?;
+            Some(utf16_offset(&content, byte))
+        }
+        (_, offset) => offset,
+    };
+
+    let _guard = LOCK.locked();
+    let mut bookmarks = load(&workspace);
+    if let Some(existing) = bookmarks
+        .iter()
+        .find(|b| b.path == relative && b.heading == heading && b.offset == offset)
+    {
+        return Ok(existing.clone());
+    }
+    let label = label
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .or_else(|| heading.clone())
+        .unwrap_or_else(|| {
+            Path::new(&relative)
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        });
+    let bookmark = Bookmark {
+        id: workspace::new_id(|id| bookmarks.iter().any(|b| b.id == id)),
+        path: relative,
+        heading,
+        offset,
+        label,
+        created: Utc::now().timestamp_millis(),
+    };
+    bookmarks.push(bookmark.clone());
+    save(&workspace, &bookmarks)?;
+    Ok(bookmark)
+}
+
+// 按添加顺序返回，收藏的笔记在前
+#[tauri::command]
+pub fn list_bookmarks(workspace: String) -> Vec<BookmarkInfo> {
+    let root = Path::new(&workspace);
+    let _guard = LOCK.locked();
+    let mut list: Vec<BookmarkInfo> = load(&workspace)
+        .into_iter()
+        .map(|bookmark| BookmarkInfo {
+            kind: kind(&bookmark),
+            missing: !root.join(&bookmark.path).is_file(),
+            bookmark,
+        })
+        .collect();
+    list.sort_by_key(|info| info.kind != "star");
+    list
+}
+
+#[tauri::command]
+pub fn goto_bookmark(workspace: String, id: String) -> Result<BookmarkTarget, MobiError> {
+    let bookmark = {
+        let _guard = LOCK.locked();
+        load(&workspace)
+            .into_iter()
+            .find(|b| b.id == id)
+            .ok_or("书签不存在")?
+    };
+    let file = Path::new(&workspace).join(&bookmark.path);
+    if !file.is_file() {
+        return Err(MobiError::NotFound(format!(
+            "书签指向的笔记已不存在: {}",
+            bookmark.path
+        )));
+    }
+    let content = read_note(&workspace, &bookmark.path)?;
+    let (byte, stale) = resolve(&content, &bookmark);
+    Ok(BookmarkTarget {
+        path: file.to_string_lossy().into_owned(),
+        offset: utf16_offset(&content, byte),
+        line: line_of(&content, byte),
+        stale,
+    })
+}
+
+#[tauri::command]
+pub fn remove_bookmark(workspace: String, id: String) -> Result<(), MobiError> {
+    let _guard = LOCK.locked();
+    let mut bookmarks = load(&workspace);
+    let before = bookmarks.len();
+    bookmarks.retain(|b| b.id != id);
+    if bookmarks.len() == before {
+        return Err("书签不存在".into());
+    }
+    Ok(save(&workspace, &bookmarks)?)
+}
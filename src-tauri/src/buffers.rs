@@ -0,0 +1,255 @@
+// 共享文档缓冲区：同一文件在多个窗口中打开（或被快速记录追加）时共用后端的一份内容
+// 每次编辑带上所基于的版本号，落后于当前版本的编辑与期间的改动三方合并，再通过 buffer-changed 事件同步到各窗口
+
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State, Window};
+
+use crate::error::{MobiError, MutexExt};
+use crate::{conflicts, workspace};
+
+// 保留的历史版本数，基于更早版本的编辑按覆盖处理
+const HISTORY: usize = 64;
+
+struct Buffer {
+    content: String,
+    version: u64,
+    // 最近一次读取或写入磁盘时的内容
+    disk: String,
+    history: VecDeque<(u64, String)>,
+    // 打开了该文件的窗口
+    windows: HashSet<String>,
+}
+
+#[derive(Default)]
+pub struct BufferState {
+    buffers: Mutex<HashMap<PathBuf, Buffer>>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BufferSnapshot {
+    // 规范化后的路径，与 buffer-changed 事件中的 path 一致
+    path: String,
+    content: String,
+    version: u64,
+    saved: bool,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct BufferChanged {
+    #[serde(flatten)]
+    snapshot: BufferSnapshot,
+    // 发起改动的窗口，来自磁盘或快速记录时为空
+    source: Option<String>,
+}
+
+fn key(path: &str) -> PathBuf {
+    let path = workspace::resolve_link(Path::new(path));
+    fs::canonicalize(&path).unwrap_or(path)
+}
+
+impl Buffer {
+    fn snapshot(&self, key: &Path) -> BufferSnapshot {
+        BufferSnapshot {
+            path: key.to_string_lossy().to_string(),
+            content: self.content.clone(),
+            version: self.version,
+            saved: self.content == self.disk,
+        }
+    }
+
+    fn set(&mut self, content: String) {
+        self.version += 1;
+        self.content = content;
+        self.history.push_back((self.version, self.content.clone()));
+        while self.history.len() > HISTORY {
+            self.history.pop_front();
+        }
+    }
+
+    // 把基于 base 版本的内容合并进当前内容，冲突处写入冲突标记
+    fn merge(&mut self, base: u64, content: String, label: &str) {
+        if base == self.version {
+            self.set(content);
+            return;
+        }
+        let merged = match self.history.iter().find(|(v, _)| *v == base) {
+            Some((_, base)) => conflicts::merge3(base, &content, &self.content, label).content,
+            None => content,
+        };
+        self.set(merged);
+    }
+}
+
+fn emit(app: &AppHandle, snapshot: BufferSnapshot, source: Option<String>) {
+    let _ = app.emit("buffer-changed", BufferChanged { snapshot, source });
+}
+
+#[tauri::command]
+pub fn open_buffer(
+    window: Window,
+    state: State<BufferState>,
+    path: String,
+) -> Result<BufferSnapshot, MobiError> {
+    let key = key(&path);
+    let mut buffers = state.buffers.locked();
+    let buffer = match buffers.entry(key.clone()) {
+        Entry::Occupied(entry) => entry.into_mut(),
+        Entry::Vacant(entry) => {
+            let content = fs::read_to_string(&key).map_err(|e| MobiError::io("读取文件失败", e))?;
+            entry.insert(Buffer {
+                disk: content.clone(),
+                history: VecDeque::from([(0, content.clone())]),
+                content,
+                version: 0,
+                windows: HashSet::new(),
+            })
+        }
+    };
+    buffer.windows.insert(window.label().to_string());
+    Ok(buffer.snapshot(&key))
+}
+
+#[tauri::command]
+pub fn update_buffer(
+    app: AppHandle,
+    window: Window,
+    state: State<BufferState>,
+    path: String,
+    base_version: u64,
+    content: String,
+) -> Result<BufferSnapshot, MobiError> {
+    let key = key(&path);
+    let snapshot = {
+        let mut buffers = state.buffers.locked();
+        let buffer = buffers.get_mut(&key).ok_or("文档未打开")?;
+        if base_version == buffer.version && content == buffer.content {
+            return Ok(buffer.snapshot(&key));
+        }
+        buffer.merge(base_version, content, "其他窗口");
+        buffer.snapshot(&key)
+    };
+    emit(&app, snapshot.clone(), Some(window.label().to_string()));
+    Ok(snapshot)
+}
+
+#[tauri::command]
+pub fn close_buffer(window: Window, state: State<BufferState>, path: String) {
+    let key = key(&path);
+    let mut buffers = state.buffers.locked();
+    if let Some(buffer) = buffers.get_mut(&key) {
+        buffer.windows.remove(window.label());
+        if buffer.windows.is_empty() {
+            buffers.remove(&key);
+        }
+    }
+}
+
+// 追加一段文字（快速记录等）：直接追加到磁盘文件，已打开的缓冲区同步追加，不影响各窗口未保存的改动
+#[tauri::command]
+pub fn append_to_buffer(
+    app: AppHandle,
+    state: State<BufferState>,
+    path: String,
+    text: String,
+) -> Result<(), MobiError> {
+    let key = key(&path);
+    let existing = fs::read_to_string(&key).unwrap_or_default();
+    let text = if existing.is_empty() || existing.ends_with('\n') {
+        text
+    } else {
+        format!("\n{}", text)
+    };
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&key)
+        .and_then(|mut file| file.write_all(text.as_bytes()))
+        .map_err(|e| MobiError::io("写入文件失败", e))?;
+
+    let snapshot = {
+        let mut buffers = state.buffers.locked();
+        let Some(buffer) = buffers.get_mut(&key) else {
+            return Ok(());
+        };
+        buffer.disk = format!("{}{}", existing, text);
+        let mut content = buffer.content.clone();
+        if !content.is_empty() && !content.ends_with('\n') && !text.starts_with('\n') {
+            content.push('\n');
+        }
+        content.push_str(&text);
+        buffer.set(content);
+        buffer.snapshot(&key)
+    };
+    emit(&app, snapshot, None);
+    Ok(())
+}
+
+// 文件保存成功后调用，内容与缓冲区一致时通知各窗口已保存
+pub fn saved(app: &AppHandle, path: &str, content: &str) {
+    let key = key(path);
+    let snapshot = {
+        let state = app.state::<BufferState>();
+        let mut buffers = state.buffers.locked();
+        let Some(buffer) = buffers.get_mut(&key) else {
+            return;
+        };
+        buffer.disk = content.to_string();
+        buffer.snapshot(&key)
+    };
+    emit(app, snapshot, None);
+}
+
+// 粉碎笔记前调用：丢弃缓冲区及其历史版本，需在文件删除前调用以便解析路径
+pub fn forget(app: &AppHandle, path: &str) {
+    let key = key(path);
+    app.state::<BufferState>().buffers.locked().remove(&key);
+}
+
+// 工作区监视到文件变化：没有未保存改动的缓冲区直接换成磁盘内容，有改动的与磁盘内容合并
+pub fn disk_changed(app: &AppHandle, paths: &[PathBuf]) {
+    let changed: Vec<BufferSnapshot> = {
+        let state = app.state::<BufferState>();
+        let mut buffers = state.buffers.locked();
+        paths
+            .iter()
+            .filter_map(|path| {
+                let key = fs::canonicalize(path).ok()?;
+                let buffer = buffers.get_mut(&key)?;
+                let disk = fs::read_to_string(&key).ok()?;
+                if disk == buffer.disk {
+                    return None;
+                }
+                let merged = if buffer.content == buffer.disk {
+                    disk.clone()
+                } else {
+                    conflicts::merge3(&buffer.disk, &buffer.content, &disk, "磁盘").content
+                };
+                buffer.disk = disk;
+                buffer.set(merged);
+                Some(buffer.snapshot(&key))
+            })
+            .collect()
+    };
+    for snapshot in changed {
+        emit(app, snapshot, None);
+    }
+}
+
+// 窗口关闭时释放它打开的缓冲区
+pub fn forget_window(app: &AppHandle, label: &str) {
+    let state = app.state::<BufferState>();
+    let mut buffers = state.buffers.locked();
+    buffers.retain(|_, buffer| {
+        buffer.windows.remove(label);
+        !buffer.windows.is_empty()
+    });
+}
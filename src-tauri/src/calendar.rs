@@ -0,0 +1,83 @@
+// 笔记日历：按创建 / 修改 / front matter 日期把笔记归到每一天，并标记当天是否有日记
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use chrono::{DateTime, Local, NaiveDate};
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::daily::{self, DateRange};
+use crate::{markdown, workspace};
+
+// front matter 中依次尝试的日期字段
+const DATE_FIELDS: &[&str] = &["date", "created"];
+
+#[derive(Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CalendarDay {
+    date: NaiveDate,
+    created: Vec<String>,
+    modified: Vec<String>,
+    // front matter 中写明日期的笔记
+    dated: Vec<String>,
+    daily_note: Option<String>,
+}
+
+fn local_date(time: SystemTime) -> NaiveDate {
+    DateTime::<Local>::from(time).date_naive()
+}
+
+fn day(days: &mut BTreeMap<NaiveDate, CalendarDay>, date: NaiveDate) -> &mut CalendarDay {
+    days.entry(date).or_insert_with(|| CalendarDay {
+        date,
+        ..Default::default()
+    })
+}
+
+// 支持 2025-01-15、2025-01-15 10:00、2025-01-15T10:00:00+08:00 等写法
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value.get(..10)?, "%Y-%m-%d").ok()
+}
+
+#[tauri::command]
+pub fn get_notes_calendar(app: AppHandle, workspace: String, range: DateRange) -> Vec<CalendarDay> {
+    let root = PathBuf::from(&workspace);
+    let mut days: BTreeMap<NaiveDate, CalendarDay> = BTreeMap::new();
+    let in_range = |date: NaiveDate| date >= range.start && date <= range.end;
+
+    for file in workspace::note_files(&root) {
+        let path = file.to_string_lossy().to_string();
+        if let Ok(meta) = fs::metadata(&file) {
+            // 部分文件系统不提供创建时间
+            if let Some(date) = meta.created().ok().map(local_date).filter(|d| in_range(*d)) {
+                day(&mut days, date).created.push(path.clone());
+            }
+            if let Some(date) = meta
+                .modified()
+                .ok()
+                .map(local_date)
+                .filter(|d| in_range(*d))
+            {
+                day(&mut days, date).modified.push(path.clone());
+            }
+        }
+        let dated = fs::read_to_string(&file).ok().and_then(|content| {
+            DATE_FIELDS
+                .iter()
+                .find_map(|key| markdown::front_matter_field(&content, key))
+                .and_then(|value| parse_date(&value))
+        });
+        if let Some(date) = dated.filter(|d| in_range(*d)) {
+            day(&mut days, date).dated.push(path);
+        }
+    }
+
+    for (date, path) in daily::existing_daily_notes(&app, Path::new(&root), &range) {
+        day(&mut days, date).daily_note = Some(path.to_string_lossy().to_string());
+    }
+
+    days.into_values().collect()
+}
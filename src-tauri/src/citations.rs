@@ -0,0 +1,343 @@
+// 文献引用：读取工作区的 BibTeX / Hayagriva YAML 文献库，导出时把 [@key] 按 CSL 样式渲染并生成参考文献
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use hayagriva::archive::{self, ArchivedStyle};
+use hayagriva::citationberg::taxonomy::Locator;
+use hayagriva::citationberg::{IndependentStyle, Style};
+use hayagriva::{
+    BibliographyDriver, BibliographyRequest, BufWriteFormat, CitationItem, CitationRequest,
+    ElemChildren, Library, LocatorPayload, SpecificLocator,
+};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::error::MobiError;
+use crate::{markdown, settings};
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct CitationSettings {
+    // 相对工作区的文献库路径，留空则自动查找工作区根目录下的 .bib / references.yaml
+    bibliography: String,
+    // 内置样式名，或 .csl 文件路径
+    style: String,
+}
+
+impl Default for CitationSettings {
+    fn default() -> Self {
+        CitationSettings {
+            bibliography: String::new(),
+            style: "gb-7714-2015-numeric".into(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CitationEntry {
+    key: String,
+    title: String,
+    authors: Vec<String>,
+    year: Option<i32>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CitationStyle {
+    name: String,
+    title: String,
+}
+
+struct Cite {
+    key: String,
+    locator: Option<String>,
+}
+
+// 正文中的一组引用，如 [@a; @b, p. 12]，start/end 为字节偏移
+struct CiteGroup {
+    start: usize,
+    end: usize,
+    items: Vec<Cite>,
+}
+
+pub fn bibliography_path(app: &AppHandle, root: &Path) -> Option<PathBuf> {
+    let config: CitationSettings = settings::load(app, "citations");
+    if !config.bibliography.is_empty() {
+        return Some(root.join(&config.bibliography));
+    }
+    for name in ["references.bib", "references.yaml", "references.yml"] {
+        if root.join(name).is_file() {
+            return Some(root.join(name));
+        }
+    }
+    let mut bib_files: Vec<PathBuf> = fs::read_dir(root)
+        .ok()?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "bib"))
+        .collect();
+    bib_files.sort();
+    bib_files.into_iter().next()
+}
+
+fn load_library(app: &AppHandle, root: &Path) -> Result<Option<Library>, String> {
+    let Some(path) = bibliography_path(app, root) else {
+        return Ok(None);
+    };
+    let text = fs::read_to_string(&path).map_err(|e| format!("读取文献库失败: {}", e))?;
+    let is_yaml = path
+        .extension()
+        .is_some_and(|ext| ext == "yaml" || ext == "yml");
+    let library = if is_yaml {
+        hayagriva::io::from_yaml_str(&text).map_err(|e| format!("解析文献库失败: {}", e))?
+    } else {
+        hayagriva::io::from_biblatex_str(&text).map_err(|errors| {
+            let first = errors.first().map(|e| e.to_string()).unwrap_or_default();
+            format!("解析文献库失败（{} 处错误）: {}", errors.len(), first)
+        })?
+    };
+    Ok(Some(library))
+}
+
+fn load_style(root: &Path, name: &str) -> Result<IndependentStyle, String> {
+    if name.ends_with(".csl") {
+        let xml =
+            fs::read_to_string(root.join(name)).map_err(|e| format!("读取 CSL 样式失败: {}", e))?;
+        return IndependentStyle::from_xml(&xml).map_err(|e| format!("解析 CSL 样式失败: {}", e));
+    }
+    let style = ArchivedStyle::by_name(name).ok_or_else(|| format!("未知的引用样式: {}", name))?;
+    match style.get() {
+        Style::Independent(style) => Ok(style),
+        Style::Dependent(_) => Err(format!("不支持依赖型样式: {}", name)),
+    }
+}
+
+fn parse_group(inner: &str) -> Option<Vec<Cite>> {
+    inner
+        .split(';')
+        .map(|part| {
+            let part = part.trim().strip_prefix('@')?;
+            let (key, locator) = match part.split_once(',') {
+                Some((key, locator)) => (key.trim(), Some(locator.trim().to_string())),
+                None => (part.trim(), None),
+            };
+            if key.is_empty() || key.contains(char::is_whitespace) {
+                return None;
+            }
+            Some(Cite {
+                key: key.to_string(),
+                locator: locator.filter(|l| !l.is_empty()),
+            })
+        })
+        .collect()
+}
+
+// 查找正文中的引用，跳过代码块和行内代码
+fn find_citations(content: &str) -> Vec<CiteGroup> {
+    let fenced = markdown::fenced_blocks(content);
+    let mut groups = Vec::new();
+    let mut offset = 0;
+
+    for (i, line) in content.split_inclusive('\n').enumerate() {
+        let in_fence = fenced.iter().any(|b| i >= b.start_line && i <= b.end_line);
+        let bytes = line.as_bytes();
+        let mut in_code = false;
+        let mut pos = 0;
+        while !in_fence && pos < bytes.len() {
+            match bytes[pos] {
+                b'`' => in_code = !in_code,
+                b'[' if !in_code && line[pos + 1..].starts_with('@') => {
+                    if let Some(close) = line[pos..].find(']') {
+                        if let Some(items) = parse_group(&line[pos + 1..pos + close]) {
+                            groups.push(CiteGroup {
+                                start: offset + pos,
+                                end: offset + pos + close + 1,
+                                items,
+                            });
+                            pos += close + 1;
+                            continue;
+                        }
+                    }
+                }
+                _ => {}
+            }
+            pos += 1;
+        }
+        offset += line.len();
+    }
+
+    groups
+}
+
+// 正文中引用到的所有 key
+pub fn cited_keys(content: &str) -> Vec<String> {
+    find_citations(content)
+        .into_iter()
+        .flat_map(|g| g.items.into_iter().map(|c| c.key))
+        .collect()
+}
+
+// BibTeX 文本中的条目 key
+pub fn bib_keys(bibtex: &str) -> Vec<String> {
+    match hayagriva::io::from_biblatex_str(bibtex) {
+        Ok(library) => library.keys().map(|k| k.to_string()).collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+// "p. 12" / "chap. 3" / "sec. 2"，没有前缀的按页码处理
+fn locator(text: &str) -> SpecificLocator<'_> {
+    let kinds = [
+        (["pp.", "p."], Locator::Page),
+        (["chap.", "ch."], Locator::Chapter),
+        (["sec.", "§"], Locator::Section),
+    ];
+    for (prefixes, kind) in kinds {
+        for prefix in prefixes {
+            if let Some(rest) = text.strip_prefix(prefix) {
+                return SpecificLocator(kind, LocatorPayload::Str(rest.trim()));
+            }
+        }
+    }
+    SpecificLocator(Locator::Page, LocatorPayload::Str(text))
+}
+
+fn to_html(children: &ElemChildren) -> String {
+    let mut html = String::new();
+    let _ = children.write_buf(&mut html, BufWriteFormat::Html);
+    html
+}
+
+// 把正文中的引用替换为渲染后的行内 HTML，并在文末追加参考文献；没有文献库或引用时原样返回
+pub fn resolve(
+    app: &AppHandle,
+    root: &Path,
+    content: &str,
+    style_override: &str,
+) -> Result<String, String> {
+    let groups = find_citations(content);
+    if groups.is_empty() {
+        return Ok(content.to_string());
+    }
+    let Some(library) = load_library(app, root)? else {
+        return Ok(content.to_string());
+    };
+    // 引用了文献库中不存在的条目时保留原文
+    let groups: Vec<&CiteGroup> = groups
+        .iter()
+        .filter(|g| g.items.iter().all(|c| library.get(&c.key).is_some()))
+        .collect();
+    if groups.is_empty() {
+        return Ok(content.to_string());
+    }
+
+    let config: CitationSettings = settings::load(app, "citations");
+    let style_name = if style_override.is_empty() {
+        &config.style
+    } else {
+        style_override
+    };
+    let style = load_style(root, style_name)?;
+    let locales = archive::locales();
+
+    let mut driver = BibliographyDriver::new();
+    for group in &groups {
+        let items = group
+            .items
+            .iter()
+            .filter_map(|c| {
+                let entry = library.get(&c.key)?;
+                Some(CitationItem::with_locator(
+                    entry,
+                    c.locator.as_deref().map(locator),
+                ))
+            })
+            .collect();
+        driver.citation(CitationRequest::from_items(items, &style, &locales));
+    }
+    let rendered = driver.finish(BibliographyRequest::new(&style, None, &locales));
+
+    let mut output = String::with_capacity(content.len());
+    let mut last = 0;
+    for (group, citation) in groups.iter().zip(&rendered.citations) {
+        output.push_str(&content[last..group.start]);
+        output.push_str(&format!(
+            "<span class=\"citation\">{}</span>",
+            to_html(&citation.citation)
+        ));
+        last = group.end;
+    }
+    output.push_str(&content[last..]);
+
+    if let Some(bibliography) = rendered.bibliography {
+        output.push_str("\n\n<section class=\"bibliography\">\n<h2>参考文献</h2>\n");
+        for item in bibliography.items {
+            let mut first = String::new();
+            if let Some(field) = item.first_field {
+                let _ = field.write_buf(&mut first, BufWriteFormat::Html);
+                first = format!("<span class=\"csl-left\">{}</span> ", first);
+            }
+            output.push_str(&format!(
+                "<div class=\"csl-entry\" id=\"ref-{}\">{}{}</div>\n",
+                markdown::escape_html(&item.key),
+                first,
+                to_html(&item.content)
+            ));
+        }
+        output.push_str("</section>\n");
+    }
+
+    Ok(output)
+}
+
+// 供插入引用的选择器使用，所有关键词都要命中 key、标题或作者
+#[tauri::command]
+pub fn search_citations(
+    app: AppHandle,
+    workspace: String,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<CitationEntry>, MobiError> {
+    let root = PathBuf::from(&workspace);
+    let Some(library) = load_library(&app, &root)? else {
+        return Ok(Vec::new());
+    };
+    let terms: Vec<String> = query.split_whitespace().map(|t| t.to_lowercase()).collect();
+
+    let mut entries = Vec::new();
+    for entry in library.iter() {
+        let title = entry.title().map(|t| t.to_string()).unwrap_or_default();
+        let authors: Vec<String> = entry
+            .authors()
+            .unwrap_or_default()
+            .iter()
+            .map(|p| p.name_first(false, false))
+            .collect();
+        let haystack = format!("{} {} {}", entry.key(), title, authors.join(" ")).to_lowercase();
+        if terms.iter().all(|t| haystack.contains(t.as_str())) {
+            entries.push(CitationEntry {
+                key: entry.key().to_string(),
+                title,
+                authors,
+                year: entry.date().map(|d| d.year),
+            });
+        }
+        if entries.len() >= limit.unwrap_or(50) {
+            break;
+        }
+    }
+    Ok(entries)
+}
+
+#[tauri::command]
+pub fn list_citation_styles() -> Vec<CitationStyle> {
+    ArchivedStyle::all()
+        .iter()
+        .map(|style| CitationStyle {
+            name: style.names()[0].to_string(),
+            title: style.display_name().to_string(),
+        })
+        .collect()
+}
@@ -0,0 +1,520 @@
+// 命令行子命令：不打开窗口，直接调用导出、检查和搜索模块，便于在脚本和 CI 中使用
+// 仍会初始化 Tauri 以读取设置，Linux 下需要图形环境（无头机器上可用 xvfb-run）
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use pulldown_cmark::{Event, Parser, Tag};
+use tauri::AppHandle;
+
+use crate::export::feed;
+use crate::export::paged::PageSetup;
+use crate::export::themes::ThemeTarget;
+use crate::export::{self, bundle, crossref, presets};
+use crate::{citations, encryption, footnotes, markdown, preview, semantic, workspace};
+
+const USAGE: &str = "用法:
+  mobi convert <文件.md> [--to html|pdf] [-o <输出文件>] [--workspace <目录>] [--code-theme <主题>]
+               [--page-size <A4|Letter|...>] [--break-before <0|1|2>]
+               [--user-password <口令>] [--owner-password <口令>] [--allow <print,copy|none>]
+  mobi export-site <工作区> <输出目录> [--preset <导出预设>] [--base-url <站点地址>]
+  mobi lint <文件或目录>
+  mobi search <工作区> <查询> [-k <数量>]";
+
+const COMMANDS: [&str; 4] = ["convert", "export-site", "lint", "search"];
+
+struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, String>,
+}
+
+impl Args {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut positional = Vec::new();
+        let mut options = HashMap::new();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            match arg.strip_prefix("--").or_else(|| arg.strip_prefix('-')) {
+                Some(name) if !name.is_empty() => {
+                    let value = iter.next().ok_or_else(|| format!("{} 缺少参数值", arg))?;
+                    options.insert(name.to_string(), value.clone());
+                }
+                _ => positional.push(arg.clone()),
+            }
+        }
+        Ok(Args {
+            positional,
+            options,
+        })
+    }
+
+    fn positional(&self, index: usize, name: &str) -> Result<&str, String> {
+        self.positional
+            .get(index)
+            .map(String::as_str)
+            .ok_or_else(|| format!("缺少{}\n\n{}", name, USAGE))
+    }
+
+    fn option(&self, names: &[&str]) -> Option<&str> {
+        names
+            .iter()
+            .find_map(|n| self.options.get(*n))
+            .map(String::as_str)
+    }
+}
+
+// 第一个参数是子命令时执行并返回退出码，否则返回 None 照常启动界面
+pub fn run(args: Vec<String>, context: fn() -> tauri::Context) -> Option<i32> {
+    let command = args.first()?.as_str();
+    if matches!(command, "help" | "--help" | "-h") {
+        println!("{}", USAGE);
+        return Some(0);
+    }
+    if !COMMANDS.contains(&command) {
+        return None;
+    }
+
+    let result = Args::parse(&args[1..]).and_then(|args| {
+        // 不创建配置里的窗口，也不进入事件循环
+        let mut context = context();
+        context.config_mut().app.windows.clear();
+        let app = tauri::Builder::default()
+            .build(context)
+            .map_err(|e| format!("初始化失败: {}", e))?;
+        let app = app.handle().clone();
+        #[cfg(target_os = "macos")]
+        let _ = app.set_activation_policy(tauri::ActivationPolicy::Accessory);
+
+        match command {
+            "convert" => convert(&app, &args),
+            "export-site" => export_site(&app, &args),
+            "lint" => lint(&app, &args),
+            _ => search(&app, &args),
+        }
+    });
+    match result {
+        Ok(code) => Some(code),
+        Err(e) => {
+            eprintln!("mobi: {}", e);
+            Some(2)
+        }
+    }
+}
+
+fn convert(app: &AppHandle, args: &Args) -> Result<i32, String> {
+    let input = PathBuf::from(args.positional(0, "输入文件")?);
+    let format = args.option(&["to", "t"]).unwrap_or("html");
+    if format != "html" && format != "pdf" {
+        return Err(format!("不支持导出为 {}，可用格式: html、pdf", format));
+    }
+    let output = args
+        .option(&["o", "output"])
+        .map(PathBuf::from)
+        .unwrap_or_else(|| input.with_extension(format));
+    // 与分享包相同：设置的 pdf.command 或自动找到的浏览器、WeasyPrint、wkhtmltopdf
+    let engine = match format {
+        "pdf" => Some(bundle::pdf_engine(app).ok_or(
+            "没有找到 PDF 引擎（Chrome、Edge、WeasyPrint 或 wkhtmltopdf），可在设置的 pdf.command 中指定",
+        )?),
+        _ => None,
+    };
+
+    // PDF 的口令和权限，--allow 列出允许的操作，未给出时不限制
+    let mut protection = bundle::PdfOptions::default();
+    if let Some(password) = args.option(&["user-password"]) {
+        protection.user_password = password.to_string();
+    }
+    if let Some(password) = args.option(&["owner-password"]) {
+        protection.owner_password = password.to_string();
+    }
+    if let Some(allow) = args.option(&["allow"]) {
+        let allowed: Vec<&str> = allow.split(',').map(str::trim).collect();
+        if let Some(unknown) = allowed
+            .iter()
+            .find(|a| !matches!(**a, "print" | "copy" | "none"))
+        {
+            return Err(format!("未知的权限: {}，可用: print、copy、none", unknown));
+        }
+        protection.allow_print = allowed.contains(&"print");
+        protection.allow_copy = allowed.contains(&"copy");
+    }
+
+    let workspace = args.option(&["workspace", "w"]).map(Path::new);
+    let (content, mut options) = preview::read_note(&input, workspace)?;
+    if let Some(theme) = args.option(&["code-theme"]) {
+        options.code_theme = theme.to_string();
+    }
+    // 指定了分页参数时输出分页样式（页眉页脚、页码、书签），便于再交给 PDF 引擎
+    let page_size = args.option(&["page-size"]);
+    let break_before = args.option(&["break-before"]);
+    if page_size.is_some() || break_before.is_some() {
+        let mut setup = PageSetup::default();
+        if let Some(size) = page_size {
+            setup.page_size = size.to_string();
+        }
+        if let Some(level) = break_before {
+            setup.break_before = level
+                .parse()
+                .map_err(|_| format!("无效的分页级别: {}", level))?;
+        }
+        options.paged = Some(setup);
+    }
+    let Some(engine) = engine else {
+        tauri::async_runtime::block_on(export::export_html(
+            app.clone(),
+            content,
+            output.to_string_lossy().to_string(),
+            Some(options),
+        ))?;
+        println!("{}", output.display());
+        return Ok(0);
+    };
+
+    // PDF：先输出带分页样式、使用 PDF 导出主题的 HTML，交给引擎打印后删除
+    options.paged.get_or_insert_with(PageSetup::default);
+    options.target = ThemeTarget::Pdf;
+    let stem = output
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    // 浏览器把文件路径当作地址，用绝对路径
+    let output = std::path::absolute(&output).map_err(|e| format!("无效的输出路径: {}", e))?;
+    let print = output.with_file_name(format!(".{}.print.html", stem));
+    tauri::async_runtime::block_on(export::export_html(
+        app.clone(),
+        content,
+        print.to_string_lossy().to_string(),
+        Some(options),
+    ))?;
+    let result = bundle::render_pdf(&engine, &print, &output);
+    let _ = fs::remove_file(&print);
+    result?;
+    bundle::protect_pdf(bundle::qpdf(app).as_deref(), &output, &protection)?;
+    println!("{}", output.display());
+    Ok(0)
+}
+
+// 文中指向本地文件的链接和图片：(行号, 解码后的路径)，外部链接和页内锚点不算
+fn local_links(content: &str) -> Vec<(usize, String)> {
+    let mut links = Vec::new();
+    for (event, range) in Parser::new_ext(content, markdown::parser_options()).into_offset_iter() {
+        let dest = match event {
+            Event::Start(Tag::Link { dest_url, .. })
+            | Event::Start(Tag::Image { dest_url, .. }) => dest_url,
+            _ => continue,
+        };
+        if dest.is_empty() || dest.starts_with('#') || dest.contains(':') {
+            continue;
+        }
+        let path = dest.split(['#', '?']).next().unwrap_or_default();
+        let path = urlencoding::decode(path)
+            .map(|p| p.to_string())
+            .unwrap_or_else(|_| path.to_string());
+        let line = content[..range.start].matches('\n').count();
+        links.push((line, path));
+    }
+    links
+}
+
+fn is_note_link(href: &str) -> bool {
+    let path = href.split(['#', '?']).next().unwrap_or_default();
+    !href.contains(':') && (path.ends_with(".md") || path.ends_with(".markdown"))
+}
+
+// 站点内的笔记链接改指向导出的 .html 页面
+fn rewrite_note_links(html: &str) -> String {
+    let mut output = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find("href=\"") {
+        let (head, tail) = rest.split_at(start + 6);
+        output.push_str(head);
+        let end = tail.find('"').unwrap_or(tail.len());
+        let href = &tail[..end];
+        if is_note_link(href) {
+            let split = href.find(['#', '?']).unwrap_or(href.len());
+            let (path, suffix) = href.split_at(split);
+            let stem = path
+                .strip_suffix(".markdown")
+                .or_else(|| path.strip_suffix(".md"))
+                .unwrap_or(path);
+            output.push_str(&format!("{}.html{}", stem, suffix));
+        } else {
+            output.push_str(href);
+        }
+        rest = &tail[end..];
+    }
+    output.push_str(rest);
+    output
+}
+
+fn site_page(relative: &str) -> String {
+    let stem = relative
+        .strip_suffix(".markdown")
+        .or_else(|| relative.strip_suffix(".md"))
+        .unwrap_or(relative);
+    format!("{}.html", stem)
+}
+
+// 把工作区导出为静态站点：每篇笔记一个页面，笔记间链接改为页面链接，引用到的本地资源一并复制
+// 指定导出预设时使用其中的代码主题、引用样式和订阅设置；--base-url 覆盖站点地址，没有预设时同时生成订阅和站点地图
+fn export_site(app: &AppHandle, args: &Args) -> Result<i32, String> {
+    let root = PathBuf::from(args.positional(0, "工作区目录")?);
+    let out = PathBuf::from(args.positional(1, "输出目录")?);
+    let root = fs::canonicalize(&root).map_err(|e| format!("工作区不存在: {}", e))?;
+    fs::create_dir_all(&out).map_err(|e| format!("创建输出目录失败: {}", e))?;
+    let preset = args
+        .option(&["preset", "p"])
+        .map(|name| presets::find(app, name))
+        .transpose()?;
+    let mut feed_settings = preset.as_ref().map(|p| p.feed.clone()).unwrap_or_default();
+    if let Some(base_url) = args.option(&["base-url"]) {
+        feed_settings.base_url = base_url.to_string();
+        if preset.is_none() {
+            feed_settings.feed = true;
+            feed_settings.sitemap = true;
+        }
+    }
+    let site_title = root
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let mut pages = Vec::new();
+    let mut site_pages = Vec::new();
+    let mut assets: BTreeSet<PathBuf> = BTreeSet::new();
+    for file in workspace::note_files(&root) {
+        let relative = workspace::relative_path(&root, &file);
+        let (content, mut options) = match preview::read_note(&file, Some(&root)) {
+            Ok(note) => note,
+            Err(e) => {
+                eprintln!("跳过 {}: {}", relative, e);
+                continue;
+            }
+        };
+        let dir = file.parent().unwrap_or(&root);
+        for (_, link) in local_links(&content) {
+            let target = dir.join(&link);
+            if !is_note_link(&link) && target.is_file() {
+                if let Ok(target) = fs::canonicalize(&target) {
+                    if target.starts_with(&root) {
+                        assets.insert(target);
+                    }
+                }
+            }
+        }
+
+        if let Some(preset) = &preset {
+            options.code_theme = preset.code_theme.clone();
+            options.theme = preset.theme.clone();
+            options.citation_style = preset.citation_style.clone();
+            options.filters = preset.filters.clone();
+        }
+        let title = options.title.clone();
+        let page = site_page(&relative);
+        site_pages.push(feed::page_meta(
+            &content,
+            &page,
+            &title,
+            workspace::modified_secs(&file),
+        ));
+        let html = tauri::async_runtime::block_on(export::render_html(
+            app.clone(),
+            content,
+            Some(options),
+        ))?;
+        let target = out.join(&page);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("创建目录失败: {}", e))?;
+        }
+        fs::write(&target, rewrite_note_links(&html))
+            .map_err(|e| format!("写入 {} 失败: {}", page, e))?;
+        pages.push((page, title));
+    }
+
+    for asset in &assets {
+        let target = out.join(asset.strip_prefix(&root).unwrap_or(asset));
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("创建目录失败: {}", e))?;
+        }
+        fs::copy(asset, &target).map_err(|e| format!("复制资源失败: {}", e))?;
+    }
+
+    // 没有 index 笔记时生成一个页面列表
+    if !pages.iter().any(|(page, _)| page == "index.html") {
+        let list: String = pages
+            .iter()
+            .map(|(page, title)| {
+                format!(
+                    "- [{}](<{}>)\n",
+                    title.replace(['[', ']'], ""),
+                    page.replace('>', "%3E")
+                )
+            })
+            .collect();
+        let options = export::ExportOptions {
+            title: site_title.clone(),
+            ..Default::default()
+        };
+        let html = export::render_document(&list, &options);
+        fs::write(out.join("index.html"), html).map_err(|e| format!("写入索引页失败: {}", e))?;
+        site_pages.push(feed::page_meta(
+            "",
+            "index.html",
+            &site_title,
+            chrono::Utc::now().timestamp(),
+        ));
+    }
+    for name in feed::write(&out, &feed_settings, &site_title, &site_pages)? {
+        println!("已生成 {}", name);
+    }
+
+    println!(
+        "已导出 {} 个页面、{} 个资源到 {}",
+        pages.len(),
+        assets.len(),
+        out.display()
+    );
+    Ok(0)
+}
+
+struct Issue {
+    // 从 0 开始，None 表示整篇文档
+    line: Option<usize>,
+    message: String,
+}
+
+fn issue(line: Option<usize>, message: String) -> Issue {
+    Issue { line, message }
+}
+
+fn lint_note(content: &str, dir: &Path, bib_keys: Option<&HashSet<String>>) -> Vec<Issue> {
+    let mut issues = Vec::new();
+
+    let crossrefs = crossref::check_crossrefs(content.to_string());
+    for unresolved in crossrefs.unresolved {
+        issues.push(issue(
+            Some(unresolved.line),
+            format!("找不到交叉引用目标 @{}", unresolved.id),
+        ));
+    }
+    for id in crossrefs.duplicates {
+        issues.push(issue(None, format!("编号目标 {} 重复定义", id)));
+    }
+
+    let notes = footnotes::report(content);
+    for label in &notes.missing {
+        let line = notes
+            .references
+            .iter()
+            .find(|r| &r.label == label)
+            .map(|r| r.line);
+        issues.push(issue(line, format!("脚注 [^{}] 没有定义", label)));
+    }
+    for label in &notes.orphaned {
+        let line = notes
+            .definitions
+            .iter()
+            .find(|d| &d.label == label)
+            .map(|d| d.line);
+        issues.push(issue(line, format!("脚注 [^{}] 没有被引用", label)));
+    }
+    for label in &notes.duplicates {
+        issues.push(issue(None, format!("脚注 [^{}] 重复定义", label)));
+    }
+
+    for (line, link) in local_links(content) {
+        if !dir.join(&link).exists() {
+            issues.push(issue(Some(line), format!("链接目标不存在: {}", link)));
+        }
+    }
+
+    if let Some(keys) = bib_keys {
+        let mut reported = HashSet::new();
+        for key in citations::cited_keys(content) {
+            if !keys.contains(&key) && reported.insert(key.clone()) {
+                issues.push(issue(None, format!("文献库中没有 @{}", key)));
+            }
+        }
+    }
+
+    issues.sort_by_key(|i| i.line);
+    issues
+}
+
+// 检查交叉引用、脚注、本地链接和文献引用，有问题时退出码为 1
+fn lint(app: &AppHandle, args: &Args) -> Result<i32, String> {
+    let target = PathBuf::from(args.positional(0, "文件或目录")?);
+    let (root, files) = if target.is_dir() {
+        let files = workspace::note_files(&target);
+        (target, files)
+    } else if target.is_file() {
+        let root = target.parent().unwrap_or(Path::new(".")).to_path_buf();
+        (root, vec![target])
+    } else {
+        return Err(format!("{} 不存在", target.display()));
+    };
+
+    // 只有 BibTeX 文献库能取到 key 列表
+    let bib_keys: Option<HashSet<String>> = citations::bibliography_path(app, &root)
+        .filter(|p| p.extension().is_some_and(|ext| ext == "bib"))
+        .and_then(|p| fs::read_to_string(p).ok())
+        .map(|bib| citations::bib_keys(&bib).into_iter().collect());
+
+    let mut total = 0;
+    for file in &files {
+        let Ok(content) = fs::read_to_string(file) else {
+            continue;
+        };
+        if encryption::is_envelope(&content) {
+            continue;
+        }
+        let dir = file.parent().unwrap_or(&root);
+        let display = workspace::relative_path(&root, file);
+        for issue in lint_note(&content, dir, bib_keys.as_ref()) {
+            match issue.line {
+                Some(line) => println!("{}:{}: {}", display, line + 1, issue.message),
+                None => println!("{}: {}", display, issue.message),
+            }
+            total += 1;
+        }
+    }
+
+    if total > 0 {
+        eprintln!("检查了 {} 篇笔记，发现 {} 个问题", files.len(), total);
+        Ok(1)
+    } else {
+        eprintln!("检查了 {} 篇笔记，没有发现问题", files.len());
+        Ok(0)
+    }
+}
+
+// 语义搜索，搜索前先增量更新索引（需要本地 Ollama）
+fn search(app: &AppHandle, args: &Args) -> Result<i32, String> {
+    let root = args.positional(0, "工作区目录")?.to_string();
+    let query = args.positional(1, "查询内容")?.to_string();
+    let k = match args.option(&["k", "limit"]) {
+        Some(k) => Some(k.parse().map_err(|_| format!("无效的数量: {}", k))?),
+        None => None,
+    };
+
+    let hits = tauri::async_runtime::block_on(async {
+        semantic::build_semantic_index(app.clone(), root.clone()).await?;
+        semantic::semantic_search(app.clone(), root.clone(), query, k).await
+    })?;
+    for hit in hits {
+        let heading = if hit.heading.is_empty() {
+            String::new()
+        } else {
+            format!(" › {}", hit.heading)
+        };
+        println!(
+            "{:.3}  {}{}",
+            hit.score,
+            workspace::relative_path(Path::new(&root), Path::new(&hit.path)),
+            heading
+        );
+        println!("       {}", hit.snippet);
+    }
+    Ok(0)
+}
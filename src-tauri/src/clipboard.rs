@@ -0,0 +1,246 @@
+// 剪贴板历史：启用后记录本次运行期间在编辑器中复制过的文字，便于找回之前复制的片段；只保存在内存中，关闭应用即清空
+// 记录其他程序中的复制需要另外开启 captureSystem，此时在后台轮询系统剪贴板
+// 条数和总字数有上限，超出时丢弃最早的记录；疑似密码、密钥的内容不记录
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+use crate::error::{MobiError, MutexExt};
+use crate::settings;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+// 每隔多少次轮询重新读取设置
+const SETTINGS_EVERY: u32 = 5;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct ClipboardSettings {
+    // 默认关闭，需要用户主动开启
+    enabled: bool,
+    // 同时记录在其他程序中复制的内容，默认关闭
+    capture_system: bool,
+    max_items: usize,
+    // 单条超过此字数的内容不记录
+    max_item_chars: usize,
+    // 所有记录的总字数上限
+    max_total_chars: usize,
+}
+
+impl Default for ClipboardSettings {
+    fn default() -> Self {
+        ClipboardSettings {
+            enabled: false,
+            capture_system: false,
+            max_items: 50,
+            max_item_chars: 20_000,
+            max_total_chars: 200_000,
+        }
+    }
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipItem {
+    id: u64,
+    text: String,
+    // 秒级时间戳
+    copied_at: i64,
+    chars: usize,
+}
+
+#[derive(Default)]
+struct History {
+    // 最新的在前
+    items: VecDeque<ClipItem>,
+    next_id: u64,
+    // 最近一次读到的剪贴板内容，用于判断是否有新的复制
+    last: Option<String>,
+}
+
+#[derive(Default)]
+pub struct ClipboardState {
+    history: Mutex<History>,
+}
+
+// 以这些前缀开头的通常是服务的访问令牌
+const TOKEN_PREFIXES: &[&str] = &[
+    "sk-",
+    "ghp_",
+    "gho_",
+    "ghs_",
+    "github_pat_",
+    "glpat-",
+    "xoxb-",
+    "xoxp-",
+    "AKIA",
+];
+
+// 没有空白、像是随机生成的一串字符：混合了三类以上的字符，或是很长的字母数字串
+fn looks_secret(text: &str) -> bool {
+    let text = text.trim();
+    if text.chars().any(char::is_whitespace) {
+        return false;
+    }
+    if TOKEN_PREFIXES.iter().any(|p| text.starts_with(p)) && text.len() >= 16 {
+        return true;
+    }
+    // 网址、路径和邮箱不算
+    if text.contains("://") || text.starts_with('/') || text.starts_with('~') || text.contains('@')
+    {
+        return false;
+    }
+    let len = text.chars().count();
+    let lower = text.chars().any(|c| c.is_ascii_lowercase());
+    let upper = text.chars().any(|c| c.is_ascii_uppercase());
+    let digit = text.chars().any(|c| c.is_ascii_digit());
+    let symbol = text.chars().any(|c| c.is_ascii_punctuation());
+    let classes = [lower, upper, digit, symbol].iter().filter(|&&b| b).count();
+    if (8..=64).contains(&len) && classes >= 3 {
+        return true;
+    }
+    len >= 32
+        && digit
+        && (lower || upper)
+        && text
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_+/=".contains(c))
+}
+
+impl History {
+    fn record(&mut self, text: String, config: &ClipboardSettings) -> bool {
+        let chars = text.chars().count();
+        if text.trim().is_empty() || chars > config.max_item_chars || looks_secret(&text) {
+            return false;
+        }
+        // 重复复制同一段内容时移到最前面
+        self.items.retain(|item| item.text != text);
+        self.next_id += 1;
+        self.items.push_front(ClipItem {
+            id: self.next_id,
+            text,
+            copied_at: chrono::Local::now().timestamp(),
+            chars,
+        });
+        self.prune(config);
+        true
+    }
+
+    fn prune(&mut self, config: &ClipboardSettings) {
+        self.items.truncate(config.max_items);
+        let mut total = 0;
+        let keep = self
+            .items
+            .iter()
+            .take_while(|item| {
+                total += item.chars;
+                total <= config.max_total_chars
+            })
+            .count();
+        self.items.truncate(keep.max(1));
+    }
+}
+
+// 启动时调用，开启 captureSystem 后轮询系统剪贴板；否则只按间隔检查设置
+pub fn start(app: AppHandle) {
+    thread::spawn(move || {
+        let mut config = ClipboardSettings::default();
+        let mut ticks = 0;
+        loop {
+            if ticks % SETTINGS_EVERY == 0 {
+                config = settings::load(&app, "clipboard");
+            }
+            ticks = ticks.wrapping_add(1);
+            thread::sleep(POLL_INTERVAL);
+
+            let state = app.state::<ClipboardState>();
+            let mut history = state.history.locked();
+            if !config.enabled {
+                // 关闭后立即清空已记录的内容
+                if history.last.is_some() || !history.items.is_empty() {
+                    *history = History::default();
+                    let _ = app.emit("clipboard-history-changed", ());
+                }
+                continue;
+            }
+            if !config.capture_system {
+                // 关闭后重新开启时，剪贴板里已有的内容不记录
+                history.last = None;
+                continue;
+            }
+            let Ok(text) = app.clipboard().read_text() else {
+                continue;
+            };
+            match &history.last {
+                Some(last) if *last == text => continue,
+                // 刚开启时剪贴板里已有的内容不记录
+                None => {
+                    history.last = Some(text);
+                    continue;
+                }
+                Some(_) => {}
+            }
+            history.last = Some(text.clone());
+            if history.record(text, &config) {
+                let _ = app.emit("clipboard-history-changed", ());
+            }
+        }
+    });
+}
+
+// 编辑器中复制或剪切时由前端调用
+#[tauri::command]
+pub fn record_clipboard_copy(app: AppHandle, state: State<ClipboardState>, text: String) {
+    let config: ClipboardSettings = settings::load(&app, "clipboard");
+    if !config.enabled {
+        return;
+    }
+    let mut history = state.history.locked();
+    // 轮询时不再把它当作新的复制
+    history.last = Some(text.clone());
+    if history.record(text, &config) {
+        let _ = app.emit("clipboard-history-changed", ());
+    }
+}
+
+#[tauri::command]
+pub fn get_clipboard_history(state: State<ClipboardState>) -> Vec<ClipItem> {
+    state.history.locked().items.iter().cloned().collect()
+}
+
+// 把一条历史放回剪贴板并返回其内容，由前端插入到光标处
+#[tauri::command]
+pub fn paste_history_item(
+    app: AppHandle,
+    state: State<ClipboardState>,
+    id: u64,
+) -> Result<String, MobiError> {
+    let mut history = state.history.locked();
+    let index = history
+        .items
+        .iter()
+        .position(|item| item.id == id)
+        .ok_or("剪贴板历史中没有这一条")?;
+    let text = history.items[index].text.clone();
+    app.clipboard()
+        .write_text(text.clone())
+        .map_err(|e| format!("写入剪贴板失败: {}", e))?;
+    // 写入成功后才移到最前面
+    if let Some(item) = history.items.remove(index) {
+        history.items.push_front(item);
+    }
+    // 轮询时不再把它当作新的复制
+    history.last = Some(text.clone());
+    Ok(text)
+}
+
+#[tauri::command]
+pub fn clear_clipboard_history(state: State<ClipboardState>) {
+    let mut history = state.history.locked();
+    history.items.clear();
+}
@@ -0,0 +1,577 @@
+// 协同编辑：基于 yrs（与 Yjs 兼容）的 CRDT 文档，通过 WebSocket 在局域网内同步
+// 主持方开放端口并把合并后的内容写回磁盘；加入方连接主持方地址，编辑以 CRDT 增量互相传递，光标等状态通过 awareness 广播
+// 线路格式是 y-sync 协议，y-websocket 等 Yjs 客户端也能直接加入
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{ErrorKind, Read, Write};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, State};
+use tungstenite::handshake::server::{Callback, ErrorResponse, Request, Response};
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{Message as WsMessage, WebSocket};
+use yrs::sync::{Awareness, AwarenessUpdate, Message, SyncMessage};
+use yrs::updates::decoder::Decode;
+use yrs::updates::encoder::Encode;
+use yrs::{Doc, GetString, OffsetKind, Options, ReadTxn, Text, TextRef, Transact, Update};
+
+use crate::error::{MobiError, MutexExt};
+use crate::{encryption, preview, share, workspace};
+
+// 读超时，也是发送队列的最长等待时间
+const POLL: Duration = Duration::from_millis(30);
+// 合并后的内容写回磁盘的间隔
+const PERSIST_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CollabSessionInfo {
+    id: String,
+    host: bool,
+    // 加入用的地址，ws://ip:port/令牌
+    url: String,
+    path: Option<String>,
+    content: String,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CollabUpdate {
+    session_id: String,
+    content: String,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CollabPeer {
+    client_id: u64,
+    state: Value,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CollabPresence {
+    session_id: String,
+    peers: Vec<CollabPeer>,
+}
+
+struct Peer {
+    tx: mpsc::Sender<Vec<u8>>,
+    // 经由这个连接出现的 awareness 客户端，断开时清除它们的状态
+    clients: HashSet<u64>,
+}
+
+struct Session {
+    id: String,
+    host: bool,
+    url: String,
+    // 合并结果写入的文件；加入方没有指定时不落盘
+    path: Option<PathBuf>,
+    awareness: Awareness,
+    text: TextRef,
+    peers: Mutex<HashMap<u64, Peer>>,
+    next_peer: AtomicU64,
+    closed: AtomicBool,
+    dirty: AtomicBool,
+    synced: AtomicBool,
+}
+
+#[derive(Default)]
+pub struct CollabState {
+    sessions: Mutex<HashMap<String, Arc<Session>>>,
+}
+
+impl Session {
+    fn new(id: String, host: bool, url: String, path: Option<PathBuf>, content: &str) -> Self {
+        // 前端的位置都是 UTF-16 下标
+        let doc = Doc::with_options(Options {
+            offset_kind: OffsetKind::Utf16,
+            ..Default::default()
+        });
+        let text = doc.get_or_insert_text("content");
+        if !content.is_empty() {
+            text.insert(&mut doc.transact_mut(), 0, content);
+        }
+        Session {
+            id,
+            host,
+            url,
+            path,
+            awareness: Awareness::new(doc),
+            text,
+            peers: Mutex::new(HashMap::new()),
+            next_peer: AtomicU64::new(1),
+            closed: AtomicBool::new(false),
+            dirty: AtomicBool::new(false),
+            synced: AtomicBool::new(host),
+        }
+    }
+
+    fn content(&self) -> String {
+        self.text.get_string(&self.awareness.doc().transact())
+    }
+
+    fn info(&self) -> CollabSessionInfo {
+        CollabSessionInfo {
+            id: self.id.clone(),
+            host: self.host,
+            url: self.url.clone(),
+            path: self.path.as_ref().map(|p| p.to_string_lossy().to_string()),
+            content: self.content(),
+        }
+    }
+
+    // 发给除 except 以外的所有连接
+    fn broadcast(&self, message: &Message, except: Option<u64>) {
+        let bytes = message.encode_v1();
+        self.peers
+            .locked()
+            .iter()
+            .filter(|(id, _)| Some(**id) != except)
+            .for_each(|(_, peer)| {
+                let _ = peer.tx.send(bytes.clone());
+            });
+    }
+
+    fn send(&self, peer: u64, message: &Message) {
+        if let Some(peer) = self.peers.locked().get(&peer) {
+            let _ = peer.tx.send(message.encode_v1());
+        }
+    }
+
+    // 新连接先交换状态向量和 awareness
+    fn greet(&self, peer: u64) {
+        let state_vector = self.awareness.doc().transact().state_vector();
+        self.send(peer, &Message::Sync(SyncMessage::SyncStep1(state_vector)));
+        if let Ok(update) = self.awareness.update() {
+            self.send(peer, &Message::Awareness(update));
+        }
+    }
+
+    fn emit_presence(&self, app: &AppHandle) {
+        let local = self.awareness.client_id();
+        let peers = self
+            .awareness
+            .iter()
+            .filter(|(id, _)| *id != local)
+            .filter_map(|(client_id, state)| {
+                let state = serde_json::from_str(state.data.as_deref()?).ok()?;
+                Some(CollabPeer { client_id, state })
+            })
+            .collect();
+        let _ = app.emit(
+            "collab-presence",
+            CollabPresence {
+                session_id: self.id.clone(),
+                peers,
+            },
+        );
+    }
+
+    fn apply_remote(&self, app: &AppHandle, from: u64, update: Vec<u8>) -> Result<(), String> {
+        let before = self.content();
+        {
+            let decoded = Update::decode_v1(&update).map_err(|e| e.to_string())?;
+            let mut txn = self.awareness.doc().transact_mut();
+            txn.apply_update(decoded).map_err(|e| e.to_string())?;
+        }
+        // 主持方把增量转发给其他加入方，重复收到的增量会被 CRDT 忽略
+        self.broadcast(&Message::Sync(SyncMessage::Update(update)), Some(from));
+        let content = self.content();
+        if content != before {
+            self.dirty.store(true, Ordering::SeqCst);
+            let _ = app.emit(
+                "collab-update",
+                CollabUpdate {
+                    session_id: self.id.clone(),
+                    content,
+                },
+            );
+        }
+        Ok(())
+    }
+
+    fn handle(&self, app: &AppHandle, from: u64, bytes: &[u8]) -> Result<(), String> {
+        match Message::decode_v1(bytes).map_err(|e| e.to_string())? {
+            Message::Sync(SyncMessage::SyncStep1(state_vector)) => {
+                let diff = self
+                    .awareness
+                    .doc()
+                    .transact()
+                    .encode_state_as_update_v1(&state_vector);
+                self.send(from, &Message::Sync(SyncMessage::SyncStep2(diff)));
+            }
+            Message::Sync(SyncMessage::SyncStep2(update)) => {
+                self.apply_remote(app, from, update)?;
+                self.synced.store(true, Ordering::SeqCst);
+            }
+            Message::Sync(SyncMessage::Update(update)) => self.apply_remote(app, from, update)?,
+            Message::Awareness(update) => {
+                if let Some(peer) = self.peers.locked().get_mut(&from) {
+                    peer.clients.extend(update.clients.keys().copied());
+                }
+                self.awareness
+                    .apply_update(update.clone())
+                    .map_err(|e| e.to_string())?;
+                self.broadcast(&Message::Awareness(update), Some(from));
+                self.emit_presence(app);
+            }
+            Message::AwarenessQuery => {
+                if let Ok(update) = self.awareness.update() {
+                    self.send(from, &Message::Awareness(update));
+                }
+            }
+            Message::Auth(_) | Message::Custom(..) => {}
+        }
+        Ok(())
+    }
+
+    fn add_peer(&self) -> (u64, mpsc::Receiver<Vec<u8>>) {
+        let id = self.next_peer.fetch_add(1, Ordering::SeqCst);
+        let (tx, rx) = mpsc::channel();
+        self.peers.locked().insert(
+            id,
+            Peer {
+                tx,
+                clients: HashSet::new(),
+            },
+        );
+        (id, rx)
+    }
+
+    fn remove_peer(&self, app: &AppHandle, id: u64) {
+        let Some(peer) = self.peers.locked().remove(&id) else {
+            return;
+        };
+        for client in &peer.clients {
+            self.awareness.remove_state(*client);
+        }
+        // 通知其他人这些客户端已离开（state 为 null）
+        let update = AwarenessUpdate {
+            clients: peer
+                .clients
+                .iter()
+                .filter_map(|client| {
+                    let (clock, _) = self.awareness.meta(*client)?;
+                    Some((
+                        *client,
+                        yrs::sync::awareness::AwarenessUpdateEntry {
+                            clock,
+                            json: "null".into(),
+                        },
+                    ))
+                })
+                .collect(),
+        };
+        if !update.clients.is_empty() {
+            self.broadcast(&Message::Awareness(update), None);
+        }
+        self.emit_presence(app);
+    }
+
+    fn persist(&self) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if self.dirty.swap(false, Ordering::SeqCst) {
+            fs::write(workspace::resolve_link(path), self.content())
+                .map_err(|e| format!("保存文件失败: {}", e))?;
+        }
+        Ok(())
+    }
+}
+
+fn is_timeout(err: &tungstenite::Error) -> bool {
+    matches!(err, tungstenite::Error::Io(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut))
+}
+
+// 单个连接的收发循环：读超时期间处理发送队列
+fn run_peer<S: Read + Write>(
+    app: &AppHandle,
+    session: &Session,
+    socket: &mut WebSocket<S>,
+    peer: u64,
+    rx: &mpsc::Receiver<Vec<u8>>,
+) {
+    session.greet(peer);
+    while !session.closed.load(Ordering::SeqCst) {
+        while let Ok(bytes) = rx.try_recv() {
+            if socket.send(WsMessage::binary(bytes)).is_err() {
+                return;
+            }
+        }
+        match socket.read() {
+            Ok(WsMessage::Binary(bytes)) => {
+                let _ = session.handle(app, peer, &bytes);
+            }
+            Ok(WsMessage::Close(_)) => return,
+            Ok(_) => {}
+            Err(e) if is_timeout(&e) => {}
+            Err(_) => return,
+        }
+    }
+    let _ = socket.close(None);
+    let _ = socket.flush();
+}
+
+// 握手时校验地址中的令牌，不对返回 404
+struct TokenCheck<'a>(&'a str);
+
+impl Callback for TokenCheck<'_> {
+    fn on_request(self, request: &Request, response: Response) -> Result<Response, ErrorResponse> {
+        if request.uri().path().trim_end_matches('/') == self.0 {
+            return Ok(response);
+        }
+        let mut denied = ErrorResponse::new(None);
+        *denied.status_mut() = tungstenite::http::StatusCode::NOT_FOUND;
+        Err(denied)
+    }
+}
+
+fn accept_peers(app: AppHandle, session: Arc<Session>, listener: TcpListener, token: String) {
+    thread::spawn(move || {
+        let _ = listener.set_nonblocking(true);
+        while !session.closed.load(Ordering::SeqCst) {
+            let stream = match listener.accept() {
+                Ok((stream, _)) => stream,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    thread::sleep(Duration::from_millis(100));
+                    continue;
+                }
+                Err(_) => break,
+            };
+            let app = app.clone();
+            let session = session.clone();
+            let expected = format!("/{}", token);
+            thread::spawn(move || {
+                let _ = stream.set_nonblocking(false);
+                let _ = stream.set_read_timeout(Some(POLL));
+                let Ok(mut socket) = tungstenite::accept_hdr(stream, TokenCheck(&expected)) else {
+                    return;
+                };
+                let (peer, rx) = session.add_peer();
+                run_peer(&app, &session, &mut socket, peer, &rx);
+                session.remove_peer(&app, peer);
+            });
+        }
+    });
+}
+
+fn start_persist(session: Arc<Session>) {
+    if session.path.is_none() {
+        return;
+    }
+    thread::spawn(move || {
+        while !session.closed.load(Ordering::SeqCst) {
+            thread::sleep(PERSIST_INTERVAL);
+            let _ = session.persist();
+        }
+    });
+}
+
+fn get_session(state: &CollabState, id: &str) -> Result<Arc<Session>, String> {
+    state
+        .sessions
+        .locked()
+        .get(id)
+        .cloned()
+        .ok_or_else(|| "协作会话不存在或已结束".into())
+}
+
+// 以当前文件内容开一个协作会话，port 留空时由系统分配
+#[tauri::command]
+pub fn host_collab_session(
+    app: AppHandle,
+    state: State<CollabState>,
+    path: String,
+    port: Option<u16>,
+) -> Result<CollabSessionInfo, MobiError> {
+    let content = fs::read_to_string(&path).map_err(|e| format!("读取文件失败: {}", e))?;
+    // 会话内容是明文，且会定期写回文件
+    if encryption::is_envelope(&content) {
+        return Err(MobiError::InvalidInput("加密笔记不能发起协作".into()));
+    }
+    let ip = preview::lan_address().ok_or("未连接局域网")?;
+    let listener = TcpListener::bind(("0.0.0.0", port.unwrap_or(0)))
+        .map_err(|e| format!("启动协作服务失败: {}", e))?;
+    let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+
+    let token = share::random_token();
+    let session = Arc::new(Session::new(
+        token.clone(),
+        true,
+        format!("ws://{}:{}/{}", ip, port, token),
+        Some(PathBuf::from(&path)),
+        &content,
+    ));
+    accept_peers(app, session.clone(), listener, token.clone());
+    start_persist(session.clone());
+
+    let info = session.info();
+    state.sessions.locked().insert(token, session);
+    Ok(info)
+}
+
+// 加入主持方的会话，等待首次同步完成后返回文档内容；path 指定时合并结果也写入本地文件
+#[tauri::command]
+pub async fn join_collab_session(
+    app: AppHandle,
+    state: State<'_, CollabState>,
+    url: String,
+    path: Option<String>,
+) -> Result<CollabSessionInfo, MobiError> {
+    let handle = app.clone();
+    let session = tauri::async_runtime::spawn_blocking(move || join(handle, url, path))
+        .await
+        .map_err(|e| e.to_string())??;
+    let info = session.info();
+    state.sessions.locked().insert(session.id.clone(), session);
+    Ok(info)
+}
+
+fn join(app: AppHandle, url: String, path: Option<String>) -> Result<Arc<Session>, String> {
+    // 合并结果以明文写回，不能覆盖加密笔记
+    if path
+        .as_deref()
+        .is_some_and(|p| encryption::is_envelope_file(Path::new(p)))
+    {
+        return Err("加密笔记不能用于协作".into());
+    }
+    let (mut socket, _) =
+        tungstenite::connect(url.as_str()).map_err(|e| format!("连接协作会话失败: {}", e))?;
+    if let MaybeTlsStream::Plain(stream) = socket.get_mut() {
+        let _ = stream.set_read_timeout(Some(POLL));
+    }
+
+    let session = Arc::new(Session::new(
+        share::random_token(),
+        false,
+        url,
+        path.map(PathBuf::from),
+        "",
+    ));
+    let (peer, rx) = session.add_peer();
+    thread::spawn({
+        let session = session.clone();
+        move || {
+            run_peer(&app, &session, &mut socket, peer, &rx);
+            session.remove_peer(&app, peer);
+            // 主持方断开时会话随之结束
+            if !session.closed.swap(true, Ordering::SeqCst) {
+                let _ = session.persist();
+                let _ = app.emit("collab-closed", &session.id);
+            }
+        }
+    });
+
+    let started = Instant::now();
+    while !session.synced.load(Ordering::SeqCst) {
+        if started.elapsed() > Duration::from_secs(5) {
+            session.closed.store(true, Ordering::SeqCst);
+            return Err("协作会话没有响应".into());
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    start_persist(session.clone());
+    Ok(session)
+}
+
+// 本地编辑：在 UTF-16 下标 index 处删除 delete 个代码单元并插入 insert
+#[tauri::command]
+pub fn collab_apply_edit(
+    state: State<CollabState>,
+    session_id: String,
+    index: u32,
+    delete: u32,
+    insert: String,
+) -> Result<(), MobiError> {
+    let session = get_session(&state, &session_id)?;
+    let update = {
+        let doc = session.awareness.doc();
+        let mut txn = doc.transact_mut();
+        let before = txn.state_vector();
+        let len = session.text.len(&txn);
+        if index > len {
+            return Err("编辑位置超出文档范围".into());
+        }
+        if delete > 0 {
+            session
+                .text
+                .remove_range(&mut txn, index, delete.min(len - index));
+        }
+        if !insert.is_empty() {
+            session.text.insert(&mut txn, index, &insert);
+        }
+        txn.encode_diff_v1(&before)
+    };
+    // 纯删除不会改变状态向量，但删除集仍在增量里
+    session.broadcast(&Message::Sync(SyncMessage::Update(update)), None);
+    session.dirty.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+// 广播本地状态（用户名、颜色、光标等），结构由前端约定
+#[tauri::command]
+pub fn collab_set_presence(
+    state: State<CollabState>,
+    session_id: String,
+    presence: Value,
+) -> Result<(), MobiError> {
+    let session = get_session(&state, &session_id)?;
+    session
+        .awareness
+        .set_local_state(presence)
+        .map_err(|e| e.to_string())?;
+    let update = session
+        .awareness
+        .update_with_clients([session.awareness.client_id()])
+        .map_err(|e| e.to_string())?;
+    session.broadcast(&Message::Awareness(update), None);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn collab_get_content(
+    state: State<CollabState>,
+    session_id: String,
+) -> Result<String, MobiError> {
+    Ok(get_session(&state, &session_id)?.content())
+}
+
+#[tauri::command]
+pub fn list_collab_sessions(state: State<CollabState>) -> Vec<CollabSessionInfo> {
+    state.sessions.locked().values().map(|s| s.info()).collect()
+}
+
+// 结束或退出会话，最后一次写回合并结果
+#[tauri::command]
+pub fn leave_collab_session(
+    state: State<CollabState>,
+    session_id: String,
+) -> Result<(), MobiError> {
+    let Some(session) = state.sessions.locked().remove(&session_id) else {
+        return Ok(());
+    };
+    session.closed.store(true, Ordering::SeqCst);
+    Ok(session.persist()?)
+}
+
+// 会话路径与给定文件相同时返回会话 ID，前端据此把编辑交给协作会话
+#[tauri::command]
+pub fn collab_session_for(state: State<CollabState>, path: String) -> Option<String> {
+    state
+        .sessions
+        .locked()
+        .values()
+        .find(|s| s.path.as_deref() == Some(Path::new(&path)))
+        .map(|s| s.id.clone())
+}
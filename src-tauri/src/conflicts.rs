@@ -0,0 +1,342 @@
+// 同步冲突：识别 iCloud / OneDrive / Dropbox / 坚果云 / Syncthing 生成的冲突副本，并提供三方合并
+// 没有共同祖先时以两个版本的公共行作为基准，只有一方改动的地方自动合并，双方都改动的地方写入冲突标记
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::error::MobiError;
+use crate::{document, workspace};
+
+// 超过这个规模不做逐行比对，整体作为一处冲突
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncConflict {
+    path: String,
+    pub original: String,
+    service: &'static str,
+    modified: i64,
+    original_modified: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeResult {
+    pub content: String,
+    // 写入冲突标记的段数，0 表示自动合并成功
+    conflicts: usize,
+}
+
+// OneDrive 追加的设备名：大写字母、数字和连字符，以字母开头，至少两个字母；
+// 排除 note-2024、note-2024-01-12、note-V2 这类普通的编号和日期后缀
+fn is_machine_name(suffix: &str) -> bool {
+    suffix.len() >= 3
+        && suffix.starts_with(|c: char| c.is_ascii_uppercase())
+        && suffix.chars().filter(|c| c.is_ascii_uppercase()).count() >= 2
+        && suffix
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '-')
+}
+
+// 从冲突副本的文件名（不含扩展名）推出原文件名，返回原文件名和同步服务
+fn original_stem(stem: &str, in_icloud: bool) -> Vec<(String, &'static str)> {
+    let mut candidates = Vec::new();
+
+    // Syncthing: note.sync-conflict-20250112-101500-ABCDEFG
+    if let Some(pos) = stem.find(".sync-conflict-") {
+        candidates.push((stem[..pos].to_string(), "syncthing"));
+    }
+
+    // Dropbox / 坚果云等: note (conflicted copy 2025-01-12)、note (Bob's conflicted copy ...)、note(冲突副本 ...)
+    if stem.ends_with(')') {
+        if let Some(open) = stem.rfind('(') {
+            let inner = stem[open..].to_lowercase();
+            if inner.contains("conflict") || inner.contains("冲突") {
+                let service = if inner.contains("冲突") {
+                    "jianguoyun"
+                } else {
+                    "dropbox"
+                };
+                candidates.push((stem[..open].trim_end().to_string(), service));
+            }
+        }
+    }
+
+    // OneDrive: note-DESKTOP-1A2B3C（追加设备名）
+    for (pos, _) in stem.match_indices('-') {
+        if pos > 0 && is_machine_name(&stem[pos + 1..]) {
+            candidates.push((stem[..pos].to_string(), "onedrive"));
+        }
+    }
+
+    // iCloud: note 2。与普通的 “另存为 name 2” 无法区分，只在 iCloud 云盘目录中识别
+    if in_icloud {
+        if let Some((base, n)) = stem.rsplit_once(' ') {
+            if !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()) && n != "1" {
+                candidates.push((base.to_string(), "icloud"));
+            }
+        }
+    }
+
+    candidates.retain(|(s, _)| !s.is_empty());
+    candidates
+}
+
+fn is_icloud(root: &Path) -> bool {
+    let root = root.to_string_lossy();
+    root.contains("Mobile Documents") || root.contains("iCloud")
+}
+
+// 判断是否为冲突副本：文件名符合某种冲突命名，且同目录下存在对应的原文件
+pub fn detect(root: &Path, path: &Path) -> Option<SyncConflict> {
+    if !path.is_file() || !workspace::is_note(path) {
+        return None;
+    }
+    let stem = path.file_stem()?.to_string_lossy().to_string();
+    let extension = path.extension()?.to_string_lossy().to_string();
+    original_stem(&stem, is_icloud(root))
+        .into_iter()
+        .map(|(original, service)| {
+            (
+                path.with_file_name(format!("{}.{}", original, extension)),
+                service,
+            )
+        })
+        .find(|(original, _)| original.is_file())
+        .map(|(original, service)| SyncConflict {
+            path: path.to_string_lossy().to_string(),
+            modified: workspace::modified_secs(path),
+            original_modified: workspace::modified_secs(&original),
+            original: original.to_string_lossy().to_string(),
+            service,
+        })
+}
+
+// 最长公共子序列的匹配行对 (a 中行号, b 中行号)
+fn matching_lines(a: &[&str], b: &[&str]) -> Option<Vec<(usize, usize)>> {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (a_mid, b_mid) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+    if a_mid.len().saturating_mul(b_mid.len()) > MAX_DIFF_CELLS {
+        return None;
+    }
+
+    let (n, m) = (a_mid.len(), b_mid.len());
+    let mut table = vec![0u32; (n + 1) * (m + 1)];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            table[i * (m + 1) + j] = if a_mid[i] == b_mid[j] {
+                table[(i + 1) * (m + 1) + j + 1] + 1
+            } else {
+                table[(i + 1) * (m + 1) + j].max(table[i * (m + 1) + j + 1])
+            };
+        }
+    }
+
+    let mut pairs: Vec<(usize, usize)> = (0..prefix).map(|i| (i, i)).collect();
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if a_mid[i] == b_mid[j] {
+            pairs.push((prefix + i, prefix + j));
+            i += 1;
+            j += 1;
+        } else if table[(i + 1) * (m + 1) + j] >= table[i * (m + 1) + j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs.extend((0..suffix).map(|k| (a.len() - suffix + k, b.len() - suffix + k)));
+    Some(pairs)
+}
+
+fn push_conflict(lines: &mut Vec<String>, ours: &[&str], theirs: &[&str], theirs_label: &str) {
+    lines.push("<<<<<<< 当前版本".into());
+    lines.extend(ours.iter().map(|l| l.to_string()));
+    lines.push("=======".into());
+    lines.extend(theirs.iter().map(|l| l.to_string()));
+    lines.push(format!(">>>>>>> {}", theirs_label));
+}
+
+// diff3：在三个版本都相同的行上对齐，逐段比较两侧相对基准的改动
+pub fn merge3(base: &str, ours: &str, theirs: &str, theirs_label: &str) -> MergeResult {
+    let base_lines: Vec<&str> = base.lines().collect();
+    let our_lines: Vec<&str> = ours.lines().collect();
+    let their_lines: Vec<&str> = theirs.lines().collect();
+
+    let mut lines = Vec::new();
+    let (Some(to_ours), Some(to_theirs)) = (
+        matching_lines(&base_lines, &our_lines),
+        matching_lines(&base_lines, &their_lines),
+    ) else {
+        push_conflict(&mut lines, &our_lines, &their_lines, theirs_label);
+        return MergeResult {
+            content: lines.join("\n") + "\n",
+            conflicts: 1,
+        };
+    };
+
+    // 基准中在两侧都保留下来的行作为同步点
+    let mut their_of_base = vec![None; base_lines.len()];
+    for &(b, t) in &to_theirs {
+        their_of_base[b] = Some(t);
+    }
+    let mut sync: Vec<(usize, usize, usize)> = to_ours
+        .iter()
+        .filter_map(|&(b, o)| their_of_base[b].map(|t| (b, o, t)))
+        .collect();
+    sync.push((base_lines.len(), our_lines.len(), their_lines.len()));
+
+    let mut conflicts = 0;
+    let (mut b0, mut o0, mut t0) = (0, 0, 0);
+    for (b, o, t) in sync {
+        let (base_chunk, our_chunk, their_chunk) =
+            (&base_lines[b0..b], &our_lines[o0..o], &their_lines[t0..t]);
+        if our_chunk == base_chunk || our_chunk == their_chunk {
+            lines.extend(their_chunk.iter().map(|l| l.to_string()));
+        } else if their_chunk == base_chunk {
+            lines.extend(our_chunk.iter().map(|l| l.to_string()));
+        } else {
+            push_conflict(&mut lines, our_chunk, their_chunk, theirs_label);
+            conflicts += 1;
+        }
+        if b < base_lines.len() {
+            lines.push(base_lines[b].to_string());
+        }
+        (b0, o0, t0) = (b + 1, o + 1, t + 1);
+    }
+
+    let mut content = lines.join("\n");
+    if ours.ends_with('\n') || theirs.ends_with('\n') {
+        content.push('\n');
+    }
+    MergeResult { content, conflicts }
+}
+
+// 没有共同祖先时的基准：两个版本的公共行
+fn common_lines(ours: &str, theirs: &str) -> String {
+    let our_lines: Vec<&str> = ours.lines().collect();
+    let their_lines: Vec<&str> = theirs.lines().collect();
+    matching_lines(&our_lines, &their_lines)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(o, _)| our_lines[o])
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn read(path: &str) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|e| format!("读取文件失败: {}", e))
+}
+
+#[tauri::command]
+pub fn list_sync_conflicts(workspace: String) -> Vec<SyncConflict> {
+    let root = PathBuf::from(&workspace);
+    workspace::note_files(&root)
+        .iter()
+        .filter_map(|path| detect(&root, path))
+        .collect()
+}
+
+// 预览合并结果，不写文件；base 为共同祖先的内容（如有）
+#[tauri::command]
+pub fn merge_sync_conflict(
+    original: String,
+    conflict: String,
+    base: Option<String>,
+) -> Result<MergeResult, MobiError> {
+    let ours = read(&original)?;
+    let theirs = read(&conflict)?;
+    let base = base.unwrap_or_else(|| common_lines(&ours, &theirs));
+    let label = Path::new(&conflict)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    Ok(merge3(&base, &ours, &theirs, &label))
+}
+
+// 把合并后的内容写回原文件并删除冲突副本
+#[tauri::command]
+pub fn resolve_sync_conflict(
+    original: String,
+    conflict: String,
+    content: String,
+) -> Result<(), MobiError> {
+    document::write_note(Path::new(&original), &content)?;
+    fs::remove_file(&conflict).map_err(|e| MobiError::io("删除冲突副本失败", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn first(stem: &str, in_icloud: bool) -> Option<(String, &'static str)> {
+        original_stem(stem, in_icloud).into_iter().next()
+    }
+
+    // 可能有多个候选（Syncthing 的设备号也像 OneDrive 的设备名），detect 取第一个存在的原文件
+    #[test]
+    fn recognizes_conflict_copies() {
+        for (stem, original, service) in [
+            (
+                "note.sync-conflict-20250112-101500-ABCDEFG",
+                "note",
+                "syncthing",
+            ),
+            ("note (Bob's conflicted copy 2025-01-12)", "note", "dropbox"),
+            ("note(冲突副本 2025-01-12)", "note", "jianguoyun"),
+            ("note-DESKTOP-1A2B3C", "note", "onedrive"),
+        ] {
+            assert_eq!(first(stem, false), Some((original.to_string(), service)));
+        }
+        assert_eq!(first("note 2", true), Some(("note".to_string(), "icloud")));
+    }
+
+    // 普通的编号、日期后缀和 “另存为 name 2” 不是冲突副本
+    #[test]
+    fn ignores_ordinary_suffixes() {
+        for stem in [
+            "note-2024",
+            "note-2024-01-12",
+            "note-V2",
+            "my-note",
+            "(draft)",
+        ] {
+            assert!(original_stem(stem, false).is_empty(), "{}", stem);
+        }
+        assert!(original_stem("note 2", false).is_empty());
+        assert!(original_stem("note 1", true).is_empty());
+    }
+
+    #[test]
+    fn merges_changes_to_different_lines() {
+        let result = merge3("a\nb\nc\n", "A\nb\nc\n", "a\nb\nC\n", "副本");
+        assert_eq!(result.content, "A\nb\nC\n");
+        assert_eq!(result.conflicts, 0);
+    }
+
+    #[test]
+    fn marks_changes_to_the_same_line() {
+        let result = merge3("a\nb\nc\n", "a\nX\nc\n", "a\nY\nc\n", "副本");
+        assert_eq!(
+            result.content,
+            "a\n<<<<<<< 当前版本\nX\n=======\nY\n>>>>>>> 副本\nc\n"
+        );
+        assert_eq!(result.conflicts, 1);
+    }
+
+    #[test]
+    fn identical_changes_do_not_conflict() {
+        let result = merge3("a\nb\n", "a\nB\n", "a\nB\n", "副本");
+        assert_eq!(result.content, "a\nB\n");
+        assert_eq!(result.conflicts, 0);
+    }
+}
@@ -0,0 +1,472 @@
+// Confluence 发布：把笔记转换为 Confluence 存储格式（XHTML + ac: 宏），本地图片作为页面附件上传，按空间和标题创建或更新页面
+// 发布后把页面 ID 写入 front matter 的 confluence_id，之后即使修改了标题也更新同一页面；Cloud 与 Server/Data Center 共用 REST v1 接口
+// 令牌保存在系统钥匙串（confluence / token）中
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use pulldown_cmark::{html, CodeBlockKind, CowStr, Event, Parser, Tag, TagEnd};
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::AppHandle;
+
+use crate::error::MobiError;
+use crate::{encryption, markdown, network, preview, secrets, settings, workspace};
+
+// 记录页面 ID 的 front matter 字段
+const PAGE_ID_FIELD: &str = "confluence_id";
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct ConfluenceSettings {
+    // Cloud 为 https://example.atlassian.net/wiki，Server 为站点根地址
+    base_url: String,
+    // Cloud 填账号邮箱；Server 使用个人访问令牌时留空
+    username: String,
+    // 默认空间键
+    space: String,
+    // 新页面默认放在这个页面下
+    parent_id: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PublishOptions {
+    // 为空时使用设置中的空间
+    space: String,
+    // 为空时依次取 front matter 的 title、唯一的一级标题、文件名
+    title: String,
+    parent_id: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PublishResult {
+    id: String,
+    url: String,
+    version: i64,
+    created: bool,
+    attachments: usize,
+}
+
+struct Page {
+    id: String,
+    version: i64,
+    space: String,
+}
+
+fn token() -> Result<String, String> {
+    secrets::get_secret("confluence", "token")?
+        .ok_or_else(|| "请先设置 Confluence 的 API 令牌".into())
+}
+
+struct Api {
+    client: Client,
+    base: String,
+    username: String,
+    token: String,
+}
+
+impl Api {
+    fn request(&self, method: reqwest::Method, path: &str) -> RequestBuilder {
+        let request = self
+            .client
+            .request(method, format!("{}/rest/api{}", self.base, path));
+        if self.username.is_empty() {
+            request.bearer_auth(&self.token)
+        } else {
+            request.basic_auth(&self.username, Some(&self.token))
+        }
+    }
+
+    async fn send(&self, request: RequestBuilder) -> Result<Value, String> {
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("无法连接 Confluence: {}", e))?;
+        let status = response.status();
+        let body: Value = response.json().await.unwrap_or(Value::Null);
+        if status.is_success() {
+            return Ok(body);
+        }
+        let message = body["message"].as_str().unwrap_or_default();
+        Err(match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                "Confluence 拒绝访问，请检查用户名和令牌".to_string()
+            }
+            _ => format!("Confluence 请求失败 {}: {}", status, message),
+        })
+    }
+
+    async fn page_by_id(&self, id: &str) -> Result<Option<Page>, String> {
+        let request = self
+            .request(reqwest::Method::GET, &format!("/content/{}", id))
+            .query(&[("expand", "version,space")]);
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("无法连接 Confluence: {}", e))?;
+        // 页面已被删除时按标题重新查找
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            return Err(format!("Confluence 请求失败 {}", response.status()));
+        }
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| format!("解析 Confluence 响应失败: {}", e))?;
+        Ok(page_from(&body))
+    }
+
+    async fn page_by_title(&self, space: &str, title: &str) -> Result<Option<Page>, String> {
+        let body = self
+            .send(self.request(reqwest::Method::GET, "/content").query(&[
+                ("spaceKey", space),
+                ("title", title),
+                ("type", "page"),
+                ("expand", "version,space"),
+            ]))
+            .await?;
+        Ok(body["results"].get(0).and_then(page_from))
+    }
+
+    // 同名附件已存在时上传新版本
+    async fn upload(&self, page: &str, path: &Path, name: &str) -> Result<(), String> {
+        let existing = self
+            .send(
+                self.request(
+                    reqwest::Method::GET,
+                    &format!("/content/{}/child/attachment", page),
+                )
+                .query(&[("filename", name)]),
+            )
+            .await?;
+        let endpoint = match existing["results"][0]["id"].as_str() {
+            Some(id) => format!("/content/{}/child/attachment/{}/data", page, id),
+            None => format!("/content/{}/child/attachment", page),
+        };
+        let bytes = fs::read(path).map_err(|e| format!("读取附件失败: {}", e))?;
+        let part = reqwest::multipart::Part::bytes(bytes)
+            .file_name(name.to_string())
+            .mime_str(preview::content_type(path))
+            .map_err(|e| e.to_string())?;
+        let form = reqwest::multipart::Form::new()
+            .part("file", part)
+            .text("minorEdit", "true");
+        self.send(
+            self.request(reqwest::Method::POST, &endpoint)
+                .header("X-Atlassian-Token", "no-check")
+                .multipart(form),
+        )
+        .await
+        .map_err(|e| format!("上传附件 {} 失败: {}", name, e))?;
+        Ok(())
+    }
+}
+
+fn page_from(value: &Value) -> Option<Page> {
+    Some(Page {
+        id: value["id"].as_str()?.to_string(),
+        version: value["version"]["number"].as_i64().unwrap_or(1),
+        space: value["space"]["key"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+    })
+}
+
+// 本地图片：源文件 → 附件名，不同目录下的同名文件加序号区分
+#[derive(Default)]
+struct Attachments {
+    files: Vec<(PathBuf, String)>,
+    names: HashMap<PathBuf, String>,
+}
+
+impl Attachments {
+    fn add(&mut self, path: PathBuf) -> String {
+        if let Some(name) = self.names.get(&path) {
+            return name.clone();
+        }
+        let stem = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let extension = path
+            .extension()
+            .map(|e| format!(".{}", e.to_string_lossy()))
+            .unwrap_or_default();
+        let mut name = format!("{}{}", stem, extension);
+        let mut n = 2;
+        while self.files.iter().any(|(_, existing)| *existing == name) {
+            name = format!("{}-{}{}", stem, n, extension);
+            n += 1;
+        }
+        self.names.insert(path.clone(), name.clone());
+        self.files.push((path, name.clone()));
+        name
+    }
+}
+
+// CDATA 中不能出现 ]]>，拆成两段
+fn cdata(text: &str) -> String {
+    format!("<![CDATA[{}]]>", text.replace("]]>", "]]]]><![CDATA[>"))
+}
+
+fn code_macro(lang: &str, code: &str) -> String {
+    let language = if lang.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "<ac:parameter ac:name=\"language\">{}</ac:parameter>",
+            markdown::escape_html(lang)
+        )
+    };
+    format!(
+        "<ac:structured-macro ac:name=\"code\">{}<ac:plain-text-body>{}</ac:plain-text-body></ac:structured-macro>",
+        language,
+        cdata(code.trim_end_matches('\n'))
+    )
+}
+
+fn image_markup(src: &str, alt: &str, note_dir: &Path, attachments: &mut Attachments) -> String {
+    let alt = if alt.is_empty() {
+        String::new()
+    } else {
+        format!(" ac:alt=\"{}\"", markdown::escape_html(alt))
+    };
+    if src.contains("://") {
+        return format!(
+            "<ac:image{}><ri:url ri:value=\"{}\" /></ac:image>",
+            alt,
+            markdown::escape_html(src)
+        );
+    }
+    let local = urlencoding::decode(src)
+        .ok()
+        .map(|decoded| note_dir.join(decoded.as_ref()))
+        .filter(|path| path.is_file());
+    match local {
+        Some(path) => format!(
+            "<ac:image{}><ri:attachment ri:filename=\"{}\" /></ac:image>",
+            alt,
+            markdown::escape_html(&attachments.add(path))
+        ),
+        // 找不到的图片保留替代文字
+        None => markdown::escape_html(&format!("[{}]", src)),
+    }
+}
+
+// 存储格式必须是合法的 XHTML：代码块转为 code 宏，图片转为 ac:image，笔记中的原始 HTML 按文字输出
+fn storage_format(body: &str, note_dir: &Path, attachments: &mut Attachments) -> String {
+    let mut events = Vec::new();
+    let mut code: Option<(String, String)> = None;
+    let mut image: Option<(String, String)> = None;
+    for event in Parser::new_ext(body, markdown::parser_options()) {
+        if let Some((_, text)) = code.as_mut() {
+            match event {
+                Event::End(TagEnd::CodeBlock) => {
+                    let (lang, text) = code.take().unwrap();
+                    events.push(Event::Html(CowStr::from(code_macro(&lang, &text))));
+                }
+                Event::Text(t) => text.push_str(&t),
+                _ => {}
+            }
+            continue;
+        }
+        if let Some((_, alt)) = image.as_mut() {
+            match event {
+                Event::End(TagEnd::Image) => {
+                    let (src, alt) = image.take().unwrap();
+                    let markup = image_markup(&src, &alt, note_dir, attachments);
+                    events.push(Event::InlineHtml(CowStr::from(markup)));
+                }
+                Event::Text(t) | Event::Code(t) => alt.push_str(&t),
+                _ => {}
+            }
+            continue;
+        }
+        match event {
+            Event::Start(Tag::CodeBlock(kind)) => {
+                let lang = match kind {
+                    CodeBlockKind::Fenced(info) => {
+                        info.split_whitespace().next().unwrap_or("").to_string()
+                    }
+                    CodeBlockKind::Indented => String::new(),
+                };
+                code = Some((lang, String::new()));
+            }
+            Event::Start(Tag::Image { dest_url, .. }) => {
+                image = Some((dest_url.to_string(), String::new()));
+            }
+            Event::Html(raw) | Event::InlineHtml(raw) => events.push(Event::Text(raw)),
+            // Confluence 不接受 <input>，复选框用符号表示
+            Event::TaskListMarker(done) => events.push(Event::Text(CowStr::from(if done {
+                "☑ "
+            } else {
+                "☐ "
+            }))),
+            event => events.push(event),
+        }
+    }
+    let mut out = String::new();
+    html::push_html(&mut out, events.into_iter());
+    out
+}
+
+fn page_title(content: &str, path: &Path) -> String {
+    if let Some(title) = markdown::front_matter_field(content, "title") {
+        return title;
+    }
+    let body = markdown::strip_front_matter(content);
+    let h1: Vec<&str> = body
+        .lines()
+        .filter_map(|line| line.strip_prefix("# "))
+        .collect();
+    if let [title] = h1.as_slice() {
+        return title.trim().to_string();
+    }
+    path.file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+// 页面标题已经作为 Confluence 标题显示，正文中与之相同的一级标题去掉
+fn strip_title_heading<'a>(body: &'a str, title: &str) -> &'a str {
+    let trimmed = body.trim_start();
+    match trimmed.split_once('\n') {
+        Some((first, rest)) if first.strip_prefix("# ").map(str::trim) == Some(title) => rest,
+        None if trimmed.strip_prefix("# ").map(str::trim) == Some(title) => "",
+        _ => body,
+    }
+}
+
+#[tauri::command]
+pub async fn publish_to_confluence(
+    app: AppHandle,
+    path: String,
+    options: Option<PublishOptions>,
+) -> Result<PublishResult, MobiError> {
+    let config: ConfluenceSettings = settings::load(&app, "confluence");
+    let options = options.unwrap_or_default();
+    if config.base_url.trim().is_empty() {
+        return Err("请先在设置中填写 Confluence 地址".into());
+    }
+    let api = Api {
+        client: network::client(&app)?,
+        base: config.base_url.trim().trim_end_matches('/').to_string(),
+        username: config.username.trim().to_string(),
+        token: token()?,
+    };
+
+    let source = PathBuf::from(&path);
+    let content = fs::read_to_string(&source).map_err(|e| format!("读取文件失败: {}", e))?;
+    if encryption::is_envelope(&content) {
+        return Err("加密笔记需要先解密才能发布".into());
+    }
+    let title = match options.title.trim() {
+        "" => page_title(&content, &source),
+        title => title.to_string(),
+    };
+    let note_dir = source.parent().unwrap_or(Path::new(".")).to_path_buf();
+    let mut attachments = Attachments::default();
+    let body = strip_title_heading(markdown::strip_front_matter(&content), &title);
+    let storage = storage_format(body, &note_dir, &mut attachments);
+
+    // 先按记录的页面 ID 查找，找不到时按空间和标题查找
+    let recorded = markdown::front_matter_field(&content, PAGE_ID_FIELD);
+    let mut existing = match &recorded {
+        Some(id) => api.page_by_id(id).await?,
+        None => None,
+    };
+    let space = match (options.space.trim(), &existing) {
+        ("", Some(page)) if !page.space.is_empty() => page.space.clone(),
+        ("", _) => config.space.trim().to_string(),
+        (space, _) => space.to_string(),
+    };
+    if space.is_empty() {
+        return Err("请指定要发布到的 Confluence 空间".into());
+    }
+    if existing.is_none() {
+        existing = api.page_by_title(&space, &title).await?;
+    }
+
+    let storage_body = json!({ "storage": { "value": storage, "representation": "storage" } });
+    let (page, created) = match existing {
+        Some(page) => {
+            let body = api
+                .send(
+                    api.request(reqwest::Method::PUT, &format!("/content/{}", page.id))
+                        .json(&json!({
+                            "id": page.id,
+                            "type": "page",
+                            "title": title,
+                            "space": { "key": space },
+                            "version": { "number": page.version + 1 },
+                            "body": storage_body,
+                        })),
+                )
+                .await?;
+            (body, false)
+        }
+        None => {
+            let parent = match options.parent_id.trim() {
+                "" => config.parent_id.trim().to_string(),
+                parent => parent.to_string(),
+            };
+            let mut payload = json!({
+                "type": "page",
+                "title": title,
+                "space": { "key": space },
+                "body": storage_body,
+            });
+            if !parent.is_empty() {
+                payload["ancestors"] = json!([{ "id": parent }]);
+            }
+            let body = api
+                .send(
+                    api.request(reqwest::Method::POST, "/content")
+                        .json(&payload),
+                )
+                .await?;
+            (body, true)
+        }
+    };
+    let id = page["id"]
+        .as_str()
+        .ok_or("Confluence 没有返回页面 ID")?
+        .to_string();
+
+    for (file, name) in &attachments.files {
+        api.upload(&id, file, name).await?;
+    }
+
+    if recorded.as_deref() != Some(id.as_str()) {
+        // 重新读取，发布期间的编辑不被覆盖
+        let latest = fs::read_to_string(&source).unwrap_or(content);
+        fs::write(
+            workspace::resolve_link(&source),
+            markdown::set_front_matter_field(&latest, PAGE_ID_FIELD, &id),
+        )
+        .map_err(|e| format!("写入页面 ID 失败: {}", e))?;
+    }
+
+    let url = match page["_links"]["webui"].as_str() {
+        Some(webui) => format!(
+            "{}{}",
+            page["_links"]["base"].as_str().unwrap_or(&api.base),
+            webui
+        ),
+        None => format!("{}/pages/viewpage.action?pageId={}", api.base, id),
+    };
+    Ok(PublishResult {
+        id,
+        url,
+        version: page["version"]["number"].as_i64().unwrap_or(1),
+        created,
+        attachments: attachments.files.len(),
+    })
+}
@@ -0,0 +1,164 @@
+// 日记：按路径模式（默认 journal/%Y-%m-%d.md）定位每天的笔记，首次打开时用模板创建
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{Local, NaiveDate, TimeZone};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::error::MobiError;
+use crate::settings;
+use crate::templates::{self, format_date, utf16_offset};
+
+const DEFAULT_TEMPLATE: &str = "# {{date}}\n\n{{cursor}}\n";
+
+// 单次查询最多覆盖的天数
+const MAX_RANGE_DAYS: i64 = 366 * 5;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct DailySettings {
+    // 相对工作区的 chrono 格式路径
+    path_pattern: String,
+    // 模板名，留空使用默认的日期标题
+    template: String,
+}
+
+impl Default for DailySettings {
+    fn default() -> Self {
+        DailySettings {
+            path_pattern: "journal/%Y-%m-%d.md".into(),
+            template: String::new(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DateRange {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+}
+
+impl DateRange {
+    // 区间内的每一天（含首尾），过长的区间会被截断
+    pub fn days(&self) -> impl Iterator<Item = NaiveDate> {
+        let count = (self.end - self.start)
+            .num_days()
+            .clamp(-1, MAX_RANGE_DAYS - 1);
+        self.start.iter_days().take((count + 1) as usize)
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyNote {
+    date: NaiveDate,
+    path: String,
+    created: bool,
+    content: String,
+    cursor: Option<usize>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyEntry {
+    date: NaiveDate,
+    path: String,
+}
+
+fn parse_date(date: Option<&str>) -> Result<NaiveDate, String> {
+    match date {
+        Some(date) => NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|e| format!("日期格式不正确: {}", e)),
+        None => Ok(Local::now().date_naive()),
+    }
+}
+
+fn pattern(app: &AppHandle) -> String {
+    settings::load::<DailySettings>(app, "daily").path_pattern
+}
+
+pub fn daily_path(root: &Path, pattern: &str, date: NaiveDate) -> PathBuf {
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+    root.join(format_date(&midnight.and_utc(), pattern))
+}
+
+// 已存在的日记，日期 -> 路径
+pub fn existing_daily_notes(
+    app: &AppHandle,
+    root: &Path,
+    range: &DateRange,
+) -> HashMap<NaiveDate, PathBuf> {
+    let pattern = pattern(app);
+    range
+        .days()
+        .map(|date| (date, daily_path(root, &pattern, date)))
+        .filter(|(_, path)| path.is_file())
+        .collect()
+}
+
+// date 为 YYYY-MM-DD，省略时为今天
+#[tauri::command]
+pub fn open_daily_note(
+    app: AppHandle,
+    workspace: String,
+    date: Option<String>,
+) -> Result<DailyNote, MobiError> {
+    let date = parse_date(date.as_deref())?;
+    let config: DailySettings = settings::load(&app, "daily");
+    let path = daily_path(Path::new(&workspace), &config.path_pattern, date);
+
+    if path.is_file() {
+        let content = fs::read_to_string(&path).map_err(|e| format!("读取文件失败: {}", e))?;
+        return Ok(DailyNote {
+            date,
+            path: path.to_string_lossy().to_string(),
+            created: false,
+            content,
+            cursor: None,
+        });
+    }
+
+    let body = match config.template.as_str() {
+        "" => DEFAULT_TEMPLATE.to_string(),
+        name => templates::find_template(&app, Some(&workspace), name)
+            .ok_or_else(|| format!("找不到模板: {}", name))?,
+    };
+    // 模板中的日期变量取日记对应的那一天
+    let now = Local::now();
+    let day = Local
+        .from_local_datetime(&date.and_time(now.time()))
+        .earliest()
+        .unwrap_or(now);
+    let mut values = HashMap::new();
+    values.insert("title".to_string(), date.format("%Y-%m-%d").to_string());
+    let (content, cursor) = templates::render(&body, &values, &day);
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("创建目录失败: {}", e))?;
+    }
+    fs::write(&path, &content).map_err(|e| format!("创建日记失败: {}", e))?;
+    Ok(DailyNote {
+        date,
+        path: path.to_string_lossy().to_string(),
+        created: true,
+        cursor: cursor.map(|c| utf16_offset(&content, c)),
+        content,
+    })
+}
+
+#[tauri::command]
+pub fn get_daily_notes(app: AppHandle, workspace: String, range: DateRange) -> Vec<DailyEntry> {
+    let mut entries: Vec<DailyEntry> = existing_daily_notes(&app, Path::new(&workspace), &range)
+        .into_iter()
+        .map(|(date, path)| DailyEntry {
+            date,
+            path: path.to_string_lossy().to_string(),
+        })
+        .collect();
+    entries.sort_by_key(|e| e.date);
+    entries
+}
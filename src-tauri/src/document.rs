@@ -0,0 +1,174 @@
+// 文档读写：保存前检查写权限，失败时返回 MobiError，前端按错误码提示“另存为”或以管理员权限保存（needsElevation）
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+use tauri::AppHandle;
+
+use crate::error::{self, MobiError};
+use crate::{buffers, undo, workspace};
+
+#[cfg(unix)]
+fn owned_by_other(path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    let uid = unsafe { libc::geteuid() };
+    fs::metadata(path).is_ok_and(|m| m.uid() != uid)
+}
+
+#[cfg(windows)]
+fn owned_by_other(_path: &Path) -> bool {
+    false
+}
+
+// 没有写入权限时能否以管理员权限保存（仅 macOS / Windows）
+const CAN_ELEVATE: bool = cfg!(any(target_os = "macos", target_os = "windows"));
+
+// 把 IO 错误归类，路径指向要写入的文件；只读卷、文件被占用等交给 MobiError::io
+fn classify(path: &Path, err: io::Error) -> MobiError {
+    if err.kind() == io::ErrorKind::NotFound {
+        return MobiError::NotFound("文件所在的文件夹不存在".into());
+    }
+    if err.kind() != io::ErrorKind::PermissionDenied || error::is_read_only_volume(&err) {
+        return MobiError::io("保存文件失败", err);
+    }
+    let target = if path.exists() {
+        path
+    } else {
+        path.parent().unwrap_or(path)
+    };
+    if fs::metadata(target).is_ok_and(|m| m.permissions().readonly()) {
+        return MobiError::ReadOnly("文件是只读的".into());
+    }
+    let message = if owned_by_other(target) {
+        "文件属于其他用户，没有写入权限"
+    } else {
+        "没有写入权限"
+    };
+    if CAN_ELEVATE {
+        MobiError::NeedsElevation(message.into())
+    } else {
+        MobiError::PermissionDenied(message.into())
+    }
+}
+
+// 不改动文件内容地检查能否写入：已有文件以追加方式打开，新文件检查所在文件夹
+#[tauri::command]
+pub fn check_writable(path: String) -> Result<(), MobiError> {
+    let path = &workspace::resolve_link(Path::new(&path));
+    if path.exists() {
+        return OpenOptions::new()
+            .append(true)
+            .open(path)
+            .map(|_| ())
+            .map_err(|e| classify(path, e));
+    }
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let probe = dir.join(format!(".mobi-write-test-{}", std::process::id()));
+    fs::write(&probe, b"").map_err(|e| classify(path, e))?;
+    let _ = fs::remove_file(probe);
+    Ok(())
+}
+
+// 写入笔记：经过符号链接时写入链接目标，链接本身保持不变；后端改写笔记（合并冲突、勾选任务等）也用它
+pub fn write_note(path: &Path, content: &str) -> Result<(), MobiError> {
+    let target = workspace::resolve_link(path);
+    fs::write(&target, content).map_err(|e| classify(&target, e))
+}
+
+#[tauri::command]
+pub fn save_document(app: AppHandle, path: String, content: String) -> Result<(), MobiError> {
+    write_note(Path::new(&path), &content)?;
+    buffers::saved(&app, &path, &content);
+    undo::saved(&app, &path, &content);
+    Ok(())
+}
+
+// 内容先写到临时文件，再通过系统授权对话框以管理员身份覆盖目标文件（保留目标文件原有的属主和权限）
+#[tauri::command]
+pub async fn save_document_elevated(
+    app: AppHandle,
+    path: String,
+    content: String,
+) -> Result<(), MobiError> {
+    // 临时文件名随机、独占创建且只有本用户可读写，其他用户无法抢先放置文件或符号链接
+    let mut temp = tempfile::Builder::new()
+        .prefix("mobi-save-")
+        .suffix(".tmp")
+        .tempfile()
+        .map_err(|e| MobiError::io("创建临时文件失败", e))?;
+    temp.write_all(content.as_bytes())
+        .and_then(|_| temp.as_file().sync_all())
+        .map_err(|e| classify(temp.path(), e))?;
+    let result = tauri::async_runtime::spawn_blocking({
+        let temp = temp.path().to_string_lossy().to_string();
+        let target = workspace::resolve_link(Path::new(&path));
+        move || elevated_copy(&temp, &target.to_string_lossy())
+    })
+    .await
+    .map_err(|e| MobiError::Failed(e.to_string()))
+    .and_then(|r| r);
+    drop(temp);
+    if result.is_ok() {
+        buffers::saved(&app, &path, &content);
+        undo::saved(&app, &path, &content);
+    }
+    result
+}
+
+#[cfg(target_os = "macos")]
+fn elevated_copy(source: &str, target: &str) -> Result<(), MobiError> {
+    // 路径作为 argv 传入，避免拼接进脚本
+    let output = std::process::Command::new("osascript")
+        .args([
+            "-e",
+            "on run argv",
+            "-e",
+            "do shell script \"cat \" & quoted form of item 1 of argv & \" > \" & quoted form of item 2 of argv with administrator privileges",
+            "-e",
+            "end run",
+            source,
+            target,
+        ])
+        .output()
+        .map_err(|e| MobiError::Failed(format!("启动授权失败: {}", e)))?;
+    check_elevated(output)
+}
+
+#[cfg(target_os = "windows")]
+fn elevated_copy(source: &str, target: &str) -> Result<(), MobiError> {
+    let quote = |s: &str| format!("'{}'", s.replace('\'', "''"));
+    let inner = format!(
+        "Copy-Item -LiteralPath {} -Destination {} -Force",
+        quote(source),
+        quote(target)
+    );
+    let script = format!(
+        "$p = Start-Process powershell -Verb RunAs -Wait -PassThru -WindowStyle Hidden -ArgumentList '-NoProfile','-Command',{}; exit $p.ExitCode",
+        quote(&inner)
+    );
+    let output = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-Command", &script])
+        .output()
+        .map_err(|e| MobiError::Failed(format!("启动授权失败: {}", e)))?;
+    check_elevated(output)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn elevated_copy(_source: &str, _target: &str) -> Result<(), MobiError> {
+    Err(MobiError::Unsupported(
+        "当前系统不支持以管理员权限保存".into(),
+    ))
+}
+
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+fn check_elevated(output: std::process::Output) -> Result<(), MobiError> {
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    Err(MobiError::PermissionDenied(format!(
+        "以管理员权限保存失败: {}",
+        stderr.trim()
+    )))
+}
@@ -0,0 +1,162 @@
+// 拖放文件：系统拖入窗口的文件按类型处理——Markdown 打开或插入链接，图片复制或移动到附件目录后插入图片，其他文件作为附件插入链接
+// 各类文件的默认处理方式保存在设置的 drop 节，调用时传入 mode 可以临时改为复制、移动或仅链接
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::error::MobiError;
+use crate::{markdown, settings, workspace};
+
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "webp", "bmp", "svg"];
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum MarkdownPolicy {
+    Open,
+    // 在当前文档中插入指向它的链接
+    Link,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum DropMode {
+    // 复制到附件目录
+    Copy,
+    // 移动到附件目录
+    Move,
+    // 不复制，直接链接到原位置
+    Link,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct DropSettings {
+    markdown: MarkdownPolicy,
+    images: DropMode,
+    files: DropMode,
+}
+
+impl Default for DropSettings {
+    fn default() -> Self {
+        DropSettings {
+            markdown: MarkdownPolicy::Open,
+            images: DropMode::Copy,
+            files: DropMode::Copy,
+        }
+    }
+}
+
+#[derive(Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DropResult {
+    // 需要打开的 Markdown 文件
+    open: Vec<String>,
+    // 插入到光标处的 Markdown，每个文件一行
+    markdown: String,
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+// 跨磁盘时 rename 会失败，改为复制后删除
+fn move_file(from: &Path, to: &Path) -> Result<(), MobiError> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    fs::copy(from, to).map_err(|e| MobiError::io("移动文件失败", e))?;
+    fs::remove_file(from).map_err(|e| MobiError::io("删除原文件失败", e))
+}
+
+// 把文件放进附件目录，返回放置后的路径；已在附件目录中的文件不再复制
+fn place(source: &Path, assets: &Path, mode: DropMode) -> Result<PathBuf, MobiError> {
+    if mode == DropMode::Link || source.parent() == Some(assets) {
+        return Ok(source.to_path_buf());
+    }
+    fs::create_dir_all(assets).map_err(|e| MobiError::io("创建附件目录失败", e))?;
+    let stem = source
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let target = workspace::unique_path(assets, &stem, &extension(source));
+    match mode {
+        DropMode::Move => move_file(source, &target)?,
+        _ => {
+            fs::copy(source, &target).map_err(|e| MobiError::io("复制文件失败", e))?;
+        }
+    }
+    Ok(target)
+}
+
+// document 为当前文档（未保存时为空），asset_dir 为附件目录相对于文档所在目录的路径；mode 覆盖设置中的处理方式
+#[tauri::command]
+pub fn handle_dropped_paths(
+    app: AppHandle,
+    paths: Vec<String>,
+    mode: Option<DropMode>,
+    document: Option<String>,
+    workspace: Option<String>,
+    asset_dir: Option<String>,
+) -> Result<DropResult, MobiError> {
+    let config: DropSettings = settings::load(&app, "drop");
+    let base = document
+        .as_deref()
+        .filter(|d| !d.is_empty())
+        .and_then(|d| Path::new(d).parent().map(Path::to_path_buf))
+        .or_else(|| workspace.filter(|w| !w.is_empty()).map(PathBuf::from));
+    let assets = base
+        .as_ref()
+        .map(|base| base.join(asset_dir.unwrap_or_default().trim_matches('/')));
+
+    let mut result = DropResult::default();
+    let mut lines = Vec::new();
+    for path in paths.iter().map(PathBuf::from) {
+        if path.is_dir() {
+            continue;
+        }
+        let ext = extension(&path);
+        let name = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if workspace::is_note(&path) || ext == "txt" {
+            let policy = match mode {
+                Some(DropMode::Link) => MarkdownPolicy::Link,
+                _ => config.markdown,
+            };
+            match (policy, &base) {
+                (MarkdownPolicy::Link, Some(base)) => lines.push(format!(
+                    "[{}]({})",
+                    name,
+                    markdown::link_destination(&workspace::relative_to(base, &path))
+                )),
+                _ => result.open.push(path.to_string_lossy().to_string()),
+            }
+            continue;
+        }
+
+        let (Some(base), Some(assets)) = (&base, &assets) else {
+            return Err("请先保存文件或打开一个目录后再拖入附件".into());
+        };
+        let is_image = IMAGE_EXTENSIONS.contains(&ext.as_str());
+        let mode = mode.unwrap_or(if is_image {
+            config.images
+        } else {
+            config.files
+        });
+        let placed = place(&path, assets, mode)?;
+        let link = markdown::link_destination(&workspace::relative_to(base, &placed));
+        lines.push(if is_image {
+            format!("![]({})", link)
+        } else {
+            format!("[{}]({})", name, link)
+        });
+    }
+    result.markdown = lines.join("\n");
+    Ok(result)
+}
@@ -0,0 +1,297 @@
+// 重复笔记检测：每篇笔记的正文切成词级片段（shingle），计算 MinHash 签名存入工作区索引库，按 LSH 分桶找出高度重合的笔记
+// merge_notes 把两篇笔记合并为一篇，并把其他笔记中指向被删除笔记的链接改为指向保留的笔记
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::error::MobiError;
+use crate::{document, encryption, index, markdown, trash, workspace};
+
+// 每个片段包含的词数；中文每个字算一个词
+const SHINGLE_WORDS: usize = 5;
+// 签名长度，分成 BANDS 段，每段 ROWS 个值完全相同的笔记成为候选
+const SIGNATURE_LEN: usize = 128;
+const BANDS: usize = 32;
+const ROWS: usize = SIGNATURE_LEN / BANDS;
+// 片段太少的笔记（只有标题或几句话）容易误判，不参与比较
+const MIN_SHINGLES: usize = 10;
+// 签名算法变化时递增，旧签名全部重算
+const SIGNATURE_VERSION: i64 = 1;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimilarNotes {
+    a: String,
+    b: String,
+    // 估计的 Jaccard 相似度，0~1
+    similarity: f32,
+    a_words: usize,
+    b_words: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeResult {
+    path: String,
+    // 改写了链接的笔记
+    updated: Vec<String>,
+}
+
+fn ensure_schema(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS minhash_notes (
+            path TEXT PRIMARY KEY,
+            mtime INTEGER NOT NULL,
+            version INTEGER NOT NULL,
+            words INTEGER NOT NULL,
+            signature BLOB
+        );",
+    )
+    .map_err(|e| format!("初始化重复检测索引失败: {}", e))
+}
+
+// 英文等按字母数字连续切词，中日韩文字每个字单独成词
+pub fn words(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    for c in text.chars() {
+        if c.is_alphanumeric() && !is_cjk(c) {
+            current.extend(c.to_lowercase());
+            continue;
+        }
+        if !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+        if is_cjk(c) {
+            words.push(c.to_string());
+        }
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF | 0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xAC00..=0xD7AF | 0xF900..=0xFAFF)
+}
+
+// FNV-1a，签名要持久化，不能用每次运行结果不同的 DefaultHasher
+fn fnv(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ *b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+// splitmix64，配合不同的种子模拟 SIGNATURE_LEN 个独立的哈希函数
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e3779b97f4a7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+fn signature(content: &str) -> (usize, Option<Vec<u64>>) {
+    let words = words(&markdown::to_plain_text(markdown::strip_front_matter(
+        content,
+    )));
+    let shingles: HashSet<u64> = words
+        .windows(SHINGLE_WORDS)
+        .map(|w| fnv(w.join(" ").as_bytes()))
+        .collect();
+    if shingles.len() < MIN_SHINGLES {
+        return (words.len(), None);
+    }
+    let signature = (0..SIGNATURE_LEN as u64)
+        .map(|seed| {
+            let seed = mix(seed);
+            shingles
+                .iter()
+                .map(|s| mix(s ^ seed))
+                .min()
+                .unwrap_or(u64::MAX)
+        })
+        .collect();
+    (words.len(), Some(signature))
+}
+
+fn to_blob(signature: &[u64]) -> Vec<u8> {
+    signature.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+fn from_blob(blob: &[u8]) -> Vec<u64> {
+    blob.chunks_exact(8)
+        .map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+        .collect()
+}
+
+fn index_note(conn: &Connection, root: &Path, path: &Path) -> Result<(), String> {
+    let relative = workspace::relative_path(root, path);
+    let content = fs::read_to_string(path).unwrap_or_default();
+    // 加密笔记的密文没有比较意义
+    let (words, signature) = if encryption::is_envelope(&content) {
+        (0, None)
+    } else {
+        signature(&content)
+    };
+    conn.execute(
+        "INSERT OR REPLACE INTO minhash_notes (path, mtime, version, words, signature)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            relative,
+            workspace::modified_secs(path),
+            SIGNATURE_VERSION,
+            words,
+            signature.as_deref().map(to_blob)
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// 增量更新签名，返回 (相对路径, 词数, 签名)
+fn refresh(root: &Path) -> Result<Vec<(String, usize, Vec<u64>)>, String> {
+    let conn = index::open(root)?;
+    ensure_schema(&conn)?;
+
+    let mut existing: HashMap<String, (i64, i64)> = HashMap::new();
+    {
+        let mut stmt = conn
+            .prepare("SELECT path, mtime, version FROM minhash_notes")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |r| Ok((r.get(0)?, (r.get(1)?, r.get(2)?))))
+            .map_err(|e| e.to_string())?;
+        existing.extend(rows.flatten());
+    }
+
+    let mut current = HashSet::new();
+    for file in workspace::note_files(root) {
+        let relative = workspace::relative_path(root, &file);
+        let stamp = (workspace::modified_secs(&file), SIGNATURE_VERSION);
+        if existing.get(&relative) != Some(&stamp) {
+            index_note(&conn, root, &file)?;
+        }
+        current.insert(relative);
+    }
+    for path in existing.keys().filter(|p| !current.contains(*p)) {
+        conn.execute("DELETE FROM minhash_notes WHERE path = ?1", [path])
+            .map_err(|e| e.to_string())?;
+    }
+
+    let mut stmt = conn
+        .prepare("SELECT path, words, signature FROM minhash_notes WHERE signature IS NOT NULL")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |r| {
+            Ok((
+                r.get::<_, String>(0)?,
+                r.get::<_, usize>(1)?,
+                from_blob(&r.get::<_, Vec<u8>>(2)?),
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(rows
+        .flatten()
+        .filter(|(path, _, _)| current.contains(path))
+        .collect())
+}
+
+// threshold 为相似度下限（默认 0.7），结果按相似度从高到低排列
+#[tauri::command]
+pub async fn find_similar_notes(
+    workspace: String,
+    threshold: Option<f32>,
+) -> Result<Vec<SimilarNotes>, MobiError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = PathBuf::from(&workspace);
+        let threshold = threshold.unwrap_or(0.7).clamp(0.1, 1.0);
+        let config = workspace::settings(&root);
+        let notes: Vec<_> = refresh(&root)?
+            .into_iter()
+            .filter(|(path, _, _)| !config.hides(path))
+            .collect();
+
+        let mut buckets: HashMap<(usize, u64), Vec<usize>> = HashMap::new();
+        for (i, (_, _, signature)) in notes.iter().enumerate() {
+            for (band, rows) in signature.chunks(ROWS).enumerate() {
+                let key = rows.iter().fold(band as u64, |hash, v| mix(hash ^ v));
+                buckets.entry((band, key)).or_default().push(i);
+            }
+        }
+        let mut candidates = HashSet::new();
+        for members in buckets.values().filter(|m| m.len() > 1) {
+            for (n, &a) in members.iter().enumerate() {
+                for &b in &members[n + 1..] {
+                    candidates.insert((a, b));
+                }
+            }
+        }
+
+        let mut pairs: Vec<SimilarNotes> = candidates
+            .into_iter()
+            .filter_map(|(a, b)| {
+                let (a_path, a_words, a_sig) = &notes[a];
+                let (b_path, b_words, b_sig) = &notes[b];
+                let same = a_sig.iter().zip(b_sig).filter(|(x, y)| x == y).count();
+                let similarity = same as f32 / SIGNATURE_LEN as f32;
+                (similarity >= threshold).then(|| SimilarNotes {
+                    a: root.join(a_path).to_string_lossy().to_string(),
+                    b: root.join(b_path).to_string_lossy().to_string(),
+                    similarity,
+                    a_words: *a_words,
+                    b_words: *b_words,
+                })
+            })
+            .collect();
+        pairs.sort_by(|x, y| {
+            y.similarity
+                .total_cmp(&x.similarity)
+                .then_with(|| x.a.cmp(&y.a))
+        });
+        Ok(pairs)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// 把 remove 的正文（去掉 front matter，相对链接按 keep 的位置重写）接在 keep 后面，把所有笔记中指向 remove 的链接改为指向 keep，再把 remove 移入回收站
+#[tauri::command]
+pub fn merge_notes(
+    workspace: String,
+    keep: String,
+    remove: String,
+) -> Result<MergeResult, MobiError> {
+    let root = PathBuf::from(&workspace);
+    let (keep, remove) = (PathBuf::from(&keep), PathBuf::from(&remove));
+    if keep == remove {
+        return Err("不能把笔记与自身合并".into());
+    }
+    let kept = fs::read_to_string(&keep).map_err(|e| format!("读取文件失败: {}", e))?;
+    let removed = fs::read_to_string(&remove).map_err(|e| format!("读取文件失败: {}", e))?;
+    if encryption::is_envelope(&kept) || encryption::is_envelope(&removed) {
+        return Err("加密笔记需要先解密才能合并".into());
+    }
+    let body = workspace::rebase_links(
+        &root,
+        markdown::strip_front_matter(&removed).trim(),
+        &remove,
+        &keep,
+    );
+    let merged = format!("{}\n\n---\n\n{}\n", kept.trim_end(), body);
+    document::write_note(&keep, &merged)?;
+
+    let updated = workspace::retarget_links(&root, &remove, &keep, Some(&remove))?;
+    // keep 已引用 remove 的图片和附件，它们不会随之移入回收站
+    trash::trash_item(workspace, remove.to_string_lossy().to_string())?;
+
+    Ok(MergeResult {
+        path: keep.to_string_lossy().to_string(),
+        updated,
+    })
+}
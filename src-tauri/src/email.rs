@@ -0,0 +1,339 @@
+// 邮件发送：把笔记渲染为内联样式的 HTML（邮件客户端大多忽略 <style>），引用的本地图片作为内嵌附件（cid:）一起发送
+// SMTP 服务器设置在 smtp 设置节中，密码保存在系统钥匙串（smtp / password）中；SMTP 连接不经过 HTTP 代理
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use lettre::message::header::ContentType;
+use lettre::message::{Attachment, Mailbox, MultiPart, SinglePart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::error::MobiError;
+use crate::export::{self, ExportOptions};
+use crate::{encryption, markdown, preview, secrets, settings};
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+// 与 export/style.css 对应的内联样式
+pub const INLINE_STYLES: &[(&str, &str)] = &[
+    ("h1", "margin:24px 0 16px;font-size:2em;font-weight:600;line-height:1.25;border-bottom:1px solid #eee;padding-bottom:0.3em"),
+    ("h2", "margin:24px 0 16px;font-size:1.5em;font-weight:600;line-height:1.25;border-bottom:1px solid #eee;padding-bottom:0.3em"),
+    ("h3", "margin:24px 0 16px;font-size:1.25em;font-weight:600;line-height:1.25"),
+    ("h4", "margin:24px 0 16px;font-weight:600;line-height:1.25"),
+    ("h5", "margin:24px 0 16px;font-weight:600;line-height:1.25"),
+    ("h6", "margin:24px 0 16px;font-weight:600;line-height:1.25"),
+    ("p", "margin:0 0 16px"),
+    ("a", "color:#0366d6;text-decoration:none"),
+    ("code", "padding:0.2em 0.4em;font-size:85%;background-color:#f6f8fa;border-radius:3px;font-family:'SF Mono',Monaco,Menlo,Consolas,monospace"),
+    ("pre", "padding:16px;overflow:auto;font-size:85%;line-height:1.45;background-color:#f6f8fa;border-radius:6px"),
+    ("blockquote", "margin:0 0 16px;padding:0 1em;color:#6a737d;border-left:4px solid #dfe2e5"),
+    ("ul", "padding-left:2em;margin:0 0 16px"),
+    ("ol", "padding-left:2em;margin:0 0 16px"),
+    ("table", "border-collapse:collapse;width:100%;margin-bottom:16px"),
+    ("th", "padding:8px 13px;border:1px solid #dfe2e5;font-weight:600;background-color:#f6f8fa"),
+    ("td", "padding:8px 13px;border:1px solid #dfe2e5"),
+    ("img", "max-width:100%;height:auto"),
+    ("hr", "height:0.25em;padding:0;margin:24px 0;background-color:#e1e4e8;border:0"),
+];
+pub const BODY_STYLE: &str = "font-family:-apple-system,BlinkMacSystemFont,'Segoe UI',Roboto,'Helvetica Neue',Arial,sans-serif;font-size:16px;line-height:1.6;color:#333";
+
+#[derive(Serialize, Deserialize, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+enum SmtpSecurity {
+    // 587 端口，明文连接后升级为 TLS
+    #[default]
+    StartTls,
+    // 465 端口，直接 TLS
+    Tls,
+    // 仅用于本机或内网中继
+    None,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct SmtpSettings {
+    host: String,
+    port: u16,
+    security: SmtpSecurity,
+    // 为空时不认证
+    username: String,
+    // 发件人，如 "张三 <zhangsan@example.com>"，为空时使用用户名
+    from: String,
+}
+
+impl Default for SmtpSettings {
+    fn default() -> Self {
+        SmtpSettings {
+            host: String::new(),
+            port: 587,
+            security: SmtpSecurity::StartTls,
+            username: String::new(),
+            from: String::new(),
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct EmailOptions {
+    // 为空时使用笔记标题
+    subject: String,
+    cc: Vec<String>,
+    bcc: Vec<String>,
+    // 放在正文前面的附言
+    message: String,
+    // 同时把 Markdown 原文作为附件
+    attach_source: bool,
+    // 工作区路径，用于解析文献引用
+    workspace: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmailResult {
+    recipients: usize,
+    images: usize,
+}
+
+fn inline_styles(html: &str) -> String {
+    inline_with(html, |name| {
+        INLINE_STYLES
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, style)| style.to_string())
+    })
+}
+
+// 按标签名给标签加上内联样式；<pre> 中的 <code> 不加行内代码的样式
+pub fn inline_with(html: &str, style_for: impl Fn(&str) -> Option<String>) -> String {
+    let mut out = String::with_capacity(html.len() * 2);
+    let mut rest = html;
+    let mut in_pre = 0usize;
+    while let Some(pos) = rest.find('<') {
+        out.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+        let end = rest.find('>').map_or(rest.len(), |i| i + 1);
+        let tag = &rest[..end];
+        rest = &rest[end..];
+        let name: String = tag[1..]
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric() || *c == '/')
+            .collect::<String>()
+            .to_ascii_lowercase();
+        match name.as_str() {
+            "pre" => in_pre += 1,
+            "/pre" => in_pre = in_pre.saturating_sub(1),
+            _ => {}
+        }
+        let style = style_for(&name).filter(|_| !(name == "code" && in_pre > 0));
+        match style {
+            // 已有的样式（表格对齐、代码高亮）放在后面，优先生效
+            Some(style) if tag.contains(" style=\"") => {
+                out.push_str(&tag.replacen(" style=\"", &format!(" style=\"{};", style), 1));
+            }
+            Some(style) => {
+                let insert = 1 + name.len();
+                out.push_str(&tag[..insert]);
+                out.push_str(&format!(" style=\"{}\"", style));
+                out.push_str(&tag[insert..]);
+            }
+            None => out.push_str(tag),
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+// 把引用本地图片的 src 改为 cid:，返回 (HTML, [(Content-ID, 文件)])
+fn embed_images(html: &str, note_dir: &Path) -> (String, Vec<(String, PathBuf)>) {
+    let mut images: Vec<(String, PathBuf)> = Vec::new();
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(pos) = rest.find("src=\"") {
+        let start = pos + "src=\"".len();
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = rest.find('"').unwrap_or(rest.len());
+        let src = &rest[..end];
+        let local = (!src.contains("://") && !src.starts_with("data:") && !src.starts_with("cid:"))
+            .then(|| urlencoding::decode(src).ok())
+            .flatten()
+            .map(|decoded| note_dir.join(decoded.as_ref()))
+            .filter(|path| path.is_file());
+        match local {
+            Some(path) => {
+                let cid = match images.iter().find(|(_, p)| *p == path) {
+                    Some((cid, _)) => cid.clone(),
+                    None => {
+                        let cid = format!("image{}@mobi", images.len() + 1);
+                        images.push((cid.clone(), path));
+                        cid
+                    }
+                };
+                out.push_str(&format!("cid:{}", cid));
+            }
+            None => out.push_str(src),
+        }
+        rest = &rest[end..];
+    }
+    out.push_str(rest);
+    (out, images)
+}
+
+fn mailboxes(addresses: &[String]) -> Result<Vec<Mailbox>, String> {
+    addresses
+        .iter()
+        .map(|a| a.trim())
+        .filter(|a| !a.is_empty())
+        .map(|a| {
+            a.parse::<Mailbox>()
+                .map_err(|_| format!("邮件地址无效: {}", a))
+        })
+        .collect()
+}
+
+fn transport(config: &SmtpSettings) -> Result<AsyncSmtpTransport<Tokio1Executor>, String> {
+    let host = config.host.trim();
+    if host.is_empty() {
+        return Err("请先在设置中填写 SMTP 服务器".into());
+    }
+    let builder = match config.security {
+        SmtpSecurity::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host),
+        SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(host),
+        SmtpSecurity::None => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(
+            host,
+        )),
+    }
+    .map_err(|e| format!("SMTP 服务器设置无效: {}", e))?;
+    let mut builder = builder.port(config.port).timeout(Some(TIMEOUT));
+    if !config.username.trim().is_empty() {
+        let password = secrets::get_secret("smtp", "password")?.unwrap_or_default();
+        builder = builder.credentials(Credentials::new(
+            config.username.trim().to_string(),
+            password,
+        ));
+    }
+    Ok(builder.build())
+}
+
+#[tauri::command]
+pub async fn email_note(
+    app: AppHandle,
+    path: String,
+    to: Vec<String>,
+    options: Option<EmailOptions>,
+) -> Result<EmailResult, MobiError> {
+    let config: SmtpSettings = settings::load(&app, "smtp");
+    let options = options.unwrap_or_default();
+    let to = mailboxes(&to)?;
+    if to.is_empty() {
+        return Err("请填写收件人".into());
+    }
+    let cc = mailboxes(&options.cc)?;
+    let bcc = mailboxes(&options.bcc)?;
+    let from = match config.from.trim() {
+        "" => config.username.trim(),
+        from => from,
+    };
+    let from: Mailbox = from
+        .parse()
+        .map_err(|_| "发件人地址无效，请检查 SMTP 设置".to_string())?;
+
+    let source = PathBuf::from(&path);
+    let content = fs::read_to_string(&source).map_err(|e| format!("读取文件失败: {}", e))?;
+    if encryption::is_envelope(&content) {
+        return Err("加密笔记需要先解密才能发送".into());
+    }
+    let title = markdown::front_matter_field(&content, "title").unwrap_or_else(|| {
+        source
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default()
+    });
+    let subject = match options.subject.trim() {
+        "" => title.clone(),
+        subject => subject.to_string(),
+    };
+
+    let body = markdown::strip_front_matter(&content).to_string();
+    let export_options = ExportOptions {
+        title,
+        workspace: options.workspace.clone(),
+        ..Default::default()
+    };
+    let (body, export_options) = export::prepare(&app, body, Some(export_options)).await?;
+    let note_dir = source.parent().unwrap_or(Path::new(".")).to_path_buf();
+    let rendered = inline_styles(&export::render_body(&body, &export_options));
+    let (rendered, images) = embed_images(&rendered, &note_dir);
+    let intro = if options.message.trim().is_empty() {
+        String::new()
+    } else {
+        inline_styles(&export::render_body(
+            &options.message,
+            &ExportOptions::default(),
+        ))
+    };
+    let html = format!(
+        "<!DOCTYPE html><html><head><meta charset=\"UTF-8\"></head><body style=\"{}\">{}{}</body></html>",
+        BODY_STYLE,
+        intro,
+        rendered
+    );
+    let text = match options.message.trim() {
+        "" => markdown::to_plain_text(&body),
+        message => format!("{}\n\n{}", message, markdown::to_plain_text(&body)),
+    };
+
+    let mut related = MultiPart::related().singlepart(SinglePart::html(html));
+    for (cid, image) in &images {
+        let bytes = fs::read(image).map_err(|e| format!("读取图片失败: {}", e))?;
+        let content_type = ContentType::parse(preview::content_type(image))
+            .map_err(|e| format!("无法识别图片类型: {}", e))?;
+        related = related.singlepart(Attachment::new_inline(cid.clone()).body(bytes, content_type));
+    }
+    let alternative = MultiPart::alternative()
+        .singlepart(SinglePart::plain(text))
+        .multipart(related);
+    let body = if options.attach_source {
+        let name = source
+            .file_name()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| "note.md".into());
+        MultiPart::mixed().multipart(alternative).singlepart(
+            Attachment::new(name).body(
+                content.clone(),
+                ContentType::parse("text/markdown; charset=utf-8")
+                    .map_err(|e| format!("无法设置附件类型: {}", e))?,
+            ),
+        )
+    } else {
+        alternative
+    };
+
+    let mut builder = Message::builder().from(from).subject(subject);
+    for mailbox in &to {
+        builder = builder.to(mailbox.clone());
+    }
+    for mailbox in &cc {
+        builder = builder.cc(mailbox.clone());
+    }
+    for mailbox in &bcc {
+        builder = builder.bcc(mailbox.clone());
+    }
+    let message = builder
+        .multipart(body)
+        .map_err(|e| format!("生成邮件失败: {}", e))?;
+
+    transport(&config)?
+        .send(message)
+        .await
+        .map_err(|e| format!("发送邮件失败: {}", e))?;
+    Ok(EmailResult {
+        recipients: to.len() + cc.len() + bcc.len(),
+        images: images.len(),
+    })
+}
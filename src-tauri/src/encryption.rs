@@ -0,0 +1,506 @@
+// 加密笔记：口令经 Argon2id 派生密钥，正文用 XChaCha20-Poly1305 加密后以信封格式写回原文件
+// 解锁后的密钥按盐值缓存在内存中；加密文件夹只派生一个主密钥，每篇笔记用自己的盐值从中导出密钥，解锁一篇即解锁整个文件夹；超时未使用自动锁定
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Emitter, Manager, State};
+use zeroize::Zeroizing;
+
+use crate::error::{MobiError, MutexExt};
+use crate::{document, settings, shred, undo, vault, workspace};
+
+// 信封首行，用于识别加密笔记
+const MAGIC: &str = "MOBI-ENCRYPTED-NOTE v1";
+
+// Argon2id 参数（OWASP 推荐的最低配置）
+pub const MEMORY_KIB: u32 = 19 * 1024;
+pub const ITERATIONS: u32 = 2;
+pub const PARALLELISM: u32 = 1;
+
+// 从文件读到的参数上限，防止被改过的文件让派生密钥耗尽内存或长时间占用 CPU
+const MAX_MEMORY_KIB: u32 = 1024 * 1024;
+const MAX_ITERATIONS: u32 = 16;
+const MAX_PARALLELISM: u32 = 16;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct EncryptionSettings {
+    // 0 表示不自动锁定
+    auto_lock_minutes: u64,
+}
+
+impl Default for EncryptionSettings {
+    fn default() -> Self {
+        EncryptionSettings {
+            auto_lock_minutes: 10,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct Envelope {
+    memory: u32,
+    iterations: u32,
+    parallelism: u32,
+    salt: String,
+    // 加密文件夹中的笔记：文件夹主密钥的盐值，笔记密钥由主密钥和 salt 导出
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    folder: Option<String>,
+    nonce: String,
+    ciphertext: String,
+}
+
+// .mobi/encryption.json：加密文件夹（相对工作区路径）-> 盐值和口令校验信封
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct FolderRegistry {
+    folders: HashMap<String, Envelope>,
+}
+
+struct CachedKey {
+    key: Zeroizing<[u8; 32]>,
+    last_used: Instant,
+}
+
+#[derive(Default)]
+pub struct EncryptionState {
+    // 盐值 -> 密钥
+    keys: Mutex<HashMap<String, CachedKey>>,
+}
+
+pub fn is_envelope(content: &str) -> bool {
+    content.starts_with(MAGIC)
+}
+
+// 只读取文件开头判断是否为加密信封
+pub fn is_envelope_file(path: &Path) -> bool {
+    use std::io::Read;
+    let mut head = [0u8; MAGIC.len()];
+    fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut head))
+        .is_ok_and(|_| head == MAGIC.as_bytes())
+}
+
+fn parse_envelope(content: &str) -> Result<Envelope, String> {
+    let body = content.strip_prefix(MAGIC).ok_or("不是加密笔记")?.trim();
+    serde_json::from_str(body).map_err(|e| format!("加密笔记已损坏: {}", e))
+}
+
+fn format_envelope(envelope: &Envelope) -> Result<String, String> {
+    let body = serde_json::to_string_pretty(envelope).map_err(|e| e.to_string())?;
+    Ok(format!("{}\n{}\n", MAGIC, body))
+}
+
+pub fn check_params(memory: u32, iterations: u32, parallelism: u32) -> Result<(), MobiError> {
+    if memory > MAX_MEMORY_KIB || iterations > MAX_ITERATIONS || parallelism > MAX_PARALLELISM {
+        return Err(MobiError::InvalidInput(
+            "加密参数超出允许范围，文件可能已损坏或被篡改".into(),
+        ));
+    }
+    Ok(())
+}
+
+pub fn derive_key(
+    passphrase: &str,
+    salt: &[u8],
+    memory: u32,
+    iterations: u32,
+    parallelism: u32,
+) -> Result<Zeroizing<[u8; 32]>, String> {
+    let params = Params::new(memory, iterations, parallelism, Some(32))
+        .map_err(|e| format!("加密参数无效: {}", e))?;
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, key.as_mut())
+        .map_err(|e| format!("派生密钥失败: {}", e))?;
+    Ok(key)
+}
+
+pub fn new_salt() -> String {
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    BASE64.encode(salt)
+}
+
+pub fn decode(value: &str) -> Result<Vec<u8>, String> {
+    BASE64
+        .decode(value)
+        .map_err(|e| format!("加密笔记已损坏: {}", e))
+}
+
+// 主密钥是均匀随机的，用带域分隔的 SHA-256 导出子密钥即可
+fn note_subkey(master: &[u8; 32], salt: &str) -> Result<Zeroizing<[u8; 32]>, String> {
+    let mut hasher = Sha256::new();
+    hasher.update(b"mobi-folder-note\0");
+    hasher.update(master);
+    hasher.update(decode(salt)?);
+    let mut key = Zeroizing::new([0u8; 32]);
+    key.copy_from_slice(&hasher.finalize());
+    Ok(key)
+}
+
+fn seal(
+    key: &[u8; 32],
+    salt: &str,
+    folder: Option<&str>,
+    plaintext: &str,
+) -> Result<Envelope, String> {
+    let cipher = XChaCha20Poly1305::new(key.into());
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext.as_bytes())
+        .map_err(|_| "加密失败")?;
+    Ok(Envelope {
+        memory: MEMORY_KIB,
+        iterations: ITERATIONS,
+        parallelism: PARALLELISM,
+        salt: salt.to_string(),
+        folder: folder.map(String::from),
+        nonce: BASE64.encode(nonce),
+        ciphertext: BASE64.encode(ciphertext),
+    })
+}
+
+fn open(key: &[u8; 32], envelope: &Envelope) -> Result<String, MobiError> {
+    let nonce = decode(&envelope.nonce)?;
+    if nonce.len() != 24 {
+        return Err("加密笔记已损坏".into());
+    }
+    let plaintext = XChaCha20Poly1305::new(key.into())
+        .decrypt(
+            XNonce::from_slice(&nonce),
+            decode(&envelope.ciphertext)?.as_ref(),
+        )
+        .map_err(|_| MobiError::WrongPassphrase("口令错误或笔记已损坏".into()))?;
+    Ok(String::from_utf8(plaintext).map_err(|e| e.to_string())?)
+}
+
+fn unlock_envelope(
+    state: &EncryptionState,
+    envelope: &Envelope,
+    passphrase: &str,
+) -> Result<String, MobiError> {
+    check_params(envelope.memory, envelope.iterations, envelope.parallelism)?;
+    let master_salt = envelope.folder.as_ref().unwrap_or(&envelope.salt);
+    let key = derive_key(
+        passphrase,
+        &decode(master_salt)?,
+        envelope.memory,
+        envelope.iterations,
+        envelope.parallelism,
+    )?;
+    let plaintext = match &envelope.folder {
+        Some(_) => {
+            let note_key = note_subkey(&key, &envelope.salt)?;
+            open(&note_key, envelope)?
+        }
+        None => open(&key, envelope)?,
+    };
+    state.keys.locked().insert(
+        master_salt.clone(),
+        CachedKey {
+            key,
+            last_used: Instant::now(),
+        },
+    );
+    Ok(plaintext)
+}
+
+fn cached_key(state: &EncryptionState, salt: &str) -> Result<Zeroizing<[u8; 32]>, MobiError> {
+    let mut keys = state.keys.locked();
+    let cached = keys
+        .get_mut(salt)
+        .ok_or_else(|| MobiError::NeedsUnlock("笔记已锁定，请先解锁".into()))?;
+    cached.last_used = Instant::now();
+    Ok(cached.key.clone())
+}
+
+// 解密这篇笔记用的密钥，文件夹中的笔记由文件夹主密钥导出
+fn note_key(
+    state: &EncryptionState,
+    envelope: &Envelope,
+) -> Result<Zeroizing<[u8; 32]>, MobiError> {
+    match &envelope.folder {
+        Some(folder) => {
+            let master = cached_key(state, folder)?;
+            Ok(note_subkey(&master, &envelope.salt)?)
+        }
+        None => cached_key(state, &envelope.salt),
+    }
+}
+
+fn registry_path(root: &Path) -> Result<PathBuf, String> {
+    Ok(workspace::data_dir(root)?.join("encryption.json"))
+}
+
+fn load_registry(root: &Path) -> FolderRegistry {
+    registry_path(root)
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+fn save_registry(root: &Path, registry: &FolderRegistry) -> Result<(), String> {
+    let text = serde_json::to_string_pretty(registry).map_err(|e| e.to_string())?;
+    fs::write(registry_path(root)?, text).map_err(|e| format!("保存加密设置失败: {}", e))
+}
+
+// 笔记所在的加密文件夹（取最近的一层）
+fn folder_of(root: &Path, path: &Path) -> Option<Envelope> {
+    let relative = workspace::relative_path(root, path);
+    load_registry(root)
+        .folders
+        .into_iter()
+        .filter(|(folder, _)| relative.starts_with(&format!("{}/", folder)))
+        .max_by_key(|(folder, _)| folder.len())
+        .map(|(_, check)| check)
+}
+
+fn read(path: &str) -> Result<String, MobiError> {
+    fs::read_to_string(path).map_err(|e| MobiError::io("读取文件失败", e))
+}
+
+// 原子写入：写到一半中断时旧的信封仍然完整，密文不会因截断而无法恢复；经过符号链接时写入链接目标
+fn write_envelope(path: &str, envelope: &Envelope) -> Result<(), MobiError> {
+    let target = workspace::resolve_link(Path::new(path));
+    vault::write_atomic(&target, format_envelope(envelope)?.as_bytes())
+        .map_err(|e| MobiError::io("保存文件失败", e))
+}
+
+// 自动锁定的时限，设置为 0 时不自动锁定（加密仓库也使用）
+pub fn auto_lock_timeout(app: &AppHandle) -> Option<Duration> {
+    let config: EncryptionSettings = settings::load(app, "encryption");
+    (config.auto_lock_minutes > 0).then(|| Duration::from_secs(config.auto_lock_minutes * 60))
+}
+
+// 后台清理超时未使用的密钥，已解锁的加密仓库也一并锁定
+pub fn start_auto_lock(app: AppHandle) {
+    thread::spawn(move || loop {
+        thread::sleep(Duration::from_secs(30));
+        let Some(timeout) = auto_lock_timeout(&app) else {
+            continue;
+        };
+        let state = app.state::<EncryptionState>();
+        let mut keys = state.keys.locked();
+        let before = keys.len();
+        keys.retain(|_, cached| cached.last_used.elapsed() < timeout);
+        if keys.len() != before {
+            let _ = app.emit("notes-locked", ());
+        }
+        drop(keys);
+        vault::lock_idle(&app, timeout);
+    });
+}
+
+// 笔记本身是加密信封，或位于加密文件夹中（新建的笔记保存时也要加密）
+#[tauri::command]
+pub fn is_encrypted(workspace: Option<String>, path: String) -> bool {
+    if fs::read_to_string(&path).is_ok_and(|content| is_envelope(&content)) {
+        return true;
+    }
+    workspace.is_some_and(|root| folder_of(Path::new(&root), Path::new(&path)).is_some())
+}
+
+// 撤销历史和索引中保存着明文，加密后一并清除
+fn forget_plaintext(app: &AppHandle, root: Option<&Path>, path: &Path) -> Result<(), MobiError> {
+    undo::purge(app, &path.to_string_lossy());
+    if let Some(root) = root {
+        shred::purge_index(root, &workspace::relative_path(root, path))?;
+    }
+    Ok(())
+}
+
+// 加密后粉碎该笔记的撤销历史，并清除工作区索引中的记录
+#[tauri::command]
+pub fn encrypt_note(
+    app: AppHandle,
+    state: State<EncryptionState>,
+    workspace: Option<String>,
+    path: String,
+    passphrase: String,
+) -> Result<(), MobiError> {
+    let content = read(&path)?;
+    if is_envelope(&content) {
+        return Err("笔记已经加密".into());
+    }
+    if passphrase.is_empty() {
+        return Err("口令不能为空".into());
+    }
+    let salt = new_salt();
+    let key = derive_key(
+        &passphrase,
+        &decode(&salt)?,
+        MEMORY_KIB,
+        ITERATIONS,
+        PARALLELISM,
+    )?;
+    write_envelope(&path, &seal(&key, &salt, None, &content)?)?;
+    state.keys.locked().insert(
+        salt,
+        CachedKey {
+            key,
+            last_used: Instant::now(),
+        },
+    );
+    forget_plaintext(&app, workspace.as_deref().map(Path::new), Path::new(&path))
+}
+
+// 把文件夹标记为加密，并加密其中已有的笔记
+#[tauri::command]
+pub fn encrypt_folder(
+    app: AppHandle,
+    state: State<EncryptionState>,
+    workspace: String,
+    folder: String,
+    passphrase: String,
+) -> Result<usize, MobiError> {
+    if passphrase.is_empty() {
+        return Err("口令不能为空".into());
+    }
+    let root = PathBuf::from(&workspace);
+    let relative = workspace::relative_path(&root, Path::new(&folder));
+    let mut registry = load_registry(&root);
+    if registry.folders.contains_key(&relative) {
+        return Err("文件夹已经加密".into());
+    }
+
+    let salt = new_salt();
+    let key = derive_key(
+        &passphrase,
+        &decode(&salt)?,
+        MEMORY_KIB,
+        ITERATIONS,
+        PARALLELISM,
+    )?;
+    let mut count = 0;
+    for file in workspace::note_files(Path::new(&folder)) {
+        let content = read(&file.to_string_lossy())?;
+        if is_envelope(&content) {
+            continue;
+        }
+        let note_salt = new_salt();
+        let note_key = note_subkey(&key, &note_salt)?;
+        let envelope = seal(&note_key, &note_salt, Some(&salt), &content)?;
+        write_envelope(&file.to_string_lossy(), &envelope)?;
+        forget_plaintext(&app, Some(&root), &file)?;
+        count += 1;
+    }
+    // 空文件夹也能用校验信封验证口令
+    registry
+        .folders
+        .insert(relative, seal(&key, &salt, None, "")?);
+    save_registry(&root, &registry)?;
+    state.keys.locked().insert(
+        salt,
+        CachedKey {
+            key,
+            last_used: Instant::now(),
+        },
+    );
+    Ok(count)
+}
+
+// path 可以是笔记，也可以是加密文件夹；返回笔记明文（文件夹返回空字符串）
+#[tauri::command]
+pub fn unlock_note(
+    state: State<EncryptionState>,
+    workspace: Option<String>,
+    path: String,
+    passphrase: String,
+) -> Result<String, MobiError> {
+    if Path::new(&path).is_dir() {
+        let root = PathBuf::from(workspace.ok_or("缺少工作区路径")?);
+        let relative = workspace::relative_path(&root, Path::new(&path));
+        let registry = load_registry(&root);
+        let check = registry.folders.get(&relative).ok_or("文件夹未加密")?;
+        return unlock_envelope(&state, check, &passphrase);
+    }
+    let envelope = parse_envelope(&read(&path)?)?;
+    unlock_envelope(&state, &envelope, &passphrase)
+}
+
+// 已解锁时读取明文
+#[tauri::command]
+pub fn read_encrypted_note(
+    state: State<EncryptionState>,
+    path: String,
+) -> Result<String, MobiError> {
+    let envelope = parse_envelope(&read(&path)?)?;
+    let key = note_key(&state, &envelope)?;
+    open(&key, &envelope)
+}
+
+// 用已解锁的密钥加密保存；加密文件夹中的新笔记使用文件夹的密钥
+#[tauri::command]
+pub fn save_encrypted_note(
+    state: State<EncryptionState>,
+    workspace: Option<String>,
+    path: String,
+    content: String,
+) -> Result<(), MobiError> {
+    let envelope = match fs::read_to_string(&path) {
+        Ok(existing) if is_envelope(&existing) => parse_envelope(&existing)?,
+        _ => {
+            let root = PathBuf::from(workspace.ok_or("缺少工作区路径")?);
+            let check = folder_of(&root, Path::new(&path)).ok_or("笔记未加密")?;
+            // 文件夹中的新笔记：取得自己的盐值，由文件夹主密钥导出密钥
+            Envelope {
+                salt: new_salt(),
+                folder: Some(check.salt.clone()),
+                ..check
+            }
+        }
+    };
+    let key = note_key(&state, &envelope)?;
+    write_envelope(
+        &path,
+        &seal(&key, &envelope.salt, envelope.folder.as_deref(), &content)?,
+    )
+}
+
+// 锁定笔记（及共用密钥的同文件夹笔记），path 为空时锁定全部
+#[tauri::command]
+pub fn lock_note(
+    app: AppHandle,
+    state: State<EncryptionState>,
+    path: Option<String>,
+) -> Result<(), MobiError> {
+    match path {
+        Some(path) => {
+            let envelope = parse_envelope(&read(&path)?)?;
+            let salt = envelope.folder.unwrap_or(envelope.salt);
+            state.keys.locked().remove(&salt);
+        }
+        None => state.keys.locked().clear(),
+    }
+    let _ = app.emit("notes-locked", ());
+    Ok(())
+}
+
+// 取消单篇笔记的加密，写回明文
+#[tauri::command]
+pub fn decrypt_note(
+    state: State<EncryptionState>,
+    path: String,
+    passphrase: String,
+) -> Result<String, MobiError> {
+    let envelope = parse_envelope(&read(&path)?)?;
+    let plaintext = unlock_envelope(&state, &envelope, &passphrase)?;
+    document::write_note(Path::new(&path), &plaintext)?;
+    Ok(plaintext)
+}
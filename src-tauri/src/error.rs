@@ -0,0 +1,175 @@
+// 命令错误：序列化为 {code, message} 返回给前端，前端按 code 给出可操作的提示，message 可直接展示给用户
+// 模块内部仍可用 String 作错误，经 ? 转为 Failed；IO 错误按类型归类，文件被占用、磁盘只读等各有错误码
+
+use std::fmt;
+use std::io;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "code", content = "message", rename_all = "camelCase")]
+pub enum MobiError {
+    NotFound(String),
+    AlreadyExists(String),
+    PermissionDenied(String),
+    // 没有写入权限，但可以通过系统授权以管理员身份保存（macOS / Windows）
+    NeedsElevation(String),
+    // 文件所在的卷以只读方式挂载
+    ReadOnly(String),
+    // 文件被其他程序占用，或正被其他实例编辑
+    Locked(String),
+    DiskFull(String),
+    InvalidInput(String),
+    // 密码错误或数据已损坏，无法解密
+    WrongPassphrase(String),
+    // 加密笔记或仓库未解锁，前端应提示输入密码
+    NeedsUnlock(String),
+    Network(String),
+    Database(String),
+    // 当前平台或配置不支持
+    Unsupported(String),
+    Failed(String),
+}
+
+// Windows 的 ERROR_SHARING_VIOLATION / ERROR_LOCK_VIOLATION，Unix 的 EBUSY / ETXTBSY
+fn is_locked(err: &io::Error) -> bool {
+    match err.raw_os_error() {
+        Some(code) if cfg!(windows) => code == 32 || code == 33,
+        Some(code) => code == 16 || code == 26,
+        None => false,
+    }
+}
+
+// EROFS 在 Linux 和 macOS 上都是 30，Windows 为 ERROR_WRITE_PROTECT
+pub fn is_read_only_volume(err: &io::Error) -> bool {
+    err.raw_os_error() == Some(if cfg!(windows) { 19 } else { 30 })
+}
+
+// ENOSPC，Windows 为 ERROR_DISK_FULL / ERROR_HANDLE_DISK_FULL
+fn is_disk_full(err: &io::Error) -> bool {
+    match err.raw_os_error() {
+        Some(code) if cfg!(windows) => code == 112 || code == 39,
+        Some(code) => code == 28,
+        None => false,
+    }
+}
+
+impl MobiError {
+    // context 说明在做什么，如“读取文件失败”，后面接归类后的原因
+    pub fn io(context: &str, err: io::Error) -> Self {
+        let message = |reason: &str| format!("{}: {}", context, reason);
+        if is_locked(&err) {
+            return MobiError::Locked(message("文件被其他程序占用，请关闭后重试"));
+        }
+        if is_read_only_volume(&err) {
+            return MobiError::ReadOnly(message("文件所在的磁盘是只读的"));
+        }
+        if is_disk_full(&err) {
+            return MobiError::DiskFull(message("磁盘空间不足"));
+        }
+        match err.kind() {
+            io::ErrorKind::NotFound => MobiError::NotFound(message("文件或文件夹不存在")),
+            io::ErrorKind::AlreadyExists => MobiError::AlreadyExists(message("同名文件已存在")),
+            io::ErrorKind::PermissionDenied => MobiError::PermissionDenied(message("没有访问权限")),
+            io::ErrorKind::InvalidData => {
+                MobiError::InvalidInput(message("文件不是有效的 UTF-8 文本"))
+            }
+            _ => MobiError::Failed(message(&err.to_string())),
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            MobiError::NotFound(m)
+            | MobiError::AlreadyExists(m)
+            | MobiError::PermissionDenied(m)
+            | MobiError::NeedsElevation(m)
+            | MobiError::ReadOnly(m)
+            | MobiError::Locked(m)
+            | MobiError::DiskFull(m)
+            | MobiError::InvalidInput(m)
+            | MobiError::WrongPassphrase(m)
+            | MobiError::NeedsUnlock(m)
+            | MobiError::Network(m)
+            | MobiError::Database(m)
+            | MobiError::Unsupported(m)
+            | MobiError::Failed(m) => m,
+        }
+    }
+}
+
+impl fmt::Display for MobiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for MobiError {}
+
+impl From<String> for MobiError {
+    fn from(message: String) -> Self {
+        MobiError::Failed(message)
+    }
+}
+
+impl From<&str> for MobiError {
+    fn from(message: &str) -> Self {
+        MobiError::Failed(message.to_string())
+    }
+}
+
+impl From<io::Error> for MobiError {
+    fn from(err: io::Error) -> Self {
+        MobiError::io("操作失败", err)
+    }
+}
+
+impl From<rusqlite::Error> for MobiError {
+    fn from(err: rusqlite::Error) -> Self {
+        MobiError::Database(format!("索引数据库出错: {}", err))
+    }
+}
+
+impl From<tauri::Error> for MobiError {
+    fn from(err: tauri::Error) -> Self {
+        MobiError::Failed(err.to_string())
+    }
+}
+
+// 仍以 String 作错误的内部函数和命令行可以直接用 ? 接收
+impl From<MobiError> for String {
+    fn from(err: MobiError) -> Self {
+        err.message().to_string()
+    }
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct BackgroundError {
+    context: String,
+    message: String,
+}
+
+// 后台线程、自动同步和链接触发的动作没有调用方可以接收错误，改发 background-error 事件由前端提示
+pub fn report(app: &AppHandle, context: &str, err: impl fmt::Display) {
+    let _ = app.emit(
+        "background-error",
+        BackgroundError {
+            context: context.to_string(),
+            message: err.to_string(),
+        },
+    );
+}
+
+// 持有锁的线程 panic 后锁会中毒，继续使用其中的数据，不让一次 panic 拖垮之后所有命令
+pub trait MutexExt<T> {
+    fn locked(&self) -> MutexGuard<'_, T>;
+}
+
+impl<T> MutexExt<T> for Mutex<T> {
+    fn locked(&self) -> MutexGuard<'_, T> {
+        self.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
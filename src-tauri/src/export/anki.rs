@@ -0,0 +1,784 @@
+// Anki 卡片导出：从笔记中提取问答——指定级别的标题为问题、其下内容为答案，"问题 :: 答案" 单行卡片，以及 {{c1::挖空}} 填空卡片
+// 卡片正文按导出 HTML 渲染，引用的本地图片作为媒体一起导出；可以生成 .apkg 文件，也可以通过 AnkiConnect 直接添加到正在运行的 Anki
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use base64::Engine;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use super::ExportOptions;
+use crate::error::MobiError;
+use crate::{encryption, markdown, workspace};
+
+const ANKI_CONNECT_URL: &str = "http://127.0.0.1:8765";
+// 字段分隔符
+const FIELD_SEPARATOR: char = '\u{1f}';
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AnkiRules {
+    // 以这一级标题为问题、其下内容为答案，0 表示不按标题生成卡片
+    heading_level: usize,
+    // "问题 :: 答案" 形式的单行卡片
+    inline: bool,
+    // 含 {{c1::...}} 的段落生成填空卡片
+    cloze: bool,
+    // 附加到每张卡片的标签，另外会自动加上笔记名
+    tags: Vec<String>,
+}
+
+impl Default for AnkiRules {
+    fn default() -> Self {
+        AnkiRules {
+            heading_level: 2,
+            inline: true,
+            cloze: true,
+            tags: Vec::new(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum AnkiTarget {
+    // 生成 .apkg 文件
+    Apkg {
+        output: String,
+    },
+    // 通过 AnkiConnect 插件添加；模型名为空时使用 Basic / Cloze，取模型的前两个字段作为正反面
+    AnkiConnect {
+        #[serde(default)]
+        url: String,
+        #[serde(default)]
+        basic_model: String,
+        #[serde(default)]
+        cloze_model: String,
+    },
+}
+
+#[derive(Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct AnkiExport {
+    cards: usize,
+    // 生成的 .apkg 路径
+    path: Option<String>,
+    // AnkiConnect 中新增和因重复跳过的数量
+    added: usize,
+    skipped: usize,
+}
+
+enum CardKind {
+    Basic { front: String, back: String },
+    Cloze { text: String },
+}
+
+struct Card {
+    // 由笔记路径和问题生成，重复导入时 Anki 按它更新而不是新增
+    guid: String,
+    kind: CardKind,
+    tags: Vec<String>,
+}
+
+#[derive(Default)]
+struct Media {
+    // (源文件, 包内文件名)
+    files: Vec<(PathBuf, String)>,
+    names: HashMap<PathBuf, String>,
+}
+
+impl Media {
+    // 把 HTML 中引用本地图片的 src 改为媒体文件名
+    fn collect(&mut self, html: &str, note_dir: &Path) -> String {
+        let mut out = String::with_capacity(html.len());
+        let mut rest = html;
+        while let Some(pos) = rest.find("src=\"") {
+            let start = pos + "src=\"".len();
+            out.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let end = rest.find('"').unwrap_or(rest.len());
+            let src = &rest[..end];
+            let local = (!src.contains("://") && !src.starts_with("data:"))
+                .then(|| urlencoding::decode(src).ok())
+                .flatten()
+                .map(|decoded| note_dir.join(decoded.as_ref()))
+                .filter(|path| path.is_file());
+            match local {
+                Some(path) => out.push_str(&self.add(path)),
+                None => out.push_str(src),
+            }
+            rest = &rest[end..];
+        }
+        out.push_str(rest);
+        out
+    }
+
+    fn add(&mut self, path: PathBuf) -> String {
+        if let Some(name) = self.names.get(&path) {
+            return name.clone();
+        }
+        // 不同目录下的同名图片加上路径摘要区分
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let digest = format!("{:x}", Sha256::digest(path.to_string_lossy().as_bytes()));
+        let name = format!("mobi-{}-{}", &digest[..8], file_name);
+        self.names.insert(path.clone(), name.clone());
+        self.files.push((path, name.clone()));
+        name
+    }
+}
+
+fn guid(source: &str, key: &str) -> String {
+    let digest = Sha256::digest(format!("{}\n{}", source, key).as_bytes());
+    format!("{:x}", digest)[..20].to_string()
+}
+
+fn render(markdown: &str, note_dir: &Path, media: &mut Media) -> String {
+    let html = super::render_body(markdown, &ExportOptions::default());
+    media.collect(html.trim(), note_dir)
+}
+
+fn is_cloze(line: &str) -> bool {
+    line.find("{{c").is_some_and(|i| {
+        let rest = &line[i + 3..];
+        let digits = rest.chars().take_while(char::is_ascii_digit).count();
+        digits > 0 && rest[digits..].starts_with("::")
+    })
+}
+
+// 去掉列表标记，"- 问题 :: 答案" 也算单行卡片
+fn strip_list_marker(line: &str) -> &str {
+    let trimmed = line.trim_start();
+    for marker in ["- ", "* ", "+ "] {
+        if let Some(rest) = trimmed.strip_prefix(marker) {
+            return rest;
+        }
+    }
+    let digits = trimmed.chars().take_while(char::is_ascii_digit).count();
+    if digits > 0 {
+        if let Some(rest) = trimmed[digits..].strip_prefix(". ") {
+            return rest;
+        }
+    }
+    trimmed
+}
+
+// 行内恰好一个不在 `代码` 中的 ::，避免把 C++ 的 std::vector 之类当成卡片
+fn split_inline(line: &str) -> Option<(&str, &str)> {
+    if is_cloze(line) {
+        return None;
+    }
+    let mut in_code = false;
+    let mut found = None;
+    let bytes = line.as_bytes();
+    for i in 0..bytes.len() {
+        match bytes[i] {
+            b'`' => in_code = !in_code,
+            b':' if !in_code
+                && bytes.get(i + 1) == Some(&b':')
+                && (i == 0 || bytes[i - 1] != b':') =>
+            {
+                if found.is_some() {
+                    return None;
+                }
+                found = Some(i);
+            }
+            _ => {}
+        }
+    }
+    found.map(|i| (&line[..i], &line[i + 2..]))
+}
+
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let trimmed = line.trim_start();
+    let level = trimmed.chars().take_while(|c| *c == '#').count();
+    let title = &trimmed[level..];
+    (level > 0 && level <= 6 && (title.is_empty() || title.starts_with(' ')))
+        .then(|| (level, title.trim().trim_end_matches('#').trim()))
+}
+
+fn extract(
+    source: &str,
+    content: &str,
+    note_dir: &Path,
+    rules: &AnkiRules,
+    tags: &[String],
+    media: &mut Media,
+) -> Vec<Card> {
+    let body = markdown::strip_front_matter(content);
+    let lines: Vec<&str> = body.lines().collect();
+    let mut in_code = vec![false; lines.len()];
+    for block in markdown::fenced_blocks(body) {
+        for flag in &mut in_code[block.start_line..=block.end_line.min(lines.len() - 1)] {
+            *flag = true;
+        }
+    }
+
+    let mut cards = Vec::new();
+    // 已生成单行或填空卡片的行，不再出现在标题卡片的答案中
+    let mut used = vec![false; lines.len()];
+    let card = |key: &str, kind| Card {
+        guid: guid(source, key),
+        kind,
+        tags: tags.to_vec(),
+    };
+
+    if rules.inline || rules.cloze {
+        // 填空卡片以段落为单位
+        let mut i = 0;
+        while i < lines.len() {
+            if in_code[i] || lines[i].trim().is_empty() {
+                i += 1;
+                continue;
+            }
+            let mut end = i;
+            while end + 1 < lines.len() && !in_code[end + 1] && !lines[end + 1].trim().is_empty() {
+                end += 1;
+            }
+            let paragraph = lines[i..=end].join("\n");
+            if rules.cloze && is_cloze(&paragraph) && heading(lines[i]).is_none() {
+                let html = render(&paragraph, note_dir, media);
+                cards.push(card(&paragraph, CardKind::Cloze { text: html }));
+                used[i..=end].iter_mut().for_each(|u| *u = true);
+            } else if rules.inline {
+                for n in i..=end {
+                    let line = strip_list_marker(lines[n]);
+                    let Some((front, back)) = split_inline(line) else {
+                        continue;
+                    };
+                    let (front, back) = (front.trim(), back.trim());
+                    if front.is_empty() || back.is_empty() || heading(lines[n]).is_some() {
+                        continue;
+                    }
+                    cards.push(card(
+                        front,
+                        CardKind::Basic {
+                            front: render(front, note_dir, media),
+                            back: render(back, note_dir, media),
+                        },
+                    ));
+                    used[n] = true;
+                }
+            }
+            i = end + 1;
+        }
+    }
+
+    if rules.heading_level > 0 {
+        let mut i = 0;
+        while i < lines.len() {
+            let Some((level, title)) = heading(lines[i]).filter(|_| !in_code[i]) else {
+                i += 1;
+                continue;
+            };
+            if level != rules.heading_level || title.is_empty() {
+                i += 1;
+                continue;
+            }
+            // 答案到下一个同级或更高级标题为止
+            let mut end = i + 1;
+            while end < lines.len()
+                && (in_code[end] || heading(lines[end]).is_none_or(|(l, _)| l > level))
+            {
+                end += 1;
+            }
+            let answer = (i + 1..end)
+                .filter(|&n| !used[n])
+                .map(|n| lines[n])
+                .collect::<Vec<_>>()
+                .join("\n");
+            if !answer.trim().is_empty() {
+                cards.push(card(
+                    title,
+                    CardKind::Basic {
+                        front: render(title, note_dir, media),
+                        back: render(&answer, note_dir, media),
+                    },
+                ));
+            }
+            i = end;
+        }
+    }
+    cards
+}
+
+// Anki 标签不能含空格
+fn tag(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join("_")
+}
+
+fn collect_cards(source: &Path, rules: &AnkiRules, media: &mut Media) -> Result<Vec<Card>, String> {
+    let (root, files) = if source.is_dir() {
+        (source.to_path_buf(), workspace::note_files(source))
+    } else {
+        let root = source.parent().map(Path::to_path_buf).unwrap_or_default();
+        (root, vec![source.to_path_buf()])
+    };
+    let mut cards = Vec::new();
+    for file in files {
+        let content = fs::read_to_string(&file).map_err(|e| format!("读取文件失败: {}", e))?;
+        if encryption::is_envelope(&content) {
+            continue;
+        }
+        let relative = workspace::relative_path(&root, &file);
+        let stem = file
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let mut tags: Vec<String> = rules.tags.iter().map(|t| tag(t)).collect();
+        tags.push(tag(&stem));
+        let note_dir = file.parent().unwrap_or(&root);
+        cards.extend(extract(&relative, &content, note_dir, rules, &tags, media));
+    }
+    Ok(cards)
+}
+
+// 由名称得到固定的 id，重复导出到同一牌组时 Anki 会合并而不是新建
+fn stable_id(name: &str) -> i64 {
+    let digest = Sha256::digest(name.as_bytes());
+    let value = u64::from_le_bytes(digest[..8].try_into().unwrap());
+    1_000_000_000_000 + (value % 1_000_000_000_000) as i64
+}
+
+fn strip_html(html: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text.trim().to_string()
+}
+
+// 笔记首字段的校验和：SHA-1 前 8 位十六进制
+fn checksum(field: &str) -> i64 {
+    let digest = Sha1::digest(strip_html(field).as_bytes());
+    u32::from_be_bytes(digest[..4].try_into().unwrap()) as i64
+}
+
+const SCHEMA: &str = "
+CREATE TABLE col (
+    id integer primary key, crt integer not null, mod integer not null, scm integer not null,
+    ver integer not null, dty integer not null, usn integer not null, ls integer not null,
+    conf text not null, models text not null, decks text not null, dconf text not null, tags text not null
+);
+CREATE TABLE notes (
+    id integer primary key, guid text not null, mid integer not null, mod integer not null,
+    usn integer not null, tags text not null, flds text not null, sfld integer not null,
+    csum integer not null, flags integer not null, data text not null
+);
+CREATE TABLE cards (
+    id integer primary key, nid integer not null, did integer not null, ord integer not null,
+    mod integer not null, usn integer not null, type integer not null, queue integer not null,
+    due integer not null, ivl integer not null, factor integer not null, reps integer not null,
+    lapses integer not null, left integer not null, odue integer not null, odid integer not null,
+    flags integer not null, data text not null
+);
+CREATE TABLE revlog (
+    id integer primary key, cid integer not null, usn integer not null, ease integer not null,
+    ivl integer not null, lastIvl integer not null, factor integer not null, time integer not null,
+    type integer not null
+);
+CREATE TABLE graves (usn integer not null, oid integer not null, type integer not null);
+CREATE INDEX ix_notes_usn on notes (usn);
+CREATE INDEX ix_cards_usn on cards (usn);
+CREATE INDEX ix_revlog_usn on revlog (usn);
+CREATE INDEX ix_cards_nid on cards (nid);
+CREATE INDEX ix_cards_sched on cards (did, queue, due);
+CREATE INDEX ix_revlog_cid on revlog (cid);
+CREATE INDEX ix_notes_csum on notes (csum);
+";
+
+const CARD_CSS: &str = ".card { font-family: -apple-system, BlinkMacSystemFont, sans-serif; font-size: 18px; \
+text-align: left; color: #1f2328; background: #fff; } .cloze { font-weight: bold; color: #0969da; } \
+pre { overflow-x: auto; padding: 8px; border-radius: 4px; }";
+
+fn field(name: &str, ord: usize) -> Value {
+    json!({ "name": name, "ord": ord, "sticky": false, "rtl": false, "font": "Arial", "size": 20, "media": [] })
+}
+
+fn model(id: i64, deck_id: i64, now: i64, cloze: bool) -> Value {
+    let (name, fields, template) = if cloze {
+        (
+            "Mobi Cloze",
+            vec![field("Text", 0), field("Back Extra", 1)],
+            json!({ "name": "Cloze", "ord": 0, "qfmt": "{{cloze:Text}}",
+                    "afmt": "{{cloze:Text}}<br>{{Back Extra}}", "did": null, "bqfmt": "", "bafmt": "" }),
+        )
+    } else {
+        (
+            "Mobi Basic",
+            vec![field("Front", 0), field("Back", 1)],
+            json!({ "name": "Card 1", "ord": 0, "qfmt": "{{Front}}",
+                    "afmt": "{{FrontSide}}<hr id=answer>{{Back}}", "did": null, "bqfmt": "", "bafmt": "" }),
+        )
+    };
+    json!({
+        "id": id, "name": name, "type": if cloze { 1 } else { 0 }, "mod": now, "usn": -1,
+        "sortf": 0, "did": deck_id, "tmpls": [template], "flds": fields, "css": CARD_CSS,
+        "latexPre": "\\documentclass[12pt]{article}\n\\special{papersize=3in,5in}\n\\usepackage{amssymb,amsmath}\n\\pagestyle{empty}\n\\setlength{\\parindent}{0in}\n\\begin{document}\n",
+        "latexPost": "\\end{document}", "tags": [], "vers": [], "req": [[0, "all", [0]]]
+    })
+}
+
+fn deck(id: i64, name: &str, now: i64) -> Value {
+    json!({
+        "id": id, "name": name, "desc": "", "mod": now, "usn": -1, "collapsed": false,
+        "newToday": [0, 0], "revToday": [0, 0], "lrnToday": [0, 0], "timeToday": [0, 0],
+        "conf": 1, "dyn": 0, "extendNew": 10, "extendRev": 50
+    })
+}
+
+fn deck_options() -> Value {
+    json!({ "1": {
+        "id": 1, "name": "Default", "replayq": true, "timer": 0, "maxTaken": 60, "usn": 0,
+        "mod": 0, "autoplay": true,
+        "lapse": { "delays": [10], "mult": 0, "minInt": 1, "leechFails": 8, "leechAction": 0 },
+        "rev": { "perDay": 100, "ease4": 1.3, "fuzz": 0.05, "minSpace": 1, "ivlFct": 1,
+                 "maxIvl": 36500, "bury": true },
+        "new": { "delays": [1, 10], "ints": [1, 4, 7], "initialFactor": 2500, "separate": true,
+                 "order": 1, "perDay": 20, "bury": true }
+    }})
+}
+
+// 按 Anki 2.1 的旧版集合格式（schema 11）写入 SQLite，各版本 Anki 都能导入
+fn write_collection(path: &Path, deck_name: &str, cards: &[Card]) -> Result<(), String> {
+    let conn = Connection::open(path).map_err(|e| format!("创建卡片集合失败: {}", e))?;
+    conn.execute_batch(SCHEMA).map_err(|e| e.to_string())?;
+
+    let now = chrono::Utc::now().timestamp();
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let deck_id = stable_id(&format!("deck:{}", deck_name));
+    let basic_id = stable_id("model:mobi-basic");
+    let cloze_id = stable_id("model:mobi-cloze");
+    let models = json!({
+        basic_id.to_string(): model(basic_id, deck_id, now, false),
+        cloze_id.to_string(): model(cloze_id, deck_id, now, true),
+    });
+    let decks = json!({
+        "1": deck(1, "Default", now),
+        deck_id.to_string(): deck(deck_id, deck_name, now),
+    });
+    let conf = json!({
+        "nextPos": cards.len() + 1, "estTimes": true, "activeDecks": [1], "sortType": "noteFld",
+        "timeLim": 0, "sortBackwards": false, "addToCur": true, "curDeck": 1, "newBury": true,
+        "newSpread": 0, "dueCounts": true, "curModel": basic_id.to_string(), "collapseTime": 1200
+    });
+    conn.execute(
+        "INSERT INTO col VALUES (1, ?1, ?2, ?2, 11, 0, 0, 0, ?3, ?4, ?5, ?6, '{}')",
+        params![
+            now,
+            now_ms,
+            conf.to_string(),
+            models.to_string(),
+            decks.to_string(),
+            deck_options().to_string()
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let mut card_id = now_ms;
+    for (i, card) in cards.iter().enumerate() {
+        let note_id = now_ms + i as i64;
+        let (model_id, fields, ords) = match &card.kind {
+            CardKind::Basic { front, back } => (basic_id, [front.as_str(), back.as_str()], vec![0]),
+            CardKind::Cloze { text } => {
+                let mut ords = cloze_numbers(text);
+                if ords.is_empty() {
+                    ords.push(0);
+                }
+                (cloze_id, [text.as_str(), ""], ords)
+            }
+        };
+        let tags = format!(" {} ", card.tags.join(" "));
+        conn.execute(
+            "INSERT INTO notes VALUES (?1, ?2, ?3, ?4, -1, ?5, ?6, ?7, ?8, 0, '')",
+            params![
+                note_id,
+                card.guid,
+                model_id,
+                now,
+                tags,
+                fields.join(&FIELD_SEPARATOR.to_string()),
+                strip_html(fields[0]),
+                checksum(fields[0])
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+        for ord in ords {
+            card_id += 1;
+            conn.execute(
+                "INSERT INTO cards VALUES (?1, ?2, ?3, ?4, ?5, -1, 0, 0, ?6, 0, 0, 0, 0, 0, 0, 0, 0, '')",
+                params![card_id, note_id, deck_id, ord, now, i + 1],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+// 填空卡片中出现的编号，c1 对应第 0 张卡片
+fn cloze_numbers(text: &str) -> Vec<usize> {
+    let mut numbers: Vec<usize> = text
+        .match_indices("{{c")
+        .filter_map(|(i, _)| {
+            let rest = &text[i + 3..];
+            let digits: String = rest.chars().take_while(char::is_ascii_digit).collect();
+            rest[digits.len()..]
+                .starts_with("::")
+                .then(|| digits.parse::<usize>().ok())
+                .flatten()
+        })
+        .filter(|n| *n > 0)
+        .map(|n| n - 1)
+        .collect();
+    numbers.sort_unstable();
+    numbers.dedup();
+    numbers
+}
+
+fn write_apkg(output: &Path, deck: &str, cards: &[Card], media: &Media) -> Result<(), String> {
+    let collection = std::env::temp_dir().join(format!(
+        "mobi-anki-{}.anki2",
+        chrono::Utc::now().timestamp_millis()
+    ));
+    let result = (|| {
+        write_collection(&collection, deck, cards)?;
+        let file = File::create(output).map_err(|e| format!("创建文件失败: {}", e))?;
+        let mut zip = ZipWriter::new(file);
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        let write_err = |e: zip::result::ZipError| format!("写入 apkg 失败: {}", e);
+        zip.start_file("collection.anki2", options)
+            .map_err(write_err)?;
+        zip.write_all(&fs::read(&collection).map_err(|e| e.to_string())?)
+            .map_err(|e| format!("写入 apkg 失败: {}", e))?;
+        // media 文件把包内的编号映射到文件名
+        let mut index = serde_json::Map::new();
+        for (i, (path, name)) in media.files.iter().enumerate() {
+            let Ok(bytes) = fs::read(path) else {
+                continue;
+            };
+            zip.start_file(i.to_string(), options).map_err(write_err)?;
+            zip.write_all(&bytes)
+                .map_err(|e| format!("写入 apkg 失败: {}", e))?;
+            index.insert(i.to_string(), Value::String(name.clone()));
+        }
+        zip.start_file("media", options).map_err(write_err)?;
+        zip.write_all(Value::Object(index).to_string().as_bytes())
+            .map_err(|e| format!("写入 apkg 失败: {}", e))?;
+        zip.finish().map_err(write_err)?;
+        Ok(())
+    })();
+    let _ = fs::remove_file(&collection);
+    result
+}
+
+async fn anki_connect(
+    client: &reqwest::Client,
+    url: &str,
+    action: &str,
+    params: Value,
+) -> Result<Value, String> {
+    #[derive(Deserialize)]
+    struct Response {
+        result: Value,
+        error: Option<String>,
+    }
+    let response: Response = client
+        .post(url)
+        .json(&json!({ "action": action, "version": 6, "params": params }))
+        .send()
+        .await
+        .map_err(|e| {
+            format!(
+                "无法连接 AnkiConnect，请确认 Anki 已启动并安装了 AnkiConnect 插件: {}",
+                e
+            )
+        })?
+        .json()
+        .await
+        .map_err(|e| format!("AnkiConnect 返回了无法识别的结果: {}", e))?;
+    match response.error {
+        Some(error) => Err(format!("AnkiConnect 错误: {}", error)),
+        None => Ok(response.result),
+    }
+}
+
+// 取模型的前两个字段名，用作正反面
+async fn model_fields(
+    client: &reqwest::Client,
+    url: &str,
+    model: &str,
+) -> Result<(String, String), String> {
+    let fields = anki_connect(
+        client,
+        url,
+        "modelFieldNames",
+        json!({ "modelName": model }),
+    )
+    .await?;
+    let names: Vec<String> = serde_json::from_value(fields).unwrap_or_default();
+    match names.as_slice() {
+        [first, second, ..] => Ok((first.clone(), second.clone())),
+        _ => Err(format!("Anki 中的模型 {} 少于两个字段", model)),
+    }
+}
+
+async fn push_anki_connect(
+    url: &str,
+    deck: &str,
+    (basic_model, cloze_model): (&str, &str),
+    cards: &[Card],
+    media: &Media,
+) -> Result<(usize, usize), String> {
+    // AnkiConnect 在本机，不走代理
+    let client = reqwest::Client::builder()
+        .no_proxy()
+        .build()
+        .map_err(|e| e.to_string())?;
+    anki_connect(&client, url, "createDeck", json!({ "deck": deck })).await?;
+    for (path, name) in &media.files {
+        let Ok(bytes) = fs::read(path) else {
+            continue;
+        };
+        let data = base64::engine::general_purpose::STANDARD.encode(bytes);
+        anki_connect(
+            &client,
+            url,
+            "storeMediaFile",
+            json!({ "filename": name, "data": data }),
+        )
+        .await?;
+    }
+
+    let basic_fields = model_fields(&client, url, basic_model).await?;
+    let cloze_fields = if cards
+        .iter()
+        .any(|c| matches!(c.kind, CardKind::Cloze { .. }))
+    {
+        Some(model_fields(&client, url, cloze_model).await?)
+    } else {
+        None
+    };
+    let notes: Vec<Value> = cards
+        .iter()
+        .map(|card| {
+            let (model, (front_name, back_name), front, back) = match &card.kind {
+                CardKind::Basic { front, back } => {
+                    (basic_model, &basic_fields, front, back.as_str())
+                }
+                CardKind::Cloze { text } => (cloze_model, cloze_fields.as_ref().unwrap(), text, ""),
+            };
+            json!({
+                "deckName": deck,
+                "modelName": model,
+                "fields": { front_name.as_str(): front, back_name.as_str(): back },
+                "tags": card.tags,
+                "options": { "allowDuplicate": false, "duplicateScope": "deck" }
+            })
+        })
+        .collect();
+    // addNotes 遇到重复会整体失败，先筛出可以添加的
+    let allowed = anki_connect(&client, url, "canAddNotes", json!({ "notes": notes })).await?;
+    let allowed: Vec<bool> = serde_json::from_value(allowed).unwrap_or_default();
+    let to_add: Vec<&Value> = notes
+        .iter()
+        .zip(allowed.iter().chain(std::iter::repeat(&false)))
+        .filter(|(_, ok)| **ok)
+        .map(|(note, _)| note)
+        .collect();
+    let added = if to_add.is_empty() {
+        0
+    } else {
+        let ids = anki_connect(&client, url, "addNotes", json!({ "notes": to_add })).await?;
+        ids.as_array()
+            .map_or(0, |ids| ids.iter().filter(|id| !id.is_null()).count())
+    };
+    Ok((added, notes.len() - added))
+}
+
+// source 为单个笔记或工作区目录；rules 为空时按默认规则（二级标题、单行、填空）提取
+#[tauri::command]
+pub async fn export_anki(
+    source: String,
+    deck: String,
+    rules: Option<AnkiRules>,
+    target: AnkiTarget,
+) -> Result<AnkiExport, MobiError> {
+    let deck = if deck.trim().is_empty() {
+        "Mobi".to_string()
+    } else {
+        deck.trim().to_string()
+    };
+    let rules = rules.unwrap_or_default();
+    let (cards, media) = tauri::async_runtime::spawn_blocking(move || {
+        let mut media = Media::default();
+        collect_cards(Path::new(&source), &rules, &mut media).map(|cards| (cards, media))
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    if cards.is_empty() {
+        return Err("没有找到可以导出的问答".into());
+    }
+
+    let mut result = AnkiExport {
+        cards: cards.len(),
+        ..Default::default()
+    };
+    match target {
+        AnkiTarget::Apkg { output } => {
+            let deck = deck.clone();
+            let path = output.clone();
+            tauri::async_runtime::spawn_blocking(move || {
+                write_apkg(Path::new(&path), &deck, &cards, &media)
+            })
+            .await
+            .map_err(|e| e.to_string())??;
+            result.path = Some(output);
+        }
+        AnkiTarget::AnkiConnect {
+            url,
+            basic_model,
+            cloze_model,
+        } => {
+            let url = if url.is_empty() {
+                ANKI_CONNECT_URL
+            } else {
+                &url
+            };
+            let basic = if basic_model.is_empty() {
+                "Basic"
+            } else {
+                &basic_model
+            };
+            let cloze = if cloze_model.is_empty() {
+                "Cloze"
+            } else {
+                &cloze_model
+            };
+            let (added, skipped) =
+                push_anki_connect(url, &deck, (basic, cloze), &cards, &media).await?;
+            result.added = added;
+            result.skipped = skipped;
+        }
+    }
+    Ok(result)
+}
@@ -0,0 +1,476 @@
+// 分享包：把一篇笔记导出为 渲染好的 HTML + 可打印的 PDF + 原始 Markdown + 引用到的本地文件，放在一个文件夹或 zip 中
+// 本地图片和附件复制到 assets/，HTML 和 Markdown 中的链接改为指向副本；PDF 交给本机的 Chromium 内核浏览器、WeasyPrint 或 wkhtmltopdf 生成，需要口令时再由 qpdf 加密
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::OsRng;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use super::paged::PageSetup;
+use super::themes::{self, ThemeTarget};
+use super::ExportOptions;
+use crate::error::MobiError;
+use crate::{encryption, markdown, settings, workspace};
+
+const ASSETS_DIR: &str = "assets";
+// 未指定命令时依次查找的 PDF 引擎
+const PDF_ENGINES: &[&str] = &[
+    "google-chrome",
+    "chromium",
+    "chromium-browser",
+    "msedge",
+    "weasyprint",
+    "wkhtmltopdf",
+];
+#[cfg(target_os = "macos")]
+const BROWSER_PATHS: &[&str] = &[
+    "/Applications/Google Chrome.app/Contents/MacOS/Google Chrome",
+    "/Applications/Microsoft Edge.app/Contents/MacOS/Microsoft Edge",
+    "/Applications/Chromium.app/Contents/MacOS/Chromium",
+];
+#[cfg(target_os = "windows")]
+const BROWSER_PATHS: &[&str] = &[
+    r"C:\Program Files (x86)\Microsoft\Edge\Application\msedge.exe",
+    r"C:\Program Files\Microsoft\Edge\Application\msedge.exe",
+    r"C:\Program Files\Google\Chrome\Application\chrome.exe",
+];
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const BROWSER_PATHS: &[&str] = &[];
+
+// PDF 引擎设置，存在设置的 pdf 节
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct PdfSettings {
+    // 引擎的可执行文件，留空时自动查找
+    command: String,
+    // 给 PDF 加密用的 qpdf，留空时在 PATH 中查找
+    qpdf: String,
+}
+
+// PDF 的口令和权限，全部留空时不加密
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PdfOptions {
+    // 打开文件所需的口令，留空时任何人都能打开，但仍受权限限制
+    pub user_password: String,
+    // 修改权限所需的口令，留空时随机生成，限制无法被解除
+    pub owner_password: String,
+    pub allow_print: bool,
+    pub allow_copy: bool,
+}
+
+impl Default for PdfOptions {
+    fn default() -> Self {
+        PdfOptions {
+            user_password: String::new(),
+            owner_password: String::new(),
+            allow_print: true,
+            allow_copy: true,
+        }
+    }
+}
+
+impl PdfOptions {
+    fn is_empty(&self) -> bool {
+        self.user_password.is_empty()
+            && self.owner_password.is_empty()
+            && self.allow_print
+            && self.allow_copy
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BundleOptions {
+    // 文件夹或 .zip 路径，留空时放在笔记旁边
+    output: String,
+    zip: bool,
+    pdf: bool,
+    code_theme: String,
+    // 工作区路径，用于 / 开头的链接和文献引用
+    workspace: String,
+    // PDF 的页面设置，留空使用默认的 A4
+    paged: Option<PageSetup>,
+    // PDF 的口令和权限，只作用于 PDF，包中的 HTML 和 Markdown 不加密
+    protection: PdfOptions,
+}
+
+impl Default for BundleOptions {
+    fn default() -> Self {
+        BundleOptions {
+            output: String::new(),
+            zip: false,
+            pdf: true,
+            code_theme: String::new(),
+            workspace: String::new(),
+            paged: None,
+            protection: PdfOptions::default(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShareBundle {
+    path: String,
+    // 包内的文件，相对包的根目录
+    files: Vec<String>,
+    // 生成 PDF 所用的引擎，没有生成时为空
+    pdf_engine: Option<String>,
+    warnings: Vec<String>,
+}
+
+// 把本地图片和附件复制到 assets/，返回改写了链接的 Markdown；链接到其他笔记的保持不变
+fn collect_assets(content: &str, root: &Path, note: &Path, dir: &Path) -> Result<String, String> {
+    let mut copied: HashMap<PathBuf, String> = HashMap::new();
+    let mut error = None;
+    let rewritten = markdown::rewrite_link_destinations(content, |destination| {
+        let source = workspace::resolve_note_link(root, note, destination)?;
+        if !source.is_file() || workspace::is_note(&source) {
+            return None;
+        }
+        let name = match copied.get(&source) {
+            Some(name) => name.clone(),
+            None => {
+                let assets = dir.join(ASSETS_DIR);
+                let stem = source.file_stem()?.to_string_lossy().to_string();
+                let extension = source
+                    .extension()
+                    .map(|e| e.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let target = if extension.is_empty() {
+                    assets.join(&stem)
+                } else {
+                    workspace::unique_path(&assets, &stem, &extension)
+                };
+                if let Err(e) = fs::create_dir_all(&assets).and_then(|_| fs::copy(&source, &target))
+                {
+                    error = Some(format!("复制 {} 失败: {}", source.display(), e));
+                    return None;
+                }
+                let name = format!("{}/{}", ASSETS_DIR, target.file_name()?.to_string_lossy());
+                copied.insert(source, name.clone());
+                name
+            }
+        };
+        let fragment = destination.find('#').map_or("", |i| &destination[i..]);
+        Some(format!("{}{}", markdown::link_destination(&name), fragment))
+    });
+    match error {
+        Some(error) => Err(error),
+        None => Ok(rewritten),
+    }
+}
+
+fn find_on_path(name: &str) -> Option<PathBuf> {
+    let names = if cfg!(windows) {
+        vec![format!("{}.exe", name), format!("{}.cmd", name)]
+    } else {
+        vec![name.to_string()]
+    };
+    std::env::var_os("PATH").and_then(|paths| {
+        std::env::split_paths(&paths)
+            .flat_map(|dir| names.iter().map(move |n| dir.join(n)))
+            .find(|p| p.is_file())
+    })
+}
+
+pub fn pdf_engine(app: &AppHandle) -> Option<PathBuf> {
+    let config: PdfSettings = settings::load(app, "pdf");
+    if !config.command.is_empty() {
+        let command = PathBuf::from(&config.command);
+        return if command.is_file() {
+            Some(command)
+        } else {
+            find_on_path(&config.command)
+        };
+    }
+    BROWSER_PATHS
+        .iter()
+        .map(PathBuf::from)
+        .find(|p| p.is_file())
+        .or_else(|| PDF_ENGINES.iter().find_map(|name| find_on_path(name)))
+}
+
+pub fn render_pdf(engine: &Path, html: &Path, pdf: &Path) -> Result<(), String> {
+    let name = engine
+        .file_stem()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let mut command = Command::new(engine);
+    if name.contains("weasyprint") || name.contains("wkhtmltopdf") {
+        if name.contains("wkhtmltopdf") {
+            command.args(["--enable-local-file-access", "--print-media-type"]);
+        }
+        command.arg(html).arg(pdf);
+    } else {
+        // Chromium 内核：无界面打印，不加浏览器自带的页眉页脚（新旧两种参数）；文件路径直接作为地址传入
+        command
+            .args([
+                "--headless",
+                "--disable-gpu",
+                "--no-pdf-header-footer",
+                "--print-to-pdf-no-header",
+            ])
+            .arg(format!("--print-to-pdf={}", pdf.display()))
+            .arg(html);
+    }
+    let output = command
+        .output()
+        .map_err(|e| format!("启动 PDF 引擎失败: {}", e))?;
+    if !output.status.success() || !pdf.is_file() {
+        return Err(format!(
+            "生成 PDF 失败: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+pub fn qpdf(app: &AppHandle) -> Option<PathBuf> {
+    let config: PdfSettings = settings::load(app, "pdf");
+    if config.qpdf.is_empty() {
+        return find_on_path("qpdf");
+    }
+    let command = PathBuf::from(&config.qpdf);
+    if command.is_file() {
+        Some(command)
+    } else {
+        find_on_path(&config.qpdf)
+    }
+}
+
+// 用 qpdf 按 AES-256 加密生成好的 PDF；口令写在只有本用户可读的参数文件中，不出现在进程列表里
+// 加密失败时删除未加密的 PDF，免得敏感文件以明文留下
+pub fn protect_pdf(qpdf: Option<&Path>, pdf: &Path, options: &PdfOptions) -> Result<(), String> {
+    if options.is_empty() {
+        return Ok(());
+    }
+    let result = (|| {
+        let qpdf = qpdf.ok_or("给 PDF 加密需要 qpdf，可在设置的 pdf.qpdf 中指定")?;
+        let owner = if options.owner_password.is_empty() {
+            let mut bytes = [0u8; 24];
+            OsRng.fill_bytes(&mut bytes);
+            URL_SAFE_NO_PAD.encode(bytes)
+        } else {
+            options.owner_password.clone()
+        };
+        let print = if options.allow_print { "full" } else { "none" };
+        let extract = if options.allow_copy { "y" } else { "n" };
+        let mut args = tempfile::NamedTempFile::new().map_err(|e| e.to_string())?;
+        let lines = [
+            "--encrypt",
+            &options.user_password,
+            &owner,
+            "256",
+            &format!("--print={}", print),
+            &format!("--extract={}", extract),
+            "--",
+        ];
+        args.write_all(format!("{}\n", lines.join("\n")).as_bytes())
+            .map_err(|e| e.to_string())?;
+        let encrypted = pdf.with_extension("pdf.part");
+        let output = Command::new(qpdf)
+            .arg(format!("@{}", args.path().display()))
+            .arg(pdf)
+            .arg(&encrypted)
+            .output()
+            .map_err(|e| format!("启动 qpdf 失败: {}", e))?;
+        // 退出码 3 表示成功但有警告
+        if !matches!(output.status.code(), Some(0 | 3)) || !encrypted.is_file() {
+            let _ = fs::remove_file(&encrypted);
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+        fs::rename(&encrypted, pdf).map_err(|e| e.to_string())
+    })();
+    result.map_err(|e| {
+        let _ = fs::remove_file(pdf);
+        format!("给 PDF 加密失败: {}", e)
+    })
+}
+
+fn list_files(dir: &Path, base: &Path, files: &mut Vec<String>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            list_files(&path, base, files);
+        } else {
+            files.push(workspace::relative_path(base, &path));
+        }
+    }
+}
+
+fn write_zip(dir: &Path, files: &[String], output: &Path) -> Result<(), String> {
+    let temp = output.with_extension("zip.part");
+    let result = (|| {
+        let mut zip = ZipWriter::new(File::create(&temp).map_err(|e| e.to_string())?);
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        let mut buf = Vec::new();
+        for name in files {
+            buf.clear();
+            File::open(dir.join(name))
+                .and_then(|mut f| f.read_to_end(&mut buf))
+                .map_err(|e| format!("读取 {} 失败: {}", name, e))?;
+            zip.start_file(name.as_str(), options)
+                .and_then(|_| zip.write_all(&buf).map_err(Into::into))
+                .map_err(|e| e.to_string())?;
+        }
+        zip.finish().map_err(|e| e.to_string())?;
+        Ok::<_, String>(())
+    })();
+    match result {
+        Ok(()) => fs::rename(&temp, output).map_err(|e| format!("保存分享包失败: {}", e)),
+        Err(e) => {
+            let _ = fs::remove_file(&temp);
+            Err(format!("写入分享包失败: {}", e))
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn export_share_bundle(
+    app: AppHandle,
+    path: String,
+    options: Option<BundleOptions>,
+) -> Result<ShareBundle, MobiError> {
+    let options = options.unwrap_or_default();
+    let note = PathBuf::from(&path);
+    let content = fs::read_to_string(&note).map_err(|e| format!("读取文件失败: {}", e))?;
+    if encryption::is_envelope(&content) {
+        return Err("加密笔记需要先解密才能导出".into());
+    }
+    let note_dir = note.parent().unwrap_or(Path::new(".")).to_path_buf();
+    let root = if options.workspace.is_empty() {
+        note_dir.clone()
+    } else {
+        PathBuf::from(&options.workspace)
+    };
+    let stem = workspace::sanitize_file_name(
+        &note
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default(),
+    );
+    let title = markdown::front_matter_field(&content, "title").unwrap_or_else(|| stem.clone());
+
+    let output = match options.output.is_empty() {
+        false => PathBuf::from(&options.output),
+        true if options.zip => {
+            workspace::unique_path(&note_dir, &format!("{} 分享包", stem), "zip")
+        }
+        true => {
+            let mut dir = note_dir.join(format!("{} 分享包", stem));
+            let mut n = 2;
+            while dir.exists() {
+                dir = note_dir.join(format!("{} 分享包 {}", stem, n));
+                n += 1;
+            }
+            dir
+        }
+    };
+    // zip 先在临时目录中组装
+    let dir = if options.zip {
+        std::env::temp_dir().join(format!("mobi-bundle-{}-{}", std::process::id(), stem))
+    } else {
+        output.clone()
+    };
+    if dir.exists() && fs::read_dir(&dir).is_ok_and(|mut d| d.next().is_some()) {
+        return Err("目标文件夹已存在且不为空".into());
+    }
+    fs::create_dir_all(&dir).map_err(|e| format!("创建文件夹失败: {}", e))?;
+
+    let result = async {
+        let mut warnings = Vec::new();
+        let bundled = collect_assets(&content, &root, &note, &dir)?;
+        fs::write(dir.join(format!("{}.md", stem)), &bundled)
+            .map_err(|e| format!("写入 Markdown 失败: {}", e))?;
+
+        let export_options = ExportOptions {
+            title: title.clone(),
+            code_theme: options.code_theme.clone(),
+            workspace: options.workspace.clone(),
+            ..Default::default()
+        };
+        let (prepared, mut export_options) =
+            super::prepare(&app, bundled, Some(export_options)).await?;
+        fs::write(
+            dir.join(format!("{}.html", stem)),
+            super::render_document(&prepared, &export_options),
+        )
+        .map_err(|e| format!("写入 HTML 失败: {}", e))?;
+
+        let mut engine_used = None;
+        if options.pdf {
+            match pdf_engine(&app) {
+                Some(engine) => {
+                    // 带分页样式的版本只用于生成 PDF，生成后删除
+                    export_options.paged = Some(options.paged.clone().unwrap_or_default());
+                    export_options.theme_style =
+                        themes::resolve(&app, &export_options.theme, ThemeTarget::Pdf);
+                    let print = dir.join(".print.html");
+                    fs::write(&print, super::render_document(&prepared, &export_options))
+                        .map_err(|e| format!("写入 HTML 失败: {}", e))?;
+                    let pdf = dir.join(format!("{}.pdf", stem));
+                    let engine_for_task = engine.clone();
+                    let task_pdf = pdf.clone();
+                    let rendered = tauri::async_runtime::spawn_blocking(move || {
+                        let result = render_pdf(&engine_for_task, &print, &task_pdf);
+                        let _ = fs::remove_file(&print);
+                        result
+                    })
+                    .await
+                    .map_err(|e| e.to_string())?;
+                    match rendered {
+                        Ok(()) => {
+                            // 要求加密时加密失败整个分享包失败，不留下未加密的 PDF
+                            let qpdf = qpdf(&app);
+                            let protection = options.protection.clone();
+                            tauri::async_runtime::spawn_blocking(move || {
+                                protect_pdf(qpdf.as_deref(), &pdf, &protection)
+                            })
+                            .await
+                            .map_err(|e| e.to_string())??;
+                            engine_used = Some(engine.to_string_lossy().to_string());
+                        }
+                        Err(e) => warnings.push(e),
+                    }
+                }
+                None => warnings.push(
+                    "没有找到 PDF 引擎（Chrome、Edge、WeasyPrint 或 wkhtmltopdf），可以打开 HTML 后打印"
+                        .into(),
+                ),
+            }
+        }
+
+        let mut files = Vec::new();
+        list_files(&dir, &dir, &mut files);
+        files.sort();
+        if options.zip {
+            write_zip(&dir, &files, &output)?;
+        }
+        Ok::<_, String>(ShareBundle {
+            path: output.to_string_lossy().to_string(),
+            files,
+            pdf_engine: engine_used,
+            warnings,
+        })
+    }
+    .await;
+    if options.zip || result.is_err() {
+        let _ = fs::remove_dir_all(&dir);
+    }
+    Ok(result?)
+}
@@ -0,0 +1,280 @@
+// 交叉引用：导出时为 {#fig:id} / {#tbl:id} / {#eq:id} / {#sec:id} 编号，把 [@fig:id] 替换为可点击的内部链接
+
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::{markdown, settings};
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct CrossrefSettings {
+    figure: String,
+    table: String,
+    equation: String,
+}
+
+impl Default for CrossrefSettings {
+    fn default() -> Self {
+        CrossrefSettings {
+            figure: "图".into(),
+            table: "表".into(),
+            equation: "式".into(),
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+enum Kind {
+    Figure,
+    Table,
+    Equation,
+    Section,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Label {
+    id: String,
+    kind: Kind,
+    number: usize,
+    title: String,
+    line: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Unresolved {
+    pub id: String,
+    pub line: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrossrefReport {
+    labels: Vec<Label>,
+    pub unresolved: Vec<Unresolved>,
+    pub duplicates: Vec<String>,
+}
+
+fn kind_of(id: &str) -> Option<Kind> {
+    let (prefix, name) = id.split_once(':')?;
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return None;
+    }
+    match prefix {
+        "fig" => Some(Kind::Figure),
+        "tbl" => Some(Kind::Table),
+        "eq" => Some(Kind::Equation),
+        "sec" => Some(Kind::Section),
+        _ => None,
+    }
+}
+
+// 行内的 {#kind:id} 属性，返回 (起始字节, 结束字节, id)
+fn find_attribute(line: &str) -> Option<(usize, usize, String)> {
+    let start = line.find("{#")?;
+    let close = line[start..].find('}')?;
+    let id = &line[start + 2..start + close];
+    kind_of(id)?;
+    Some((start, start + close + 1, id.to_string()))
+}
+
+fn caption(line: &str, kind: Kind) -> String {
+    let line = line.trim();
+    match kind {
+        Kind::Figure => line
+            .strip_prefix("![")
+            .and_then(|rest| rest.split_once("]("))
+            .map(|(alt, _)| alt.to_string())
+            .unwrap_or_default(),
+        Kind::Table => line
+            .strip_prefix("Table:")
+            .or_else(|| line.strip_prefix("表:"))
+            .or_else(|| line.strip_prefix(':'))
+            .unwrap_or(line)
+            .trim()
+            .to_string(),
+        Kind::Section => line.trim_start_matches('#').trim().to_string(),
+        Kind::Equation => String::new(),
+    }
+}
+
+fn scan_lines(content: &str) -> Vec<(usize, &str)> {
+    let code: HashSet<usize> = markdown::fenced_blocks(content)
+        .iter()
+        .flat_map(|b| b.start_line..=b.end_line)
+        .collect();
+    content
+        .lines()
+        .enumerate()
+        .filter(|(i, _)| !code.contains(i))
+        .collect()
+}
+
+fn collect(content: &str) -> (Vec<Label>, Vec<String>) {
+    let mut labels: Vec<Label> = Vec::new();
+    let mut duplicates = Vec::new();
+    let mut counts: HashMap<&'static str, usize> = HashMap::new();
+
+    for (i, line) in scan_lines(content) {
+        let Some((start, end, id)) = find_attribute(line) else {
+            continue;
+        };
+        let kind = kind_of(&id).unwrap_or(Kind::Section);
+        if labels.iter().any(|l| l.id == id) {
+            duplicates.push(id);
+            continue;
+        }
+        let counter = match kind {
+            Kind::Figure => "fig",
+            Kind::Table => "tbl",
+            Kind::Equation => "eq",
+            Kind::Section => "sec",
+        };
+        let number = counts.entry(counter).or_insert(0);
+        *number += 1;
+        let without = format!("{}{}", &line[..start], &line[end..]);
+        labels.push(Label {
+            id,
+            kind,
+            number: *number,
+            title: caption(&without, kind),
+            line: i,
+        });
+    }
+
+    (labels, duplicates)
+}
+
+// 逐个处理行内的 [@kind:id] 引用（跳过行内代码）
+fn map_references(line: &str, mut f: impl FnMut(&str) -> String) -> String {
+    let mut output = String::with_capacity(line.len());
+    let mut in_code = false;
+    let mut rest = line;
+    while let Some(pos) = rest.find(['`', '[']) {
+        output.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+        if rest.starts_with('`') {
+            in_code = !in_code;
+            output.push('`');
+            rest = &rest[1..];
+            continue;
+        }
+        let id = rest
+            .strip_prefix("[@")
+            .and_then(|r| r.find(']').map(|close| &r[..close]))
+            .filter(|id| kind_of(id).is_some());
+        match id {
+            Some(id) if !in_code => {
+                output.push_str(&f(id));
+                rest = &rest[id.len() + 3..];
+            }
+            _ => {
+                output.push('[');
+                rest = &rest[1..];
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+fn reference_text(label: &Label, config: &CrossrefSettings) -> String {
+    match label.kind {
+        Kind::Figure => format!("{} {}", config.figure, label.number),
+        Kind::Table => format!("{} {}", config.table, label.number),
+        Kind::Equation => format!("{} ({})", config.equation, label.number),
+        Kind::Section => label.title.clone(),
+    }
+}
+
+// 替换编号和引用；找不到目标的引用标红保留
+pub fn resolve(app: &AppHandle, content: &str) -> String {
+    let (labels, _) = collect(content);
+    let config: CrossrefSettings = settings::load(app, "crossref");
+    let by_id: HashMap<&str, &Label> = labels.iter().map(|l| (l.id.as_str(), l)).collect();
+    let by_line: HashMap<usize, &Label> = labels.iter().map(|l| (l.line, l)).collect();
+    let scanned: HashSet<usize> = scan_lines(content).into_iter().map(|(i, _)| i).collect();
+
+    let mut output: Vec<String> = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        if !scanned.contains(&i) {
+            output.push(line.to_string());
+            continue;
+        }
+        let mut line = line.to_string();
+        if let (Some(label), Some((start, end, _))) = (by_line.get(&i), find_attribute(&line)) {
+            let id = markdown::escape_html(&label.id);
+            let text = reference_text(label, &config);
+            let caption = if label.title.is_empty() {
+                markdown::escape_html(&text)
+            } else {
+                markdown::escape_html(&format!("{}：{}", text, label.title))
+            };
+            line = match label.kind {
+                Kind::Figure => format!(
+                    "{}{}\n\n<p class=\"caption\" id=\"{}\">{}</p>\n",
+                    &line[..start],
+                    &line[end..],
+                    id,
+                    caption
+                ),
+                Kind::Table => format!("\n<p class=\"caption\" id=\"{}\">{}</p>\n", id, caption),
+                Kind::Equation => format!(
+                    "{}<span class=\"equation-number\" id=\"{}\">({})</span>{}",
+                    &line[..start],
+                    id,
+                    label.number,
+                    &line[end..]
+                ),
+                // 标题的 {#id} 由 pulldown-cmark 的标题属性语法处理
+                Kind::Section => line,
+            };
+        }
+        let line = map_references(&line, |id| match by_id.get(id) {
+            Some(label) => format!(
+                "<a class=\"crossref\" href=\"#{}\">{}</a>",
+                markdown::escape_html(id),
+                markdown::escape_html(&reference_text(label, &config))
+            ),
+            None => format!(
+                "<span class=\"crossref-error\">[@{}]</span>",
+                markdown::escape_html(id)
+            ),
+        });
+        output.push(line);
+    }
+
+    let mut text = output.join("\n");
+    if content.ends_with('\n') {
+        text.push('\n');
+    }
+    text
+}
+
+// 列出所有编号目标以及未解析 / 重复的引用，供编辑器提示和补全
+#[tauri::command]
+pub fn check_crossrefs(content: String) -> CrossrefReport {
+    let (labels, duplicates) = collect(&content);
+    let known: HashSet<&str> = labels.iter().map(|l| l.id.as_str()).collect();
+    let mut unresolved = Vec::new();
+    for (i, line) in scan_lines(&content) {
+        map_references(line, |id| {
+            if !known.contains(id) {
+                unresolved.push(Unresolved {
+                    id: id.to_string(),
+                    line: i,
+                });
+            }
+            String::new()
+        });
+    }
+    CrossrefReport {
+        labels,
+        unresolved,
+        duplicates,
+    }
+}
@@ -0,0 +1,248 @@
+// 图表渲染：把 Mermaid / PlantUML / Graphviz 代码渲染为 SVG，供导出使用
+// 默认只用本机命令行工具，设置了 Kroki 地址才会把图表源码发给该服务；结果清理掉脚本后按内容缓存，渲染过的图表离线也能导出
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+use std::thread;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Manager};
+
+use crate::error::{self, MobiError};
+use crate::{network, settings};
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct DiagramSettings {
+    // 留空（默认）则不使用 Kroki，如 https://kroki.io 或自建的服务
+    kroki_url: String,
+    prefer_local: bool,
+    mermaid_command: String,
+    plantuml_command: String,
+    graphviz_command: String,
+}
+
+impl Default for DiagramSettings {
+    fn default() -> Self {
+        DiagramSettings {
+            kroki_url: String::new(),
+            prefer_local: false,
+            mermaid_command: "mmdc".into(),
+            plantuml_command: "plantuml".into(),
+            graphviz_command: "dot".into(),
+        }
+    }
+}
+
+// 代码块语言到 Kroki 图表类型的映射，非图表语言返回 None
+pub fn diagram_kind(lang: &str) -> Option<&'static str> {
+    match lang.to_ascii_lowercase().as_str() {
+        "mermaid" => Some("mermaid"),
+        "plantuml" | "puml" => Some("plantuml"),
+        "graphviz" | "dot" => Some("graphviz"),
+        "d2" => Some("d2"),
+        _ => None,
+    }
+}
+
+pub fn cache_key(kind: &str, source: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(kind.as_bytes());
+    hasher.update([0]);
+    hasher.update(source.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+fn cache_path(app: &AppHandle, key: &str) -> Option<PathBuf> {
+    let dir = app.path().app_cache_dir().ok()?.join("diagrams");
+    fs::create_dir_all(&dir).ok()?;
+    Some(dir.join(format!("{}.svg", key)))
+}
+
+// 去掉 XML 声明和 DOCTYPE，便于直接内嵌到 HTML
+fn inline_svg(svg: &str) -> String {
+    match svg.find("<svg") {
+        Some(start) => svg[start..].trim().to_string(),
+        None => svg.trim().to_string(),
+    }
+}
+
+// SVG 直接内嵌到导出的 HTML 中：去掉脚本、foreignObject 中的 HTML、on* 事件属性和 javascript: 链接
+fn sanitize_svg(svg: &str) -> String {
+    static PATTERNS: OnceLock<Vec<Regex>> = OnceLock::new();
+    let patterns = PATTERNS.get_or_init(|| {
+        [
+            r"(?is)<script\b.*?(?:</script\s*>|/>)",
+            r"(?is)<foreignObject\b.*?(?:</foreignObject\s*>|/>)",
+            r#"(?i)\s+on[a-z]+\s*=\s*(?:"[^"]*"|'[^']*'|[^\s>]+)"#,
+            r#"(?i)\s+(?:xlink:)?href\s*=\s*(?:"\s*javascript:[^"]*"|'\s*javascript:[^']*')"#,
+        ]
+        .iter()
+        .filter_map(|p| Regex::new(p).ok())
+        .collect()
+    });
+    patterns.iter().fold(svg.to_string(), |svg, re| {
+        re.replace_all(&svg, "").into_owned()
+    })
+}
+
+async fn render_kroki(
+    app: &AppHandle,
+    url: &str,
+    kind: &str,
+    source: &str,
+) -> Result<String, String> {
+    let response = network::client(app)?
+        .post(format!("{}/{}/svg", url.trim_end_matches('/'), kind))
+        .header("Content-Type", "text/plain")
+        .body(source.to_string())
+        .send()
+        .await
+        .map_err(|e| format!("请求 Kroki 失败: {}", e))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        return Err(format!("Kroki 渲染失败 {}: {}", status, text.trim()));
+    }
+    response.text().await.map_err(|e| e.to_string())
+}
+
+fn run_piped(program: &str, args: &[&str], input: &str) -> Result<String, String> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("启动 {} 失败: {}", program, e))?;
+    // 大图的输出可能写满管道，在线程里写入，同时读取输出
+    let stdin = child.stdin.take().map(|mut pipe| {
+        let input = input.to_string();
+        thread::spawn(move || {
+            let _ = pipe.write_all(input.as_bytes());
+        })
+    });
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    if let Some(stdin) = stdin {
+        let _ = stdin.join();
+    }
+    if !output.status.success() {
+        return Err(format!(
+            "{} 渲染失败: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+fn render_local(config: &DiagramSettings, kind: &str, source: &str) -> Result<String, String> {
+    match kind {
+        "plantuml" => run_piped(&config.plantuml_command, &["-tsvg", "-pipe"], source),
+        "graphviz" => run_piped(&config.graphviz_command, &["-Tsvg"], source),
+        "mermaid" => {
+            // mermaid-cli 只能读写文件
+            let dir =
+                std::env::temp_dir().join(format!("mobi-mermaid-{}", cache_key(kind, source)));
+            fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+            let input = dir.join("diagram.mmd");
+            let output = dir.join("diagram.svg");
+            fs::write(&input, source).map_err(|e| e.to_string())?;
+            let result = Command::new(&config.mermaid_command)
+                .arg("-i")
+                .arg(&input)
+                .arg("-o")
+                .arg(&output)
+                .output()
+                .map_err(|e| format!("启动 {} 失败: {}", config.mermaid_command, e))
+                .and_then(|out| {
+                    if out.status.success() {
+                        fs::read_to_string(&output).map_err(|e| e.to_string())
+                    } else {
+                        Err(format!(
+                            "mermaid-cli 渲染失败: {}",
+                            String::from_utf8_lossy(&out.stderr).trim()
+                        ))
+                    }
+                });
+            let _ = fs::remove_dir_all(&dir);
+            result
+        }
+        other => Err(format!("{} 没有可用的本地渲染工具", other)),
+    }
+}
+
+pub async fn render(app: &AppHandle, kind: &str, source: &str) -> Result<String, String> {
+    let kind = diagram_kind(kind).ok_or_else(|| format!("不支持的图表类型: {}", kind))?;
+    let key = cache_key(kind, source);
+    let cache = cache_path(app, &key);
+    if let Some(svg) = cache.as_ref().and_then(|p| fs::read_to_string(p).ok()) {
+        return Ok(sanitize_svg(&svg));
+    }
+
+    let config: DiagramSettings = settings::load(app, "diagrams");
+    let local = || {
+        let (app, kind, source) = (app.clone(), kind.to_string(), source.to_string());
+        tauri::async_runtime::spawn_blocking(move || {
+            render_local(&settings::load(&app, "diagrams"), &kind, &source)
+        })
+    };
+
+    let result = if config.prefer_local || config.kroki_url.is_empty() {
+        match local().await.map_err(|e| e.to_string())? {
+            Ok(svg) => Ok(svg),
+            Err(e) if config.kroki_url.is_empty() => Err(e),
+            Err(_) => render_kroki(app, &config.kroki_url, kind, source).await,
+        }
+    } else {
+        match render_kroki(app, &config.kroki_url, kind, source).await {
+            Ok(svg) => Ok(svg),
+            Err(remote_error) => local()
+                .await
+                .map_err(|e| e.to_string())?
+                .map_err(|local_error| format!("{}；{}", remote_error, local_error)),
+        }
+    };
+
+    let svg = sanitize_svg(&inline_svg(&result?));
+    if let Some(path) = cache {
+        let _ = fs::write(path, &svg);
+    }
+    Ok(svg)
+}
+
+// 预先渲染文档中的所有图表代码块，返回 缓存键 -> SVG；渲染失败的图表保留为代码块
+pub async fn prerender(app: &AppHandle, content: &str) -> HashMap<String, String> {
+    let mut rendered = HashMap::new();
+    for (lang, source) in super::code_blocks(content) {
+        let Some(kind) = diagram_kind(&lang) else {
+            continue;
+        };
+        let key = cache_key(kind, &source);
+        if rendered.contains_key(&key) {
+            continue;
+        }
+        match render(app, kind, &source).await {
+            Ok(svg) => {
+                rendered.insert(key, svg);
+            }
+            Err(e) => error::report(app, "图表渲染失败", e),
+        }
+    }
+    rendered
+}
+
+#[tauri::command]
+pub async fn render_diagram(
+    app: AppHandle,
+    kind: String,
+    source: String,
+) -> Result<String, MobiError> {
+    Ok(render(&app, &kind, &source).await?)
+}
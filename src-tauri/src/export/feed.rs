@@ -0,0 +1,282 @@
+// 站点订阅与站点地图：静态站点导出时根据笔记 front matter（title、date、summary/description、updated、draft）生成 feed.xml 和 sitemap.xml
+// 订阅只收录有日期且不是草稿的笔记，按日期从新到旧；两者都需要站点的绝对地址
+
+use std::fs;
+use std::path::Path;
+
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, NaiveDateTime, TimeZone};
+use serde::{Deserialize, Serialize};
+
+use crate::markdown;
+
+// 没有 summary 时从正文截取的长度（字符数）
+const SUMMARY_CHARS: usize = 200;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum FeedFormat {
+    #[default]
+    Atom,
+    Rss,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FeedSettings {
+    pub feed: bool,
+    pub sitemap: bool,
+    // 站点的绝对地址，如 https://blog.example.com/
+    pub base_url: String,
+    // 为空时使用工作区目录名
+    pub title: String,
+    pub description: String,
+    pub author: String,
+    pub format: FeedFormat,
+    // 订阅中最多收录的篇数
+    pub limit: usize,
+}
+
+impl Default for FeedSettings {
+    fn default() -> Self {
+        FeedSettings {
+            feed: false,
+            sitemap: false,
+            base_url: String::new(),
+            title: String::new(),
+            description: String::new(),
+            author: String::new(),
+            format: FeedFormat::Atom,
+            limit: 20,
+        }
+    }
+}
+
+pub struct SitePage {
+    // 相对站点根目录的页面路径，如 posts/hello.html
+    pub page: String,
+    pub title: String,
+    pub date: Option<DateTime<FixedOffset>>,
+    pub updated: DateTime<FixedOffset>,
+    pub summary: String,
+    pub draft: bool,
+}
+
+// 支持 2025-01-15、2025-01-15 10:30、2025-01-15T10:30:00 和带时区的 RFC 3339，无时区的按本地时间
+fn parse_date(value: &str) -> Option<DateTime<FixedOffset>> {
+    let value = value.trim();
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Some(date);
+    }
+    let naive = [
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%dT%H:%M",
+    ]
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+    .or_else(|| {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .ok()
+            .and_then(|d| d.and_hms_opt(0, 0, 0))
+    })?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|d| d.fixed_offset())
+}
+
+fn summary(content: &str) -> String {
+    if let Some(summary) = markdown::front_matter_field(content, "summary")
+        .or_else(|| markdown::front_matter_field(content, "description"))
+    {
+        return summary;
+    }
+    // 第一段正文（跳过标题）
+    let body = markdown::strip_front_matter(content);
+    let paragraph = body
+        .split("\n\n")
+        .map(str::trim)
+        .find(|block| !block.is_empty() && !block.starts_with('#'))
+        .unwrap_or_default();
+    let text = markdown::to_plain_text(paragraph)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    if text.chars().count() > SUMMARY_CHARS {
+        text.chars().take(SUMMARY_CHARS).collect::<String>() + "…"
+    } else {
+        text
+    }
+}
+
+// modified 为文件修改时间（秒），front matter 没有 updated 时用作更新时间
+pub fn page_meta(content: &str, page: &str, title: &str, modified: i64) -> SitePage {
+    let field = |key: &str| markdown::front_matter_field(content, key);
+    let date = field("date").as_deref().and_then(parse_date);
+    let updated = field("updated")
+        .or_else(|| field("lastmod"))
+        .as_deref()
+        .and_then(parse_date)
+        .or_else(|| {
+            Local
+                .timestamp_opt(modified, 0)
+                .single()
+                .map(|d| d.fixed_offset())
+        })
+        .or(date)
+        .unwrap_or_else(|| Local::now().fixed_offset());
+    SitePage {
+        page: page.to_string(),
+        title: field("title").unwrap_or_else(|| title.to_string()),
+        date,
+        updated,
+        summary: summary(content),
+        draft: field("draft").is_some_and(|v| v == "true" || v == "yes"),
+    }
+}
+
+fn page_url(base: &str, page: &str) -> String {
+    let path: Vec<String> = page
+        .split('/')
+        .map(|segment| urlencoding::encode(segment).into_owned())
+        .collect();
+    format!("{}/{}", base, path.join("/"))
+}
+
+fn atom(config: &FeedSettings, base: &str, title: &str, items: &[&SitePage]) -> String {
+    let updated = items
+        .iter()
+        .map(|p| p.updated.max(p.date.unwrap_or(p.updated)))
+        .max()
+        .unwrap_or_else(|| Local::now().fixed_offset());
+    let mut xml = format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<feed xmlns=\"http://www.w3.org/2005/Atom\">\n\
+         <title>{}</title>\n<link href=\"{}/\"/>\n<link rel=\"self\" href=\"{}/feed.xml\"/>\n\
+         <id>{}/</id>\n<updated>{}</updated>\n",
+        markdown::escape_html(title),
+        base,
+        base,
+        base,
+        updated.to_rfc3339()
+    );
+    if !config.description.is_empty() {
+        xml.push_str(&format!(
+            "<subtitle>{}</subtitle>\n",
+            markdown::escape_html(&config.description)
+        ));
+    }
+    if !config.author.is_empty() {
+        xml.push_str(&format!(
+            "<author><name>{}</name></author>\n",
+            markdown::escape_html(&config.author)
+        ));
+    }
+    for item in items {
+        let url = page_url(base, &item.page);
+        let published = item.date.unwrap_or(item.updated);
+        xml.push_str(&format!(
+            "<entry>\n<title>{}</title>\n<link href=\"{}\"/>\n<id>{}</id>\n\
+             <published>{}</published>\n<updated>{}</updated>\n<summary>{}</summary>\n</entry>\n",
+            markdown::escape_html(&item.title),
+            markdown::escape_html(&url),
+            markdown::escape_html(&url),
+            published.to_rfc3339(),
+            item.updated.max(published).to_rfc3339(),
+            markdown::escape_html(&item.summary)
+        ));
+    }
+    xml.push_str("</feed>\n");
+    xml
+}
+
+fn rss(config: &FeedSettings, base: &str, title: &str, items: &[&SitePage]) -> String {
+    let mut xml = format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+         <rss version=\"2.0\" xmlns:atom=\"http://www.w3.org/2005/Atom\">\n<channel>\n\
+         <title>{}</title>\n<link>{}/</link>\n<description>{}</description>\n\
+         <atom:link href=\"{}/feed.xml\" rel=\"self\" type=\"application/rss+xml\"/>\n",
+        markdown::escape_html(title),
+        base,
+        markdown::escape_html(&config.description),
+        base
+    );
+    if let Some(latest) = items.first().and_then(|p| p.date) {
+        xml.push_str(&format!(
+            "<lastBuildDate>{}</lastBuildDate>\n",
+            latest.to_rfc2822()
+        ));
+    }
+    for item in items {
+        let url = markdown::escape_html(&page_url(base, &item.page));
+        xml.push_str(&format!(
+            "<item>\n<title>{}</title>\n<link>{}</link>\n<guid>{}</guid>\n\
+             <pubDate>{}</pubDate>\n<description>{}</description>\n</item>\n",
+            markdown::escape_html(&item.title),
+            url,
+            url,
+            item.date.unwrap_or(item.updated).to_rfc2822(),
+            markdown::escape_html(&item.summary)
+        ));
+    }
+    xml.push_str("</channel>\n</rss>\n");
+    xml
+}
+
+fn sitemap(base: &str, pages: &[SitePage]) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    for page in pages.iter().filter(|p| !p.draft) {
+        xml.push_str(&format!(
+            "<url><loc>{}</loc><lastmod>{}</lastmod></url>\n",
+            markdown::escape_html(&page_url(base, &page.page)),
+            page.updated.format("%Y-%m-%d")
+        ));
+    }
+    xml.push_str("</urlset>\n");
+    xml
+}
+
+// 按设置写出 feed.xml / sitemap.xml，返回写出的文件名
+pub fn write(
+    out: &Path,
+    config: &FeedSettings,
+    site_title: &str,
+    pages: &[SitePage],
+) -> Result<Vec<String>, String> {
+    if !config.feed && !config.sitemap {
+        return Ok(Vec::new());
+    }
+    let base = config.base_url.trim().trim_end_matches('/');
+    if !base.starts_with("http://") && !base.starts_with("https://") {
+        return Err("生成订阅和站点地图需要填写站点地址（http:// 或 https:// 开头）".into());
+    }
+    let mut written = Vec::new();
+    if config.feed {
+        let mut items: Vec<&SitePage> = pages
+            .iter()
+            .filter(|p| p.date.is_some() && !p.draft)
+            .collect();
+        items.sort_by_key(|p| std::cmp::Reverse(p.date));
+        items.truncate(config.limit.max(1));
+        let title = if config.title.is_empty() {
+            site_title
+        } else {
+            &config.title
+        };
+        let xml = match config.format {
+            FeedFormat::Atom => atom(config, base, title, &items),
+            FeedFormat::Rss => rss(config, base, title, &items),
+        };
+        fs::write(out.join("feed.xml"), xml).map_err(|e| format!("写入订阅失败: {}", e))?;
+        written.push("feed.xml".to_string());
+    }
+    if config.sitemap {
+        fs::write(out.join("sitemap.xml"), sitemap(base, pages))
+            .map_err(|e| format!("写入站点地图失败: {}", e))?;
+        written.push("sitemap.xml".to_string());
+    }
+    Ok(written)
+}
@@ -0,0 +1,281 @@
+// 导出过滤器：导出前改写 Markdown、渲染后改写 HTML，如去掉 %%私有注释%%、把内部链接换成公开地址、插入统计代码
+// 过滤器是正则替换或用户脚本（从标准输入读全文，把结果写到标准输出），保存在设置的 filters 节中，由导出预设按名字引用
+
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use regex::RegexBuilder;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use super::presets;
+use crate::error::MobiError;
+use crate::settings;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum FilterStage {
+    // 渲染前处理 Markdown 源文
+    #[default]
+    Markdown,
+    // 处理渲染好的完整 HTML
+    Html,
+}
+
+impl FilterStage {
+    fn as_str(self) -> &'static str {
+        match self {
+            FilterStage::Markdown => "markdown",
+            FilterStage::Html => "html",
+        }
+    }
+}
+
+fn default_timeout() -> u64 {
+    30
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase",
+    tag = "type"
+)]
+pub enum FilterRule {
+    // replacement 中可用 $1、${name} 引用分组
+    Regex {
+        pattern: String,
+        #[serde(default)]
+        replacement: String,
+        #[serde(default)]
+        case_insensitive: bool,
+    },
+    // 环境变量 MOBI_EXPORT_STAGE 为 markdown 或 html；退出码不为 0 时导出失败
+    Script {
+        program: String,
+        #[serde(default)]
+        args: Vec<String>,
+        #[serde(default = "default_timeout")]
+        timeout_secs: u64,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportFilter {
+    pub name: String,
+    #[serde(default)]
+    pub stage: FilterStage,
+    pub rule: FilterRule,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct FilterSettings {
+    filters: Vec<ExportFilter>,
+}
+
+fn load(app: &AppHandle) -> FilterSettings {
+    settings::load(app, "filters")
+}
+
+// 按预设中的顺序取出过滤器，有名字找不到时报错，免得私有内容被悄悄发布出去
+pub fn resolve(app: &AppHandle, names: &[String]) -> Result<Vec<ExportFilter>, MobiError> {
+    if names.is_empty() {
+        return Ok(Vec::new());
+    }
+    let config = load(app);
+    names
+        .iter()
+        .map(|name| {
+            config
+                .filters
+                .iter()
+                .find(|f| f.name == *name)
+                .cloned()
+                .ok_or_else(|| MobiError::NotFound(format!("找不到导出过滤器: {}", name)))
+        })
+        .collect()
+}
+
+fn run_regex(
+    pattern: &str,
+    replacement: &str,
+    case_insensitive: bool,
+    text: &str,
+) -> Result<String, MobiError> {
+    let re = RegexBuilder::new(pattern)
+        .case_insensitive(case_insensitive)
+        .multi_line(true)
+        .build()
+        .map_err(|e| MobiError::InvalidInput(format!("正则表达式无效: {}", e)))?;
+    Ok(re.replace_all(text, replacement).into_owned())
+}
+
+fn run_script(
+    program: &str,
+    args: &[String],
+    timeout_secs: u64,
+    stage: FilterStage,
+    text: &str,
+) -> Result<String, MobiError> {
+    let mut child = Command::new(program)
+        .args(args)
+        .env("MOBI_EXPORT_STAGE", stage.as_str())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| MobiError::io(&format!("启动 {} 失败", program), e))?;
+    // 输入和输出都可能写满管道，分别在线程里读写，避免互相等待
+    let stdin = child.stdin.take().map(|mut pipe| {
+        let text = text.to_string();
+        thread::spawn(move || {
+            let _ = pipe.write_all(text.as_bytes());
+        })
+    });
+    let read = |pipe: Option<Box<dyn Read + Send>>| {
+        thread::spawn(move || {
+            let mut bytes = Vec::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_end(&mut bytes);
+            }
+            bytes
+        })
+    };
+    let stdout = read(
+        child
+            .stdout
+            .take()
+            .map(|p| Box::new(p) as Box<dyn Read + Send>),
+    );
+    let stderr = read(
+        child
+            .stderr
+            .take()
+            .map(|p| Box::new(p) as Box<dyn Read + Send>),
+    );
+
+    let timeout = Duration::from_secs(timeout_secs.max(1));
+    let started = Instant::now();
+    let status = loop {
+        if let Ok(Some(status)) = child.try_wait() {
+            break status;
+        }
+        if started.elapsed() >= timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(format!("过滤脚本 {} 超过 {} 秒未结束", program, timeout_secs).into());
+        }
+        thread::sleep(Duration::from_millis(20));
+    };
+    if let Some(stdin) = stdin {
+        let _ = stdin.join();
+    }
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+    if !status.success() {
+        return Err(format!(
+            "过滤脚本 {} 执行失败: {}",
+            program,
+            String::from_utf8_lossy(&stderr).trim()
+        )
+        .into());
+    }
+    String::from_utf8(stdout)
+        .map_err(|_| MobiError::InvalidInput(format!("过滤脚本 {} 输出的不是 UTF-8 文本", program)))
+}
+
+fn run(filter: &ExportFilter, text: &str) -> Result<String, MobiError> {
+    match &filter.rule {
+        FilterRule::Regex {
+            pattern,
+            replacement,
+            case_insensitive,
+        } => run_regex(pattern, replacement, *case_insensitive, text),
+        FilterRule::Script {
+            program,
+            args,
+            timeout_secs,
+        } => run_script(program, args, *timeout_secs, filter.stage, text),
+    }
+    .map_err(|e| match e {
+        MobiError::Failed(m) => MobiError::Failed(format!("导出过滤器 {}: {}", filter.name, m)),
+        e => e,
+    })
+}
+
+// 依次执行属于 stage 的过滤器，前一个的输出是后一个的输入
+pub fn apply(
+    filters: &[ExportFilter],
+    stage: FilterStage,
+    text: String,
+) -> Result<String, MobiError> {
+    filters
+        .iter()
+        .filter(|f| f.stage == stage)
+        .try_fold(text, |text, filter| run(filter, &text))
+}
+
+// 脚本可能运行较久，放到阻塞线程池中执行
+pub async fn apply_async(
+    filters: &[ExportFilter],
+    stage: FilterStage,
+    text: String,
+) -> Result<String, MobiError> {
+    if !filters.iter().any(|f| f.stage == stage) {
+        return Ok(text);
+    }
+    let filters = filters.to_vec();
+    tauri::async_runtime::spawn_blocking(move || apply(&filters, stage, text))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub fn list_export_filters(app: AppHandle) -> Vec<ExportFilter> {
+    load(&app).filters
+}
+
+// 同名过滤器会被覆盖；previous 为改名前的名字，引用它的预设随之改名
+#[tauri::command]
+pub fn save_export_filter(
+    app: AppHandle,
+    filter: ExportFilter,
+    previous: Option<String>,
+) -> Result<(), MobiError> {
+    if filter.name.trim().is_empty() {
+        return Err(MobiError::InvalidInput("过滤器名称不能为空".into()));
+    }
+    if let FilterRule::Regex { pattern, .. } = &filter.rule {
+        run_regex(pattern, "", false, "")?;
+    }
+    let mut config = load(&app);
+    if let Some(previous) = previous.filter(|p| *p != filter.name) {
+        config.filters.retain(|f| f.name != previous);
+        presets::rename_filter(&app, &previous, Some(&filter.name))?;
+    }
+    match config.filters.iter_mut().find(|f| f.name == filter.name) {
+        Some(existing) => *existing = filter,
+        None => config.filters.push(filter),
+    }
+    Ok(settings::save(&app, "filters", &config)?)
+}
+
+// 同时从引用它的预设中去掉
+#[tauri::command]
+pub fn delete_export_filter(app: AppHandle, name: String) -> Result<(), MobiError> {
+    let mut config = load(&app);
+    config.filters.retain(|f| f.name != name);
+    settings::save(&app, "filters", &config)?;
+    Ok(presets::rename_filter(&app, &name, None)?)
+}
+
+// 在设置界面中试运行一个尚未保存的过滤器
+#[tauri::command]
+pub async fn test_export_filter(filter: ExportFilter, input: String) -> Result<String, MobiError> {
+    let stage = filter.stage;
+    apply_async(&[filter], stage, input).await
+}
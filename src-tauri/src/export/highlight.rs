@@ -0,0 +1,81 @@
+// 代码高亮：用 syntect 生成带内联样式的 HTML，导出文件和公众号复制都无需脚本
+
+use std::sync::OnceLock;
+
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::html::{styled_line_to_highlighted_html, IncludeBackground};
+use syntect::parsing::SyntaxSet;
+use syntect::util::LinesWithEndings;
+
+use crate::markdown;
+
+pub const DEFAULT_THEME: &str = "base16-ocean.light";
+
+fn syntax_set() -> &'static SyntaxSet {
+    static SET: OnceLock<SyntaxSet> = OnceLock::new();
+    SET.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn theme_set() -> &'static ThemeSet {
+    static SET: OnceLock<ThemeSet> = OnceLock::new();
+    SET.get_or_init(ThemeSet::load_defaults)
+}
+
+fn theme(name: &str) -> &'static Theme {
+    let themes = &theme_set().themes;
+    themes.get(name).unwrap_or_else(|| &themes[DEFAULT_THEME])
+}
+
+fn css_color(color: syntect::highlighting::Color) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+}
+
+// 输出完整的 <pre> 块；找不到语言时按纯文本输出，但仍使用主题配色
+pub fn highlight(code: &str, lang: &str, theme_name: &str) -> String {
+    let ss = syntax_set();
+    let theme = theme(theme_name);
+    let syntax = ss
+        .find_syntax_by_token(lang)
+        .unwrap_or_else(|| ss.find_syntax_plain_text());
+
+    let background = theme
+        .settings
+        .background
+        .map(css_color)
+        .unwrap_or_else(|| "#f6f8fa".into());
+    let foreground = theme
+        .settings
+        .foreground
+        .map(css_color)
+        .unwrap_or_else(|| "#24292e".into());
+
+    let mut body = String::new();
+    let mut highlighter = HighlightLines::new(syntax, theme);
+    for line in LinesWithEndings::from(code) {
+        let styled = highlighter.highlight_line(line, ss);
+        match styled
+            .and_then(|regions| styled_line_to_highlighted_html(&regions, IncludeBackground::No))
+        {
+            Ok(html) => body.push_str(&html),
+            Err(_) => body.push_str(&markdown::escape_html(line)),
+        }
+    }
+
+    format!(
+        "<pre class=\"highlight\" style=\"background-color:{};color:{};\"><code>{}</code></pre>\n",
+        background, foreground, body
+    )
+}
+
+#[tauri::command]
+pub fn highlight_code(code: String, lang: String, theme: Option<String>) -> String {
+    highlight(&code, &lang, theme.as_deref().unwrap_or(DEFAULT_THEME))
+}
+
+#[tauri::command]
+pub fn list_code_themes() -> Vec<String> {
+    let mut names: Vec<String> = theme_set().themes.keys().cloned().collect();
+    names.sort();
+    names
+}
@@ -0,0 +1,33 @@
+// 公式预渲染：把 $...$ / $$...$$ 中的 LaTeX 转为 MathML，导出文件和复制到公众号时无需前端 KaTeX
+
+use latex2mathml::{latex_to_mathml, DisplayStyle};
+
+use crate::error::MobiError;
+use crate::markdown;
+
+fn to_mathml(latex: &str, display: bool) -> Result<String, String> {
+    let style = if display {
+        DisplayStyle::Block
+    } else {
+        DisplayStyle::Inline
+    };
+    latex_to_mathml(latex.trim(), style).map_err(|e| format!("公式解析失败: {}", e))
+}
+
+// 转换失败时保留原始 LaTeX，避免整篇导出失败
+pub fn render(latex: &str, display: bool) -> String {
+    to_mathml(latex, display).unwrap_or_else(|_| {
+        let delimiter = if display { "$$" } else { "$" };
+        format!(
+            "<code class=\"math-error\">{}{}{}</code>",
+            delimiter,
+            markdown::escape_html(latex),
+            delimiter
+        )
+    })
+}
+
+#[tauri::command]
+pub fn render_math(latex: String, display: Option<bool>) -> Result<String, MobiError> {
+    Ok(to_mathml(&latex, display.unwrap_or(false))?)
+}
@@ -0,0 +1,488 @@
+// 思维导图导出：把笔记的标题层级和列表层级转成一棵树，输出为 XMind 文件、JSON Canvas（Obsidian 白板）或 SVG 图片
+// 只有一个一级标题时以它为中心主题，否则以 front matter 中的 title 或文件名为中心主题
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use pulldown_cmark::{Event, HeadingLevel, Parser, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::error::MobiError;
+use crate::{encryption, markdown, tables, workspace};
+
+// 超过这个显示宽度的主题在 SVG 中截断
+const SVG_MAX_WIDTH: usize = 40;
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum MindmapFormat {
+    Xmind,
+    Canvas,
+    Svg,
+}
+
+impl MindmapFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            MindmapFormat::Xmind => "xmind",
+            MindmapFormat::Canvas => "canvas",
+            MindmapFormat::Svg => "svg",
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MindmapExport {
+    path: String,
+    // 不含中心主题的节点数
+    topics: usize,
+}
+
+struct Topic {
+    title: String,
+    children: Vec<usize>,
+}
+
+// 布局参数：节点尺寸按显示宽度估算
+struct Metrics {
+    char_width: f64,
+    padding: f64,
+    min_width: f64,
+    max_width: f64,
+    height: f64,
+    gap_x: f64,
+    gap_y: f64,
+}
+
+const SVG_METRICS: Metrics = Metrics {
+    char_width: 8.0,
+    padding: 24.0,
+    min_width: 60.0,
+    max_width: 360.0,
+    height: 32.0,
+    gap_x: 48.0,
+    gap_y: 12.0,
+};
+
+const CANVAS_METRICS: Metrics = Metrics {
+    char_width: 9.0,
+    padding: 40.0,
+    min_width: 120.0,
+    max_width: 400.0,
+    height: 60.0,
+    gap_x: 80.0,
+    gap_y: 40.0,
+};
+
+struct Placed {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+}
+
+// 节点 0 为中心主题
+fn outline(content: &str, fallback_title: &str) -> Vec<Topic> {
+    let body = markdown::strip_front_matter(content);
+    let root_title = markdown::front_matter_field(content, "title")
+        .unwrap_or_else(|| fallback_title.to_string());
+    let mut topics = vec![Topic {
+        title: root_title,
+        children: Vec::new(),
+    }];
+    let single_h1 = Parser::new_ext(body, super::parser_options())
+        .filter(|e| {
+            matches!(
+                e,
+                Event::Start(Tag::Heading {
+                    level: HeadingLevel::H1,
+                    ..
+                })
+            )
+        })
+        .count()
+        == 1;
+
+    // (标题级别, 节点)
+    let mut headings: Vec<(usize, usize)> = Vec::new();
+    // 当前所在的列表项
+    let mut items: Vec<usize> = Vec::new();
+    // 正在收集文字的节点
+    let mut capturing: Option<usize> = None;
+    let add = |topics: &mut Vec<Topic>, parent: usize| {
+        topics.push(Topic {
+            title: String::new(),
+            children: Vec::new(),
+        });
+        let index = topics.len() - 1;
+        topics[parent].children.push(index);
+        index
+    };
+
+    for event in Parser::new_ext(body, super::parser_options()) {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                let level = level as usize;
+                items.clear();
+                if level == 1 && single_h1 {
+                    topics[0].title.clear();
+                    headings.clear();
+                    headings.push((1, 0));
+                    capturing = Some(0);
+                    continue;
+                }
+                while headings.last().is_some_and(|(l, _)| *l >= level) {
+                    headings.pop();
+                }
+                let parent = headings.last().map_or(0, |(_, node)| *node);
+                let node = add(&mut topics, parent);
+                headings.push((level, node));
+                capturing = Some(node);
+            }
+            Event::End(TagEnd::Heading(_)) => capturing = None,
+            Event::Start(Tag::Item) => {
+                let parent = items
+                    .last()
+                    .or(headings.last().map(|(_, node)| node))
+                    .copied()
+                    .unwrap_or(0);
+                let node = add(&mut topics, parent);
+                items.push(node);
+                capturing = Some(node);
+            }
+            Event::End(TagEnd::Item) => {
+                items.pop();
+                capturing = None;
+            }
+            // 列表项只取第一段文字，嵌套列表成为子节点
+            Event::Start(Tag::List(_)) | Event::End(TagEnd::Paragraph)
+                if items.last().is_some_and(|node| capturing == Some(*node)) =>
+            {
+                capturing = None;
+            }
+            Event::Text(text) | Event::Code(text) | Event::InlineMath(text) => {
+                if let Some(node) = capturing {
+                    topics[node].title.push_str(&text);
+                }
+            }
+            Event::SoftBreak | Event::HardBreak => {
+                if let Some(node) = capturing {
+                    topics[node].title.push(' ');
+                }
+            }
+            _ => {}
+        }
+    }
+    for topic in &mut topics {
+        topic.title = topic.title.split_whitespace().collect::<Vec<_>>().join(" ");
+    }
+    if topics[0].title.is_empty() {
+        topics[0].title = fallback_title.to_string();
+    }
+    topics
+}
+
+fn truncate(title: &str, max: usize) -> String {
+    if tables::display_width(title) <= max {
+        return title.to_string();
+    }
+    let mut out = String::new();
+    for c in title.chars() {
+        if tables::display_width(&out) + tables::display_width(&c.to_string()) >= max {
+            break;
+        }
+        out.push(c);
+    }
+    out + "…"
+}
+
+fn node_width(title: &str, metrics: &Metrics) -> f64 {
+    (tables::display_width(title) as f64 * metrics.char_width + metrics.padding)
+        .clamp(metrics.min_width, metrics.max_width)
+}
+
+// 从左到右的树状布局：同一层的节点在同一列，叶子依次向下排列，父节点对齐到子节点的中间
+fn layout(topics: &[Topic], titles: &[String], metrics: &Metrics) -> Vec<Placed> {
+    let mut depth = vec![0; topics.len()];
+    for (i, topic) in topics.iter().enumerate() {
+        for &child in &topic.children {
+            depth[child] = depth[i] + 1;
+        }
+    }
+    let widths: Vec<f64> = titles.iter().map(|t| node_width(t, metrics)).collect();
+    let levels = depth.iter().max().copied().unwrap_or(0) + 1;
+    let mut column_width = vec![0.0f64; levels];
+    for (i, d) in depth.iter().enumerate() {
+        column_width[*d] = column_width[*d].max(widths[i]);
+    }
+    let mut column_x = vec![0.0; levels];
+    for d in 1..levels {
+        column_x[d] = column_x[d - 1] + column_width[d - 1] + metrics.gap_x;
+    }
+
+    let mut y = vec![0.0; topics.len()];
+    // 返回子树占据的下边界
+    fn place(node: usize, top: f64, topics: &[Topic], metrics: &Metrics, y: &mut [f64]) -> f64 {
+        let children = &topics[node].children;
+        if children.is_empty() {
+            y[node] = top;
+            return top + metrics.height;
+        }
+        let mut bottom = top;
+        for (i, &child) in children.iter().enumerate() {
+            let start = if i == 0 { top } else { bottom + metrics.gap_y };
+            bottom = place(child, start, topics, metrics, y);
+        }
+        let first = y[children[0]];
+        let last = y[children[children.len() - 1]];
+        y[node] = (first + last) / 2.0;
+        bottom
+    }
+    place(0, 0.0, topics, metrics, &mut y);
+
+    (0..topics.len())
+        .map(|i| Placed {
+            x: column_x[depth[i]],
+            y: y[i],
+            width: widths[i],
+            height: metrics.height,
+        })
+        .collect()
+}
+
+fn xmind_json(topics: &[Topic], node: usize) -> Value {
+    let mut topic = json!({
+        "id": format!("mobi-topic-{}", node),
+        "class": "topic",
+        "title": topics[node].title,
+    });
+    if node == 0 {
+        topic["structureClass"] = json!("org.xmind.ui.map.unbalanced");
+    }
+    if !topics[node].children.is_empty() {
+        let attached: Vec<Value> = topics[node]
+            .children
+            .iter()
+            .map(|&child| xmind_json(topics, child))
+            .collect();
+        topic["children"] = json!({ "attached": attached });
+    }
+    topic
+}
+
+// XMind 8 读取的旧格式
+fn xmind_xml(topics: &[Topic], node: usize, out: &mut String) {
+    out.push_str(&format!(
+        "<topic id=\"mobi-topic-{}\"><title>{}</title>",
+        node,
+        markdown::escape_html(&topics[node].title)
+    ));
+    if !topics[node].children.is_empty() {
+        out.push_str("<children><topics type=\"attached\">");
+        for &child in &topics[node].children {
+            xmind_xml(topics, child, out);
+        }
+        out.push_str("</topics></children>");
+    }
+    out.push_str("</topic>");
+}
+
+fn write_xmind(output: &Path, topics: &[Topic]) -> Result<(), String> {
+    let sheet = json!([{
+        "id": "mobi-sheet",
+        "class": "sheet",
+        "title": topics[0].title,
+        "rootTopic": xmind_json(topics, 0),
+    }]);
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\"?>\
+         <xmap-content xmlns=\"urn:xmind:xmap:xmlns:content:2.0\" version=\"2.0\"><sheet id=\"mobi-sheet\">",
+    );
+    xmind_xml(topics, 0, &mut xml);
+    xml.push_str(&format!(
+        "<title>{}</title></sheet></xmap-content>",
+        markdown::escape_html(&topics[0].title)
+    ));
+    let metadata = json!({
+        "creator": { "name": "Mobi", "version": env!("CARGO_PKG_VERSION") },
+    });
+    let manifest = json!({
+        "file-entries": { "content.json": {}, "metadata.json": {} },
+    });
+    let manifest_xml = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\"?>\
+        <manifest xmlns=\"urn:xmind:xmap:xmlns:manifest:1.0\">\
+        <file-entry full-path=\"content.xml\" media-type=\"text/xml\"/>\
+        <file-entry full-path=\"META-INF/\" media-type=\"\"/>\
+        <file-entry full-path=\"META-INF/manifest.xml\" media-type=\"text/xml\"/>\
+        </manifest>";
+
+    let file = File::create(output).map_err(|e| format!("创建文件失败: {}", e))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let entries = [
+        ("content.json", sheet.to_string()),
+        ("metadata.json", metadata.to_string()),
+        ("manifest.json", manifest.to_string()),
+        ("content.xml", xml),
+        ("META-INF/manifest.xml", manifest_xml.to_string()),
+    ];
+    for (name, text) in entries {
+        zip.start_file(name, options)
+            .map_err(|e| format!("写入 XMind 文件失败: {}", e))?;
+        zip.write_all(text.as_bytes())
+            .map_err(|e| format!("写入 XMind 文件失败: {}", e))?;
+    }
+    zip.finish()
+        .map_err(|e| format!("写入 XMind 文件失败: {}", e))?;
+    Ok(())
+}
+
+// JSON Canvas 1.0：文字节点加从父到子的连线
+fn canvas(topics: &[Topic]) -> String {
+    let titles: Vec<String> = topics.iter().map(|t| t.title.clone()).collect();
+    let placed = layout(topics, &titles, &CANVAS_METRICS);
+    let nodes: Vec<Value> = placed
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            let mut node = json!({
+                "id": format!("topic-{}", i),
+                "type": "text",
+                "text": titles[i],
+                "x": p.x.round() as i64,
+                "y": p.y.round() as i64,
+                "width": p.width.round() as i64,
+                "height": p.height.round() as i64,
+            });
+            if i == 0 {
+                node["color"] = json!("5");
+            }
+            node
+        })
+        .collect();
+    let edges: Vec<Value> = topics
+        .iter()
+        .enumerate()
+        .flat_map(|(parent, topic)| {
+            topic.children.iter().map(move |&child| {
+                json!({
+                    "id": format!("edge-{}", child),
+                    "fromNode": format!("topic-{}", parent),
+                    "fromSide": "right",
+                    "toNode": format!("topic-{}", child),
+                    "toSide": "left",
+                })
+            })
+        })
+        .collect();
+    serde_json::to_string_pretty(&json!({ "nodes": nodes, "edges": edges })).unwrap_or_default()
+}
+
+fn svg(topics: &[Topic]) -> String {
+    let titles: Vec<String> = topics
+        .iter()
+        .map(|t| truncate(&t.title, SVG_MAX_WIDTH))
+        .collect();
+    let placed = layout(topics, &titles, &SVG_METRICS);
+    let margin = 16.0;
+    let width = placed.iter().map(|p| p.x + p.width).fold(0.0, f64::max) + margin * 2.0;
+    let height = placed.iter().map(|p| p.y + p.height).fold(0.0, f64::max) + margin * 2.0;
+
+    let mut out = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" \
+         font-family=\"-apple-system, 'PingFang SC', 'Microsoft YaHei', sans-serif\" font-size=\"14\">\n",
+        w = width.ceil(),
+        h = height.ceil()
+    );
+    out.push_str(&format!(
+        "<g transform=\"translate({m} {m})\" fill=\"none\" stroke=\"#9aa4b2\" stroke-width=\"1.5\">\n",
+        m = margin
+    ));
+    for (parent, topic) in topics.iter().enumerate() {
+        let from = &placed[parent];
+        let (x1, y1) = (from.x + from.width, from.y + from.height / 2.0);
+        for &child in &topic.children {
+            let to = &placed[child];
+            let (x2, y2) = (to.x, to.y + to.height / 2.0);
+            let mid = (x1 + x2) / 2.0;
+            out.push_str(&format!(
+                "<path d=\"M{:.1} {:.1} C{:.1} {:.1} {:.1} {:.1} {:.1} {:.1}\"/>\n",
+                x1, y1, mid, y1, mid, y2, x2, y2
+            ));
+        }
+    }
+    out.push_str("</g>\n");
+    out.push_str(&format!(
+        "<g transform=\"translate({m} {m})\">\n",
+        m = margin
+    ));
+    for (i, p) in placed.iter().enumerate() {
+        let (fill, stroke, color) = match i {
+            0 => ("#3b5bdb", "#3b5bdb", "#ffffff"),
+            _ if topics[0].children.contains(&i) => ("#edf2ff", "#748ffc", "#1c2a5e"),
+            _ => ("#ffffff", "#ced4da", "#212529"),
+        };
+        out.push_str(&format!(
+            "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" rx=\"6\" fill=\"{}\" stroke=\"{}\"/>\n",
+            p.x, p.y, p.width, p.height, fill, stroke
+        ));
+        out.push_str(&format!(
+            "<text x=\"{:.1}\" y=\"{:.1}\" fill=\"{}\" text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>\n",
+            p.x + p.width / 2.0,
+            p.y + p.height / 2.0,
+            color,
+            markdown::escape_html(&titles[i])
+        ));
+    }
+    out.push_str("</g>\n</svg>\n");
+    out
+}
+
+// output 为空时写到笔记旁边的同名文件（已存在时自动编号），返回写入的路径
+#[tauri::command]
+pub fn export_mindmap(
+    path: String,
+    format: MindmapFormat,
+    output: Option<String>,
+) -> Result<MindmapExport, MobiError> {
+    let source = PathBuf::from(&path);
+    let content = fs::read_to_string(&source).map_err(|e| format!("读取文件失败: {}", e))?;
+    if encryption::is_envelope(&content) {
+        return Err("加密笔记需要先解密才能导出".into());
+    }
+    let stem = source
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let topics = outline(&content, &stem);
+    if topics.len() < 2 {
+        return Err("笔记中没有标题或列表，无法生成思维导图".into());
+    }
+
+    let output = match output.filter(|o| !o.is_empty()) {
+        Some(output) => PathBuf::from(output),
+        None => workspace::unique_path(
+            source.parent().unwrap_or(Path::new(".")),
+            &workspace::sanitize_file_name(&stem),
+            format.extension(),
+        ),
+    };
+    match format {
+        MindmapFormat::Xmind => write_xmind(&output, &topics)?,
+        MindmapFormat::Canvas => {
+            fs::write(&output, canvas(&topics)).map_err(|e| format!("保存文件失败: {}", e))?
+        }
+        MindmapFormat::Svg => {
+            fs::write(&output, svg(&topics)).map_err(|e| format!("保存文件失败: {}", e))?
+        }
+    }
+    Ok(MindmapExport {
+        path: output.to_string_lossy().to_string(),
+        topics: topics.len() - 1,
+    })
+}
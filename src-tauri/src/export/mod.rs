@@ -0,0 +1,233 @@
+// 导出：在后端把 Markdown 渲染为自包含的 HTML，代码高亮以内联样式写入，不依赖前端脚本
+
+pub mod anki;
+pub mod bundle;
+pub mod crossref;
+pub mod diagram;
+pub mod feed;
+pub mod filters;
+pub mod highlight;
+pub mod math;
+pub mod mindmap;
+pub mod paged;
+pub mod presets;
+pub mod themes;
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use pulldown_cmark::{html, CodeBlockKind, CowStr, Event, Options, Parser, Tag, TagEnd};
+use serde::Deserialize;
+use tauri::AppHandle;
+
+use crate::error::MobiError;
+use crate::{citations, markdown, zotero};
+use filters::{ExportFilter, FilterStage};
+use themes::{ExportTheme, ThemeTarget};
+
+const STYLE: &str = include_str!("style.css");
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ExportOptions {
+    pub title: String,
+    // syntect 主题名，留空使用默认浅色主题
+    pub code_theme: String,
+    // 工作区路径，用于查找文献库；留空则不处理引用
+    pub workspace: String,
+    // 引用样式，留空使用设置中的样式
+    pub citation_style: String,
+    // 分页设置（页面尺寸、页眉页脚、分页、书签），留空时不输出分页样式
+    pub paged: Option<paged::PageSetup>,
+    // 导出主题名，留空使用为 target 指定的主题
+    pub theme: String,
+    // 导出过滤器名，按顺序在渲染前处理 Markdown、渲染后处理 HTML
+    pub filters: Vec<String>,
+    // 导出去向，决定默认的导出主题
+    #[serde(skip)]
+    pub target: ThemeTarget,
+    // prepare 中解析出的导出主题
+    #[serde(skip)]
+    pub theme_style: Option<ExportTheme>,
+    // prepare 中解析出的导出过滤器
+    #[serde(skip)]
+    pub filter_rules: Vec<ExportFilter>,
+    // 预先渲染好的图表，键为 diagram::cache_key
+    #[serde(skip)]
+    pub diagrams: HashMap<String, String>,
+}
+
+impl ExportOptions {
+    // 依次取选项中的、导出主题中的代码主题，都为空时使用默认主题
+    fn code_theme(&self) -> &str {
+        let theme = self
+            .theme_style
+            .as_ref()
+            .map_or("", |t| t.code_theme.as_str());
+        [self.code_theme.as_str(), theme]
+            .into_iter()
+            .find(|t| !t.is_empty())
+            .unwrap_or(highlight::DEFAULT_THEME)
+    }
+}
+
+fn code_block_lang(kind: &CodeBlockKind) -> String {
+    match kind {
+        CodeBlockKind::Fenced(info) => info.split_whitespace().next().unwrap_or("").to_string(),
+        CodeBlockKind::Indented => String::new(),
+    }
+}
+
+// 导出 HTML 时另外识别 $公式$ 和标题后的 {#id .class}；其他模块按普通文字处理，不会把金额之类的 $ 当作公式
+fn parser_options() -> Options {
+    markdown::parser_options() | Options::ENABLE_MATH | Options::ENABLE_HEADING_ATTRIBUTES
+}
+
+// 文档中所有代码块的 (语言, 代码)，与 render_body 看到的内容一致
+pub fn code_blocks(content: &str) -> Vec<(String, String)> {
+    let mut blocks = Vec::new();
+    let mut code_block: Option<(String, String)> = None;
+    for event in Parser::new_ext(content, parser_options()) {
+        match event {
+            Event::Start(Tag::CodeBlock(kind)) => {
+                code_block = Some((code_block_lang(&kind), String::new()))
+            }
+            Event::End(TagEnd::CodeBlock) => blocks.extend(code_block.take()),
+            Event::Text(text) => {
+                if let Some((_, code)) = code_block.as_mut() {
+                    code.push_str(&text);
+                }
+            }
+            _ => {}
+        }
+    }
+    blocks
+}
+
+fn render_code_block(lang: &str, code: &str, options: &ExportOptions) -> String {
+    let diagram = diagram::diagram_kind(lang)
+        .and_then(|kind| options.diagrams.get(&diagram::cache_key(kind, code)));
+    match diagram {
+        Some(svg) => format!("<figure class=\"diagram\">{}</figure>\n", svg),
+        None => highlight::highlight(code, lang, options.code_theme()),
+    }
+}
+
+// 渲染正文 HTML，围栏代码块替换为高亮后的 <pre>，已渲染的图表替换为内联 SVG，公式转为 MathML
+pub fn render_body(content: &str, options: &ExportOptions) -> String {
+    let mut events: Vec<Event> = Vec::new();
+    let mut code_block: Option<(String, String)> = None;
+
+    for event in Parser::new_ext(content, parser_options()) {
+        match event {
+            Event::Start(Tag::CodeBlock(kind)) => {
+                code_block = Some((code_block_lang(&kind), String::new()));
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                if let Some((lang, code)) = code_block.take() {
+                    let html = render_code_block(&lang, &code, options);
+                    events.push(Event::Html(CowStr::from(html)));
+                }
+            }
+            Event::InlineMath(latex) => {
+                events.push(Event::InlineHtml(CowStr::from(math::render(&latex, false))));
+            }
+            Event::DisplayMath(latex) => {
+                let html = format!(
+                    "<div class=\"math-display\">{}</div>",
+                    math::render(&latex, true)
+                );
+                events.push(Event::InlineHtml(CowStr::from(html)));
+            }
+            Event::Text(text) if code_block.is_some() => {
+                if let Some((_, code)) = code_block.as_mut() {
+                    code.push_str(&text);
+                }
+            }
+            other => events.push(other),
+        }
+    }
+
+    let mut body = String::new();
+    html::push_html(&mut body, events.into_iter());
+    body
+}
+
+pub fn render_document(content: &str, options: &ExportOptions) -> String {
+    let title = if options.title.is_empty() {
+        "document"
+    } else {
+        &options.title
+    };
+    let mut style = themes::stylesheet(options.theme_style.as_ref());
+    if let Some(setup) = &options.paged {
+        let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+        let author = markdown::front_matter_field(content, "author").unwrap_or_default();
+        style.push_str(&paged::stylesheet(
+            setup,
+            &paged::Variables {
+                title,
+                date: &date,
+                author: &author,
+            },
+        ));
+    }
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"zh-CN\">\n<head>\n<meta charset=\"UTF-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\">\n\
+         <title>{}</title>\n<style>\n{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        markdown::escape_html(title),
+        style,
+        render_body(content, options)
+    )
+}
+
+// 渲染前的准备：执行 Markdown 过滤器、编号交叉引用、同步并解析文献引用、选定导出主题、预先渲染图表
+pub async fn prepare(
+    app: &AppHandle,
+    content: String,
+    options: Option<ExportOptions>,
+) -> Result<(String, ExportOptions), String> {
+    let mut options = options.unwrap_or_default();
+    options.filter_rules = filters::resolve(app, &options.filters)?;
+    // 过滤器最先执行，去掉的私有内容不会进入引用和图表
+    let content =
+        filters::apply_async(&options.filter_rules, FilterStage::Markdown, content).await?;
+    // 交叉引用先于文献引用处理，避免 [@fig:id] 被当成文献 key
+    let content = crossref::resolve(app, &content);
+    let content = if options.workspace.is_empty() {
+        content
+    } else {
+        let root = Path::new(&options.workspace);
+        zotero::auto_sync(app, root).await;
+        citations::resolve(app, root, &content, &options.citation_style)?
+    };
+    options.theme_style = themes::resolve(app, &options.theme, options.target);
+    options.diagrams = diagram::prerender(app, &content).await;
+    Ok((content, options))
+}
+
+#[tauri::command]
+pub async fn render_html(
+    app: AppHandle,
+    content: String,
+    options: Option<ExportOptions>,
+) -> Result<String, MobiError> {
+    let (content, options) = prepare(&app, content, options).await?;
+    let html = render_document(&content, &options);
+    filters::apply_async(&options.filter_rules, FilterStage::Html, html).await
+}
+
+#[tauri::command]
+pub async fn export_html(
+    app: AppHandle,
+    content: String,
+    output_path: String,
+    options: Option<ExportOptions>,
+) -> Result<(), MobiError> {
+    let (content, options) = prepare(&app, content, options).await?;
+    let html = render_document(&content, &options);
+    let html = filters::apply_async(&options.filter_rules, FilterStage::Html, html).await?;
+    fs::write(&output_path, html).map_err(|e| MobiError::io("导出 HTML 失败", e))
+}
@@ -0,0 +1,220 @@
+// 分页排版：为导出和打印生成 CSS 分页媒体样式——页面尺寸和边距、带变量的页眉页脚、页码、标题前分页、PDF 书签
+// 页眉页脚放在 @page 边距区，Chromium 内核（Chrome、WebView2）以及 WeasyPrint、Prince 支持，WebKit 不显示
+// 书签通过 bookmark-level 声明，由支持分页媒体的 PDF 引擎生成大纲；Chromium 另按标题结构生成
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Margins {
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub left: f32,
+}
+
+impl Default for Margins {
+    fn default() -> Self {
+        Margins {
+            top: 20.0,
+            right: 18.0,
+            bottom: 20.0,
+            left: 18.0,
+        }
+    }
+}
+
+// 页眉或页脚左中右三栏的模板
+// 可用变量：{title} {date} {author}（front matter）{section}（最近的一级或二级标题）{page} {pages}
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Running {
+    pub left: String,
+    pub center: String,
+    pub right: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PageSetup {
+    // A3 / A4 / A5 / Letter / Legal
+    pub page_size: String,
+    pub landscape: bool,
+    // 单位毫米
+    pub margins: Margins,
+    pub header: Running,
+    pub footer: Running,
+    // 在该级别及以上的标题前分页：1 为一级标题，2 为一、二级标题，0 不分页
+    pub break_before: u8,
+    pub bookmarks: bool,
+}
+
+impl Default for PageSetup {
+    fn default() -> Self {
+        PageSetup {
+            page_size: "A4".into(),
+            landscape: false,
+            margins: Margins::default(),
+            header: Running {
+                center: "{title}".into(),
+                ..Default::default()
+            },
+            footer: Running {
+                center: "{page} / {pages}".into(),
+                ..Default::default()
+            },
+            break_before: 0,
+            bookmarks: true,
+        }
+    }
+}
+
+// 渲染时确定的变量值
+pub struct Variables<'a> {
+    pub title: &'a str,
+    pub date: &'a str,
+    pub author: &'a str,
+}
+
+fn page_size(name: &str) -> &'static str {
+    match name.to_ascii_lowercase().as_str() {
+        "a3" => "A3",
+        "a5" => "A5",
+        "letter" => "letter",
+        "legal" => "legal",
+        _ => "A4",
+    }
+}
+
+fn css_string(text: &str) -> String {
+    let escaped: String = text
+        .chars()
+        .flat_map(|c| match c {
+            '"' => vec!['\\', '"'],
+            '\\' => vec!['\\', '\\'],
+            '\n' => vec![' '],
+            c => vec![c],
+        })
+        .collect();
+    format!("\"{}\"", escaped)
+}
+
+enum Segment<'a> {
+    Text(&'a str),
+    Variable(&'a str),
+}
+
+// 把模板拆成文字和 {变量}
+fn segments(template: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        segments.push(Segment::Text(&rest[..start]));
+        segments.push(Segment::Variable(&rest[start + 1..start + len]));
+        rest = &rest[start + len + 1..];
+    }
+    segments.push(Segment::Text(rest));
+    segments
+}
+
+fn text_variable<'a>(name: &str, vars: &Variables<'a>) -> Option<&'a str> {
+    match name {
+        "title" => Some(vars.title),
+        "date" => Some(vars.date),
+        "author" => Some(vars.author),
+        _ => None,
+    }
+}
+
+// 模板转成 CSS content 的值，页码和章节名由排版引擎逐页填入
+fn content_value(template: &str, vars: &Variables) -> String {
+    segments(template)
+        .into_iter()
+        .filter_map(|segment| match segment {
+            Segment::Text("") => None,
+            Segment::Text(text) => Some(css_string(text)),
+            Segment::Variable("page") => Some("counter(page)".into()),
+            Segment::Variable("pages") => Some("counter(pages)".into()),
+            Segment::Variable("section") => Some("string(section)".into()),
+            Segment::Variable(name) => Some(css_string(
+                &text_variable(name, vars).map_or_else(|| format!("{{{}}}", name), String::from),
+            )),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// 模板展开为纯文本，用于不支持边距区的内核；页码和章节名无法逐页填入，直接略去
+pub fn plain_text(template: &str, vars: &Variables) -> String {
+    let text: String = segments(template)
+        .into_iter()
+        .map(|segment| match segment {
+            Segment::Text(text) => text.to_string(),
+            Segment::Variable("page" | "pages" | "section") => String::new(),
+            Segment::Variable(name) => {
+                text_variable(name, vars).map_or_else(|| format!("{{{}}}", name), String::from)
+            }
+        })
+        .collect();
+    text.trim().to_string()
+}
+
+fn margin_boxes(css: &mut String, position: &str, running: &Running, vars: &Variables) {
+    for (side, template) in [
+        ("left", &running.left),
+        ("center", &running.center),
+        ("right", &running.right),
+    ] {
+        let value = content_value(template, vars);
+        if !value.is_empty() {
+            css.push_str(&format!(
+                "  @{}-{} {{ content: {}; font-size: 9pt; color: #666; }}\n",
+                position, side, value
+            ));
+        }
+    }
+}
+
+pub fn stylesheet(setup: &PageSetup, vars: &Variables) -> String {
+    let m = &setup.margins;
+    let mut css = format!(
+        "@page {{\n  size: {} {};\n  margin: {}mm {}mm {}mm {}mm;\n",
+        page_size(&setup.page_size),
+        if setup.landscape {
+            "landscape"
+        } else {
+            "portrait"
+        },
+        m.top,
+        m.right,
+        m.bottom,
+        m.left
+    );
+    margin_boxes(&mut css, "top", &setup.header, vars);
+    margin_boxes(&mut css, "bottom", &setup.footer, vars);
+    css.push_str("}\n");
+
+    css.push_str("h1, h2 { string-set: section content(text); }\n");
+    match setup.break_before {
+        0 => {}
+        1 => css.push_str("h1 { break-before: page; }\n"),
+        _ => css.push_str("h1, h2 { break-before: page; }\n"),
+    }
+    // 文档开头的标题不另起一页
+    css.push_str(":is(h1, h2):first-child { break-before: auto; }\n");
+
+    if setup.bookmarks {
+        for level in 1..=6 {
+            css.push_str(&format!(
+                "h{0} {{ bookmark-level: {0}; bookmark-label: content(text); }}\n",
+                level
+            ));
+        }
+    } else {
+        css.push_str("h1, h2, h3, h4, h5, h6 { bookmark-level: none; }\n");
+    }
+    css
+}
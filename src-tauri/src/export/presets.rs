@@ -0,0 +1,310 @@
+// 导出预设：把格式、代码主题、分页设置、输出位置和导出后的动作存成有名字的一组，一次点击完成导出
+// 预设保存在设置的 export 节中
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tauri_plugin_opener::OpenerExt;
+
+use super::feed::FeedSettings;
+use super::filters::{self, FilterStage};
+use super::paged::PageSetup;
+use crate::error::MobiError;
+use crate::{preview, settings, workspace};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum PresetFormat {
+    #[default]
+    Html,
+    // 原样复制 Markdown 源文件
+    Markdown,
+}
+
+impl PresetFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            PresetFormat::Html => "html",
+            PresetFormat::Markdown => "md",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase",
+    tag = "type"
+)]
+pub enum PostAction {
+    // 用系统默认程序打开导出的文件
+    Open,
+    // 在文件管理器中显示
+    Reveal,
+    // 通过系统自带的 scp 上传，需要事先配置好密钥登录
+    Upload {
+        host: String,
+        #[serde(default)]
+        port: Option<u16>,
+        #[serde(default)]
+        user: String,
+        // 远程目录，留空为登录目录
+        #[serde(default)]
+        remote_dir: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ExportPreset {
+    pub name: String,
+    pub format: PresetFormat,
+    pub code_theme: String,
+    // 导出主题名，留空使用为 HTML 导出指定的主题
+    pub theme: String,
+    pub citation_style: String,
+    pub paged: Option<PageSetup>,
+    // 输出目录：留空为笔记所在目录，相对路径相对于工作区，~/ 开头相对于用户目录
+    pub output_dir: String,
+    // 文件名模板（不含扩展名），可用 {name} 和 {date}，留空为 {name}
+    pub file_name: String,
+    // 导出过滤器名，按顺序执行，见 filters.rs
+    pub filters: Vec<String>,
+    pub post_actions: Vec<PostAction>,
+    // 用这个预设导出静态站点（mobi export-site --preset）时生成的订阅和站点地图
+    pub feed: FeedSettings,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct ExportSettings {
+    presets: Vec<ExportPreset>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PresetResult {
+    output: String,
+    // 上传后的远程位置
+    uploaded: Vec<String>,
+    // 打开、显示导出文件失败时导出本身已经完成，只作提示
+    warnings: Vec<String>,
+}
+
+fn output_path(preset: &ExportPreset, note: &Path, workspace: Option<&Path>) -> PathBuf {
+    let note_dir = note.parent().unwrap_or(Path::new("."));
+    let dir = match preset.output_dir.trim() {
+        "" => note_dir.to_path_buf(),
+        dir => match dir.strip_prefix("~/") {
+            Some(rest) => std::env::var_os("HOME")
+                .or_else(|| std::env::var_os("USERPROFILE"))
+                .map(|home| PathBuf::from(home).join(rest))
+                .unwrap_or_else(|| PathBuf::from(dir)),
+            None if Path::new(dir).is_absolute() => PathBuf::from(dir),
+            None => workspace.unwrap_or(note_dir).join(dir),
+        },
+    };
+    let name = note
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let template = if preset.file_name.trim().is_empty() {
+        "{name}"
+    } else {
+        preset.file_name.trim()
+    };
+    let stem = template.replace("{name}", &name).replace(
+        "{date}",
+        &chrono::Local::now().format("%Y-%m-%d").to_string(),
+    );
+    dir.join(format!(
+        "{}.{}",
+        workspace::sanitize_file_name(&stem),
+        preset.format.extension()
+    ))
+}
+
+fn upload(
+    file: &Path,
+    host: &str,
+    port: Option<u16>,
+    user: &str,
+    remote_dir: &str,
+) -> Result<String, String> {
+    let host = if user.is_empty() {
+        host.to_string()
+    } else {
+        format!("{}@{}", user, host)
+    };
+    let remote = format!("{}:{}", host, remote_dir.trim_end_matches('/'));
+    let remote = if remote_dir.is_empty() {
+        remote
+    } else {
+        format!("{}/", remote)
+    };
+    // -B 禁止交互式询问密码，没有可用密钥时直接失败
+    let mut command = Command::new("scp");
+    command.arg("-B").arg("-q");
+    if let Some(port) = port {
+        command.arg("-P").arg(port.to_string());
+    }
+    // -- 之后都是文件参数，以 - 开头的主机名或路径不会被当作选项
+    let output = command
+        .arg("--")
+        .arg(file)
+        .arg(&remote)
+        .output()
+        .map_err(|e| format!("无法运行 scp: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "上传失败: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let name = file
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    Ok(format!("{}{}", remote, name))
+}
+
+pub fn find(app: &AppHandle, name: &str) -> Result<ExportPreset, String> {
+    let config: ExportSettings = settings::load(app, "export");
+    config
+        .presets
+        .into_iter()
+        .find(|p| p.name == name)
+        .ok_or_else(|| format!("找不到导出预设: {}", name))
+}
+
+// 过滤器改名或删除（to 为 None）时更新引用它的预设
+pub fn rename_filter(app: &AppHandle, from: &str, to: Option<&str>) -> Result<(), String> {
+    let mut config: ExportSettings = settings::load(app, "export");
+    let mut changed = false;
+    for preset in &mut config.presets {
+        if !preset.filters.iter().any(|f| f == from) {
+            continue;
+        }
+        changed = true;
+        match to {
+            Some(to) => preset
+                .filters
+                .iter_mut()
+                .filter(|f| *f == from)
+                .for_each(|f| *f = to.to_string()),
+            None => preset.filters.retain(|f| f != from),
+        }
+    }
+    if changed {
+        settings::save(app, "export", &config)?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_export_presets(app: AppHandle) -> Vec<ExportPreset> {
+    let config: ExportSettings = settings::load(&app, "export");
+    config.presets
+}
+
+// 同名预设会被覆盖
+#[tauri::command]
+pub fn save_export_preset(app: AppHandle, preset: ExportPreset) -> Result<(), MobiError> {
+    if preset.name.trim().is_empty() {
+        return Err("预设名称不能为空".into());
+    }
+    let mut config: ExportSettings = settings::load(&app, "export");
+    match config.presets.iter_mut().find(|p| p.name == preset.name) {
+        Some(existing) => *existing = preset,
+        None => config.presets.push(preset),
+    }
+    Ok(settings::save(&app, "export", &config)?)
+}
+
+#[tauri::command]
+pub fn delete_export_preset(app: AppHandle, name: String) -> Result<(), MobiError> {
+    let mut config: ExportSettings = settings::load(&app, "export");
+    config.presets.retain(|p| p.name != name);
+    Ok(settings::save(&app, "export", &config)?)
+}
+
+// 按预设导出一篇笔记，依次执行导出后的动作；动作失败时导出结果仍然保留
+#[tauri::command]
+pub async fn run_export_preset(
+    app: AppHandle,
+    name: String,
+    path: String,
+    workspace: Option<String>,
+) -> Result<PresetResult, MobiError> {
+    let preset = find(&app, &name)?;
+
+    let note = PathBuf::from(&path);
+    let workspace = workspace.filter(|w| !w.is_empty()).map(PathBuf::from);
+    let output = output_path(&preset, &note, workspace.as_deref());
+    if let Some(dir) = output.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("创建输出目录失败: {}", e))?;
+    }
+
+    let (content, mut options) = preview::read_note(&note, workspace.as_deref())?;
+    let output_str = output.to_string_lossy().to_string();
+    match preset.format {
+        PresetFormat::Html => {
+            options.code_theme = preset.code_theme.clone();
+            options.theme = preset.theme.clone();
+            options.citation_style = preset.citation_style.clone();
+            options.paged = preset.paged.clone();
+            options.filters = preset.filters.clone();
+            super::export_html(app.clone(), content, output_str.clone(), Some(options)).await?;
+        }
+        PresetFormat::Markdown => {
+            let rules = filters::resolve(&app, &preset.filters)?;
+            let content = filters::apply_async(&rules, FilterStage::Markdown, content).await?;
+            std::fs::write(&output, content).map_err(|e| format!("导出失败: {}", e))?;
+        }
+    }
+
+    let mut uploaded = Vec::new();
+    let mut warnings = Vec::new();
+    for action in &preset.post_actions {
+        match action {
+            PostAction::Open => {
+                if let Err(e) = app.opener().open_path(output_str.clone(), None::<&str>) {
+                    warnings.push(format!("打开导出文件失败: {}", e));
+                }
+            }
+            PostAction::Reveal => {
+                if let Err(e) = app.opener().reveal_item_in_dir(&output) {
+                    warnings.push(format!("显示导出文件失败: {}", e));
+                }
+            }
+            PostAction::Upload {
+                host,
+                port,
+                user,
+                remote_dir,
+            } => {
+                let (file, host, port, user, remote_dir) = (
+                    output.clone(),
+                    host.clone(),
+                    *port,
+                    user.clone(),
+                    remote_dir.clone(),
+                );
+                let remote = tauri::async_runtime::spawn_blocking(move || {
+                    upload(&file, &host, port, &user, &remote_dir)
+                })
+                .await
+                .map_err(|e| e.to_string())??;
+                uploaded.push(remote);
+            }
+        }
+    }
+
+    Ok(PresetResult {
+        output: output_str,
+        uploaded,
+        warnings,
+    })
+}
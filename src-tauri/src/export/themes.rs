@@ -0,0 +1,314 @@
+// 导出主题：与编辑器主题分开，为 HTML、PDF、公众号复制和打印分别指定自定义 CSS，主题和指定关系保存在设置的 themes 节中
+// 主题 CSS 追加在内置样式之后，或完全替换内置样式；公众号编辑器会丢弃 <style>，复制时把标签选择器的规则写成内联样式
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State, Window};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+use super::{ExportOptions, STYLE};
+use crate::error::MobiError;
+use crate::preview::{self, PreviewState};
+use crate::{email, markdown, settings};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum ThemeTarget {
+    #[default]
+    Html,
+    Pdf,
+    // 复制到微信公众号编辑器
+    Wechat,
+    Print,
+}
+
+const TARGETS: [ThemeTarget; 4] = [
+    ThemeTarget::Html,
+    ThemeTarget::Pdf,
+    ThemeTarget::Wechat,
+    ThemeTarget::Print,
+];
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ExportTheme {
+    pub name: String,
+    pub css: String,
+    // 为 true 时不使用内置样式，只用这里的 CSS
+    pub replace_base: bool,
+    // syntect 主题名，导出选项中没有指定代码主题时使用
+    pub code_theme: String,
+}
+
+// 每种去向使用的主题名，留空为内置样式
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ThemeAssignments {
+    html: String,
+    pdf: String,
+    wechat: String,
+    print: String,
+}
+
+impl ThemeAssignments {
+    fn get_mut(&mut self, target: ThemeTarget) -> &mut String {
+        match target {
+            ThemeTarget::Html => &mut self.html,
+            ThemeTarget::Pdf => &mut self.pdf,
+            ThemeTarget::Wechat => &mut self.wechat,
+            ThemeTarget::Print => &mut self.print,
+        }
+    }
+
+    fn get(&self, target: ThemeTarget) -> &str {
+        match target {
+            ThemeTarget::Html => &self.html,
+            ThemeTarget::Pdf => &self.pdf,
+            ThemeTarget::Wechat => &self.wechat,
+            ThemeTarget::Print => &self.print,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ThemeSettings {
+    themes: Vec<ExportTheme>,
+    assigned: ThemeAssignments,
+}
+
+fn load(app: &AppHandle) -> ThemeSettings {
+    settings::load(app, "themes")
+}
+
+fn save(app: &AppHandle, config: &ThemeSettings) -> Result<(), String> {
+    settings::save(app, "themes", config)?;
+    // 打开着的预览页换用新样式
+    preview::reload(app);
+    Ok(())
+}
+
+// name 不为空时按名字查找，否则使用为 target 指定的主题；都没有时为内置样式
+pub fn resolve(app: &AppHandle, name: &str, target: ThemeTarget) -> Option<ExportTheme> {
+    let config = load(app);
+    let name = match name.trim() {
+        "" => config.assigned.get(target),
+        name => name,
+    };
+    if name.is_empty() {
+        return None;
+    }
+    config.themes.into_iter().find(|t| t.name == name)
+}
+
+pub fn stylesheet(theme: Option<&ExportTheme>) -> String {
+    match theme {
+        Some(theme) if theme.replace_base => format!("{}\n", theme.css),
+        Some(theme) => format!("{}\n{}\n", STYLE, theme.css),
+        None => STYLE.to_string(),
+    }
+}
+
+// 主题 CSS 中标签选择器（含逗号分隔的列表）的声明：标签名 -> 声明；@ 规则、类选择器、后代选择器等忽略
+fn tag_rules(css: &str) -> HashMap<String, Vec<String>> {
+    let mut css = css.to_string();
+    while let Some(start) = css.find("/*") {
+        let end = css[start..].find("*/").map_or(css.len(), |i| start + i + 2);
+        css.replace_range(start..end, "");
+    }
+    let mut rules: HashMap<String, Vec<String>> = HashMap::new();
+    let mut rest = css.as_str();
+    while let Some(open) = rest.find('{') {
+        let selector = rest[..open].trim();
+        // 找到配对的右括号，@media 等嵌套块整段跳过
+        let mut depth = 0;
+        let mut close = rest.len();
+        for (i, c) in rest[open..].char_indices() {
+            match c {
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        close = open + i;
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+        let body = &rest[open + 1..close];
+        rest = rest.get(close + 1..).unwrap_or("");
+        if selector.starts_with('@') || body.contains('{') {
+            continue;
+        }
+        let declarations: Vec<String> = body
+            .split(';')
+            .map(|d| {
+                d.split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ")
+                    .replace('"', "'")
+            })
+            .filter(|d| d.contains(':'))
+            .collect();
+        for tag in selector.split(',').map(|s| s.trim().to_ascii_lowercase()) {
+            if !tag.is_empty() && tag.chars().all(|c| c.is_ascii_alphanumeric()) {
+                rules
+                    .entry(tag)
+                    .or_default()
+                    .extend(declarations.iter().cloned());
+            }
+        }
+    }
+    rules
+}
+
+// 公众号用的 HTML 片段：正文包在一个带正文样式的 <section> 中，所有样式都是内联的
+pub fn wechat_html(body: &str, theme: Option<&ExportTheme>) -> String {
+    let mut styles: HashMap<String, String> = HashMap::new();
+    let mut wrapper = String::new();
+    if !theme.is_some_and(|t| t.replace_base) {
+        for (tag, style) in email::INLINE_STYLES {
+            styles.insert(tag.to_string(), style.to_string());
+        }
+        wrapper = email::BODY_STYLE.to_string();
+    }
+    for (tag, declarations) in theme.map(|t| tag_rules(&t.css)).unwrap_or_default() {
+        let declarations = declarations.join(";");
+        let target = match tag.as_str() {
+            "html" | "body" => &mut wrapper,
+            _ => styles.entry(tag).or_default(),
+        };
+        if !target.is_empty() {
+            target.push(';');
+        }
+        target.push_str(&declarations);
+    }
+    format!(
+        "<section style=\"{}\">{}</section>",
+        wrapper,
+        email::inline_with(body, |name| styles.get(name).cloned())
+    )
+}
+
+#[tauri::command]
+pub fn list_export_themes(app: AppHandle) -> ThemeSettings {
+    load(&app)
+}
+
+// 同名主题会被覆盖；previous 为改名前的名字，指定了它的去向随之改名
+#[tauri::command]
+pub fn save_export_theme(
+    app: AppHandle,
+    theme: ExportTheme,
+    previous: Option<String>,
+) -> Result<(), MobiError> {
+    if theme.name.trim().is_empty() {
+        return Err("主题名称不能为空".into());
+    }
+    let mut config = load(&app);
+    if let Some(previous) = previous.filter(|p| *p != theme.name) {
+        config.themes.retain(|t| t.name != previous);
+        for target in TARGETS {
+            let assigned = config.assigned.get_mut(target);
+            if *assigned == previous {
+                *assigned = theme.name.clone();
+            }
+        }
+    }
+    match config.themes.iter_mut().find(|t| t.name == theme.name) {
+        Some(existing) => *existing = theme,
+        None => config.themes.push(theme),
+    }
+    Ok(save(&app, &config)?)
+}
+
+// 删除主题，使用它的去向改回内置样式
+#[tauri::command]
+pub fn delete_export_theme(app: AppHandle, name: String) -> Result<(), MobiError> {
+    let mut config = load(&app);
+    config.themes.retain(|t| t.name != name);
+    for target in TARGETS {
+        let assigned = config.assigned.get_mut(target);
+        if *assigned == name {
+            assigned.clear();
+        }
+    }
+    Ok(save(&app, &config)?)
+}
+
+// 为某种去向指定主题，name 为空时改回内置样式
+#[tauri::command]
+pub fn assign_export_theme(
+    app: AppHandle,
+    target: ThemeTarget,
+    name: Option<String>,
+) -> Result<(), MobiError> {
+    let mut config = load(&app);
+    let name = name.unwrap_or_default();
+    if !name.is_empty() && !config.themes.iter().any(|t| t.name == name) {
+        return Err(MobiError::NotFound(format!("找不到导出主题: {}", name)));
+    }
+    *config.assigned.get_mut(target) = name;
+    Ok(save(&app, &config)?)
+}
+
+// 实时预览当前文档套用主题后的效果，返回预览地址；预览服务未启动时先启动
+#[tauri::command]
+pub fn preview_export_theme(
+    app: AppHandle,
+    window: Window,
+    state: State<PreviewState>,
+    name: Option<String>,
+    target: Option<ThemeTarget>,
+) -> Result<String, MobiError> {
+    let base = match preview::running_url(&state, window.label()) {
+        Some(url) => url,
+        None => preview::start(app, &state, window.label(), None, None)?.url,
+    };
+    let mut query = Vec::new();
+    if let Some(name) = name.filter(|n| !n.is_empty()) {
+        query.push(format!("theme={}", urlencoding::encode(&name)));
+    }
+    if let Some(target) = target {
+        let value = serde_json::to_value(target).map_err(|e| e.to_string())?;
+        query.push(format!("target={}", value.as_str().unwrap_or_default()));
+    }
+    Ok(if query.is_empty() {
+        base
+    } else {
+        format!("{}?{}", base, query.join("&"))
+    })
+}
+
+// 按公众号主题渲染并复制为富文本，返回复制的 HTML；本地图片需要在公众号编辑器中重新上传
+#[tauri::command]
+pub async fn copy_wechat_html(
+    app: AppHandle,
+    content: String,
+    path: Option<String>,
+    workspace: Option<String>,
+) -> Result<String, MobiError> {
+    let title = path
+        .as_deref()
+        .and_then(|p| std::path::Path::new(p).file_stem())
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let options = ExportOptions {
+        title,
+        workspace: workspace.unwrap_or_default(),
+        target: ThemeTarget::Wechat,
+        ..Default::default()
+    };
+    let body = markdown::strip_front_matter(&content).to_string();
+    let (body, options) = super::prepare(&app, body, Some(options)).await?;
+    let html = wechat_html(
+        &super::render_body(&body, &options),
+        options.theme_style.as_ref(),
+    );
+    app.clipboard()
+        .write_html(html.clone(), Some(markdown::to_plain_text(&body)))
+        .map_err(|e| format!("复制失败: {}", e))?;
+    Ok(html)
+}
@@ -0,0 +1,221 @@
+// 打开前检查：二进制文件和过大的文件不直接读进编辑器（会显示乱码或卡住界面），而是返回警告由用户选择只读打开、分段打开或取消
+// 分段模式按字节偏移读取一段文本，段首尾对齐到 UTF-8 字符边界
+
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::error::MobiError;
+use crate::settings;
+
+// 判断是否为二进制时读取的文件开头字节数
+const SNIFF_BYTES: usize = 8 * 1024;
+
+// 设置中的 open 节
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct OpenSettings {
+    // 超过此大小（MB）的文件打开前提示
+    pub max_size_mb: u64,
+    // 分段模式每段的大小（KB）
+    pub chunk_size_kb: u64,
+}
+
+impl Default for OpenSettings {
+    fn default() -> Self {
+        OpenSettings {
+            max_size_mb: 5,
+            chunk_size_kb: 512,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum WarningKind {
+    Binary,
+    TooLarge,
+}
+
+#[derive(Serialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum OpenChoice {
+    ReadOnly,
+    Chunked,
+    Cancel,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenWarning {
+    kind: WarningKind,
+    path: String,
+    size: u64,
+    // 大小上限（字节）
+    limit: u64,
+    message: String,
+    // 可选的处理方式，按推荐顺序
+    choices: Vec<OpenChoice>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileChunk {
+    text: String,
+    offset: u64,
+    // 下一段的起始偏移，读到文末时等于文件大小
+    next: u64,
+    size: u64,
+    eof: bool,
+}
+
+fn format_size(bytes: u64) -> String {
+    if bytes >= 1024 * 1024 {
+        format!("{:.1} MB", bytes as f64 / 1024.0 / 1024.0)
+    } else {
+        format!("{} KB", bytes.div_ceil(1024))
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+// 文件开头含 NUL、是 UTF-16 或不是合法的 UTF-8、控制字符过多时视为二进制，返回原因
+fn binary_reason(sample: &[u8]) -> Option<&'static str> {
+    if sample.starts_with(&[0xFF, 0xFE]) || sample.starts_with(&[0xFE, 0xFF]) {
+        return Some("UTF-16 编码的文件");
+    }
+    if sample.contains(&0) {
+        return Some("二进制文件");
+    }
+    if let Err(e) = std::str::from_utf8(sample) {
+        // 样本末尾被截断的多字节字符不算
+        if e.error_len().is_some() {
+            return Some("不是 UTF-8 编码的文本");
+        }
+    }
+    let control = sample
+        .iter()
+        .filter(|b| b.is_ascii_control() && !matches!(b, b'\t' | b'\n' | b'\r' | 0x0C | 0x1B))
+        .count();
+    if control * 10 > sample.len() {
+        return Some("二进制文件");
+    }
+    None
+}
+
+// 检查文件是否适合直接打开，返回 None 表示可以正常打开
+pub fn inspect(app: &AppHandle, path: &Path) -> Result<Option<OpenWarning>, MobiError> {
+    let config: OpenSettings = settings::load(app, "open");
+    let size = fs::metadata(path)
+        .map_err(|e| MobiError::io("读取文件信息失败", e))?
+        .len();
+    let limit = config.max_size_mb.max(1) * 1024 * 1024;
+
+    let mut sample = Vec::with_capacity(SNIFF_BYTES);
+    File::open(path)
+        .map_err(|e| MobiError::io("打开文件失败", e))?
+        .take(SNIFF_BYTES as u64)
+        .read_to_end(&mut sample)
+        .map_err(|e| MobiError::io("读取文件失败", e))?;
+
+    let warning = |kind, message: String, choices| OpenWarning {
+        kind,
+        path: path.to_string_lossy().to_string(),
+        size,
+        limit,
+        message,
+        choices,
+    };
+    if let Some(reason) = binary_reason(&sample) {
+        return Ok(Some(warning(
+            WarningKind::Binary,
+            format!(
+                "{}（{}）看起来是{}，以文本打开会显示乱码",
+                file_name(path),
+                format_size(size),
+                reason
+            ),
+            vec![OpenChoice::Cancel, OpenChoice::ReadOnly],
+        )));
+    }
+    if size > limit {
+        return Ok(Some(warning(
+            WarningKind::TooLarge,
+            format!(
+                "{} 有 {}，超过了 {}，完整打开可能导致界面卡顿",
+                file_name(path),
+                format_size(size),
+                format_size(limit)
+            ),
+            vec![
+                OpenChoice::Chunked,
+                OpenChoice::ReadOnly,
+                OpenChoice::Cancel,
+            ],
+        )));
+    }
+    Ok(None)
+}
+
+// 用于 UTF-8 续字节判断：10xxxxxx
+fn is_continuation(byte: u8) -> bool {
+    byte & 0xC0 == 0x80
+}
+
+#[tauri::command]
+pub fn check_open_file(app: AppHandle, path: String) -> Result<Option<OpenWarning>, MobiError> {
+    inspect(&app, Path::new(&path))
+}
+
+// 从 offset 起读取一段，length 缺省为设置中的段大小；非 UTF-8 内容按替换字符显示
+#[tauri::command]
+pub fn read_file_chunk(
+    app: AppHandle,
+    path: String,
+    offset: Option<u64>,
+    length: Option<u64>,
+) -> Result<FileChunk, MobiError> {
+    let config: OpenSettings = settings::load(&app, "open");
+    let mut file = File::open(&path).map_err(|e| MobiError::io("打开文件失败", e))?;
+    let size = file
+        .metadata()
+        .map_err(|e| MobiError::io("读取文件信息失败", e))?
+        .len();
+    let offset = offset.unwrap_or(0).min(size);
+    let length = length.unwrap_or(config.chunk_size_kb.max(1) * 1024).max(4);
+
+    // 多读 3 个字节，保证段尾的多字节字符完整
+    let mut buffer = Vec::new();
+    file.seek(SeekFrom::Start(offset))
+        .map_err(|e| MobiError::io("读取文件失败", e))?;
+    file.take(length + 3)
+        .read_to_end(&mut buffer)
+        .map_err(|e| MobiError::io("读取文件失败", e))?;
+
+    // 段首落在字符中间时跳过残余的续字节，这些字节已包含在上一段
+    let start = buffer
+        .iter()
+        .take(3)
+        .take_while(|b| is_continuation(**b))
+        .count()
+        .min(buffer.len());
+    let mut end = (length as usize).min(buffer.len());
+    while end > start && end < buffer.len() && is_continuation(buffer[end]) {
+        end += 1;
+    }
+    let next = offset + end as u64;
+    Ok(FileChunk {
+        text: String::from_utf8_lossy(&buffer[start..end]).to_string(),
+        offset,
+        next,
+        size,
+        eof: next >= size,
+    })
+}
@@ -0,0 +1,95 @@
+// 前端的文件读写：打开、新建、删除、重命名笔记和保存粘贴的附件，路径先经 asset::check_path 检查是否在该窗口的工作区范围内
+// fs 插件的范围只会扩大、无法收回，工作区范围改由这里按窗口检查，切换工作区后之前的目录随即不可访问
+
+use std::fs;
+use std::path::Path;
+
+use tauri::ipc::{InvokeBody, Request};
+use tauri::{AppHandle, Window};
+
+use crate::asset;
+use crate::error::MobiError;
+
+#[tauri::command]
+pub fn read_text_file(app: AppHandle, window: Window, path: String) -> Result<String, MobiError> {
+    let path = asset::check_path(&app, window.label(), Path::new(&path))?;
+    fs::read_to_string(&path).map_err(|e| MobiError::io("读取文件失败", e))
+}
+
+#[tauri::command]
+pub fn write_text_file(
+    app: AppHandle,
+    window: Window,
+    path: String,
+    contents: String,
+) -> Result<(), MobiError> {
+    let path = asset::check_path(&app, window.label(), Path::new(&path))?;
+    fs::write(&path, contents).map_err(|e| MobiError::io("写入文件失败", e))
+}
+
+// 二进制内容直接作为请求体传入，路径放在 path 请求头中（百分号编码）
+#[tauri::command]
+pub fn write_binary_file(
+    app: AppHandle,
+    window: Window,
+    request: Request<'_>,
+) -> Result<(), MobiError> {
+    let path = request
+        .headers()
+        .get("path")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| urlencoding::decode(v).ok())
+        .ok_or("缺少文件路径")?;
+    let InvokeBody::Raw(data) = request.body() else {
+        return Err("文件内容应为二进制数据".into());
+    };
+    let path = asset::check_path(&app, window.label(), Path::new(path.as_ref()))?;
+    fs::write(&path, data).map_err(|e| MobiError::io("写入文件失败", e))
+}
+
+// 范围外的路径同样返回 false
+#[tauri::command]
+pub fn path_exists(app: AppHandle, window: Window, path: String) -> bool {
+    asset::check_path(&app, window.label(), Path::new(&path)).is_ok_and(|path| path.exists())
+}
+
+#[tauri::command]
+pub fn create_dir(
+    app: AppHandle,
+    window: Window,
+    path: String,
+    recursive: Option<bool>,
+) -> Result<(), MobiError> {
+    let path = asset::check_path(&app, window.label(), Path::new(&path))?;
+    let result = if recursive.unwrap_or(false) {
+        fs::create_dir_all(&path)
+    } else {
+        fs::create_dir(&path)
+    };
+    result.map_err(|e| MobiError::io("创建目录失败", e))
+}
+
+// 目录连同其中内容一起删除；符号链接只删除链接本身
+#[tauri::command]
+pub fn remove_path(app: AppHandle, window: Window, path: String) -> Result<(), MobiError> {
+    let path = asset::check_path(&app, window.label(), Path::new(&path))?;
+    let is_dir = fs::symlink_metadata(&path).is_ok_and(|m| m.is_dir());
+    let result = if is_dir {
+        fs::remove_dir_all(&path)
+    } else {
+        fs::remove_file(&path)
+    };
+    result.map_err(|e| MobiError::io("删除失败", e))
+}
+
+#[tauri::command]
+pub fn rename_path(
+    app: AppHandle,
+    window: Window,
+    from: String,
+    to: String,
+) -> Result<(), MobiError> {
+    let from = asset::check_path(&app, window.label(), Path::new(&from))?;
+    let to = asset::check_path(&app, window.label(), Path::new(&to))?;
+    fs::rename(&from, &to).map_err(|e| MobiError::io("重命名失败", e))
+}
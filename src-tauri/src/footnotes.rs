@@ -0,0 +1,367 @@
+// 脚注整理：统计脚注引用与定义，找出孤立或重复的定义，按出现顺序重新编号
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::error::MobiError;
+use crate::{document, markdown};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FootnoteRef {
+    pub label: String,
+    pub line: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FootnoteDef {
+    pub label: String,
+    pub line: usize,
+    text: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FootnoteReport {
+    pub references: Vec<FootnoteRef>,
+    pub definitions: Vec<FootnoteDef>,
+    // 有定义但没有被引用
+    pub orphaned: Vec<String>,
+    // 被引用但没有定义
+    pub missing: Vec<String>,
+    pub duplicates: Vec<String>,
+}
+
+// 脚注定义及其续行，行号从 0 开始，end 不含
+struct Definition {
+    label: String,
+    start: usize,
+    end: usize,
+}
+
+// [^label]: 形式的定义行，返回标签和定义正文的起始字节
+fn definition_label(line: &str) -> Option<(String, usize)> {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    if indent > 3 {
+        return None;
+    }
+    let rest = line[indent..].strip_prefix("[^")?;
+    let close = rest.find("]:")?;
+    let label = &rest[..close];
+    if label.is_empty() || label.contains(char::is_whitespace) {
+        return None;
+    }
+    Some((label.to_string(), indent + 2 + close + 2))
+}
+
+fn is_continuation(line: &str) -> bool {
+    line.starts_with("    ") || line.starts_with('\t')
+}
+
+fn code_lines(content: &str) -> HashSet<usize> {
+    markdown::fenced_blocks(content)
+        .iter()
+        .flat_map(|b| b.start_line..=b.end_line)
+        .collect()
+}
+
+fn definitions(lines: &[&str], code: &HashSet<usize>) -> Vec<Definition> {
+    let mut defs = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let Some((label, _)) = definition_label(lines[i]).filter(|_| !code.contains(&i)) else {
+            i += 1;
+            continue;
+        };
+        let start = i;
+        i += 1;
+        // 续行需缩进，中间可以夹空行
+        let mut end = i;
+        while i < lines.len() {
+            if is_continuation(lines[i]) {
+                i += 1;
+                end = i;
+            } else if lines[i].trim().is_empty() {
+                i += 1;
+            } else {
+                break;
+            }
+        }
+        i = end;
+        defs.push(Definition { label, start, end });
+    }
+    defs
+}
+
+// 逐个处理行内的 [^label] 引用（跳过行内代码），f 返回替换后的标签
+fn map_references(line: &str, mut f: impl FnMut(&str) -> String) -> String {
+    let mut output = String::with_capacity(line.len());
+    let mut in_code = false;
+    let mut rest = line;
+    while let Some(pos) = rest.find(['`', '[']) {
+        output.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+        if rest.starts_with('`') {
+            in_code = !in_code;
+            output.push('`');
+            rest = &rest[1..];
+            continue;
+        }
+        let label = rest
+            .strip_prefix("[^")
+            .and_then(|r| r.find(']').map(|close| &r[..close]))
+            .filter(|l| !l.is_empty() && !l.contains(char::is_whitespace));
+        match label {
+            Some(label) if !in_code => {
+                output.push_str("[^");
+                output.push_str(&f(label));
+                output.push(']');
+                rest = &rest[label.len() + 3..];
+            }
+            _ => {
+                output.push('[');
+                rest = &rest[1..];
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+pub fn report(content: &str) -> FootnoteReport {
+    let lines: Vec<&str> = content.lines().collect();
+    let code = code_lines(content);
+    let defs = definitions(&lines, &code);
+
+    let mut references = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        if code.contains(&i) {
+            continue;
+        }
+        let body = match definition_label(line) {
+            Some((_, offset)) => &line[offset..],
+            None => line,
+        };
+        map_references(body, |label| {
+            references.push(FootnoteRef {
+                label: label.to_string(),
+                line: i,
+            });
+            label.to_string()
+        });
+    }
+
+    let referenced: HashSet<&str> = references.iter().map(|r| r.label.as_str()).collect();
+    let mut seen = HashSet::new();
+    let mut duplicates = Vec::new();
+    let mut orphaned = Vec::new();
+    for def in &defs {
+        if !seen.insert(def.label.as_str()) && !duplicates.contains(&def.label) {
+            duplicates.push(def.label.clone());
+        }
+        if !referenced.contains(def.label.as_str()) && !orphaned.contains(&def.label) {
+            orphaned.push(def.label.clone());
+        }
+    }
+    let mut missing: Vec<String> = Vec::new();
+    for r in &references {
+        if !seen.contains(r.label.as_str()) && !missing.contains(&r.label) {
+            missing.push(r.label.clone());
+        }
+    }
+
+    let definitions = defs
+        .iter()
+        .map(|d| {
+            let (_, offset) = definition_label(lines[d.start]).unwrap_or_default();
+            let mut text = lines[d.start][offset..].trim().to_string();
+            for line in &lines[d.start + 1..d.end] {
+                text.push('\n');
+                text.push_str(line.trim());
+            }
+            FootnoteDef {
+                label: d.label.clone(),
+                line: d.start,
+                text: text.trim().to_string(),
+            }
+        })
+        .collect();
+
+    FootnoteReport {
+        references,
+        definitions,
+        orphaned,
+        missing,
+        duplicates,
+    }
+}
+
+// 按首次引用的顺序编号为 1..n，未被引用的定义排在后面，没有定义的引用不参与编号，编号跳过它们占用的数字
+pub fn renumber(content: &str, move_to_end: bool) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let code = code_lines(content);
+    let defs = definitions(&lines, &code);
+    let def_labels: Vec<&str> = defs.iter().map(|d| d.label.as_str()).collect();
+
+    // 先找出没有定义的引用，它们保留原标签，新编号要避开其中的数字，否则两个脚注会合并
+    let mut order: Vec<String> = Vec::new();
+    let mut undefined: HashSet<String> = HashSet::new();
+    for (i, line) in lines.iter().enumerate() {
+        if code.contains(&i) {
+            continue;
+        }
+        let body = match definition_label(line) {
+            Some((_, offset)) => &line[offset..],
+            None => line,
+        };
+        map_references(body, |label| {
+            if def_labels.contains(&label) {
+                order.push(label.to_string());
+            } else {
+                undefined.insert(label.to_string());
+            }
+            label.to_string()
+        });
+    }
+    order.extend(def_labels.iter().map(|l| l.to_string()));
+
+    let mut numbers: HashMap<String, usize> = HashMap::new();
+    let mut next = 0;
+    for label in order {
+        if numbers.contains_key(&label) {
+            continue;
+        }
+        next += 1;
+        while undefined.contains(&next.to_string()) {
+            next += 1;
+        }
+        numbers.insert(label, next);
+    }
+    let relabel = |label: &str| {
+        numbers
+            .get(label)
+            .map(|n| n.to_string())
+            .unwrap_or_else(|| label.to_string())
+    };
+
+    let mut def_start: HashMap<usize, &Definition> = HashMap::new();
+    for def in &defs {
+        def_start.insert(def.start, def);
+    }
+
+    let mut body: Vec<String> = Vec::new();
+    let mut moved: Vec<(usize, Vec<String>)> = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        if code.contains(&i) {
+            body.push(lines[i].to_string());
+            i += 1;
+            continue;
+        }
+        if let Some(def) = def_start.get(&i) {
+            let (_, offset) = definition_label(lines[i]).unwrap_or_default();
+            let mut block = vec![format!(
+                "[^{}]:{}",
+                relabel(&def.label),
+                map_references(&lines[i][offset..], relabel)
+            )];
+            block.extend(
+                lines[i + 1..def.end]
+                    .iter()
+                    .map(|l| map_references(l, relabel)),
+            );
+            if move_to_end {
+                moved.push((
+                    numbers.get(&def.label).copied().unwrap_or(usize::MAX),
+                    block,
+                ));
+            } else {
+                body.extend(block);
+            }
+            i = def.end;
+            continue;
+        }
+        body.push(map_references(lines[i], relabel));
+        i += 1;
+    }
+
+    if move_to_end && !moved.is_empty() {
+        // 去掉定义移走后留下的多余空行
+        let mut cleaned: Vec<String> = Vec::with_capacity(body.len());
+        for line in body {
+            let blank = line.trim().is_empty();
+            if blank && cleaned.last().is_some_and(|l: &String| l.trim().is_empty()) {
+                continue;
+            }
+            cleaned.push(line);
+        }
+        while cleaned.last().is_some_and(|l| l.trim().is_empty()) {
+            cleaned.pop();
+        }
+        body = cleaned;
+        body.push(String::new());
+        moved.sort_by_key(|(n, _)| *n);
+        for (_, block) in moved {
+            body.extend(block);
+        }
+    }
+
+    let mut text = body.join("\n");
+    if content.ends_with('\n') {
+        text.push('\n');
+    }
+    text
+}
+
+#[tauri::command]
+pub fn get_footnotes(path: String) -> Result<FootnoteReport, MobiError> {
+    let content = fs::read_to_string(&path).map_err(|e| format!("读取文件失败: {}", e))?;
+    Ok(report(&content))
+}
+
+// 重新编号并写回文件，返回新内容供编辑器刷新
+#[tauri::command]
+pub fn renumber_footnotes(path: String, move_to_end: Option<bool>) -> Result<String, MobiError> {
+    let content = fs::read_to_string(&path).map_err(|e| format!("读取文件失败: {}", e))?;
+    let text = renumber(&content, move_to_end.unwrap_or(false));
+    if text != content {
+        document::write_note(Path::new(&path), &text)?;
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renumbers_in_reference_order() {
+        let content = "x[^b] y[^a]\n\n[^a]: A\n[^b]: B\n";
+        assert_eq!(
+            renumber(content, false),
+            "x[^1] y[^2]\n\n[^2]: A\n[^1]: B\n"
+        );
+        assert_eq!(renumber(content, true), "x[^1] y[^2]\n\n[^1]: B\n[^2]: A\n");
+    }
+
+    // 没有定义的 [^1] 保留原样，新编号跳过 1，否则两个脚注会合并
+    #[test]
+    fn skips_numbers_held_by_undefined_references() {
+        assert_eq!(
+            renumber("A[^a] B[^1]\n\n[^a]: note a\n", false),
+            "A[^2] B[^1]\n\n[^2]: note a\n"
+        );
+    }
+
+    #[test]
+    fn leaves_code_blocks_alone() {
+        assert_eq!(
+            renumber("One[^x]\n\n```\n[^y]\n```\n\n[^x]: X\n", false),
+            "One[^1]\n\n```\n[^y]\n```\n\n[^1]: X\n"
+        );
+    }
+}
@@ -0,0 +1,761 @@
+// 收件箱：监视设置中指定的文件夹，放入其中的 Markdown、HTML、Word 文档和图片自动转换为笔记导入工作区，导入后发送系统通知
+// 扫描仪、邮件规则、浏览器下载等只需把文件存到该文件夹即可；文件大小稳定后才导入，避免读到写了一半的文件
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State, Window};
+use tauri_plugin_notification::NotificationExt;
+use zip::ZipArchive;
+
+use crate::error::{self, MobiError, MutexExt};
+use crate::{markdown, settings, workspace};
+
+// 文件大小在这段时间内没有变化才认为写入完成
+const SETTLE: Duration = Duration::from_secs(1);
+// 保留原文件时移入收件箱下的这个子文件夹，不会被再次导入
+const IMPORTED_DIR: &str = "imported";
+
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "webp", "svg", "bmp"];
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct InboxSettings {
+    // 留空为不启用
+    folder: String,
+    // 导入到的目录，相对路径相对于工作区
+    target_dir: String,
+    keep_original: bool,
+}
+
+impl Default for InboxSettings {
+    fn default() -> Self {
+        InboxSettings {
+            folder: String::new(),
+            target_dir: "Inbox".into(),
+            keep_original: false,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct InboxState {
+    // 各窗口的工作区，按调用先后排列；收件箱文件夹只有一个，只导入到最后调用的窗口的工作区，免得同一个文件被导入多次
+    windows: Mutex<Vec<(String, Option<String>)>>,
+    active: Mutex<Option<RecommendedWatcher>>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct InboxImported {
+    source: String,
+    path: String,
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+// 隐藏文件、Office 锁文件和下载中的临时文件（.crdownload、.part 等）都不在支持的类型内
+fn is_candidate(path: &Path) -> bool {
+    let Some(name) = path.file_name().map(|n| n.to_string_lossy().to_string()) else {
+        return false;
+    };
+    if name.starts_with('.') || name.starts_with("~$") || !path.is_file() {
+        return false;
+    }
+    let ext = extension(path);
+    matches!(
+        ext.as_str(),
+        "md" | "markdown" | "txt" | "html" | "htm" | "docx"
+    ) || IMAGE_EXTENSIONS.contains(&ext.as_str())
+}
+
+fn import(source: &Path, target: &Path) -> Result<PathBuf, String> {
+    fs::create_dir_all(target).map_err(|e| format!("创建导入目录失败: {}", e))?;
+    let stem = workspace::sanitize_file_name(
+        &source
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default(),
+    );
+    let note = workspace::unique_path(target, &stem, "md");
+    let ext = extension(source);
+    let content = match ext.as_str() {
+        "md" | "markdown" | "txt" => {
+            fs::read_to_string(source).map_err(|e| format!("读取文件失败: {}", e))?
+        }
+        "html" | "htm" => {
+            let html = fs::read_to_string(source).map_err(|e| format!("读取文件失败: {}", e))?;
+            htmd::HtmlToMarkdown::builder()
+                .skip_tags(vec!["head", "script", "style", "noscript"])
+                .build()
+                .convert(&html)
+                .map_err(|e| format!("转换 HTML 失败: {}", e))?
+        }
+        "docx" => {
+            let note_stem = note
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default();
+            docx::convert(source, target, &format!("assets/{}", note_stem))?
+        }
+        _ => {
+            let assets = target.join("assets");
+            fs::create_dir_all(&assets).map_err(|e| format!("创建附件目录失败: {}", e))?;
+            let image = workspace::unique_path(&assets, &stem, &ext);
+            fs::copy(source, &image).map_err(|e| format!("复制图片失败: {}", e))?;
+            let name = image
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            format!(
+                "![{}](assets/{})\n",
+                stem,
+                markdown::link_destination(&name)
+            )
+        }
+    };
+    fs::write(&note, content).map_err(|e| format!("写入笔记失败: {}", e))?;
+    Ok(note)
+}
+
+// 导入成功后删除原文件，或移入 imported 子文件夹
+fn dispose(source: &Path, keep_original: bool) -> Result<(), String> {
+    if !keep_original {
+        return fs::remove_file(source).map_err(|e| format!("删除原文件失败: {}", e));
+    }
+    let dir = source.parent().unwrap_or(Path::new(".")).join(IMPORTED_DIR);
+    fs::create_dir_all(&dir).map_err(|e| format!("移动原文件失败: {}", e))?;
+    let stem = source
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    fs::rename(
+        source,
+        workspace::unique_path(&dir, &stem, &extension(source)),
+    )
+    .map_err(|e| format!("移动原文件失败: {}", e))
+}
+
+fn process(app: &AppHandle, source: &Path, target: &Path, keep_original: bool) {
+    let name = source
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let result = import(source, target).and_then(|note| {
+        dispose(source, keep_original)?;
+        Ok(note)
+    });
+    let (title, body) = match result {
+        Ok(note) => {
+            let _ = app.emit(
+                "inbox-imported",
+                InboxImported {
+                    source: source.to_string_lossy().to_string(),
+                    path: note.to_string_lossy().to_string(),
+                },
+            );
+            ("已从收件箱导入".to_string(), name)
+        }
+        Err(error) => ("收件箱导入失败".to_string(), format!("{}: {}", name, error)),
+    };
+    let _ = app.notification().builder().title(title).body(body).show();
+}
+
+fn forward(
+    app: AppHandle,
+    existing: Vec<PathBuf>,
+    target: PathBuf,
+    keep_original: bool,
+    rx: mpsc::Receiver<Vec<PathBuf>>,
+) {
+    thread::spawn(move || {
+        // 等待大小稳定的文件：上次检查到的大小和检查时间
+        let mut pending: HashMap<PathBuf, (Option<u64>, Instant)> = existing
+            .into_iter()
+            .map(|path| (path, (None, Instant::now())))
+            .collect();
+        loop {
+            match rx.recv_timeout(SETTLE) {
+                Ok(paths) => {
+                    for path in paths {
+                        pending.entry(path).or_insert((None, Instant::now()));
+                    }
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                // 监视器已释放
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            }
+
+            let mut ready = Vec::new();
+            pending.retain(|path, (size, checked)| {
+                if checked.elapsed() < SETTLE {
+                    return true;
+                }
+                if !is_candidate(path) {
+                    return false;
+                }
+                let current = fs::metadata(path).map(|m| m.len()).ok();
+                if current.is_some() && current == *size {
+                    ready.push(path.clone());
+                    return false;
+                }
+                *size = current;
+                *checked = Instant::now();
+                true
+            });
+            for path in ready {
+                process(&app, &path, &target, keep_original);
+            }
+        }
+    });
+}
+
+// 按最后调用的窗口的工作区重新开始监视
+fn restart(app: &AppHandle, state: &InboxState) -> Result<(), MobiError> {
+    let mut active = state.active.locked();
+    *active = None;
+    let Some(workspace) = state.windows.locked().last().map(|(_, w)| w.clone()) else {
+        return Ok(());
+    };
+
+    let config: InboxSettings = settings::load(app, "inbox");
+    if config.folder.trim().is_empty() {
+        return Ok(());
+    }
+    let folder = PathBuf::from(config.folder.trim());
+    let target = match config.target_dir.trim() {
+        dir if Path::new(dir).is_absolute() => PathBuf::from(dir),
+        dir => match workspace.filter(|w| !w.is_empty()) {
+            Some(workspace) => Path::new(&workspace).join(dir),
+            // 没有打开工作区时无处导入
+            None => return Ok(()),
+        },
+    };
+    if target.starts_with(&folder) {
+        return Err("导入目录不能位于收件箱文件夹内".into());
+    }
+    fs::create_dir_all(&folder).map_err(|e| format!("创建收件箱文件夹失败: {}", e))?;
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        if let Ok(event) = res {
+            if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                let _ = tx.send(event.paths);
+            }
+        }
+    })
+    .map_err(|e| format!("创建文件监视失败: {}", e))?;
+    watcher
+        .watch(&folder, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("监视收件箱失败: {}", e))?;
+
+    // 应用未运行期间放入的文件
+    let existing = fs::read_dir(&folder)
+        .map(|entries| entries.flatten().map(|e| e.path()).collect())
+        .unwrap_or_default();
+    forward(app.clone(), existing, target, config.keep_original, rx);
+    *active = Some(watcher);
+    Ok(())
+}
+
+// 窗口关闭时去掉它的工作区，收件箱原来导入到这个窗口时交给下一个窗口
+pub fn forget_window(app: &AppHandle, label: &str) {
+    let Some(state) = app.try_state::<InboxState>() else {
+        return;
+    };
+    let was_owner = {
+        let mut windows = state.windows.locked();
+        let was_owner = windows.last().is_some_and(|(l, _)| l == label);
+        windows.retain(|(l, _)| l != label);
+        was_owner
+    };
+    if was_owner {
+        if let Err(e) = restart(app, &state) {
+            error::report(app, "收件箱", e);
+        }
+    }
+}
+
+// 切换工作区或修改收件箱设置后调用；未设置收件箱文件夹时停止监视
+#[tauri::command]
+pub fn watch_inbox(
+    app: AppHandle,
+    window: Window,
+    state: State<InboxState>,
+    workspace: Option<String>,
+) -> Result<(), MobiError> {
+    {
+        let mut windows = state.windows.locked();
+        windows.retain(|(label, _)| label != window.label());
+        windows.push((window.label().to_string(), workspace));
+    }
+    restart(&app, &state)
+}
+
+// Word 文档转 Markdown：读取 word/document.xml 中的段落、标题、粗斜体、列表、表格、超链接和图片
+// 图片解压到笔记旁的附件目录；其余格式（批注、修订、页眉页脚、文本框等）不导入
+mod docx {
+    use super::*;
+
+    struct Rel {
+        target: String,
+        external: bool,
+    }
+
+    #[derive(Default)]
+    struct Numbering {
+        // numId -> abstractNumId
+        nums: HashMap<String, String>,
+        // (abstractNumId, ilvl) -> 是否为编号列表
+        ordered: HashMap<(String, String), bool>,
+    }
+
+    impl Numbering {
+        fn is_ordered(&self, num_id: &str, level: &str) -> bool {
+            self.nums
+                .get(num_id)
+                .and_then(|abs| self.ordered.get(&(abs.clone(), level.to_string())))
+                .copied()
+                .unwrap_or(false)
+        }
+    }
+
+    struct Run {
+        text: String,
+        bold: bool,
+        italic: bool,
+        link: Option<String>,
+        // 已经是 Markdown 的片段（图片、换行），不再转义
+        raw: bool,
+    }
+
+    #[derive(Default)]
+    struct Paragraph {
+        heading: Option<usize>,
+        // (numId, ilvl)
+        list: Option<(String, String)>,
+        runs: Vec<Run>,
+    }
+
+    struct Block {
+        text: String,
+        list: bool,
+    }
+
+    struct Converter<'a> {
+        archive: &'a mut ZipArchive<File>,
+        rels: HashMap<String, Rel>,
+        numbering: Numbering,
+        dir: &'a Path,
+        assets: &'a str,
+        blocks: Vec<Block>,
+        paragraph: Option<Paragraph>,
+        bold: bool,
+        italic: bool,
+        link: Option<String>,
+        in_text: bool,
+        table_depth: usize,
+        rows: Vec<Vec<String>>,
+    }
+
+    fn read_entry(archive: &mut ZipArchive<File>, name: &str) -> Option<Vec<u8>> {
+        let mut entry = archive.by_name(name).ok()?;
+        let mut bytes = Vec::new();
+        entry.read_to_end(&mut bytes).ok()?;
+        Some(bytes)
+    }
+
+    fn attr(e: &BytesStart, name: &[u8]) -> Option<String> {
+        e.attributes()
+            .flatten()
+            .find(|a| a.key.as_ref() == name)
+            .and_then(|a| a.unescape_value().ok())
+            .map(|v| v.into_owned())
+    }
+
+    // <w:b/> 表示开启，w:val 为 0 / false 时关闭
+    fn toggle(e: &BytesStart) -> bool {
+        !matches!(attr(e, b"w:val").as_deref(), Some("0" | "false" | "none"))
+    }
+
+    // 逐个回调 XML 元素的开始（含空元素）、结束和文字
+    fn walk(xml: &[u8], mut on: impl FnMut(XmlEvent)) -> Result<(), String> {
+        let mut reader = Reader::from_reader(xml);
+        let mut buf = Vec::new();
+        loop {
+            match reader.read_event_into(&mut buf) {
+                Ok(Event::Start(e)) => on(XmlEvent::Start(&e)),
+                Ok(Event::Empty(e)) => {
+                    on(XmlEvent::Start(&e));
+                    on(XmlEvent::End(e.name().as_ref()));
+                }
+                Ok(Event::End(e)) => on(XmlEvent::End(e.name().as_ref())),
+                Ok(Event::Text(t)) => {
+                    if let Ok(text) = t.unescape() {
+                        on(XmlEvent::Text(&text));
+                    }
+                }
+                Ok(Event::Eof) => return Ok(()),
+                Ok(_) => {}
+                Err(e) => return Err(format!("解析 Word 文档失败: {}", e)),
+            }
+            buf.clear();
+        }
+    }
+
+    enum XmlEvent<'e> {
+        Start(&'e BytesStart<'e>),
+        End(&'e [u8]),
+        Text(&'e str),
+    }
+
+    fn relationships(archive: &mut ZipArchive<File>) -> HashMap<String, Rel> {
+        let mut rels = HashMap::new();
+        if let Some(xml) = read_entry(archive, "word/_rels/document.xml.rels") {
+            let _ = walk(&xml, |event| {
+                if let XmlEvent::Start(e) = event {
+                    if let (Some(id), Some(target)) = (attr(e, b"Id"), attr(e, b"Target")) {
+                        let external = attr(e, b"TargetMode").as_deref() == Some("External");
+                        rels.insert(id, Rel { target, external });
+                    }
+                }
+            });
+        }
+        rels
+    }
+
+    fn numbering(archive: &mut ZipArchive<File>) -> Numbering {
+        let mut numbering = Numbering::default();
+        let Some(xml) = read_entry(archive, "word/numbering.xml") else {
+            return numbering;
+        };
+        let (mut abs, mut level, mut num) = (None, None, None);
+        let _ = walk(&xml, |event| match event {
+            XmlEvent::Start(e) => match e.name().as_ref() {
+                b"w:abstractNum" => abs = attr(e, b"w:abstractNumId"),
+                b"w:lvl" => level = attr(e, b"w:ilvl"),
+                b"w:numFmt" => {
+                    if let (Some(abs), Some(level), Some(format)) =
+                        (&abs, &level, attr(e, b"w:val"))
+                    {
+                        numbering
+                            .ordered
+                            .insert((abs.clone(), level.clone()), format != "bullet");
+                    }
+                }
+                b"w:num" => num = attr(e, b"w:numId"),
+                b"w:abstractNumId" => {
+                    if let (Some(num), Some(abs)) = (&num, attr(e, b"w:val")) {
+                        numbering.nums.insert(num.clone(), abs);
+                    }
+                }
+                _ => {}
+            },
+            XmlEvent::End(b"w:abstractNum") => abs = None,
+            XmlEvent::End(b"w:num") => num = None,
+            _ => {}
+        });
+        numbering
+    }
+
+    fn escape(text: &str) -> String {
+        let mut escaped = String::with_capacity(text.len());
+        for c in text.chars() {
+            if matches!(c, '\\' | '*' | '_' | '`' | '[' | ']' | '<') {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+        }
+        escaped
+    }
+
+    // 相邻且格式相同的片段已合并；强调标记放在首尾空白以内
+    fn render_runs(runs: &[Run]) -> String {
+        let mut out = String::new();
+        for run in runs {
+            if run.raw {
+                out.push_str(&run.text);
+                continue;
+            }
+            let trimmed = run.text.trim();
+            if trimmed.is_empty() {
+                out.push_str(&run.text);
+                continue;
+            }
+            let start = run.text.len() - run.text.trim_start().len();
+            let end = run.text.trim_end().len();
+            let marker = match (run.bold, run.italic) {
+                (true, true) => "***",
+                (true, false) => "**",
+                (false, true) => "*",
+                (false, false) => "",
+            };
+            let mut inner = format!("{0}{1}{0}", marker, escape(trimmed));
+            if let Some(link) = &run.link {
+                inner = format!(
+                    "[{}]({})",
+                    inner,
+                    link.replace(' ', "%20")
+                        .replace('(', "%28")
+                        .replace(')', "%29")
+                );
+            }
+            out.push_str(&run.text[..start]);
+            out.push_str(&inner);
+            out.push_str(&run.text[end..]);
+        }
+        out.trim().to_string()
+    }
+
+    impl Converter<'_> {
+        fn push_run(&mut self, text: String, raw: bool) {
+            let Some(paragraph) = self.paragraph.as_mut() else {
+                return;
+            };
+            if let Some(last) = paragraph.runs.last_mut() {
+                if !raw
+                    && !last.raw
+                    && last.bold == self.bold
+                    && last.italic == self.italic
+                    && last.link == self.link
+                {
+                    last.text.push_str(&text);
+                    return;
+                }
+            }
+            paragraph.runs.push(Run {
+                text,
+                bold: self.bold,
+                italic: self.italic,
+                link: self.link.clone(),
+                raw,
+            });
+        }
+
+        // 把图片解压到附件目录，返回 Markdown 图片
+        fn image(&mut self, id: &str) -> Option<String> {
+            let rel = self.rels.get(id).filter(|r| !r.external)?;
+            let entry = match rel.target.strip_prefix('/') {
+                Some(absolute) => absolute.to_string(),
+                None => format!("word/{}", rel.target),
+            };
+            let name = Path::new(&entry).file_name()?.to_string_lossy().to_string();
+            let bytes = read_entry(self.archive, &entry)?;
+            let dir = self.dir.join(self.assets);
+            fs::create_dir_all(&dir).ok()?;
+            fs::write(dir.join(&name), bytes).ok()?;
+            Some(format!(
+                "![]({}/{})",
+                markdown::link_destination(self.assets),
+                markdown::link_destination(&name)
+            ))
+        }
+
+        fn start(&mut self, e: &BytesStart) {
+            match e.name().as_ref() {
+                b"w:p" => self.paragraph = Some(Paragraph::default()),
+                b"w:pStyle" => {
+                    let style = attr(e, b"w:val").unwrap_or_default().to_lowercase();
+                    let level = match style.as_str() {
+                        "title" => Some(1),
+                        s => s
+                            .strip_prefix("heading")
+                            .and_then(|n| n.trim().parse::<usize>().ok()),
+                    };
+                    if let (Some(paragraph), Some(level)) = (self.paragraph.as_mut(), level) {
+                        paragraph.heading = Some(level.clamp(1, 6));
+                    }
+                }
+                b"w:outlineLvl" => {
+                    let level = attr(e, b"w:val").and_then(|v| v.parse::<usize>().ok());
+                    if let (Some(paragraph), Some(level)) = (self.paragraph.as_mut(), level) {
+                        if level < 9 {
+                            paragraph.heading.get_or_insert((level + 1).min(6));
+                        }
+                    }
+                }
+                b"w:ilvl" => {
+                    if let Some(paragraph) = self.paragraph.as_mut() {
+                        let level = attr(e, b"w:val").unwrap_or_else(|| "0".into());
+                        let entry = paragraph.list.get_or_insert((String::new(), String::new()));
+                        entry.1 = level;
+                    }
+                }
+                b"w:numId" => {
+                    if let Some(paragraph) = self.paragraph.as_mut() {
+                        let id = attr(e, b"w:val").unwrap_or_default();
+                        let entry = paragraph.list.get_or_insert((String::new(), "0".into()));
+                        entry.0 = id;
+                    }
+                }
+                b"w:r" => {
+                    self.bold = false;
+                    self.italic = false;
+                }
+                b"w:b" => self.bold = toggle(e),
+                b"w:i" => self.italic = toggle(e),
+                b"w:t" => self.in_text = true,
+                b"w:tab" => self.push_run(" ".into(), false),
+                b"w:br" => self.push_run("<br>".into(), true),
+                b"w:hyperlink" => {
+                    self.link = attr(e, b"r:id")
+                        .and_then(|id| self.rels.get(&id))
+                        .filter(|r| r.external)
+                        .map(|r| r.target.clone());
+                }
+                b"a:blip" => {
+                    if let Some(image) = attr(e, b"r:embed").and_then(|id| self.image(&id)) {
+                        self.push_run(image, true);
+                    }
+                }
+                b"w:tbl" => {
+                    self.table_depth += 1;
+                    if self.table_depth == 1 {
+                        self.rows.clear();
+                    }
+                }
+                b"w:tr" if self.table_depth == 1 => self.rows.push(Vec::new()),
+                b"w:tc" if self.table_depth == 1 => {
+                    if let Some(row) = self.rows.last_mut() {
+                        row.push(String::new());
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        fn end(&mut self, name: &[u8]) {
+            match name {
+                b"w:t" => self.in_text = false,
+                b"w:hyperlink" => self.link = None,
+                b"w:p" => self.end_paragraph(),
+                b"w:tbl" => {
+                    self.table_depth = self.table_depth.saturating_sub(1);
+                    if self.table_depth == 0 {
+                        self.end_table();
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        fn end_paragraph(&mut self) {
+            let Some(paragraph) = self.paragraph.take() else {
+                return;
+            };
+            let text = render_runs(&paragraph.runs);
+            if text.is_empty() {
+                return;
+            }
+            if self.table_depth > 0 {
+                // 单元格中的多个段落用换行标签隔开
+                if let Some(cell) = self.rows.last_mut().and_then(|row| row.last_mut()) {
+                    if !cell.is_empty() {
+                        cell.push_str("<br>");
+                    }
+                    cell.push_str(&text.replace('|', "\\|"));
+                }
+                return;
+            }
+            let block = match (paragraph.heading, paragraph.list) {
+                (Some(level), _) => Block {
+                    text: format!("{} {}", "#".repeat(level), text),
+                    list: false,
+                },
+                (None, Some((num_id, level))) if num_id != "0" => {
+                    let depth = level.parse::<usize>().unwrap_or(0);
+                    let marker = if self.numbering.is_ordered(&num_id, &level) {
+                        "1."
+                    } else {
+                        "-"
+                    };
+                    Block {
+                        text: format!("{}{} {}", "   ".repeat(depth), marker, text),
+                        list: true,
+                    }
+                }
+                _ => Block { text, list: false },
+            };
+            self.blocks.push(block);
+        }
+
+        // 第一行作为表头，列数取最多的一行
+        fn end_table(&mut self) {
+            let rows = std::mem::take(&mut self.rows);
+            let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+            if columns == 0 {
+                return;
+            }
+            let line = |row: &[String]| {
+                let cells: Vec<&str> = (0..columns)
+                    .map(|i| row.get(i).map(String::as_str).unwrap_or(""))
+                    .collect();
+                format!("| {} |", cells.join(" | "))
+            };
+            let mut table = vec![line(&rows[0]), format!("|{}", " --- |".repeat(columns))];
+            table.extend(rows[1..].iter().map(|row| line(row)));
+            self.blocks.push(Block {
+                text: table.join("\n"),
+                list: false,
+            });
+        }
+    }
+
+    // dir 为笔记所在目录，assets 为图片目录相对于它的路径
+    pub fn convert(source: &Path, dir: &Path, assets: &str) -> Result<String, String> {
+        let file = File::open(source).map_err(|e| format!("读取文件失败: {}", e))?;
+        let mut archive =
+            ZipArchive::new(file).map_err(|e| format!("不是有效的 Word 文档: {}", e))?;
+        let document = read_entry(&mut archive, "word/document.xml")
+            .ok_or("不是有效的 Word 文档: 缺少 word/document.xml")?;
+        let rels = relationships(&mut archive);
+        let numbering = numbering(&mut archive);
+
+        let mut converter = Converter {
+            archive: &mut archive,
+            rels,
+            numbering,
+            dir,
+            assets,
+            blocks: Vec::new(),
+            paragraph: None,
+            bold: false,
+            italic: false,
+            link: None,
+            in_text: false,
+            table_depth: 0,
+            rows: Vec::new(),
+        };
+        walk(&document, |event| match event {
+            XmlEvent::Start(e) => converter.start(e),
+            XmlEvent::End(name) => converter.end(name),
+            XmlEvent::Text(text) if converter.in_text => converter.push_run(text.into(), false),
+            XmlEvent::Text(_) => {}
+        })?;
+
+        let mut markdown = String::new();
+        for (i, block) in converter.blocks.iter().enumerate() {
+            if i > 0 {
+                let tight = block.list && converter.blocks[i - 1].list;
+                markdown.push_str(if tight { "\n" } else { "\n\n" });
+            }
+            markdown.push_str(&block.text);
+        }
+        markdown.push('\n');
+        Ok(markdown)
+    }
+}
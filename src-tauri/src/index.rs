@@ -0,0 +1,15 @@
+// 工作区索引数据库：{workspace}/.mobi/index.db，各模块自行建表
+
+use std::path::Path;
+
+use rusqlite::Connection;
+
+use crate::workspace;
+
+pub fn open(root: &Path) -> Result<Connection, String> {
+    let path = workspace::data_dir(root)?.join("index.db");
+    let conn = Connection::open(path).map_err(|e| format!("打开索引数据库失败: {}", e))?;
+    conn.pragma_update(None, "journal_mode", "WAL")
+        .map_err(|e| e.to_string())?;
+    Ok(conn)
+}
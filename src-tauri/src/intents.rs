@@ -0,0 +1,284 @@
+// 外部打开与分享：macOS 的"打开方式"、iOS 的"拷贝到墨笔"、mobi://share 链接以及 Android 的 ACTION_VIEW / ACTION_SEND 传入的内容，统一经 opened_file / open-file 交给前端
+// 分享来的文字先保存为新笔记再打开；Android 由 gen/android 中的 MainActivity 经 JNI 转交；仓库中没有 iOS 分享扩展，其他来源的文字可以调用 receive_shared_content
+
+use std::fs;
+use std::path::{Path, PathBuf};
+#[cfg(target_os = "android")]
+use std::sync::{Mutex, OnceLock};
+
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+use tauri::Url;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_fs::FsExt;
+
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+use crate::actions;
+#[cfg(any(target_os = "macos", target_os = "ios", target_os = "android"))]
+use crate::error;
+use crate::error::{MobiError, MutexExt};
+use crate::{filecheck, workspace, AppState};
+
+#[cfg(any(target_os = "macos", target_os = "ios", target_os = "android"))]
+const NOTE_EXTENSIONS: &[&str] = &["md", "markdown", "txt"];
+
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+fn is_openable(path: &Path) -> bool {
+    path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .is_some_and(|e| NOTE_EXTENSIONS.contains(&e.as_str()))
+}
+
+// 分享的内容保存到 {文稿}/Mobi/Shared
+fn shared_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .document_dir()
+        .map_err(|e| format!("无法定位文稿目录: {}", e))?
+        .join("Mobi")
+        .join("Shared");
+    fs::create_dir_all(&dir).map_err(|e| format!("创建分享目录失败: {}", e))?;
+    Ok(dir)
+}
+
+// 记下待打开的文件，窗口已就绪时直接通知前端，否则由前端启动后通过 get_opened_file 取走
+// 二进制或过大的文件改为发送 open-file-warning，由用户选择打开方式
+fn open_path(app: &AppHandle, path: String) {
+    let state = app.state::<AppState>();
+    // 前端用 fs 插件读取，能力文件不再授予全盘权限
+    let _ = app.fs_scope().allow_file(&path);
+    *state.opened_file.locked() = Some(path.clone());
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = match filecheck::inspect(app, Path::new(&path)) {
+            Ok(Some(warning)) => window.emit("open-file-warning", warning),
+            _ => window.emit("open-file", path),
+        };
+    }
+}
+
+// iOS 把"拷贝到"的文件放在只读的 Documents/Inbox 中，复制出来才能编辑保存
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+fn writable_copy(app: &AppHandle, path: PathBuf) -> Result<PathBuf, String> {
+    if !path.components().any(|c| c.as_os_str() == "Inbox") || !cfg!(target_os = "ios") {
+        return Ok(path);
+    }
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_string())
+        .unwrap_or_else(|| "md".into());
+    let target = workspace::unique_path(&shared_dir(app)?, &stem, &ext);
+    fs::copy(&path, &target).map_err(|e| format!("复制文件失败: {}", e))?;
+    let _ = fs::remove_file(&path);
+    Ok(target)
+}
+
+// 没有标题时取正文第一行
+fn note_title(title: Option<&str>, text: &str) -> String {
+    let title = title
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(String::from)
+        .or_else(|| {
+            text.lines()
+                .map(|line| line.trim().trim_start_matches('#').trim())
+                .find(|line| !line.is_empty())
+                .map(|line| line.chars().take(40).collect())
+        });
+    title.unwrap_or_else(|| format!("分享 {}", chrono::Local::now().format("%Y-%m-%d %H%M")))
+}
+
+fn create_shared_note(
+    app: &AppHandle,
+    text: &str,
+    title: Option<&str>,
+    url: Option<&str>,
+) -> Result<PathBuf, String> {
+    let title = note_title(title, text);
+    let mut content = format!("# {}\n\n{}\n", title, text.trim());
+    if let Some(url) = url.map(str::trim).filter(|u| !u.is_empty()) {
+        if !text.contains(url) {
+            content.push_str(&format!("\n<{}>\n", url));
+        }
+    }
+    let name = workspace::sanitize_file_name(&title);
+    let path = workspace::unique_path(&shared_dir(app)?, &name, "md");
+    fs::write(&path, content).map_err(|e| format!("保存分享内容失败: {}", e))?;
+    Ok(path)
+}
+
+// RunEvent::Opened 传入的地址：file:// 为要打开的文件，mobi://share?text=&title=&url= 为分享扩展转来的内容，mobi://action/{id}?参数=值 执行允许外部触发的动作
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+pub fn handle_urls(app: &AppHandle, urls: Vec<Url>) {
+    for url in urls {
+        let result = match url.scheme() {
+            "file" => match url.to_file_path() {
+                Ok(path) if is_openable(&path) => writable_copy(app, path).map(Some),
+                _ => Ok(None),
+            },
+            "mobi" if url.host_str() == Some("action") => {
+                let id = url.path().trim_matches('/').to_string();
+                let args = url
+                    .query_pairs()
+                    .map(|(k, v)| (k.to_string(), serde_json::Value::String(v.to_string())))
+                    .collect();
+                actions::run_external(app, &id, args);
+                Ok(None)
+            }
+            "mobi" if url.host_str() == Some("share") => {
+                let param = |key: &str| {
+                    url.query_pairs()
+                        .find(|(k, _)| k == key)
+                        .map(|(_, v)| v.to_string())
+                };
+                let text = param("text").unwrap_or_default();
+                let link = param("url");
+                if text.trim().is_empty() && link.is_none() {
+                    continue;
+                }
+                create_shared_note(app, &text, param("title").as_deref(), link.as_deref()).map(Some)
+            }
+            _ => Ok(None),
+        };
+        match result {
+            Ok(Some(path)) => open_path(app, path.to_string_lossy().to_string()),
+            Ok(None) => {}
+            Err(error) => error::report(app, "处理外部打开失败", error),
+        }
+    }
+}
+
+// Android 上用其他应用打开的文件以 content:// 传入，没有可写的路径，由 MainActivity 读出内容后存一份到分享目录
+#[cfg(target_os = "android")]
+fn save_received_file(app: &AppHandle, name: &str, content: &str) -> Result<PathBuf, String> {
+    let name = Path::new(name);
+    let stem = name
+        .file_stem()
+        .map(|s| workspace::sanitize_file_name(&s.to_string_lossy()))
+        .unwrap_or_default();
+    // 提供方给的名称可能没有扩展名，按 Markdown 保存
+    let ext = name
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .filter(|e| NOTE_EXTENSIONS.contains(&e.as_str()))
+        .unwrap_or_else(|| "md".into());
+    let path = workspace::unique_path(&shared_dir(app)?, &stem, &ext);
+    fs::write(&path, content).map_err(|e| format!("保存文件失败: {}", e))?;
+    Ok(path)
+}
+
+#[cfg(target_os = "android")]
+enum Received {
+    Text {
+        text: String,
+        title: Option<String>,
+        url: Option<String>,
+    },
+    File {
+        name: String,
+        content: String,
+    },
+}
+
+// 应用启动完成前（冷启动时 onCreate 里的 intent）收到的内容先排队，setup 中由 start 取出
+#[cfg(target_os = "android")]
+static APP: OnceLock<AppHandle> = OnceLock::new();
+#[cfg(target_os = "android")]
+static PENDING: Mutex<Vec<Received>> = Mutex::new(Vec::new());
+
+#[cfg(target_os = "android")]
+fn deliver(app: &AppHandle, received: Received) {
+    let result = match received {
+        Received::Text { text, title, url } => {
+            create_shared_note(app, &text, title.as_deref(), url.as_deref())
+        }
+        Received::File { name, content } => save_received_file(app, &name, &content),
+    };
+    match result {
+        Ok(path) => open_path(app, path.to_string_lossy().to_string()),
+        Err(error) => error::report(app, "处理外部打开失败", error),
+    }
+}
+
+#[cfg(target_os = "android")]
+fn receive(received: Received) {
+    let mut pending = PENDING.locked();
+    match APP.get() {
+        Some(app) => {
+            drop(pending);
+            deliver(app, received);
+        }
+        None => pending.push(received),
+    }
+}
+
+#[cfg(target_os = "android")]
+pub fn start(app: &AppHandle) {
+    let queued = {
+        let mut pending = PENDING.locked();
+        let _ = APP.set(app.clone());
+        std::mem::take(&mut *pending)
+    };
+    for received in queued {
+        deliver(app, received);
+    }
+}
+
+#[cfg(target_os = "android")]
+fn java_string(env: &mut jni::JNIEnv, value: &jni::objects::JString) -> Option<String> {
+    if value.is_null() {
+        return None;
+    }
+    env.get_string(value).ok().map(String::from)
+}
+
+// MainActivity.receiveShared(text, title, url)：ACTION_SEND 分享的文字
+#[cfg(target_os = "android")]
+#[no_mangle]
+pub extern "system" fn Java_com_mobi_editor_MainActivity_receiveShared(
+    mut env: jni::JNIEnv,
+    _activity: jni::objects::JObject,
+    text: jni::objects::JString,
+    title: jni::objects::JString,
+    url: jni::objects::JString,
+) {
+    let text = java_string(&mut env, &text).unwrap_or_default();
+    let url = java_string(&mut env, &url);
+    if text.trim().is_empty() && url.is_none() {
+        return;
+    }
+    let title = java_string(&mut env, &title);
+    receive(Received::Text { text, title, url });
+}
+
+// MainActivity.receiveFile(name, content)：ACTION_VIEW 打开或 ACTION_SEND 分享的文本文件
+#[cfg(target_os = "android")]
+#[no_mangle]
+pub extern "system" fn Java_com_mobi_editor_MainActivity_receiveFile(
+    mut env: jni::JNIEnv,
+    _activity: jni::objects::JObject,
+    name: jni::objects::JString,
+    content: jni::objects::JString,
+) {
+    let Some(content) = java_string(&mut env, &content) else {
+        return;
+    };
+    let name = java_string(&mut env, &name).unwrap_or_default();
+    receive(Received::File { name, content });
+}
+
+// 文字保存为新笔记并在编辑器中打开，返回笔记路径
+#[tauri::command]
+pub fn receive_shared_content(
+    app: AppHandle,
+    text: String,
+    title: Option<String>,
+    url: Option<String>,
+) -> Result<String, MobiError> {
+    let path = create_shared_note(&app, &text, title.as_deref(), url.as_deref())?;
+    let path = path.to_string_lossy().to_string();
+    open_path(&app, path.clone());
+    Ok(path)
+}
@@ -0,0 +1,351 @@
+// 看板：按 Obsidian Kanban 插件的格式把笔记解析为看板——二级标题为列，其下的列表项为卡片，缩进的后续行属于同一张卡片
+// 移动卡片时直接改写 Markdown 原文，列中的 **Complete** 标记表示完成列，移入时勾选复选框，移出时取消
+
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::MobiError;
+use crate::{markdown, workspace};
+
+const COMPLETE_MARKER: &str = "**Complete**";
+// 看板设置块 %% kanban:settings ... %% 之后不再属于任何列
+const SETTINGS_MARKER: &str = "%% kanban:settings";
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KanbanCard {
+    // 卡片内容（去掉列表标记和复选框），多行时以换行连接
+    text: String,
+    // 没有复选框的卡片为 None
+    done: Option<bool>,
+    // 首行行号，从 0 开始
+    line: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KanbanColumn {
+    title: String,
+    complete: bool,
+    cards: Vec<KanbanCard>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Board {
+    columns: Vec<KanbanColumn>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CardPosition {
+    column: usize,
+    card: usize,
+}
+
+// 卡片占据的行 [start, end)
+struct CardSpan {
+    start: usize,
+    end: usize,
+}
+
+struct ColumnSpan {
+    title: String,
+    heading: usize,
+    // 列中最后一个非空行之后
+    content_end: usize,
+    complete: Option<usize>,
+    cards: Vec<CardSpan>,
+}
+
+// "- [ ] 文字" / "- 文字"：返回 (标记后的文字, 复选框状态)
+fn list_item(line: &str) -> Option<(&str, Option<bool>)> {
+    let rest = ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|marker| line.strip_prefix(marker))?;
+    let checkbox = |prefix: &str| rest.strip_prefix(prefix);
+    if let Some(text) = checkbox("[ ] ").or_else(|| (rest == "[ ]").then_some("")) {
+        return Some((text, Some(false)));
+    }
+    if let Some(text) = checkbox("[x] ")
+        .or_else(|| checkbox("[X] "))
+        .or_else(|| (rest == "[x]" || rest == "[X]").then_some(""))
+    {
+        return Some((text, Some(true)));
+    }
+    Some((rest, None))
+}
+
+fn is_column_heading(line: &str) -> bool {
+    line.starts_with("## ") || line == "##"
+}
+
+fn parse(content: &str) -> Vec<ColumnSpan> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut in_code = vec![false; lines.len()];
+    for block in markdown::fenced_blocks(content) {
+        for flag in in_code
+            .iter_mut()
+            .take(block.end_line + 1)
+            .skip(block.start_line)
+        {
+            *flag = true;
+        }
+    }
+    // front matter 不属于任何列
+    let start = match markdown::front_matter(content) {
+        Some(front) => front.lines().count() + 2,
+        None => 0,
+    };
+
+    let mut columns: Vec<ColumnSpan> = Vec::new();
+    let mut i = start;
+    while i < lines.len() {
+        let line = lines[i];
+        if !in_code[i] && line.starts_with(SETTINGS_MARKER) {
+            break;
+        }
+        if !in_code[i] && is_column_heading(line) {
+            columns.push(ColumnSpan {
+                title: line.trim_start_matches('#').trim().to_string(),
+                heading: i,
+                content_end: i + 1,
+                complete: None,
+                cards: Vec::new(),
+            });
+            i += 1;
+            continue;
+        }
+        let Some(column) = columns.last_mut() else {
+            i += 1;
+            continue;
+        };
+        if !line.trim().is_empty() {
+            column.content_end = i + 1;
+        }
+        if in_code[i] {
+            i += 1;
+            continue;
+        }
+        if line.trim() == COMPLETE_MARKER {
+            column.complete = Some(i);
+        } else if list_item(line).is_some() {
+            // 缩进的后续行属于同一张卡片
+            let mut end = i + 1;
+            while end < lines.len()
+                && !lines[end].trim().is_empty()
+                && lines[end].starts_with([' ', '\t'])
+            {
+                end += 1;
+            }
+            column.cards.push(CardSpan { start: i, end });
+            column.content_end = end;
+            i = end;
+            continue;
+        }
+        i += 1;
+    }
+    columns
+}
+
+fn board(content: &str) -> Board {
+    let lines: Vec<&str> = content.lines().collect();
+    let columns = parse(content)
+        .into_iter()
+        .map(|column| KanbanColumn {
+            title: column.title,
+            complete: column.complete.is_some(),
+            cards: column
+                .cards
+                .iter()
+                .map(|card| {
+                    let (first, done) = list_item(lines[card.start]).unwrap_or(("", None));
+                    let text = std::iter::once(first)
+                        .chain(lines[card.start + 1..card.end].iter().map(|l| l.trim()))
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    KanbanCard {
+                        text,
+                        done,
+                        line: card.start,
+                    }
+                })
+                .collect(),
+        })
+        .collect();
+    Board { columns }
+}
+
+// 设置卡片首行的复选框状态，没有复选框的卡片不变
+fn set_done(line: &str, done: bool) -> String {
+    let Some((text, Some(_))) = list_item(line) else {
+        return line.to_string();
+    };
+    let marker = &line[..2];
+    let checkbox = if done { "[x]" } else { "[ ]" };
+    if text.is_empty() {
+        format!("{}{}", marker, checkbox)
+    } else {
+        format!("{}{} {}", marker, checkbox, text)
+    }
+}
+
+fn move_in(content: &str, from: &CardPosition, to: usize, index: usize) -> Result<String, String> {
+    let columns = parse(content);
+    let source = columns.get(from.column).ok_or("列不存在")?;
+    let card = source.cards.get(from.card).ok_or("卡片不存在")?;
+    let target = columns.get(to).ok_or("目标列不存在")?;
+
+    // 目标列中除被移动卡片外的其他卡片，index 按它们计算
+    let others: Vec<&CardSpan> = target
+        .cards
+        .iter()
+        .filter(|c| c.start != card.start)
+        .collect();
+    let insert_at = match others.get(index) {
+        Some(next) => next.start,
+        None => match others.last() {
+            Some(last) => last.end,
+            None => target
+                .complete
+                .map(|line| line + 1)
+                .unwrap_or(target.content_end.max(target.heading + 1)),
+        },
+    };
+
+    let mut lines: Vec<String> = content.lines().map(String::from).collect();
+    let mut moved: Vec<String> = lines.drain(card.start..card.end).collect();
+    if source.complete.is_some() != target.complete.is_some() {
+        moved[0] = set_done(&moved[0], target.complete.is_some());
+    }
+    let mut insert_at = if insert_at > card.start {
+        insert_at - (card.end - card.start)
+    } else {
+        insert_at
+    };
+    // 空列的卡片与标题之间空一行，已有空行时放在空行之后
+    let after_heading = others.is_empty()
+        && target.complete.is_none()
+        && insert_at > 0
+        && lines
+            .get(insert_at - 1)
+            .is_some_and(|l| is_column_heading(l));
+    if after_heading {
+        if lines.get(insert_at).is_some_and(|l| l.trim().is_empty()) {
+            insert_at += 1;
+        } else {
+            moved.insert(0, String::new());
+        }
+    }
+    lines.splice(insert_at..insert_at, moved);
+
+    let mut text = lines.join("\n");
+    if content.ends_with('\n') {
+        text.push('\n');
+    }
+    Ok(text)
+}
+
+#[tauri::command]
+pub fn get_board(path: String) -> Result<Board, MobiError> {
+    let content = fs::read_to_string(&path).map_err(|e| format!("读取文件失败: {}", e))?;
+    Ok(board(&content))
+}
+
+// 把 from 处的卡片移到第 to 列的第 index 个位置（按移动后的顺序），返回新的看板
+#[tauri::command]
+pub fn move_card(
+    path: String,
+    from: CardPosition,
+    to: usize,
+    index: usize,
+) -> Result<Board, MobiError> {
+    let content = fs::read_to_string(&path).map_err(|e| format!("读取文件失败: {}", e))?;
+    let text = move_in(&content, &from, to, index)?;
+    fs::write(workspace::resolve_link(Path::new(&path)), &text)
+        .map_err(|e| format!("保存文件失败: {}", e))?;
+    Ok(board(&text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOARD: &str = "---\nkanban-plugin: basic\n---\n\n## 待办\n\n- [ ] 写提纲\n  补充参考文献\n- [ ] 校对\n\n## 进行中\n\n## 完成\n\n**Complete**\n- [x] 立项\n\n%% kanban:settings\n```\n{}\n```\n%%\n";
+
+    fn at(column: usize, card: usize) -> CardPosition {
+        CardPosition { column, card }
+    }
+
+    #[test]
+    fn parses_columns_and_cards() {
+        let board = board(BOARD);
+        let titles: Vec<&str> = board.columns.iter().map(|c| c.title.as_str()).collect();
+        assert_eq!(titles, ["待办", "进行中", "完成"]);
+        assert_eq!(board.columns[0].cards[0].text, "写提纲\n补充参考文献");
+        assert_eq!(board.columns[0].cards[0].done, Some(false));
+        assert_eq!(board.columns[0].cards[1].line, 8);
+        assert!(board.columns[1].cards.is_empty());
+        assert!(board.columns[2].complete);
+        assert_eq!(board.columns[2].cards.len(), 1);
+    }
+
+    // 多行卡片整体移动，移入完成列时勾选，放在 **Complete** 标记之后
+    #[test]
+    fn moves_card_into_complete_column() {
+        assert_eq!(
+            move_in(BOARD, &at(0, 0), 2, 0).unwrap(),
+            BOARD.replace("- [ ] 写提纲\n  补充参考文献\n", "").replace(
+                "**Complete**\n",
+                "**Complete**\n- [x] 写提纲\n  补充参考文献\n"
+            )
+        );
+    }
+
+    #[test]
+    fn moves_card_out_of_complete_column() {
+        assert_eq!(
+            move_in(BOARD, &at(2, 0), 0, 1).unwrap(),
+            BOARD
+                .replace("- [x] 立项\n", "")
+                .replace("- [ ] 校对\n", "- [ ] 立项\n- [ ] 校对\n")
+        );
+    }
+
+    #[test]
+    fn moves_card_into_empty_column() {
+        assert_eq!(
+            move_in(BOARD, &at(0, 1), 1, 0).unwrap(),
+            BOARD
+                .replace("- [ ] 校对\n", "")
+                .replace("## 进行中\n\n", "## 进行中\n\n- [ ] 校对\n")
+        );
+        let content = "## A\n- x\n## B\n";
+        assert_eq!(
+            move_in(content, &at(0, 0), 1, 0).unwrap(),
+            "## A\n## B\n\n- x\n"
+        );
+    }
+
+    // index 按移动后的顺序计算
+    #[test]
+    fn reorders_within_column() {
+        let content = "## A\n- 1\n- 2\n- 3\n";
+        assert_eq!(
+            move_in(content, &at(0, 0), 0, 2).unwrap(),
+            "## A\n- 2\n- 3\n- 1\n"
+        );
+        assert_eq!(
+            move_in(content, &at(0, 2), 0, 0).unwrap(),
+            "## A\n- 3\n- 1\n- 2\n"
+        );
+    }
+
+    #[test]
+    fn rejects_missing_positions() {
+        assert!(move_in(BOARD, &at(1, 0), 0, 0).is_err());
+        assert!(move_in(BOARD, &at(0, 0), 5, 0).is_err());
+    }
+}
@@ -1,8 +1,73 @@
 // 墨笔 - Markdown Editor
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 
+mod actions;
+mod ai;
+mod aliases;
+mod archive;
+mod asset;
+mod attachments;
+mod bookmarks;
+mod buffers;
+mod calendar;
+mod citations;
+mod cli;
+mod clipboard;
+mod collab;
+mod conflicts;
+mod confluence;
+mod daily;
+mod document;
+mod drop;
+mod duplicates;
+mod email;
+mod encryption;
+mod error;
+mod export;
+mod filecheck;
+mod files;
+mod footnotes;
+mod inbox;
+mod index;
+mod intents;
+mod kanban;
+mod locks;
+mod markdown;
+mod network;
+mod note_archive;
+mod onsave;
+mod preview;
+mod print;
+mod recording;
+mod reminders;
+mod repair;
+mod review;
+mod runner;
+mod search;
+mod secrets;
+mod semantic;
+mod settings;
+mod share;
+mod shred;
+mod snippets;
+mod speech;
+mod stats;
+mod tables;
+mod tasks;
+mod templates;
+mod trash;
+mod undo;
+mod unfurl;
+mod vault;
+mod watcher;
+mod workspace;
+mod workspaces;
+mod zotero;
+
 use std::sync::{Arc, Mutex};
-use tauri::{Emitter, Manager, RunEvent};
+use tauri::{Manager, RunEvent, WindowEvent};
+
+use crate::error::MutexExt;
 
 // 用于存储启动时打开的文件路径
 struct AppState {
@@ -12,57 +77,289 @@ struct AppState {
 // 前端准备好后调用此命令获取打开的文件
 #[tauri::command]
 fn get_opened_file(state: tauri::State<AppState>) -> Option<String> {
-    let mut file = state.opened_file.lock().unwrap();
+    let mut file = state.opened_file.locked();
     file.take() // 获取并清空
 }
 
+// 界面和命令行共用，避免前端资源被嵌入两次
+fn context() -> tauri::Context {
+    tauri::generate_context!()
+}
+
+// 命令行子命令（convert / export-site / lint / search），不是子命令时返回 None
+pub fn run_cli() -> Option<i32> {
+    cli::run(std::env::args().skip(1).collect(), context)
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     let app_state = AppState {
         opened_file: Arc::new(Mutex::new(None)),
     };
-    let opened_file = app_state.opened_file.clone();
 
-    tauri::Builder::default()
+    let built = tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
         .manage(app_state)
-        .invoke_handler(tauri::generate_handler![get_opened_file])
-        .build(tauri::generate_context!())
-        .expect("error while building tauri application")
-        .run(move |app_handle, event| {
-            match event {
-                RunEvent::Opened { urls } => {
-                    // macOS "打开方式" 触发此事件
-                    for url in urls {
-                        let url_str = url.to_string();
-                        // 处理 file:// URL
-                        if url_str.starts_with("file://") {
-                            // 解码 URL 并移除 file:// 前缀
-                            let file_path = url_str[7..].to_string();
-                            // URL 解码
-                            let file_path = urlencoding::decode(&file_path)
-                                .map(|s| s.to_string())
-                                .unwrap_or(file_path);
-
-                            if file_path.ends_with(".md")
-                                || file_path.ends_with(".markdown")
-                                || file_path.ends_with(".txt")
-                            {
-                                // 存储到状态中
-                                let mut state = opened_file.lock().unwrap();
-                                *state = Some(file_path.clone());
-
-                                // 如果窗口已经准备好，直接发送事件
-                                if let Some(window) = app_handle.get_webview_window("main") {
-                                    let _ = window.emit("open-file", file_path);
-                                }
-                            }
-                        }
-                    }
-                }
-                _ => {}
+        .manage(speech::SpeechState::default())
+        .manage(recording::RecordingState::default())
+        .manage(ai::AiState::default())
+        .manage(runner::RunnerState::default())
+        .manage(reminders::ReminderState::default())
+        .manage(encryption::EncryptionState::default())
+        .manage(locks::LockState::default())
+        .manage(watcher::WatcherState::default())
+        .manage(preview::PreviewState::default())
+        .manage(share::ShareState::default())
+        .manage(collab::CollabState::default())
+        .manage(buffers::BufferState::default())
+        .manage(inbox::InboxState::default())
+        .manage(clipboard::ClipboardState::default())
+        .manage(asset::AssetState::default())
+        .manage(actions::ActionState::default())
+        .manage(vault::VaultState::default())
+        .register_asynchronous_uri_scheme_protocol(asset::SCHEME, |ctx, request, responder| {
+            let app = ctx.app_handle().clone();
+            let label = ctx.webview_label().to_string();
+            tauri::async_runtime::spawn_blocking(move || {
+                responder.respond(asset::respond(&app, &label, &request));
+            });
+        })
+        .setup(|app| {
+            reminders::start(app.handle().clone());
+            encryption::start_auto_lock(app.handle().clone());
+            locks::start_heartbeat(app.handle().clone());
+            clipboard::start(app.handle().clone());
+            undo::start(app.handle().clone());
+            #[cfg(target_os = "android")]
+            intents::start(app.handle());
+            Ok(())
+        })
+        .on_window_event(|window, event| {
+            if let WindowEvent::Destroyed = event {
+                buffers::forget_window(window.app_handle(), window.label());
+                actions::forget_window(window.app_handle(), window.label());
+                watcher::forget_window(window.app_handle(), window.label());
+                inbox::forget_window(window.app_handle(), window.label());
+                asset::forget_window(window.app_handle(), window.label());
+                preview::forget_window(window.app_handle(), window.label());
             }
-        });
+        })
+        .invoke_handler(tauri::generate_handler![
+            get_opened_file,
+            secrets::set_secret,
+            secrets::has_secret,
+            settings::get_setting,
+            settings::set_setting,
+            speech::list_voices,
+            speech::speak,
+            speech::pause_speech,
+            speech::resume_speech,
+            speech::stop_speech,
+            recording::start_recording,
+            recording::stop_recording,
+            recording::transcribe_audio,
+            ai::set_ai_api_key,
+            ai::has_ai_api_key,
+            ai::list_ai_templates,
+            ai::ai_chat,
+            ai::ai_run_template,
+            ai::ai_cancel,
+            ai::ollama::ollama_list_models,
+            ai::ollama::ollama_pull_model,
+            ai::ollama::ollama_delete_model,
+            semantic::build_semantic_index,
+            semantic::semantic_search,
+            semantic::related_notes,
+            runner::run_code_block,
+            runner::cancel_code_run,
+            runner::insert_code_output,
+            citations::search_citations,
+            citations::list_citation_styles,
+            zotero::zotero_search,
+            zotero::zotero_sync,
+            footnotes::get_footnotes,
+            footnotes::renumber_footnotes,
+            templates::list_templates,
+            templates::create_from_template,
+            snippets::list_snippets,
+            snippets::expand_snippet,
+            snippets::save_snippet,
+            snippets::delete_snippet,
+            daily::open_daily_note,
+            daily::get_daily_notes,
+            calendar::get_notes_calendar,
+            tasks::get_tasks,
+            tasks::toggle_task,
+            reminders::watch_reminders,
+            reminders::get_upcoming_reminders,
+            reminders::snooze_reminder,
+            document::check_writable,
+            document::save_document,
+            document::save_document_elevated,
+            buffers::open_buffer,
+            buffers::update_buffer,
+            buffers::close_buffer,
+            buffers::append_to_buffer,
+            locks::acquire_lock,
+            locks::release_lock,
+            locks::get_lock_status,
+            watcher::watch_workspace,
+            inbox::watch_inbox,
+            clipboard::record_clipboard_copy,
+            clipboard::get_clipboard_history,
+            clipboard::paste_history_item,
+            clipboard::clear_clipboard_history,
+            undo::load_undo_history,
+            undo::append_undo_ops,
+            archive::export_workspace_archive,
+            archive::import_workspace_archive,
+            drop::handle_dropped_paths,
+            intents::receive_shared_content,
+            network::test_proxy,
+            unfurl::fetch_link_metadata,
+            duplicates::find_similar_notes,
+            duplicates::merge_notes,
+            kanban::get_board,
+            kanban::move_card,
+            tables::import_csv,
+            tables::export_table_csv,
+            asset::set_asset_scope,
+            confluence::publish_to_confluence,
+            email::email_note,
+            bookmarks::add_bookmark,
+            bookmarks::list_bookmarks,
+            bookmarks::goto_bookmark,
+            bookmarks::remove_bookmark,
+            note_archive::archive_note,
+            note_archive::unarchive_note,
+            aliases::get_aliases,
+            aliases::resolve_wiki_link,
+            aliases::find_notes_by_name,
+            aliases::get_backlinks,
+            stats::get_workspace_stats,
+            review::get_due_reviews,
+            review::grade_review,
+            onsave::run_save_pipeline,
+            filecheck::check_open_file,
+            filecheck::read_file_chunk,
+            files::read_text_file,
+            files::write_text_file,
+            files::write_binary_file,
+            files::path_exists,
+            files::create_dir,
+            files::remove_path,
+            files::rename_path,
+            trash::trash_item,
+            trash::list_trashed,
+            trash::restore_trashed,
+            trash::purge_trashed,
+            attachments::attach_files,
+            attachments::list_attachments,
+            attachments::open_attachment,
+            tables::paste_as_table,
+            shred::shred_note,
+            workspaces::list_workspaces,
+            workspaces::add_workspace,
+            workspaces::update_workspace,
+            workspaces::remove_workspace,
+            workspaces::mark_workspace_opened,
+            workspaces::open_workspace_window,
+            workspaces::get_window_workspace,
+            repair::repair_broken_links,
+            repair::apply_link_fix,
+            actions::list_actions,
+            actions::register_action,
+            actions::unregister_action,
+            actions::run_action,
+            vault::create_vault,
+            vault::is_vault,
+            vault::unlock_vault,
+            vault::lock_vault,
+            vault::list_vault,
+            vault::read_vault_note,
+            vault::write_vault_note,
+            vault::rename_vault_note,
+            vault::delete_vault_note,
+            vault::search_vault,
+            search::search_notes,
+            search::list_tags,
+            search::notes_with_tag,
+            search::refresh_search_index,
+            workspace::get_workspace_settings,
+            workspace::set_workspace_settings,
+            workspace::list_workspace_dir,
+            conflicts::list_sync_conflicts,
+            conflicts::merge_sync_conflict,
+            conflicts::resolve_sync_conflict,
+            encryption::is_encrypted,
+            encryption::encrypt_note,
+            encryption::encrypt_folder,
+            encryption::unlock_note,
+            encryption::read_encrypted_note,
+            encryption::save_encrypted_note,
+            encryption::lock_note,
+            encryption::decrypt_note,
+            preview::start_preview_server,
+            preview::stop_preview_server,
+            preview::set_preview_document,
+            share::share_note_on_lan,
+            share::stop_lan_share,
+            share::list_lan_shares,
+            collab::host_collab_session,
+            collab::join_collab_session,
+            collab::collab_apply_edit,
+            collab::collab_set_presence,
+            collab::collab_get_content,
+            collab::list_collab_sessions,
+            collab::leave_collab_session,
+            collab::collab_session_for,
+            print::print_document,
+            export::render_html,
+            export::export_html,
+            export::presets::list_export_presets,
+            export::presets::save_export_preset,
+            export::presets::delete_export_preset,
+            export::presets::run_export_preset,
+            export::crossref::check_crossrefs,
+            export::diagram::render_diagram,
+            export::highlight::highlight_code,
+            export::math::render_math,
+            export::anki::export_anki,
+            export::mindmap::export_mindmap,
+            export::bundle::export_share_bundle,
+            export::themes::list_export_themes,
+            export::themes::save_export_theme,
+            export::themes::delete_export_theme,
+            export::themes::assign_export_theme,
+            export::themes::preview_export_theme,
+            export::themes::copy_wechat_html,
+            export::filters::list_export_filters,
+            export::filters::save_export_filter,
+            export::filters::delete_export_filter,
+            export::filters::test_export_filter,
+            export::highlight::list_code_themes
+        ])
+        .build(context());
+    // 构建失败（如配置损坏、WebView 不可用）时给出原因后退出，不直接 panic
+    let app = match built {
+        Ok(app) => app,
+        Err(e) => {
+            eprintln!("启动失败: {}", e);
+            std::process::exit(1);
+        }
+    };
+    app.run(move |app_handle, event| {
+        if let RunEvent::Exit = event {
+            locks::release_all(app_handle);
+        }
+        // macOS "打开方式"、iOS 的"拷贝到"和分享扩展触发此事件
+        #[cfg(any(target_os = "macos", target_os = "ios"))]
+        if let RunEvent::Opened { urls } = event {
+            intents::handle_urls(app_handle, urls);
+        }
+    });
 }
@@ -0,0 +1,222 @@
+// 编辑锁：打开文件编辑时在旁边写一个 .{文件名}.mobi-lock 副本文件，随同步盘一起同步，其他机器上的 Mobi 据此提示文件正在别处编辑
+// 锁文件由后台线程定时刷新心跳，超过 STALE_SECS 没有刷新（如程序崩溃）视为失效，可以直接接管
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::error::{MobiError, MutexExt};
+use crate::workspace;
+
+const HEARTBEAT_SECS: u64 = 60;
+const STALE_SECS: i64 = 5 * 60;
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LockOwner {
+    host: String,
+    user: String,
+    // 每次启动随机生成，区分同一台机器上的多个实例
+    instance: String,
+    acquired_at: i64,
+    heartbeat: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockStatus {
+    // 当前实例持有锁
+    held: bool,
+    // 被其他实例锁定且锁仍有效
+    locked_by_other: bool,
+    owner: Option<LockOwner>,
+}
+
+// 发给前端的 lock-lost 事件：锁被其他实例强制接管
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LockLost {
+    path: String,
+    owner: LockOwner,
+}
+
+pub struct LockState {
+    instance: String,
+    held: Mutex<HashSet<PathBuf>>,
+}
+
+impl Default for LockState {
+    fn default() -> Self {
+        LockState {
+            instance: format!(
+                "{}-{}",
+                std::process::id(),
+                chrono::Local::now().timestamp_millis()
+            ),
+            held: Mutex::new(HashSet::new()),
+        }
+    }
+}
+
+// 经由不同链接打开同一文件时共用一把锁
+fn lock_path(path: &Path) -> PathBuf {
+    let path = workspace::resolve_link(path);
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{}.mobi-lock", name))
+}
+
+#[cfg(unix)]
+fn host_name() -> String {
+    let mut buf = [0u8; 256];
+    let ok = unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) } == 0;
+    let end = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    if ok && end > 0 {
+        String::from_utf8_lossy(&buf[..end]).to_string()
+    } else {
+        "unknown".into()
+    }
+}
+
+#[cfg(windows)]
+fn host_name() -> String {
+    std::env::var("COMPUTERNAME").unwrap_or_else(|_| "unknown".into())
+}
+
+fn user_name() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_default()
+}
+
+fn now() -> i64 {
+    chrono::Local::now().timestamp()
+}
+
+fn read_owner(path: &Path) -> Option<LockOwner> {
+    fs::read_to_string(lock_path(path))
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+}
+
+fn is_stale(owner: &LockOwner) -> bool {
+    now() - owner.heartbeat > STALE_SECS
+}
+
+fn write_owner(path: &Path, owner: &LockOwner) -> Result<(), MobiError> {
+    let text = serde_json::to_string_pretty(owner).map_err(|e| e.to_string())?;
+    fs::write(lock_path(path), text).map_err(|e| MobiError::io("写入锁文件失败", e))
+}
+
+fn status(state: &LockState, path: &Path) -> LockStatus {
+    let owner = read_owner(path);
+    let mine = owner.as_ref().is_some_and(|o| o.instance == state.instance);
+    LockStatus {
+        held: mine,
+        locked_by_other: owner.as_ref().is_some_and(|o| !mine && !is_stale(o)),
+        owner,
+    }
+}
+
+// 锁仍属于自己时才删除
+fn release(state: &LockState, path: &Path) {
+    if read_owner(path).is_some_and(|o| o.instance == state.instance) {
+        let _ = fs::remove_file(lock_path(path));
+    }
+}
+
+// 后台刷新心跳；发现锁被别的实例接管时通知前端并放弃
+pub fn start_heartbeat(app: AppHandle) {
+    thread::spawn(move || loop {
+        thread::sleep(Duration::from_secs(HEARTBEAT_SECS));
+        let state = app.state::<LockState>();
+        let mut held = state.held.locked();
+        held.retain(|path| match read_owner(path) {
+            Some(mut owner) if owner.instance == state.instance => {
+                owner.heartbeat = now();
+                let _ = write_owner(path, &owner);
+                true
+            }
+            Some(owner) => {
+                let _ = app.emit(
+                    "lock-lost",
+                    LockLost {
+                        path: path.to_string_lossy().to_string(),
+                        owner,
+                    },
+                );
+                false
+            }
+            // 锁文件被删掉（如同步冲突），重新写回
+            None => {
+                let owner = LockOwner {
+                    host: host_name(),
+                    user: user_name(),
+                    instance: state.instance.clone(),
+                    acquired_at: now(),
+                    heartbeat: now(),
+                };
+                write_owner(path, &owner).is_ok()
+            }
+        });
+    });
+}
+
+// 退出时释放本实例持有的所有锁
+pub fn release_all(app: &AppHandle) {
+    let state = app.state::<LockState>();
+    for path in state.held.locked().drain() {
+        release(&state, &path);
+    }
+}
+
+// 被其他实例有效锁定时不获取锁，返回对方信息；force 为 true 时强制接管
+#[tauri::command]
+pub fn acquire_lock(
+    state: State<LockState>,
+    path: String,
+    force: bool,
+) -> Result<LockStatus, MobiError> {
+    let path = PathBuf::from(path);
+    let current = status(&state, &path);
+    if current.locked_by_other && !force {
+        return Ok(current);
+    }
+    let acquired_at = current
+        .owner
+        .filter(|_| current.held)
+        .map(|o| o.acquired_at)
+        .unwrap_or_else(now);
+    write_owner(
+        &path,
+        &LockOwner {
+            host: host_name(),
+            user: user_name(),
+            instance: state.instance.clone(),
+            acquired_at,
+            heartbeat: now(),
+        },
+    )?;
+    state.held.locked().insert(path.clone());
+    Ok(status(&state, &path))
+}
+
+#[tauri::command]
+pub fn release_lock(state: State<LockState>, path: String) {
+    let path = PathBuf::from(path);
+    state.held.locked().remove(&path);
+    release(&state, &path);
+}
+
+#[tauri::command]
+pub fn get_lock_status(state: State<LockState>, path: String) -> LockStatus {
+    status(&state, Path::new(&path))
+}
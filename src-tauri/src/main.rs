@@ -2,5 +2,10 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    // 带子命令启动时按命令行工具运行，不打开窗口
+    // Windows 发布版没有控制台，命令行输出需要从终端以重定向方式查看
+    if let Some(code) = mobi_lib::run_cli() {
+        std::process::exit(code);
+    }
     mobi_lib::run()
 }
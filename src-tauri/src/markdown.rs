@@ -0,0 +1,255 @@
+// Markdown 文本处理工具
+
+use pulldown_cmark::{Event, LinkType, Options, Parser, Tag, TagEnd};
+
+// 默认启用的 Markdown 扩展语法
+pub fn parser_options() -> Options {
+    Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+}
+
+// 围栏代码块，行号从 0 开始，start/end 为围栏所在行
+pub struct FencedBlock {
+    pub lang: String,
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+// 按行扫描 ``` / ~~~ 围栏代码块，未闭合的代码块延伸到文末
+// 闭合围栏须用同一种字符且不短于开头的围栏，````` 包围的代码块中可以出现 ```
+pub fn fenced_blocks(markdown: &str) -> Vec<FencedBlock> {
+    let mut blocks = Vec::new();
+    let mut open: Option<(String, usize, char, usize)> = None;
+
+    let lines: Vec<&str> = markdown.lines().collect();
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim_start();
+        let Some(marker) = trimmed.chars().next().filter(|c| *c == '`' || *c == '~') else {
+            continue;
+        };
+        let run = trimmed.chars().take_while(|c| *c == marker).count();
+        match open {
+            None => {
+                if run >= 3 {
+                    let lang = trimmed[run..].split_whitespace().next().unwrap_or("");
+                    open = Some((lang.to_string(), i, marker, run));
+                }
+            }
+            Some((_, _, fence, length)) => {
+                if marker == fence && run >= length && trimmed[run..].trim().is_empty() {
+                    let (lang, start_line, _, _) = open.take().unwrap();
+                    blocks.push(FencedBlock {
+                        lang,
+                        start_line,
+                        end_line: i,
+                    });
+                }
+            }
+        }
+    }
+    if let Some((lang, start_line, _, _)) = open {
+        blocks.push(FencedBlock {
+            lang,
+            start_line,
+            end_line: lines.len().saturating_sub(1),
+        });
+    }
+
+    blocks
+}
+
+// 文首 --- 包围的 YAML front matter 正文（不含分隔线）
+pub fn front_matter(markdown: &str) -> Option<&str> {
+    let rest = markdown
+        .strip_prefix("---\n")
+        .or_else(|| markdown.strip_prefix("---\r\n"))?;
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" || line.trim_end() == "..." {
+            return Some(&rest[..offset]);
+        }
+        offset += line.len();
+    }
+    None
+}
+
+// 去掉 front matter（含分隔线）之后的正文
+pub fn strip_front_matter(markdown: &str) -> &str {
+    let Some(front) = front_matter(markdown) else {
+        return markdown;
+    };
+    // front 是 markdown 的切片，其后紧跟结束分隔线所在的行
+    let end = front.as_ptr() as usize - markdown.as_ptr() as usize + front.len();
+    let rest = &markdown[end..];
+    rest.split_once('\n').map_or("", |(_, body)| body)
+}
+
+// front matter 中的单行标量字段，如 date: 2025-01-15
+pub fn front_matter_field(markdown: &str, key: &str) -> Option<String> {
+    front_matter(markdown)?.lines().find_map(|line| {
+        let (k, v) = line.split_once(':')?;
+        if k.trim() != key {
+            return None;
+        }
+        let value = v.trim().trim_matches(['"', '\'']).trim();
+        (!value.is_empty()).then(|| value.to_string())
+    })
+}
+
+// front matter 中的列表字段：key: [a, b]、缩进的 - a 列表，或单个值
+pub fn front_matter_list(markdown: &str, key: &str) -> Vec<String> {
+    let Some(front) = front_matter(markdown) else {
+        return Vec::new();
+    };
+    let unquote = |v: &str| v.trim().trim_matches(['"', '\'']).trim().to_string();
+    let mut lines = front.lines();
+    while let Some(line) = lines.next() {
+        let Some((k, v)) = line.split_once(':') else {
+            continue;
+        };
+        if k.trim_end() != key || k.starts_with([' ', '\t']) {
+            continue;
+        }
+        let value = v.trim();
+        let items: Vec<String> = if let Some(inner) = value
+            .strip_prefix('[')
+            .and_then(|rest| rest.strip_suffix(']'))
+        {
+            inner.split(',').map(unquote).collect()
+        } else if value.is_empty() {
+            lines
+                .take_while(|l| l.trim().is_empty() || l.trim_start().starts_with('-'))
+                .filter_map(|l| l.trim_start().strip_prefix('-').map(unquote))
+                .collect()
+        } else {
+            vec![unquote(value)]
+        };
+        return items.into_iter().filter(|item| !item.is_empty()).collect();
+    }
+    Vec::new()
+}
+
+// 设置 front matter 中的单行字段：已有时替换该行，否则追加到末尾；没有 front matter 时新建
+pub fn set_front_matter_field(markdown: &str, key: &str, value: &str) -> String {
+    let field = format!("{}: {}", key, value);
+    let Some(front) = front_matter(markdown) else {
+        return format!("---\n{}\n---\n{}", field, markdown);
+    };
+    let start = front.as_ptr() as usize - markdown.as_ptr() as usize;
+    let mut replaced = false;
+    let mut lines: Vec<String> = front
+        .lines()
+        .map(|line| match line.split_once(':') {
+            // 只替换顶层字段，缩进的是嵌套字段
+            Some((k, _)) if !replaced && k == key => {
+                replaced = true;
+                field.clone()
+            }
+            _ => line.to_string(),
+        })
+        .collect();
+    if !replaced {
+        lines.push(field);
+    }
+    format!(
+        "{}{}\n{}",
+        &markdown[..start],
+        lines.join("\n"),
+        &markdown[start + front.len()..]
+    )
+}
+
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// 用作 Markdown 链接目标的相对路径，空格和括号需要编码
+pub fn link_destination(path: &str) -> String {
+    path.replace('%', "%25")
+        .replace(' ', "%20")
+        .replace('(', "%28")
+        .replace(')', "%29")
+}
+
+// 改写行内链接 [文字](目标) 和图片 ![说明](目标) 的目标：f 收到原文中的目标（未解码），返回 Some 时替换；代码块中的内容不受影响
+pub fn rewrite_link_destinations(
+    markdown: &str,
+    mut f: impl FnMut(&str) -> Option<String>,
+) -> String {
+    let mut replacements = Vec::new();
+    for (event, range) in Parser::new_ext(markdown, parser_options()).into_offset_iter() {
+        let (Event::Start(Tag::Link {
+            link_type: LinkType::Inline,
+            ..
+        })
+        | Event::Start(Tag::Image {
+            link_type: LinkType::Inline,
+            ..
+        })) = event
+        else {
+            continue;
+        };
+        let source = &markdown[range.clone()];
+        let Some(open) = source.rfind("](").map(|i| range.start + i + 2) else {
+            continue;
+        };
+        let rest = &markdown[open..range.end];
+        let trimmed = rest.trim_start();
+        let start = open + rest.len() - trimmed.len();
+        // <带空格的目标> 或到空白 / 右括号为止
+        let (start, len) = match trimmed.strip_prefix('<') {
+            Some(inner) => (start + 1, inner.find('>').unwrap_or(0)),
+            None => (
+                start,
+                trimmed
+                    .find(|c: char| c.is_whitespace())
+                    .unwrap_or(trimmed.len().saturating_sub(1)),
+            ),
+        };
+        let destination = &markdown[start..start + len];
+        if let Some(new) = f(destination) {
+            replacements.push((start, start + len, new));
+        }
+    }
+    let mut result = markdown.to_string();
+    for (start, end, new) in replacements.into_iter().rev() {
+        result.replace_range(start..end, &new);
+    }
+    result
+}
+
+// 提取纯文本（去掉标记符号和代码块），用于朗读等场景
+pub fn to_plain_text(markdown: &str) -> String {
+    let mut text = String::new();
+    let mut in_code_block = false;
+
+    for event in Parser::new_ext(markdown, parser_options()) {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+            Event::End(TagEnd::CodeBlock) => in_code_block = false,
+            Event::Text(t) | Event::Code(t) if !in_code_block => text.push_str(&t),
+            Event::SoftBreak | Event::End(TagEnd::TableCell) => text.push(' '),
+            Event::HardBreak
+            | Event::End(TagEnd::Paragraph)
+            | Event::End(TagEnd::Heading(_))
+            | Event::End(TagEnd::Item)
+            | Event::End(TagEnd::TableRow) => text.push('\n'),
+            _ => {}
+        }
+    }
+
+    text
+}
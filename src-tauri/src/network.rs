@@ -0,0 +1,133 @@
+// 网络代理：AI、语音转写、Kroki 图表渲染等联网功能统一通过 client() 创建 HTTP 客户端，共用设置中的代理
+// 代理密码保存在系统钥匙串（proxy / password）中；Ollama、Zotero 等本机服务始终直连
+
+use std::time::{Duration, Instant};
+
+use reqwest::{Client, ClientBuilder, NoProxy, Proxy, Url};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::error::MobiError;
+use crate::{secrets, settings};
+
+// 未指定地址时用来测试代理的页面
+const TEST_URL: &str = "https://www.gstatic.com/generate_204";
+const TEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Serialize, Deserialize, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ProxyMode {
+    // 跟随系统代理设置和 HTTP_PROXY 等环境变量
+    #[default]
+    System,
+    // 不使用代理
+    None,
+    Http,
+    Socks5,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ProxySettings {
+    mode: ProxyMode,
+    host: String,
+    port: u16,
+    // 为空时不认证
+    username: String,
+    // 不走代理的地址，逗号分隔，如 localhost,*.corp.example.com
+    no_proxy: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyTestResult {
+    status: u16,
+    elapsed_ms: u64,
+}
+
+fn password() -> Result<String, String> {
+    Ok(secrets::get_secret("proxy", "password")?.unwrap_or_default())
+}
+
+fn proxy(config: &ProxySettings) -> Result<Proxy, String> {
+    if config.host.trim().is_empty() || config.port == 0 {
+        return Err("请填写代理服务器地址和端口".into());
+    }
+    // socks5h 由代理解析域名，被污染的 DNS 也不影响访问
+    let scheme = match config.mode {
+        ProxyMode::Socks5 => "socks5h",
+        _ => "http",
+    };
+    let mut url = Url::parse(&format!(
+        "{}://{}:{}",
+        scheme,
+        config.host.trim(),
+        config.port
+    ))
+    .map_err(|e| format!("代理地址无效: {}", e))?;
+    let username = config.username.trim();
+    let mut proxy = if username.is_empty() {
+        Proxy::all(url.as_str())
+    } else if config.mode == ProxyMode::Socks5 {
+        // SOCKS5 的用户名密码只能放在地址里
+        let _ = url.set_username(username);
+        let _ = url.set_password(Some(&password()?));
+        Proxy::all(url.as_str())
+    } else {
+        let password = password()?;
+        Proxy::all(url.as_str()).map(|proxy| proxy.basic_auth(username, &password))
+    }
+    .map_err(|e| format!("代理地址无效: {}", e))?;
+    if !config.no_proxy.trim().is_empty() {
+        proxy = proxy.no_proxy(NoProxy::from_string(&config.no_proxy));
+    }
+    Ok(proxy)
+}
+
+fn apply(builder: ClientBuilder, config: &ProxySettings) -> Result<ClientBuilder, String> {
+    Ok(match config.mode {
+        ProxyMode::System => builder,
+        ProxyMode::None => builder.no_proxy(),
+        ProxyMode::Http | ProxyMode::Socks5 => builder.proxy(proxy(config)?),
+    })
+}
+
+// 按代理设置预先配置好的客户端构造器，调用方可以再加超时等选项
+pub fn client_builder(app: &AppHandle) -> Result<ClientBuilder, String> {
+    let config: ProxySettings = settings::load(app, "proxy");
+    apply(Client::builder(), &config)
+}
+
+pub fn client(app: &AppHandle) -> Result<Client, String> {
+    client_builder(app)?.build().map_err(|e| e.to_string())
+}
+
+// config 为设置页中尚未保存的代理配置，为空时测试已保存的配置
+#[tauri::command]
+pub async fn test_proxy(
+    app: AppHandle,
+    config: Option<ProxySettings>,
+    url: Option<String>,
+) -> Result<ProxyTestResult, MobiError> {
+    let config = config.unwrap_or_else(|| settings::load(&app, "proxy"));
+    let client = apply(Client::builder().timeout(TEST_TIMEOUT), &config)?
+        .build()
+        .map_err(|e| e.to_string())?;
+    let url = url
+        .filter(|u| !u.trim().is_empty())
+        .unwrap_or_else(|| TEST_URL.into());
+    let start = Instant::now();
+    let response = client
+        .get(url.trim())
+        .send()
+        .await
+        .map_err(|e| format!("无法通过代理连接: {}", e))?;
+    let status = response.status();
+    if status == reqwest::StatusCode::PROXY_AUTHENTICATION_REQUIRED {
+        return Err("代理服务器要求认证，请检查用户名和密码".into());
+    }
+    Ok(ProxyTestResult {
+        status: status.as_u16(),
+        elapsed_ms: start.elapsed().as_millis() as u64,
+    })
+}
@@ -0,0 +1,96 @@
+// 笔记归档：把不再活跃的笔记移到工作区设置中的归档目录（默认 archive/），保持原有的目录结构，取消归档时移回原处
+// 移动时改写其他笔记中指向它的链接和它自身的相对链接；归档的笔记默认不出现在搜索、相关笔记和重复检测中
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::error::MobiError;
+use crate::{bookmarks, encryption, workspace};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MoveResult {
+    // 移动后的路径
+    path: String,
+    // 改写了链接的笔记
+    updated: Vec<String>,
+}
+
+fn move_note(root: &Path, from: &Path, to: PathBuf) -> Result<MoveResult, String> {
+    let content = fs::read_to_string(from).map_err(|e| format!("读取笔记失败: {}", e))?;
+    let dir = to.parent().unwrap_or(root);
+    fs::create_dir_all(dir).map_err(|e| format!("创建目录失败: {}", e))?;
+    // 目标已有同名文件时加序号
+    let to = if to.exists() {
+        let stem = to
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let extension = to
+            .extension()
+            .map(|e| e.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "md".into());
+        workspace::unique_path(dir, &stem, &extension)
+    } else {
+        to
+    };
+
+    fs::rename(from, &to).map_err(|e| format!("移动笔记失败: {}", e))?;
+    // 加密笔记的正文无法改写，只移动文件
+    if !encryption::is_envelope(&content) {
+        let rebased = workspace::rebase_links(root, &content, from, &to);
+        if rebased != content {
+            fs::write(&to, rebased).map_err(|e| format!("更新链接失败: {}", e))?;
+        }
+    }
+    let updated = workspace::retarget_links(root, from, &to, Some(&to))?;
+    let workspace = root.to_string_lossy();
+    bookmarks::moved(
+        &workspace,
+        &workspace::relative_path(root, from),
+        &workspace::relative_path(root, &to),
+    )?;
+    Ok(MoveResult {
+        path: to.to_string_lossy().to_string(),
+        updated,
+    })
+}
+
+#[tauri::command]
+pub fn archive_note(workspace: String, path: String) -> Result<MoveResult, MobiError> {
+    let root = PathBuf::from(&workspace);
+    let from = PathBuf::from(&path);
+    if !from.is_file() || !from.starts_with(&root) {
+        return Err("只能归档工作区中的笔记".into());
+    }
+    let config = workspace::settings(&root);
+    let archive_dir = config.archive_dir.trim_matches('/');
+    if archive_dir.is_empty() {
+        return Err("工作区设置中没有指定归档目录".into());
+    }
+    let relative = workspace::relative_path(&root, &from);
+    if config.is_archived(&relative) {
+        return Err("笔记已经归档".into());
+    }
+    Ok(move_note(
+        &root,
+        &from,
+        root.join(archive_dir).join(&relative),
+    )?)
+}
+
+// 移回归档前的位置，原目录已被删除时重新创建
+#[tauri::command]
+pub fn unarchive_note(workspace: String, path: String) -> Result<MoveResult, MobiError> {
+    let root = PathBuf::from(&workspace);
+    let from = PathBuf::from(&path);
+    let config = workspace::settings(&root);
+    let relative = workspace::relative_path(&root, &from);
+    if !from.is_file() || !from.starts_with(&root) || !config.is_archived(&relative) {
+        return Err("笔记不在归档目录中".into());
+    }
+    let original = relative[config.archive_dir.trim_matches('/').len()..].trim_start_matches('/');
+    Ok(move_note(&root, &from, root.join(original))?)
+}
@@ -0,0 +1,432 @@
+// 保存流水线：保存前按工作区设置依次执行的步骤——整理格式、修复可自动修复的问题、更新目录、更新字数字段、把网络图片下载到本地
+// 只在内存中处理并返回新内容和每一步的改动，由前端写回编辑器后再保存；整条流水线有超时，超时后剩余步骤跳过
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::error::MobiError;
+use crate::{duplicates, encryption, markdown, network, workspace};
+
+const TOC_START: &str = "<!-- toc -->";
+const TOC_END: &str = "<!-- tocstop -->";
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum SaveStep {
+    // 去掉行尾空白（保留两个空格的硬换行）、合并连续空行、文末保留一个换行
+    Format,
+    // 脚注按出现顺序重新编号、补上未闭合的代码块
+    LintFix,
+    // 重写 <!-- toc --> 与 <!-- tocstop --> 之间的目录
+    UpdateToc,
+    // 把正文字数写入 front matter
+    WordCount,
+    // 下载网络图片到附件目录并改为相对链接
+    LocalizeImages,
+}
+
+// 工作区设置中的 onSave 节
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct OnSaveSettings {
+    // 按顺序执行，为空时不处理
+    pub steps: Vec<SaveStep>,
+    pub timeout_secs: u64,
+    pub word_count_field: String,
+    // 下载的图片存放的目录，相对笔记所在目录
+    pub image_dir: String,
+}
+
+impl Default for OnSaveSettings {
+    fn default() -> Self {
+        OnSaveSettings {
+            steps: Vec::new(),
+            timeout_secs: 10,
+            word_count_field: "words".into(),
+            image_dir: "assets".into(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StepReport {
+    step: SaveStep,
+    changed: bool,
+    // 改动说明，如"更新了 5 个目录项"
+    detail: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveReport {
+    content: String,
+    changed: bool,
+    steps: Vec<StepReport>,
+    // 超时未执行的步骤
+    skipped: Vec<SaveStep>,
+    errors: Vec<String>,
+}
+
+fn code_lines(content: &str) -> Vec<bool> {
+    let mut code = vec![false; content.lines().count()];
+    for block in markdown::fenced_blocks(content) {
+        for flag in code
+            .iter_mut()
+            .take(block.end_line + 1)
+            .skip(block.start_line)
+        {
+            *flag = true;
+        }
+    }
+    code
+}
+
+fn join_lines(lines: &[String], trailing_newline: bool) -> String {
+    let mut text = lines.join("\n");
+    if trailing_newline {
+        text.push('\n');
+    }
+    text
+}
+
+fn format(content: &str) -> (String, String) {
+    let code = code_lines(content);
+    let mut lines: Vec<String> = Vec::new();
+    let mut trimmed = 0;
+    for (i, line) in content.lines().enumerate() {
+        if code[i] {
+            lines.push(line.to_string());
+            continue;
+        }
+        let stripped = line.trim_end();
+        // 两个以上空格结尾是硬换行，统一为两个
+        let new = if line.ends_with("  ") && !stripped.is_empty() {
+            format!("{}  ", stripped)
+        } else {
+            stripped.to_string()
+        };
+        if new != line {
+            trimmed += 1;
+        }
+        if new.is_empty() && lines.last().is_some_and(|l| l.is_empty()) {
+            trimmed += 1;
+            continue;
+        }
+        lines.push(new);
+    }
+    while lines.last().is_some_and(|l| l.is_empty()) {
+        lines.pop();
+    }
+    let text = join_lines(&lines, !lines.is_empty());
+    (text, format!("整理了 {} 行", trimmed))
+}
+
+fn lint_fix(content: &str) -> (String, String) {
+    let mut fixes = Vec::new();
+    let mut text = crate::footnotes::renumber(content, false);
+    if text != content {
+        fixes.push("重新编号脚注");
+    }
+    // 未闭合的代码块会吞掉之后的全部内容
+    if let Some(block) = markdown::fenced_blocks(&text).last() {
+        let lines: Vec<&str> = text.lines().collect();
+        let fence = if lines[block.start_line].trim_start().starts_with('~') {
+            "~~~"
+        } else {
+            "```"
+        };
+        let closed = block.end_line > block.start_line
+            && lines.get(block.end_line).is_some_and(|l| {
+                let t = l.trim_start();
+                t.starts_with(fence) && t.trim_start_matches(['`', '~']).trim().is_empty()
+            });
+        if !closed {
+            if !text.ends_with('\n') {
+                text.push('\n');
+            }
+            text.push_str(fence);
+            text.push('\n');
+            fixes.push("补上未闭合的代码块");
+        }
+    }
+    (text, fixes.join("、"))
+}
+
+// 与预览中的标题锚点一致（github-slugger）：小写，去掉标点，空格换成 -，重复的加 -1、-2
+fn slug(text: &str, seen: &mut HashMap<String, usize>) -> String {
+    let base: String = text
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            '-' | '_' => Some(c),
+            c if c.is_alphanumeric() => Some(c),
+            _ => None,
+        })
+        .collect();
+    let count = seen.entry(base.clone()).or_insert(0);
+    let slug = if *count == 0 {
+        base.clone()
+    } else {
+        format!("{}-{}", base, count)
+    };
+    *count += 1;
+    slug
+}
+
+// 标题列表：(级别, 文字)，跳过 front matter 和代码块
+fn headings(content: &str) -> Vec<(usize, String)> {
+    let body = markdown::strip_front_matter(content);
+    let mut headings = Vec::new();
+    let mut current: Option<(usize, String)> = None;
+    for event in Parser::new_ext(body, markdown::parser_options()) {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                current = Some((level as usize, String::new()))
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((_, title)) = current.as_mut() {
+                    title.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::Heading(_)) => headings.extend(current.take()),
+            _ => {}
+        }
+    }
+    headings
+}
+
+fn update_toc(content: &str) -> (String, String) {
+    let Some(start) = content.find(TOC_START) else {
+        return (content.to_string(), String::new());
+    };
+    let after = start + TOC_START.len();
+    let end = content[after..].find(TOC_END).map(|i| after + i);
+
+    let all = headings(content);
+    // 只有一个一级标题时它是文档标题，不列入目录
+    let skip_title = all.iter().filter(|(l, _)| *l == 1).count() == 1;
+    let mut seen = HashMap::new();
+    let entries: Vec<(usize, String, String)> = all
+        .into_iter()
+        .map(|(level, title)| {
+            let anchor = slug(&title, &mut seen);
+            (level, title, anchor)
+        })
+        .filter(|(level, _, _)| !(skip_title && *level == 1))
+        .collect();
+    let min = entries.iter().map(|(l, _, _)| *l).min().unwrap_or(1);
+    let list: String = entries
+        .iter()
+        .map(|(level, title, anchor)| {
+            format!(
+                "{}- [{}](#{})\n",
+                "  ".repeat(level - min),
+                title.replace('[', "\\[").replace(']', "\\]"),
+                anchor
+            )
+        })
+        .collect();
+
+    let block = format!("{}\n\n{}\n{}", TOC_START, list, TOC_END);
+    let text = match end {
+        Some(end) => format!(
+            "{}{}{}",
+            &content[..start],
+            block,
+            &content[end + TOC_END.len()..]
+        ),
+        None => format!("{}{}{}", &content[..start], block, &content[after..]),
+    };
+    (text, format!("更新了 {} 个目录项", entries.len()))
+}
+
+fn word_count(content: &str, field: &str) -> (String, String) {
+    let field = if field.trim().is_empty() {
+        "words"
+    } else {
+        field.trim()
+    };
+    let body = markdown::strip_front_matter(content);
+    let words = duplicates::words(&markdown::to_plain_text(body)).len();
+    if markdown::front_matter_field(content, field) == Some(words.to_string()) {
+        return (content.to_string(), String::new());
+    }
+    (
+        markdown::set_front_matter_field(content, field, &words.to_string()),
+        format!("{}: {}", field, words),
+    )
+}
+
+fn image_extension(url: &str, content_type: &str) -> String {
+    let from_url = url
+        .split(['?', '#'])
+        .next()
+        .and_then(|path| path.rsplit('/').next())
+        .and_then(|name| name.rsplit_once('.'))
+        .map(|(_, ext)| ext.to_lowercase())
+        .filter(|ext| {
+            ["png", "jpg", "jpeg", "gif", "webp", "svg", "bmp", "avif"].contains(&ext.as_str())
+        });
+    from_url.unwrap_or_else(|| {
+        match content_type.split(';').next().unwrap_or("").trim() {
+            "image/jpeg" => "jpg",
+            "image/gif" => "gif",
+            "image/webp" => "webp",
+            "image/svg+xml" => "svg",
+            "image/avif" => "avif",
+            _ => "png",
+        }
+        .to_string()
+    })
+}
+
+async fn localize_images(
+    app: &AppHandle,
+    content: &str,
+    note: &Path,
+    image_dir: &str,
+    deadline: Instant,
+    errors: &mut Vec<String>,
+) -> (String, String) {
+    let mut urls: Vec<String> = Vec::new();
+    for event in Parser::new_ext(content, markdown::parser_options()) {
+        if let Event::Start(Tag::Image { dest_url, .. }) = event {
+            let url = dest_url.to_string();
+            if (url.starts_with("http://") || url.starts_with("https://")) && !urls.contains(&url) {
+                urls.push(url);
+            }
+        }
+    }
+    if urls.is_empty() {
+        return (content.to_string(), String::new());
+    }
+    let Ok(client) = network::client(app) else {
+        errors.push("创建网络连接失败".into());
+        return (content.to_string(), String::new());
+    };
+    let note_dir = note.parent().unwrap_or(Path::new("."));
+    let dir = note_dir.join(image_dir.trim_matches('/'));
+    let stem = workspace::sanitize_file_name(
+        &note
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default(),
+    );
+
+    let mut local: HashMap<String, String> = HashMap::new();
+    for url in urls {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            errors.push(format!("超时，未下载: {}", url));
+            continue;
+        }
+        let downloaded = async {
+            let response = client
+                .get(&url)
+                .timeout(remaining)
+                .send()
+                .await
+                .and_then(|r| r.error_for_status())
+                .map_err(|e| e.to_string())?;
+            let content_type = response
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or_default()
+                .to_string();
+            if !content_type.is_empty() && !content_type.starts_with("image/") {
+                return Err(format!("不是图片（{}）", content_type));
+            }
+            let bytes = response.bytes().await.map_err(|e| e.to_string())?;
+            fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+            let target = workspace::unique_path(
+                &dir,
+                if stem.is_empty() { "image" } else { &stem },
+                &image_extension(&url, &content_type),
+            );
+            fs::write(&target, &bytes).map_err(|e| e.to_string())?;
+            Ok::<_, String>(target)
+        }
+        .await;
+        match downloaded {
+            Ok(target) => {
+                local.insert(
+                    url,
+                    markdown::link_destination(&workspace::relative_to(note_dir, &target)),
+                );
+            }
+            Err(e) => errors.push(format!("下载 {} 失败: {}", url, e)),
+        }
+    }
+    let text =
+        markdown::rewrite_link_destinations(content, |destination| local.get(destination).cloned());
+    (text, format!("下载了 {} 张图片", local.len()))
+}
+
+// 按工作区设置对即将保存的内容执行保存流水线，不写文件
+#[tauri::command]
+pub async fn run_save_pipeline(
+    app: AppHandle,
+    workspace: String,
+    path: String,
+    content: String,
+) -> Result<SaveReport, MobiError> {
+    let config = workspace::settings(Path::new(&workspace)).on_save;
+    let mut report = SaveReport {
+        changed: false,
+        content,
+        steps: Vec::new(),
+        skipped: Vec::new(),
+        errors: Vec::new(),
+    };
+    if config.steps.is_empty() || encryption::is_envelope(&report.content) {
+        return Ok(report);
+    }
+    let deadline = Instant::now() + Duration::from_secs(config.timeout_secs.max(1));
+    let note = Path::new(&path);
+
+    for (i, &step) in config.steps.iter().enumerate() {
+        if Instant::now() >= deadline {
+            report.skipped.extend_from_slice(&config.steps[i..]);
+            break;
+        }
+        let before = &report.content;
+        let (text, detail) = match step {
+            SaveStep::Format => format(before),
+            SaveStep::LintFix => lint_fix(before),
+            SaveStep::UpdateToc => update_toc(before),
+            SaveStep::WordCount => word_count(before, &config.word_count_field),
+            SaveStep::LocalizeImages => {
+                localize_images(
+                    &app,
+                    before,
+                    note,
+                    &config.image_dir,
+                    deadline,
+                    &mut report.errors,
+                )
+                .await
+            }
+        };
+        let changed = text != report.content;
+        report.steps.push(StepReport {
+            step,
+            changed,
+            detail: if changed { detail } else { String::new() },
+        });
+        if changed {
+            report.content = text;
+            report.changed = true;
+        }
+    }
+    Ok(report)
+}
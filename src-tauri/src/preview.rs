@@ -0,0 +1,479 @@
+// 实时预览服务：在本机 HTTP 端口上提供当前文档的渲染结果和工作区资源，文档保存后通过 SSE 通知浏览器刷新
+// 可选监听局域网地址，方便在手机上预览；地址带每次启动随机生成的令牌（/{令牌}/），不提供 .mobi、隐藏文件和归档中的笔记
+// 一个服务供所有窗口使用，每个窗口的当前文档在 /{令牌}/{窗口标签}/ 下
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Read, Write};
+use std::net::{IpAddr, UdpSocket};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State, Window};
+use tiny_http::{Header, Request, Response, Server};
+
+use crate::error::{MobiError, MutexExt};
+use crate::export::{self, ExportOptions};
+use crate::{asset, encryption, share, workspace};
+
+// 注入到预览页中的刷新脚本，相对地址落在令牌前缀下
+const RELOAD_SCRIPT: &str =
+    "<script>new EventSource('__mobi/events').onmessage = () => location.reload();</script>";
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewServerInfo {
+    pub url: String,
+    // 监听局域网时手机可访问的地址
+    lan_url: Option<String>,
+}
+
+#[derive(Default, Clone)]
+struct PreviewDocument {
+    path: Option<PathBuf>,
+    workspace: Option<PathBuf>,
+}
+
+struct RunningServer {
+    server: Arc<Server>,
+    port: u16,
+    lan: bool,
+    token: String,
+}
+
+// SSE 连接，带所预览的窗口标签
+type Clients = Mutex<Vec<(String, mpsc::Sender<()>)>>;
+
+#[derive(Default)]
+pub struct PreviewState {
+    running: Mutex<Option<RunningServer>>,
+    // 按窗口标签区分的当前文档
+    documents: Arc<Mutex<HashMap<String, PreviewDocument>>>,
+    // 最近切换过文档的窗口，没有指明窗口时（例如外部动作启动预览）预览它
+    last: Mutex<Option<String>>,
+    // 每个打开的预览页一个 SSE 连接
+    clients: Arc<Clients>,
+}
+
+// 本机在局域网中的地址：向外“连接”一个 UDP 套接字，只查路由，不发数据包
+pub fn lan_address() -> Option<IpAddr> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("192.168.0.1:80").ok()?;
+    Some(socket.local_addr().ok()?.ip()).filter(|ip| !ip.is_loopback())
+}
+
+pub fn content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" => "text/javascript; charset=utf-8",
+        "md" | "markdown" | "txt" => "text/plain; charset=utf-8",
+        "json" => "application/json",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "bmp" => "image/bmp",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "ogg" => "audio/ogg",
+        "m4a" => "audio/mp4",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "pdf" => "application/pdf",
+        _ => "application/octet-stream",
+    }
+}
+
+// 值中含有不能出现在响应头里的字节（如换行）时不加这个头
+fn with_header<R: Read>(response: Response<R>, name: &str, value: &str) -> Response<R> {
+    match Header::from_bytes(name.as_bytes(), value.as_bytes()) {
+        Ok(header) => response.with_header(header),
+        Err(_) => response,
+    }
+}
+
+pub fn respond_html(request: Request, html: String) {
+    let response = Response::from_string(html);
+    let response = with_header(response, "Content-Type", "text/html; charset=utf-8");
+    let _ = request.respond(with_header(response, "Cache-Control", "no-store"));
+}
+
+pub fn respond_status(request: Request, status: u16, message: &str) {
+    let response = Response::from_string(message).with_status_code(status);
+    let _ = request.respond(with_header(
+        response,
+        "Content-Type",
+        "text/plain; charset=utf-8",
+    ));
+}
+
+// 在 roots 中查找 URL 路径对应的文件，解析后必须仍位于某个根目录内，且不是 .mobi 中的数据或隐藏文件；
+// 给出工作区时，默认搜索中隐藏的归档笔记也不提供
+pub fn resolve_asset(
+    url_path: &str,
+    roots: &[PathBuf],
+    workspace: Option<&Path>,
+) -> Option<PathBuf> {
+    let decoded = urlencoding::decode(url_path.split(['?', '#']).next()?).ok()?;
+    let relative = decoded.trim_start_matches('/');
+    if relative.is_empty() {
+        return None;
+    }
+    let path = roots.iter().find_map(|root| {
+        let root = fs::canonicalize(root).ok()?;
+        let path = fs::canonicalize(root.join(relative)).ok()?;
+        let inside = path
+            .strip_prefix(&root)
+            .is_ok_and(|rest| !asset::is_private(rest));
+        (inside && path.is_file()).then_some(path)
+    })?;
+    if let Some(root) = workspace.and_then(|w| fs::canonicalize(w).ok()) {
+        if path.starts_with(&root)
+            && workspace::settings(&root).hides(&workspace::relative_path(&root, &path))
+        {
+            return None;
+        }
+    }
+    Some(path)
+}
+
+pub fn serve_file(request: Request, path: &Path) {
+    match fs::File::open(path) {
+        Ok(file) => {
+            let response = with_header(
+                Response::from_file(file),
+                "Content-Type",
+                content_type(path),
+            );
+            let _ = request.respond(response);
+        }
+        Err(_) => respond_status(request, 404, "Not Found"),
+    }
+}
+
+// 读取笔记并生成导出选项，加密笔记不对外提供
+pub fn read_note(path: &Path, workspace: Option<&Path>) -> Result<(String, ExportOptions), String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("读取文件失败: {}", e))?;
+    if encryption::is_envelope(&content) {
+        return Err("加密笔记不能预览".into());
+    }
+    let options = ExportOptions {
+        title: path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        workspace: workspace
+            .map(|w| w.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        ..Default::default()
+    };
+    Ok((content, options))
+}
+
+// 在服务线程中同步渲染为完整 HTML 页面；查询参数 theme、target 用于预览导出主题
+fn render_note(
+    app: &AppHandle,
+    path: &Path,
+    workspace: Option<&Path>,
+    query: &str,
+) -> Result<String, String> {
+    let (content, mut options) = read_note(path, workspace)?;
+    for (key, value) in query.split('&').filter_map(|pair| pair.split_once('=')) {
+        let value = urlencoding::decode(value)
+            .map(|v| v.into_owned())
+            .unwrap_or_default();
+        match key {
+            "theme" => options.theme = value,
+            "target" => {
+                if let Ok(target) = serde_json::from_value(serde_json::Value::String(value)) {
+                    options.target = target;
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(tauri::async_runtime::block_on(export::render_html(
+        app.clone(),
+        content,
+        Some(options),
+    ))?)
+}
+
+// 资源的查找范围：文档所在目录，其次是工作区根目录
+pub fn asset_roots(path: &Path, workspace: Option<&Path>) -> Vec<PathBuf> {
+    path.parent()
+        .map(Path::to_path_buf)
+        .into_iter()
+        .chain(workspace.map(Path::to_path_buf))
+        .collect()
+}
+
+fn serve_events(request: Request, clients: &Clients, label: &str) {
+    let (tx, rx) = mpsc::channel();
+    clients.locked().push((label.to_string(), tx));
+    thread::spawn(move || {
+        let mut writer = request.into_writer();
+        let head = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n";
+        if writer
+            .write_all(head.as_bytes())
+            .and_then(|_| writer.flush())
+            .is_err()
+        {
+            return;
+        }
+        loop {
+            // 定时发送注释行，浏览器关掉后写入失败即退出
+            let message = match rx.recv_timeout(Duration::from_secs(15)) {
+                Ok(()) => "data: reload\n\n",
+                Err(mpsc::RecvTimeoutError::Timeout) => ": ping\n\n",
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            };
+            if writer
+                .write_all(message.as_bytes())
+                .and_then(|_| writer.flush())
+                .is_err()
+            {
+                break;
+            }
+        }
+    });
+}
+
+fn handle(app: &AppHandle, request: Request, state: &PreviewShared) {
+    // 令牌不对时与不存在的路径一样返回 404；令牌后是窗口标签
+    let prefix = format!("/{}/", state.token);
+    let Some((label, url)) = request
+        .url()
+        .strip_prefix(&prefix)
+        .map(|rest| rest.split_at(rest.find(['/', '?']).unwrap_or(rest.len())))
+        .filter(|(label, _)| !label.is_empty())
+        .map(|(label, url)| (label.to_string(), url.to_string()))
+    else {
+        return respond_status(request, 404, "Not Found");
+    };
+    if url.starts_with("/__mobi/events") {
+        return serve_events(request, &state.clients, &label);
+    }
+
+    let document = state.documents.locked().get(&label).cloned();
+    let Some((path, document)) = document.and_then(|d| Some((d.path.clone()?, d))) else {
+        return respond_status(request, 404, "没有打开的文档");
+    };
+    if url.is_empty() || url.starts_with('?') {
+        // 没有结尾斜杠时跳转，页面中的相对地址才落在窗口前缀下
+        let location = format!("{}{}/{}", prefix, label, url);
+        let _ = request.respond(with_header(Response::empty(302), "Location", &location));
+        return;
+    }
+    if url == "/" || url.starts_with("/?") {
+        let query = url.split_once('?').map_or("", |(_, q)| q);
+        return match render_note(app, &path, document.workspace.as_deref(), query) {
+            Ok(html) => respond_html(
+                request,
+                html.replace("</body>", &format!("{}</body>", RELOAD_SCRIPT)),
+            ),
+            Err(e) => respond_status(request, 500, &e),
+        };
+    }
+    let workspace = document.workspace.as_deref();
+    match resolve_asset(&url, &asset_roots(&path, workspace), workspace) {
+        Some(file) => serve_file(request, &file),
+        None => respond_status(request, 404, "Not Found"),
+    }
+}
+
+struct PreviewShared {
+    token: String,
+    documents: Arc<Mutex<HashMap<String, PreviewDocument>>>,
+    clients: Arc<Clients>,
+}
+
+// label 为 None 时通知所有预览页
+fn notify_clients(clients: &Clients, label: Option<&str>) {
+    clients
+        .locked()
+        .retain(|(l, tx)| label.is_some_and(|label| label != l) || tx.send(()).is_ok());
+}
+
+// 轮询各窗口当前文档的修改时间，变化时通知预览该窗口的页面刷新
+fn watch_document(server: Arc<Server>, shared: Arc<PreviewShared>) {
+    thread::spawn(move || {
+        let mut last: HashMap<String, (PathBuf, i64)> = HashMap::new();
+        // 服务停止后只剩这里持有 server
+        while Arc::strong_count(&server) > 1 {
+            let paths: Vec<(String, Option<PathBuf>)> = shared
+                .documents
+                .locked()
+                .iter()
+                .map(|(label, d)| (label.clone(), d.path.clone()))
+                .collect();
+            let mut current = HashMap::new();
+            for (label, path) in paths {
+                let Some(path) = path else { continue };
+                let mtime = workspace::modified_secs(&path);
+                current.insert(label, (path, mtime));
+            }
+            for (label, document) in &current {
+                if last.get(label).is_some_and(|previous| previous != document) {
+                    notify_clients(&shared.clients, Some(label));
+                }
+            }
+            last = current;
+            thread::sleep(Duration::from_millis(500));
+        }
+    });
+}
+
+fn info(running: &RunningServer, label: &str) -> PreviewServerInfo {
+    let url = |host: &str| {
+        format!(
+            "http://{}:{}/{}/{}/",
+            host, running.port, running.token, label
+        )
+    };
+    PreviewServerInfo {
+        url: url("127.0.0.1"),
+        lan_url: running
+            .lan
+            .then(lan_address)
+            .flatten()
+            .map(|ip| url(&ip.to_string())),
+    }
+}
+
+// 让所有打开的预览页刷新，例如导出主题改变后
+pub fn reload(app: &AppHandle) {
+    if let Some(state) = app.try_state::<PreviewState>() {
+        notify_clients(&state.clients, None);
+    }
+}
+
+// 最近切换过文档的窗口，还没有时为主窗口
+pub fn default_label(state: &PreviewState) -> String {
+    state.last.locked().clone().unwrap_or_else(|| "main".into())
+}
+
+// 正在运行的预览服务中预览第 label 个窗口的本机地址
+pub fn running_url(state: &PreviewState, label: &str) -> Option<String> {
+    let running = state.running.locked();
+    running.as_ref().map(|r| info(r, label).url)
+}
+
+// 窗口关闭时去掉它的文档和预览页
+pub fn forget_window(app: &AppHandle, label: &str) {
+    if let Some(state) = app.try_state::<PreviewState>() {
+        state.documents.locked().remove(label);
+        state.clients.locked().retain(|(l, _)| l != label);
+        let mut last = state.last.locked();
+        if last.as_deref() == Some(label) {
+            *last = None;
+        }
+    }
+}
+
+// port 为 0 或留空时由系统分配；lan 为 true 时监听所有网卡；返回预览 label 窗口当前文档的地址
+pub fn start(
+    app: AppHandle,
+    state: &PreviewState,
+    label: &str,
+    port: Option<u16>,
+    lan: Option<bool>,
+) -> Result<PreviewServerInfo, MobiError> {
+    let lan = lan.unwrap_or(false);
+    let mut running = state.running.locked();
+    if let Some(existing) = running.as_ref() {
+        if existing.lan == lan && port.is_none_or(|p| p == 0 || p == existing.port) {
+            return Ok(info(existing, label));
+        }
+    }
+    if let Some(old) = running.take() {
+        old.server.unblock();
+    }
+
+    let host = if lan { "0.0.0.0" } else { "127.0.0.1" };
+    let server =
+        Server::http((host, port.unwrap_or(0))).map_err(|e| format!("启动预览服务失败: {}", e))?;
+    let port = server
+        .server_addr()
+        .to_ip()
+        .map(|addr| addr.port())
+        .ok_or("启动预览服务失败")?;
+    let server = Arc::new(server);
+    let token = share::random_token();
+    let shared = Arc::new(PreviewShared {
+        token: token.clone(),
+        documents: state.documents.clone(),
+        clients: state.clients.clone(),
+    });
+
+    let accept = server.clone();
+    let handler = shared.clone();
+    thread::spawn(move || {
+        for request in accept.incoming_requests() {
+            let app = app.clone();
+            let handler = handler.clone();
+            thread::spawn(move || handle(&app, request, &handler));
+        }
+    });
+    watch_document(server.clone(), shared);
+
+    let started = RunningServer {
+        server,
+        port,
+        lan,
+        token,
+    };
+    let result = info(&started, label);
+    *running = Some(started);
+    Ok(result)
+}
+
+// 预览调用的窗口
+#[tauri::command]
+pub fn start_preview_server(
+    app: AppHandle,
+    window: Window,
+    state: State<PreviewState>,
+    port: Option<u16>,
+    lan: Option<bool>,
+) -> Result<PreviewServerInfo, MobiError> {
+    start(app, &state, window.label(), port, lan)
+}
+
+#[tauri::command]
+pub fn stop_preview_server(state: State<PreviewState>) {
+    if let Some(running) = state.running.locked().take() {
+        running.server.unblock();
+    }
+    // 断开所有 SSE 连接
+    state.clients.locked().clear();
+}
+
+// 前端切换文档时调用，预览该窗口的页面随之刷新
+#[tauri::command]
+pub fn set_preview_document(
+    window: Window,
+    state: State<PreviewState>,
+    path: Option<String>,
+    workspace: Option<String>,
+) {
+    let label = window.label().to_string();
+    state.documents.locked().insert(
+        label.clone(),
+        PreviewDocument {
+            path: path.map(PathBuf::from),
+            workspace: workspace.filter(|w| !w.is_empty()).map(PathBuf::from),
+        },
+    );
+    *state.last.locked() = Some(label);
+}
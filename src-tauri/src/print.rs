@@ -0,0 +1,207 @@
+// 打印：把当前文档渲染为带打印样式的页面，在单独的窗口中加载后调用系统打印对话框
+// 页面由临时 HTTP 服务提供（与局域网分享相同），文档引用的本地图片可以正常加载；打印完成或关闭窗口后服务随之停止
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+
+use serde::{Deserialize, Serialize};
+use tauri::webview::PageLoadEvent;
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder, WindowEvent};
+use tiny_http::Server;
+
+use crate::error::MobiError;
+use crate::export::paged::{self, PageSetup, Running, Variables};
+use crate::export::themes::ThemeTarget;
+use crate::export::{self, ExportOptions};
+use crate::{markdown, preview, settings, share};
+
+// 打印完成后页面请求此地址，关闭打印窗口
+const DONE_PATH: &str = "/__mobi/printed";
+
+// WebView2 支持 @page 边距区，页眉页脚和页码由分页样式生成；其他内核改用每页重复的表头表尾，页码无法显示
+const MARGIN_BOXES: bool = cfg!(target_os = "windows");
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PrintOptions {
+    // 页面尺寸、边距、页眉页脚等，与分页导出相同
+    #[serde(flatten)]
+    page: PageSetup,
+    code_theme: String,
+    // 追加在打印样式之后的自定义 CSS
+    css: String,
+}
+
+fn print_css(options: &PrintOptions) -> String {
+    format!(
+        "@media print {{\n\
+           body {{ max-width: none; margin: 0; padding: 0; }}\n\
+           pre, table, figure, img, blockquote, .math-display {{ break-inside: avoid; }}\n\
+           h1, h2, h3, h4 {{ break-after: avoid; }}\n\
+           a {{ color: inherit; text-decoration: none; }}\n\
+         }}\n\
+         .print-layout {{ width: 100%; border: none; border-collapse: collapse; margin: 0; }}\n\
+         .print-layout > thead > tr > td, .print-layout > tbody > tr > td, .print-layout > tfoot > tr > td {{ border: none; padding: 0; background: none; }}\n\
+         .print-running {{ display: flex; justify-content: space-between; gap: 1em; font-size: 9pt; color: #666; }}\n\
+         .print-running span {{ flex: 1; }}\n\
+         .print-running span:nth-child(2) {{ text-align: center; }}\n\
+         .print-running span:nth-child(3) {{ text-align: right; }}\n\
+         thead .print-running {{ padding-bottom: 6mm; }}\n\
+         tfoot .print-running {{ padding-top: 6mm; }}\n\
+         {}\n",
+        options.css
+    )
+}
+
+// 页眉或页脚的一行，三栏都为空时不显示
+fn running_row(running: &Running, vars: &Variables) -> Option<String> {
+    let cells: Vec<String> = [&running.left, &running.center, &running.right]
+        .iter()
+        .map(|t| markdown::escape_html(&paged::plain_text(t, vars)))
+        .collect();
+    if cells.iter().all(String::is_empty) {
+        return None;
+    }
+    Some(format!(
+        "<tr><td><div class=\"print-running\"><span>{}</span></div></td></tr>",
+        cells.join("</span><span>")
+    ))
+}
+
+// 不支持边距区时，正文放进表格，页眉页脚放在 thead / tfoot 中，分页时每页都会重复
+fn print_page(html: &str, options: &PrintOptions, vars: &Variables) -> String {
+    let (Some(body_start), Some(body_end)) = (html.find("<body>"), html.rfind("</body>")) else {
+        return html.to_string();
+    };
+    let body = &html[body_start + "<body>".len()..body_end];
+    let head = html[..body_start].replacen(
+        "</head>",
+        &format!("<style>\n{}</style>\n</head>", print_css(options)),
+        1,
+    );
+
+    let mut page = head;
+    page.push_str("<body>\n");
+    if MARGIN_BOXES {
+        page.push_str(body);
+    } else {
+        page.push_str("<table class=\"print-layout\">");
+        if let Some(row) = running_row(&options.page.header, vars) {
+            page.push_str(&format!("<thead>{}</thead>", row));
+        }
+        if let Some(row) = running_row(&options.page.footer, vars) {
+            page.push_str(&format!("<tfoot>{}</tfoot>", row));
+        }
+        page.push_str(&format!(
+            "<tbody><tr><td>\n{}</td></tr></tbody></table>",
+            body
+        ));
+    }
+    page.push_str(&format!(
+        "\n<script>window.addEventListener('afterprint', () => fetch('{}'));</script>\n</body>\n</html>\n",
+        DONE_PATH
+    ));
+    page
+}
+
+fn serve(app: AppHandle, server: Arc<Server>, label: String, html: String, roots: Vec<PathBuf>) {
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let url = request.url().to_string();
+            if url == "/" {
+                preview::respond_html(request, html.clone());
+            } else if url == DONE_PATH {
+                preview::respond_status(request, 204, "");
+                if let Some(window) = app.get_webview_window(&label) {
+                    let _ = window.close();
+                }
+            } else {
+                match preview::resolve_asset(&url, &roots, None) {
+                    Some(file) => preview::serve_file(request, &file),
+                    None => preview::respond_status(request, 404, "Not Found"),
+                }
+            }
+        }
+    });
+}
+
+// 打印当前内容（可以是未保存的）；path 用于标题和解析相对图片，options 留空时使用设置中的打印选项
+#[tauri::command]
+pub async fn print_document(
+    app: AppHandle,
+    content: String,
+    path: Option<String>,
+    workspace: Option<String>,
+    options: Option<PrintOptions>,
+) -> Result<(), MobiError> {
+    let options = options.unwrap_or_else(|| settings::load(&app, "print"));
+    let path = path.filter(|p| !p.is_empty()).map(PathBuf::from);
+    let workspace = workspace.filter(|w| !w.is_empty()).map(PathBuf::from);
+    let title = path
+        .as_deref()
+        .and_then(Path::file_stem)
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "未命名".into());
+
+    let mut page = options.page.clone();
+    if !MARGIN_BOXES {
+        // 页眉页脚改由表格输出
+        page.header = Running::default();
+        page.footer = Running::default();
+    }
+    let author = markdown::front_matter_field(&content, "author").unwrap_or_default();
+    let export_options = ExportOptions {
+        title: title.clone(),
+        code_theme: options.code_theme.clone(),
+        target: ThemeTarget::Print,
+        paged: Some(page),
+        workspace: workspace
+            .as_deref()
+            .map(|w| w.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        ..Default::default()
+    };
+    let html = export::render_html(app.clone(), content, Some(export_options)).await?;
+    let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let vars = Variables {
+        title: &title,
+        date: &date,
+        author: &author,
+    };
+    let html = print_page(&html, &options, &vars);
+    let roots = match &path {
+        Some(path) => preview::asset_roots(path, workspace.as_deref()),
+        None => workspace.into_iter().collect(),
+    };
+
+    let server = Server::http(("127.0.0.1", 0)).map_err(|e| format!("启动打印服务失败: {}", e))?;
+    let port = server
+        .server_addr()
+        .to_ip()
+        .map(|addr| addr.port())
+        .ok_or("启动打印服务失败")?;
+    let server = Arc::new(server);
+    let label = format!("print-{}", share::random_token());
+    serve(app.clone(), server.clone(), label.clone(), html, roots);
+
+    let url = format!("http://127.0.0.1:{}/", port)
+        .parse()
+        .map_err(|e| format!("打印地址无效: {}", e))?;
+    let window = WebviewWindowBuilder::new(&app, &label, WebviewUrl::External(url))
+        .title(format!("打印 - {}", title))
+        .inner_size(820.0, 1000.0)
+        .on_page_load(|window, payload| {
+            if payload.event() == PageLoadEvent::Finished {
+                let _ = window.print();
+            }
+        })
+        .build()
+        .map_err(|e| format!("打开打印窗口失败: {}", e))?;
+    window.on_window_event(move |event| {
+        if let WindowEvent::Destroyed = event {
+            server.unblock();
+        }
+    });
+    Ok(())
+}
@@ -0,0 +1,411 @@
+// 语音备忘：录制麦克风音频到笔记附件目录，可选用 whisper.cpp 或在线接口转写
+
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, Sample, SampleFormat, SizedSample, Stream, StreamConfig};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+
+use crate::error::{self, MobiError, MutexExt};
+use crate::{network, secrets, settings};
+
+// 语音备忘统一存为 16kHz 单声道，体积小且 whisper 可直接处理
+const TARGET_RATE: u32 = 16000;
+
+type WavWriter = hound::WavWriter<BufWriter<File>>;
+
+#[derive(Serialize, Deserialize, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+enum TranscriptionProvider {
+    #[default]
+    None,
+    WhisperCpp,
+    Api,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct TranscriptionSettings {
+    provider: TranscriptionProvider,
+    // whisper.cpp 可执行文件（whisper-cli）与模型路径
+    whisper_binary: String,
+    whisper_model: String,
+    // OpenAI 兼容的 /audio/transcriptions 接口
+    // API Key 保存在钥匙串中（secrets: transcription / api-key）
+    api_url: String,
+    api_model: String,
+    // 留空则自动识别
+    language: String,
+}
+
+struct Recording {
+    stop_tx: mpsc::Sender<()>,
+    worker: JoinHandle<Result<(), String>>,
+    path: PathBuf,
+    relative_path: String,
+    started: Instant,
+}
+
+#[derive(Default)]
+pub struct RecordingState {
+    active: Mutex<Option<Recording>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingResult {
+    path: String,
+    relative_path: String,
+    markdown: String,
+    duration_secs: f64,
+    transcript: Option<String>,
+    // 转写失败时录音仍已保存，前端照常插入链接并提示这条错误
+    transcription_error: Option<String>,
+}
+
+// 区间平均降采样，顺带起到简单的低通作用
+struct Downsampler {
+    step: f64,
+    pos: f64,
+    sum: f32,
+    count: u32,
+    last: f32,
+}
+
+impl Downsampler {
+    fn new(source_rate: u32) -> Self {
+        Downsampler {
+            step: source_rate as f64 / TARGET_RATE as f64,
+            pos: 0.0,
+            sum: 0.0,
+            count: 0,
+            last: 0.0,
+        }
+    }
+
+    fn push(&mut self, sample: f32, mut out: impl FnMut(f32)) {
+        self.sum += sample;
+        self.count += 1;
+        self.pos += 1.0;
+        while self.pos >= self.step {
+            if self.count > 0 {
+                self.last = self.sum / self.count as f32;
+                self.sum = 0.0;
+                self.count = 0;
+            }
+            out(self.last);
+            self.pos -= self.step;
+        }
+    }
+}
+
+fn build_stream<T>(
+    app: AppHandle,
+    device: &cpal::Device,
+    config: &StreamConfig,
+    writer: Arc<Mutex<Option<WavWriter>>>,
+) -> Result<Stream, String>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let channels = config.channels as usize;
+    let mut downsampler = Downsampler::new(config.sample_rate.0);
+
+    device
+        .build_input_stream(
+            config,
+            move |data: &[T], _| {
+                let mut guard = writer.locked();
+                let Some(writer) = guard.as_mut() else {
+                    return;
+                };
+                // 多声道混为单声道
+                for frame in data.chunks(channels) {
+                    let mono =
+                        frame.iter().map(|s| f32::from_sample(*s)).sum::<f32>() / channels as f32;
+                    downsampler.push(mono, |v| {
+                        let _ = writer.write_sample((v.clamp(-1.0, 1.0) * i16::MAX as f32) as i16);
+                    });
+                }
+            },
+            move |e| error::report(&app, "录音出错", e),
+            None,
+        )
+        .map_err(|e| format!("打开麦克风失败: {}", e))
+}
+
+fn open_stream(app: AppHandle, writer: Arc<Mutex<Option<WavWriter>>>) -> Result<Stream, String> {
+    let device = cpal::default_host()
+        .default_input_device()
+        .ok_or("未找到麦克风设备")?;
+    let supported = device
+        .default_input_config()
+        .map_err(|e| format!("读取麦克风配置失败: {}", e))?;
+    let config: StreamConfig = supported.config();
+
+    let stream = match supported.sample_format() {
+        SampleFormat::F32 => build_stream::<f32>(app.clone(), &device, &config, writer),
+        SampleFormat::I16 => build_stream::<i16>(app.clone(), &device, &config, writer),
+        SampleFormat::U16 => build_stream::<u16>(app.clone(), &device, &config, writer),
+        SampleFormat::I32 => build_stream::<i32>(app.clone(), &device, &config, writer),
+        other => Err(format!("不支持的采样格式: {}", other)),
+    }?;
+    stream.play().map_err(|e| format!("开始录音失败: {}", e))?;
+    Ok(stream)
+}
+
+// 与前端粘贴附件一致：{笔记目录}/{子目录}/{笔记名}/
+fn attachment_dir(note_path: &Path, subfolder: &str) -> Result<(PathBuf, String), String> {
+    let base = note_path.parent().ok_or("无效的笔记路径")?;
+    if subfolder.is_empty() {
+        return Ok((base.to_path_buf(), String::new()));
+    }
+    let stem = note_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    Ok((
+        base.join(subfolder).join(&stem),
+        format!("{}/{}", subfolder, stem),
+    ))
+}
+
+#[tauri::command]
+pub fn start_recording(
+    app: AppHandle,
+    state: State<RecordingState>,
+    note_path: String,
+    subfolder: Option<String>,
+) -> Result<String, MobiError> {
+    let mut active = state.active.locked();
+    if active.is_some() {
+        return Err("已经在录音中".into());
+    }
+
+    let subfolder = subfolder.unwrap_or_else(|| "assets".into());
+    let (dir, relative_dir) = attachment_dir(Path::new(&note_path), &subfolder)?;
+    fs::create_dir_all(&dir).map_err(|e| format!("创建附件目录失败: {}", e))?;
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default();
+    let file_name = format!("memo_{}.wav", timestamp);
+    let path = dir.join(&file_name);
+    let relative_path = if relative_dir.is_empty() {
+        file_name
+    } else {
+        format!("{}/{}", relative_dir, file_name)
+    };
+
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: TARGET_RATE,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let writer = WavWriter::create(&path, spec).map_err(|e| format!("创建音频文件失败: {}", e))?;
+    let writer = Arc::new(Mutex::new(Some(writer)));
+
+    // cpal 的 Stream 不能跨线程移动，录音期间由独立线程持有
+    let (ready_tx, ready_rx) = mpsc::channel();
+    let (stop_tx, stop_rx) = mpsc::channel::<()>();
+    let worker = thread::spawn(move || {
+        let stream = match open_stream(app, writer.clone()) {
+            Ok(stream) => {
+                let _ = ready_tx.send(Ok(()));
+                stream
+            }
+            Err(e) => {
+                let _ = ready_tx.send(Err(e.clone()));
+                return Err(e);
+            }
+        };
+        let _ = stop_rx.recv();
+        drop(stream);
+
+        match writer.locked().take() {
+            Some(writer) => writer
+                .finalize()
+                .map_err(|e| format!("写入音频文件失败: {}", e)),
+            None => Ok(()),
+        }
+    });
+
+    if let Err(e) = ready_rx.recv().unwrap_or(Err("录音线程异常退出".into())) {
+        let _ = fs::remove_file(&path);
+        return Err(e.into());
+    }
+
+    *active = Some(Recording {
+        stop_tx,
+        worker,
+        path,
+        relative_path: relative_path.clone(),
+        started: Instant::now(),
+    });
+    Ok(relative_path)
+}
+
+#[tauri::command]
+pub async fn stop_recording(
+    app: AppHandle,
+    state: State<'_, RecordingState>,
+    transcribe: Option<bool>,
+) -> Result<RecordingResult, MobiError> {
+    let recording = state.active.locked().take().ok_or("当前没有在录音")?;
+
+    let duration_secs = recording.started.elapsed().as_secs_f64();
+    let _ = recording.stop_tx.send(());
+    let worker = recording.worker;
+    tauri::async_runtime::spawn_blocking(move || worker.join())
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|_| "录音线程异常退出".to_string())??;
+
+    let path = recording.path.to_string_lossy().to_string();
+    let (transcript, transcription_error) = if transcribe.unwrap_or(false) {
+        match transcribe_file(&app, &path).await {
+            Ok(text) => (Some(text), None),
+            Err(e) => (None, Some(e)),
+        }
+    } else {
+        (None, None)
+    };
+
+    let name = recording
+        .path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    Ok(RecordingResult {
+        markdown: format!("[{}]({})", name, recording.relative_path),
+        path,
+        relative_path: recording.relative_path,
+        duration_secs,
+        transcript,
+        transcription_error,
+    })
+}
+
+#[tauri::command]
+pub async fn transcribe_audio(app: AppHandle, path: String) -> Result<String, MobiError> {
+    Ok(transcribe_file(&app, &path).await?)
+}
+
+async fn transcribe_file(app: &AppHandle, path: &str) -> Result<String, String> {
+    secrets::migrate_setting(app, "transcription", "apiKey", "transcription", "api-key");
+    let config: TranscriptionSettings = settings::load(app, "transcription");
+    match config.provider {
+        TranscriptionProvider::None => Err("未配置语音转写".into()),
+        TranscriptionProvider::WhisperCpp => {
+            let path = path.to_string();
+            tauri::async_runtime::spawn_blocking(move || transcribe_whisper_cpp(&config, &path))
+                .await
+                .map_err(|e| e.to_string())?
+        }
+        TranscriptionProvider::Api => transcribe_api(app, &config, path).await,
+    }
+}
+
+fn transcribe_whisper_cpp(config: &TranscriptionSettings, path: &str) -> Result<String, String> {
+    if config.whisper_binary.is_empty() || config.whisper_model.is_empty() {
+        return Err("请先设置 whisper.cpp 程序和模型路径".into());
+    }
+    let language = if config.language.is_empty() {
+        "auto"
+    } else {
+        config.language.as_str()
+    };
+
+    let output = Command::new(&config.whisper_binary)
+        .args(["-m", &config.whisper_model, "-f", path, "-l", language])
+        .args(["--no-timestamps", "--no-prints"])
+        .output()
+        .map_err(|e| format!("启动 whisper.cpp 失败: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "whisper.cpp 转写失败: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let text = String::from_utf8_lossy(&output.stdout);
+    Ok(text
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+async fn transcribe_api(
+    app: &AppHandle,
+    config: &TranscriptionSettings,
+    path: &str,
+) -> Result<String, String> {
+    #[derive(Deserialize)]
+    struct Response {
+        text: String,
+    }
+
+    if config.api_url.is_empty() {
+        return Err("请先设置语音转写接口地址".into());
+    }
+    let bytes = read_file(path).await?;
+    let file_name = Path::new(path)
+        .file_name()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "memo.wav".into());
+
+    let part = reqwest::multipart::Part::bytes(bytes)
+        .file_name(file_name)
+        .mime_str("audio/wav")
+        .map_err(|e| e.to_string())?;
+    let model = if config.api_model.is_empty() {
+        "whisper-1".to_string()
+    } else {
+        config.api_model.clone()
+    };
+    let mut form = reqwest::multipart::Form::new()
+        .part("file", part)
+        .text("model", model);
+    if !config.language.is_empty() {
+        form = form.text("language", config.language.clone());
+    }
+
+    let mut request = network::client(app)?.post(&config.api_url).multipart(form);
+    if let Some(api_key) = secrets::get_secret("transcription", "api-key")? {
+        request = request.bearer_auth(api_key);
+    }
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("请求转写接口失败: {}", e))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("转写接口返回错误 {}: {}", status, body));
+    }
+    let result: Response = response
+        .json()
+        .await
+        .map_err(|e| format!("解析转写结果失败: {}", e))?;
+    Ok(result.text.trim().to_string())
+}
+
+async fn read_file(path: &str) -> Result<Vec<u8>, String> {
+    let path = path.to_string();
+    tauri::async_runtime::spawn_blocking(move || fs::read(path))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| format!("读取音频文件失败: {}", e))
+}
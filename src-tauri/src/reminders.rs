@@ -0,0 +1,204 @@
+// 任务提醒：后台定时扫描任务索引，到期时发送系统通知，窗口隐藏时也照常工作
+// 只有日期没有时间的任务按设置中的默认时间提醒；已提醒和稍后提醒的状态记录在工作区索引库
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::error::{self, MobiError, MutexExt};
+use crate::tasks::{self, Task};
+use crate::{index, settings};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+// 超过这么久的过期任务不再补发提醒
+const MAX_OVERDUE_SECS: i64 = 24 * 3600;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct ReminderSettings {
+    enabled: bool,
+    // HH:MM
+    default_time: String,
+    // 提前提醒的分钟数
+    lead_minutes: i64,
+}
+
+impl Default for ReminderSettings {
+    fn default() -> Self {
+        ReminderSettings {
+            enabled: true,
+            default_time: "09:00".into(),
+            lead_minutes: 0,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ReminderState {
+    workspace: Mutex<Option<PathBuf>>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Reminder {
+    // 提醒的唯一标识，用于稍后提醒
+    key: String,
+    #[serde(flatten)]
+    task: Task,
+    // 秒级时间戳
+    fire_at: i64,
+    snoozed: bool,
+}
+
+fn ensure_schema(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS reminder_state (
+            key TEXT PRIMARY KEY,
+            fired INTEGER NOT NULL DEFAULT 0,
+            snoozed_until INTEGER
+        );",
+    )
+    .map_err(|e| format!("初始化提醒状态失败: {}", e))
+}
+
+// 行号会随编辑变化，用笔记、正文和截止时间标识一条提醒
+fn reminder_key(root: &Path, task: &Task) -> String {
+    format!(
+        "{}\n{}\n{} {}",
+        crate::workspace::relative_path(root, Path::new(&task.path)),
+        task.text,
+        task.due.as_deref().unwrap_or_default(),
+        task.due_time.as_deref().unwrap_or_default()
+    )
+}
+
+fn fire_at(task: &Task, config: &ReminderSettings) -> Option<i64> {
+    let date = NaiveDate::parse_from_str(task.due.as_deref()?, "%Y-%m-%d").ok()?;
+    let time = task.due_time.as_deref().unwrap_or(&config.default_time);
+    let time = NaiveTime::parse_from_str(time, "%H:%M").unwrap_or_default();
+    let local = Local
+        .from_local_datetime(&NaiveDateTime::new(date, time))
+        .earliest()?;
+    Some(local.timestamp() - config.lead_minutes * 60)
+}
+
+fn state_of(conn: &Connection, key: &str) -> Result<(bool, Option<i64>), String> {
+    conn.query_row(
+        "SELECT fired, snoozed_until FROM reminder_state WHERE key = ?1",
+        [key],
+        |r| Ok((r.get(0)?, r.get(1)?)),
+    )
+    .optional()
+    .map(|state| state.unwrap_or((false, None)))
+    .map_err(|e| e.to_string())
+}
+
+// 所有未完成且有截止日期的任务对应的提醒，按提醒时间排序
+fn reminders(app: &AppHandle, root: &Path) -> Result<(Connection, Vec<Reminder>), String> {
+    let config: ReminderSettings = settings::load(app, "reminders");
+    let conn = tasks::refresh(root)?;
+    ensure_schema(&conn)?;
+
+    let mut reminders = Vec::new();
+    for task in tasks::query(root, &conn, &Default::default())? {
+        let Some(mut at) = fire_at(&task, &config) else {
+            continue;
+        };
+        let key = reminder_key(root, &task);
+        let (fired, snoozed_until) = state_of(&conn, &key)?;
+        if let Some(until) = snoozed_until {
+            at = until;
+        } else if fired {
+            continue;
+        }
+        reminders.push(Reminder {
+            key,
+            task,
+            fire_at: at,
+            snoozed: snoozed_until.is_some(),
+        });
+    }
+    reminders.sort_by_key(|r| r.fire_at);
+    Ok((conn, reminders))
+}
+
+fn check(app: &AppHandle, root: &Path) -> Result<(), String> {
+    let now = Local::now().timestamp();
+    let (conn, reminders) = reminders(app, root)?;
+    for reminder in reminders.into_iter().filter(|r| r.fire_at <= now) {
+        if now - reminder.fire_at <= MAX_OVERDUE_SECS {
+            let body = match (&reminder.task.due, &reminder.task.due_time) {
+                (Some(date), Some(time)) => format!("截止 {} {}", date, time),
+                (Some(date), None) => format!("截止 {}", date),
+                _ => String::new(),
+            };
+            let _ = app
+                .notification()
+                .builder()
+                .title(&reminder.task.text)
+                .body(body)
+                .show();
+            let _ = app.emit("task-reminder", reminder.clone());
+        }
+        conn.execute(
+            "INSERT OR REPLACE INTO reminder_state (key, fired, snoozed_until) VALUES (?1, 1, NULL)",
+            [&reminder.key],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+// 在 setup 中启动后台检查线程
+pub fn start(app: AppHandle) {
+    thread::spawn(move || loop {
+        let config: ReminderSettings = settings::load(&app, "reminders");
+        let workspace = app.state::<ReminderState>().workspace.locked().clone();
+        if let (true, Some(root)) = (config.enabled, workspace) {
+            if let Err(e) = check(&app, &root) {
+                error::report(&app, "检查任务提醒失败", e);
+            }
+        }
+        thread::sleep(CHECK_INTERVAL);
+    });
+}
+
+// 前端打开工作区后调用，提醒只针对当前工作区
+#[tauri::command]
+pub fn watch_reminders(state: State<ReminderState>, workspace: Option<String>) {
+    *state.workspace.locked() = workspace.map(PathBuf::from);
+}
+
+#[tauri::command]
+pub fn get_upcoming_reminders(
+    app: AppHandle,
+    workspace: String,
+    hours: Option<i64>,
+) -> Result<Vec<Reminder>, MobiError> {
+    let until = Local::now().timestamp() + hours.unwrap_or(24 * 7) * 3600;
+    let (_, reminders) = reminders(&app, Path::new(&workspace))?;
+    Ok(reminders
+        .into_iter()
+        .filter(|r| r.fire_at <= until)
+        .collect())
+}
+
+#[tauri::command]
+pub fn snooze_reminder(workspace: String, key: String, minutes: i64) -> Result<(), MobiError> {
+    let conn = index::open(Path::new(&workspace))?;
+    ensure_schema(&conn)?;
+    let until = Local::now().timestamp() + minutes.max(1) * 60;
+    conn.execute(
+        "INSERT OR REPLACE INTO reminder_state (key, fired, snoozed_until) VALUES (?1, 0, ?2)",
+        params![key, until],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
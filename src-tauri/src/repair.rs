@@ -0,0 +1,229 @@
+// 修复失效链接：在 Mobi 之外整理文件夹后，笔记中的图片和文件链接常指向已不存在的路径
+// 按文件名在工作区中查找同名文件，多个候选内容相同时取离原路径最近的一个，内容不同时只给出候选，由用户选择
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use pulldown_cmark::{Event, LinkType, Parser, Tag};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::error::MobiError;
+use crate::{encryption, markdown, workspace};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BrokenLink {
+    note: String,
+    // 行号从 0 开始
+    line: usize,
+    // 原文中的链接目标
+    destination: String,
+    image: bool,
+    // 同名文件，离原路径近的在前
+    candidates: Vec<String>,
+    // 建议的新链接目标；候选内容不同或没有候选时为空
+    fix: Option<String>,
+    applied: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RepairReport {
+    links: Vec<BrokenLink>,
+    // 写回了修复的笔记
+    updated: Vec<String>,
+}
+
+// data:、tel: 等非文件链接
+fn has_scheme(destination: &str) -> bool {
+    destination.split_once(':').is_some_and(|(scheme, _)| {
+        scheme.len() > 1
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+    })
+}
+
+fn file_key(path: &Path) -> Option<String> {
+    path.file_name().map(|n| n.to_string_lossy().to_lowercase())
+}
+
+fn hash(path: &Path) -> Option<String> {
+    let bytes = fs::read(path).ok()?;
+    Some(format!("{:x}", Sha256::digest(&bytes)))
+}
+
+// 与原路径相同的目录层数，越多越近
+fn closeness(missing: &Path, candidate: &Path) -> usize {
+    let (Some(a), Some(b)) = (missing.parent(), candidate.parent()) else {
+        return 0;
+    };
+    a.components()
+        .zip(b.components())
+        .take_while(|(a, b)| a == b)
+        .count()
+}
+
+// 笔记中的行内链接和图片：(行号, 原文目标, 是否图片)；代码块中的不算
+fn local_links(content: &str) -> Vec<(usize, String, bool)> {
+    let mut links = Vec::new();
+    for (event, range) in Parser::new_ext(content, markdown::parser_options()).into_offset_iter() {
+        let (dest_url, image) = match event {
+            Event::Start(Tag::Link {
+                link_type: LinkType::Inline,
+                dest_url,
+                ..
+            }) => (dest_url, false),
+            Event::Start(Tag::Image {
+                link_type: LinkType::Inline,
+                dest_url,
+                ..
+            }) => (dest_url, true),
+            _ => continue,
+        };
+        if has_scheme(&dest_url) {
+            continue;
+        }
+        let line = content[..range.start].matches('\n').count();
+        links.push((line, dest_url.to_string(), image));
+    }
+    links
+}
+
+// 新的链接目标：原来以 / 开头（相对工作区根目录）的保持这种写法，并保留 #锚点
+fn new_destination(root: &Path, note: &Path, destination: &str, target: &Path) -> String {
+    let fragment = destination.find('#').map_or("", |i| &destination[i..]);
+    let path = if destination.starts_with('/') {
+        format!("/{}", workspace::relative_path(root, target))
+    } else {
+        workspace::relative_to(note.parent().unwrap_or(root), target)
+    };
+    format!("{}{}", markdown::link_destination(&path), fragment)
+}
+
+// 把笔记中指向 missing 的链接改为指向 target，返回是否有改动
+fn apply_fix(root: &Path, note: &Path, missing: &Path, target: &Path) -> Result<bool, String> {
+    let content = fs::read_to_string(note).map_err(|e| format!("读取笔记失败: {}", e))?;
+    let rewritten = markdown::rewrite_link_destinations(&content, |destination| {
+        (workspace::resolve_note_link(root, note, destination)? == missing)
+            .then(|| new_destination(root, note, destination, target))
+    });
+    if rewritten == content {
+        return Ok(false);
+    }
+    fs::write(workspace::resolve_link(note), rewritten)
+        .map_err(|e| format!("保存笔记失败: {}", e))?;
+    Ok(true)
+}
+
+// 找出失效的本地链接并给出修复；apply 为 true 时直接写回能确定的修复
+#[tauri::command]
+pub async fn repair_broken_links(
+    workspace: String,
+    apply: Option<bool>,
+) -> Result<RepairReport, MobiError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = PathBuf::from(&workspace);
+        let apply = apply.unwrap_or(false);
+
+        let mut by_name: HashMap<String, Vec<PathBuf>> = HashMap::new();
+        for file in workspace::all_files(&root) {
+            if let Some(key) = file_key(&file) {
+                by_name.entry(key).or_default().push(file);
+            }
+        }
+        let mut hashes: HashMap<PathBuf, Option<String>> = HashMap::new();
+
+        let mut report = RepairReport {
+            links: Vec::new(),
+            updated: Vec::new(),
+        };
+        for note in workspace::note_files(&root) {
+            let Ok(content) = fs::read_to_string(&note) else {
+                continue;
+            };
+            if encryption::is_envelope(&content) {
+                continue;
+            }
+            // 同一个失效目标在笔记中只修复一次
+            let mut fixes: Vec<(PathBuf, PathBuf)> = Vec::new();
+            for (line, destination, image) in local_links(&content) {
+                let Some(missing) = workspace::resolve_note_link(&root, &note, &destination) else {
+                    continue;
+                };
+                if missing.exists() {
+                    continue;
+                }
+                let mut candidates: Vec<PathBuf> = file_key(&missing)
+                    .and_then(|key| by_name.get(&key))
+                    .cloned()
+                    .unwrap_or_default();
+                candidates.sort_by_key(|c| std::cmp::Reverse(closeness(&missing, c)));
+                let identical = candidates.len() == 1
+                    || candidates.windows(2).all(|pair| {
+                        let a = hashes
+                            .entry(pair[0].clone())
+                            .or_insert_with(|| hash(&pair[0]))
+                            .clone();
+                        let b = hashes
+                            .entry(pair[1].clone())
+                            .or_insert_with(|| hash(&pair[1]))
+                            .clone();
+                        a.is_some() && a == b
+                    });
+                let target = candidates.first().filter(|_| identical).cloned();
+                let applied = apply
+                    && target.as_ref().is_some_and(|target| {
+                        if !fixes.iter().any(|(m, _)| *m == missing) {
+                            fixes.push((missing.clone(), target.clone()));
+                        }
+                        true
+                    });
+                report.links.push(BrokenLink {
+                    note: note.to_string_lossy().to_string(),
+                    line,
+                    fix: target
+                        .as_ref()
+                        .map(|target| new_destination(&root, &note, &destination, target)),
+                    destination,
+                    image,
+                    candidates: candidates
+                        .iter()
+                        .map(|c| c.to_string_lossy().to_string())
+                        .collect(),
+                    applied,
+                });
+            }
+            let mut changed = false;
+            for (missing, target) in &fixes {
+                changed |= apply_fix(&root, &note, missing, target)?;
+            }
+            if changed {
+                report.updated.push(note.to_string_lossy().to_string());
+            }
+        }
+        Ok(report)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// 应用用户选定的修复：把 note 中目标为 destination 的链接改为指向 target
+#[tauri::command]
+pub fn apply_link_fix(
+    workspace: String,
+    note: String,
+    destination: String,
+    target: String,
+) -> Result<bool, MobiError> {
+    let root = PathBuf::from(&workspace);
+    let note = PathBuf::from(&note);
+    let missing = workspace::resolve_note_link(&root, &note, &destination).ok_or("不是本地链接")?;
+    let target = PathBuf::from(&target);
+    if !target.exists() {
+        return Err("目标文件不存在".into());
+    }
+    Ok(apply_fix(&root, &note, &missing, &target)?)
+}
@@ -0,0 +1,487 @@
+// 间隔重复：front matter tags 含 review 的笔记，以及带 #review 标签的段落或标题（整节），按 SM-2 算法安排复习
+// 复习项目随笔记增量解析到工作区索引库；块以 ^块引用 为标识，没有时以首行文字为标识，改动首行会当作新项目重新开始
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{Duration, Local, NaiveDate};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::error::MobiError;
+use crate::{encryption, index, markdown, workspace};
+
+const TAG: &str = "#review";
+// 摘录的长度（字符数）
+const EXCERPT_CHARS: usize = 200;
+const INITIAL_EASE: f64 = 2.5;
+const MIN_EASE: f64 = 1.3;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewItem {
+    id: String,
+    path: String,
+    // 行号从 0 开始，整篇笔记为 0
+    line: usize,
+    // note 或 block
+    kind: String,
+    title: String,
+    excerpt: String,
+    // YYYY-MM-DD，新项目为今天
+    due: String,
+    // 天
+    interval: i64,
+    repetitions: i64,
+    ease: f64,
+    // 还没有复习过
+    new: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewSchedule {
+    due: String,
+    interval: i64,
+    repetitions: i64,
+    ease: f64,
+}
+
+struct ParsedItem {
+    id: String,
+    line: usize,
+    kind: &'static str,
+    title: String,
+    excerpt: String,
+}
+
+fn ensure_schema(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS review_notes (
+            path TEXT PRIMARY KEY,
+            mtime INTEGER NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS review_items (
+            id TEXT PRIMARY KEY,
+            path TEXT NOT NULL,
+            line INTEGER NOT NULL,
+            kind TEXT NOT NULL,
+            title TEXT NOT NULL,
+            excerpt TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_review_items_path ON review_items(path);
+        CREATE TABLE IF NOT EXISTS review_schedule (
+            id TEXT PRIMARY KEY,
+            repetitions INTEGER NOT NULL,
+            interval INTEGER NOT NULL,
+            ease REAL NOT NULL,
+            due TEXT NOT NULL,
+            reviewed TEXT NOT NULL
+        );",
+    )
+    .map_err(|e| format!("初始化复习索引失败: {}", e))
+}
+
+// FNV-1a，标识要持久化
+fn fnv(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn excerpt(text: &str) -> String {
+    let text = text
+        .replace(TAG, "")
+        .split_whitespace()
+        .filter(|word| block_ref(word).is_none())
+        .collect::<Vec<_>>()
+        .join(" ");
+    if text.chars().count() > EXCERPT_CHARS {
+        text.chars().take(EXCERPT_CHARS).collect::<String>() + "…"
+    } else {
+        text
+    }
+}
+
+fn heading_level(line: &str) -> Option<usize> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    (level > 0 && level <= 6 && line[level..].starts_with(' ')).then_some(level)
+}
+
+// 行中的 ^块引用 标识
+fn block_ref(line: &str) -> Option<&str> {
+    let last = line.split_whitespace().last()?;
+    let id = last.strip_prefix('^')?;
+    (!id.is_empty() && id.chars().all(|c| c.is_alphanumeric() || c == '-')).then_some(id)
+}
+
+fn is_list_item(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    let digits = trimmed.chars().take_while(|c| c.is_ascii_digit()).count();
+    ["- ", "* ", "+ "].iter().any(|m| trimmed.starts_with(m))
+        || (digits > 0
+            && (trimmed[digits..].starts_with(". ") || trimmed[digits..].starts_with(") ")))
+}
+
+fn has_tag(line: &str) -> bool {
+    line.match_indices(TAG).any(|(i, _)| {
+        let before = line[..i].chars().next_back();
+        let after = line[i + TAG.len()..].chars().next();
+        before.is_none_or(char::is_whitespace)
+            && after.is_none_or(|c| !(c.is_alphanumeric() || c == '_' || c == '-' || c == '/'))
+    })
+}
+
+fn parse_items(relative: &str, content: &str) -> Vec<ParsedItem> {
+    if encryption::is_envelope(content) {
+        return Vec::new();
+    }
+    let mut items = Vec::new();
+    let stem = Path::new(relative)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let tagged_note = markdown::front_matter_list(content, "tags")
+        .iter()
+        .flat_map(|t| t.split([',', ' ']))
+        .any(|t| {
+            t.trim()
+                .trim_start_matches('#')
+                .eq_ignore_ascii_case("review")
+        });
+    if tagged_note {
+        let body = markdown::strip_front_matter(content);
+        items.push(ParsedItem {
+            id: relative.to_string(),
+            line: 0,
+            kind: "note",
+            title: markdown::front_matter_field(content, "title").unwrap_or(stem),
+            excerpt: excerpt(&markdown::to_plain_text(body)),
+        });
+    }
+
+    let code: HashSet<usize> = markdown::fenced_blocks(content)
+        .iter()
+        .flat_map(|b| b.start_line..=b.end_line)
+        .collect();
+    let lines: Vec<&str> = content.lines().collect();
+    let front_lines = content.len() - markdown::strip_front_matter(content).len();
+    let front_lines = content[..front_lines].lines().count();
+    for (i, line) in lines.iter().enumerate().skip(front_lines) {
+        if code.contains(&i) || !has_tag(line) {
+            continue;
+        }
+        // 标题带标签时复习整节，否则复习所在的段落或列表项
+        let (start, end) = match heading_level(line.trim_start()) {
+            Some(level) => {
+                let end = (i + 1..lines.len())
+                    .find(|&j| {
+                        !code.contains(&j)
+                            && heading_level(lines[j].trim_start()).is_some_and(|l| l <= level)
+                    })
+                    .unwrap_or(lines.len());
+                (i, end)
+            }
+            None if is_list_item(line) => {
+                // 列表项连同缩进更深的续行
+                let indent = line.len() - line.trim_start().len();
+                let end = (i + 1..lines.len())
+                    .find(|&j| {
+                        let l = lines[j];
+                        l.trim().is_empty() || l.len() - l.trim_start().len() <= indent
+                    })
+                    .unwrap_or(lines.len());
+                (i, end)
+            }
+            None => {
+                let start = (0..i)
+                    .rev()
+                    .find(|&j| lines[j].trim().is_empty() || j < front_lines)
+                    .map_or(0, |j| j + 1);
+                let end = (i + 1..lines.len())
+                    .find(|&j| lines[j].trim().is_empty())
+                    .unwrap_or(lines.len());
+                (start, end)
+            }
+        };
+        let block = lines[start..end].join("\n");
+        let first = lines[start].trim();
+        let anchor = lines[start..end]
+            .iter()
+            .find_map(|l| block_ref(l))
+            .map(|id| format!("^{}", id))
+            .unwrap_or_else(|| format!("{:016x}", fnv(&excerpt(first))));
+        let title = match heading_level(first) {
+            Some(level) => excerpt(first[level..].trim()),
+            None => excerpt(&markdown::to_plain_text(
+                first.trim_start_matches(['-', '*', '+', ' ']),
+            )),
+        };
+        items.push(ParsedItem {
+            id: format!("{}#{}", relative, anchor),
+            line: start,
+            kind: "block",
+            title,
+            excerpt: excerpt(&markdown::to_plain_text(&block)),
+        });
+    }
+    // 同一段落中有多个标签时只算一次
+    let mut seen = HashSet::new();
+    items.retain(|item| seen.insert(item.id.clone()));
+    items
+}
+
+fn index_note(conn: &Connection, relative: &str, content: &str, mtime: i64) -> Result<(), String> {
+    conn.execute("DELETE FROM review_items WHERE path = ?1", [relative])
+        .map_err(|e| e.to_string())?;
+    for item in parse_items(relative, content) {
+        conn.execute(
+            "INSERT OR REPLACE INTO review_items (id, path, line, kind, title, excerpt)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                item.id,
+                relative,
+                item.line,
+                item.kind,
+                item.title,
+                item.excerpt
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    conn.execute(
+        "INSERT OR REPLACE INTO review_notes (path, mtime) VALUES (?1, ?2)",
+        params![relative, mtime],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// 只重新解析修改过的笔记；笔记删除后其项目和复习记录一并清理
+fn refresh(root: &Path) -> Result<Connection, String> {
+    let conn = index::open(root)?;
+    ensure_schema(&conn)?;
+
+    let mut existing: HashMap<String, i64> = HashMap::new();
+    {
+        let mut stmt = conn
+            .prepare("SELECT path, mtime FROM review_notes")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
+            .map_err(|e| e.to_string())?;
+        existing.extend(rows.flatten());
+    }
+
+    let mut current = HashSet::new();
+    for file in workspace::note_files(root) {
+        let relative = workspace::relative_path(root, &file);
+        let mtime = workspace::modified_secs(&file);
+        if existing.get(&relative) != Some(&mtime) {
+            let content = fs::read_to_string(&file).unwrap_or_default();
+            index_note(&conn, &relative, &content, mtime)?;
+        }
+        current.insert(relative);
+    }
+    for path in existing.keys().filter(|p| !current.contains(*p)) {
+        conn.execute("DELETE FROM review_items WHERE path = ?1", [path])
+            .map_err(|e| e.to_string())?;
+        conn.execute("DELETE FROM review_notes WHERE path = ?1", [path])
+            .map_err(|e| e.to_string())?;
+    }
+    conn.execute(
+        "DELETE FROM review_schedule WHERE id NOT IN (SELECT id FROM review_items)
+            AND substr(id, 1, instr(id || '#', '#') - 1) NOT IN (SELECT path FROM review_notes)",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(conn)
+}
+
+// SM-2：score 为 0~5，低于 3 重新开始；间隔依次为 1 天、6 天，之后乘以难度系数
+fn schedule(repetitions: i64, interval: i64, ease: f64, score: u8) -> (i64, i64, f64) {
+    let q = score.min(5) as f64;
+    let ease = (ease + 0.1 - (5.0 - q) * (0.08 + (5.0 - q) * 0.02)).max(MIN_EASE);
+    if score < 3 {
+        return (0, 1, ease);
+    }
+    let interval = match repetitions {
+        0 => 1,
+        1 => 6,
+        _ => ((interval as f64) * ease).round() as i64,
+    };
+    (repetitions + 1, interval.max(1), ease)
+}
+
+fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+// 到期（含新项目）的复习，按到期日排列，新项目在已到期的之后；date 默认今天
+#[tauri::command]
+pub async fn get_due_reviews(
+    workspace: String,
+    date: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<ReviewItem>, MobiError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = PathBuf::from(&workspace);
+        let date = match date {
+            Some(date) => NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+                .map_err(|_| format!("无效的日期: {}", date))?,
+            None => today(),
+        };
+        let date = date.format("%Y-%m-%d").to_string();
+        let conn = refresh(&root)?;
+        let config = workspace::settings(&root);
+        let mut stmt = conn
+            .prepare(
+                "SELECT i.id, i.path, i.line, i.kind, i.title, i.excerpt,
+                        s.due, s.interval, s.repetitions, s.ease
+                 FROM review_items i LEFT JOIN review_schedule s ON s.id = i.id
+                 WHERE s.due IS NULL OR s.due <= ?1
+                 ORDER BY s.due IS NULL, s.due, i.path, i.line",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([&date], |r| {
+                let due: Option<String> = r.get(6)?;
+                Ok(ReviewItem {
+                    id: r.get(0)?,
+                    path: r.get(1)?,
+                    line: r.get(2)?,
+                    kind: r.get(3)?,
+                    title: r.get(4)?,
+                    excerpt: r.get(5)?,
+                    new: due.is_none(),
+                    due: due.unwrap_or_else(|| date.clone()),
+                    interval: r.get::<_, Option<i64>>(7)?.unwrap_or(0),
+                    repetitions: r.get::<_, Option<i64>>(8)?.unwrap_or(0),
+                    ease: r.get::<_, Option<f64>>(9)?.unwrap_or(INITIAL_EASE),
+                })
+            })
+            .map_err(|e| e.to_string())?;
+        // 归档的笔记不再安排复习
+        Ok(rows
+            .flatten()
+            .filter(|item| !config.is_archived(&item.path))
+            .take(limit.unwrap_or(usize::MAX))
+            .map(|item| ReviewItem {
+                path: root.join(&item.path).to_string_lossy().to_string(),
+                ..item
+            })
+            .collect())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// score：0 完全不记得 ~ 5 毫不费力
+#[tauri::command]
+pub fn grade_review(workspace: String, id: String, score: u8) -> Result<ReviewSchedule, MobiError> {
+    if score > 5 {
+        return Err("评分应在 0 到 5 之间".into());
+    }
+    let conn = index::open(Path::new(&workspace))?;
+    ensure_schema(&conn)?;
+    let known: bool = conn
+        .query_row("SELECT 1 FROM review_items WHERE id = ?1", [&id], |_| {
+            Ok(true)
+        })
+        .optional()
+        .map_err(|e| e.to_string())?
+        .unwrap_or(false);
+    if !known {
+        return Err("复习项目不存在".into());
+    }
+    let (repetitions, interval, ease) = conn
+        .query_row(
+            "SELECT repetitions, interval, ease FROM review_schedule WHERE id = ?1",
+            [&id],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .unwrap_or((0, 0, INITIAL_EASE));
+    let (repetitions, interval, ease) = schedule(repetitions, interval, ease, score);
+    let today = today();
+    let due = (today + Duration::days(interval))
+        .format("%Y-%m-%d")
+        .to_string();
+    conn.execute(
+        "INSERT OR REPLACE INTO review_schedule (id, repetitions, interval, ease, due, reviewed)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            id,
+            repetitions,
+            interval,
+            ease,
+            due,
+            today.format("%Y-%m-%d").to_string()
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(ReviewSchedule {
+        due,
+        interval,
+        repetitions,
+        ease,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_schedule(actual: (i64, i64, f64), expected: (i64, i64, f64)) {
+        assert_eq!((actual.0, actual.1), (expected.0, expected.1));
+        assert!((actual.2 - expected.2).abs() < 1e-9, "{:?}", actual);
+    }
+
+    #[test]
+    fn schedules_by_sm2() {
+        let first = schedule(0, 0, INITIAL_EASE, 5);
+        assert_schedule(first, (1, 1, 2.6));
+        let second = schedule(first.0, first.1, first.2, 4);
+        assert_schedule(second, (2, 6, 2.6));
+        assert_schedule(schedule(second.0, second.1, second.2, 3), (3, 15, 2.46));
+    }
+
+    // 低于 3 分从头开始，难度系数不低于下限
+    #[test]
+    fn restarts_on_lapse() {
+        assert_schedule(schedule(3, 15, 2.46, 1), (0, 1, 1.92));
+        assert_schedule(schedule(5, 40, MIN_EASE, 0), (0, 1, MIN_EASE));
+    }
+
+    #[test]
+    fn matches_whole_tag_only() {
+        assert!(has_tag("复习 #review"));
+        assert!(has_tag("#review, 其他"));
+        for line in ["#reviewer", "#review/later", "a#review", "#review-2"] {
+            assert!(!has_tag(line), "{}", line);
+        }
+    }
+
+    #[test]
+    fn parses_review_items() {
+        let content = "---\ntags: [review]\ntitle: 笔记\n---\n\n## 一节 #review\n正文\n### 小节\n内容\n## 下一节\n\n第一行\n第二行 #review ^abc\n\n- 条目 #review\n  续行\n- 其他\n\n```\n#review\n```\n";
+        let items = parse_items("dir/n.md", content);
+        let summary: Vec<(&str, usize, &str)> = items
+            .iter()
+            .map(|i| (i.id.as_str(), i.line, i.kind))
+            .collect();
+        assert_eq!(summary.len(), 4);
+        assert_eq!(summary[0], ("dir/n.md", 0, "note"));
+        assert_eq!(items[0].title, "笔记");
+        // 标题带标签时复习整节，包括下级标题
+        assert_eq!((summary[1].1, summary[1].2), (5, "block"));
+        assert_eq!(items[1].title, "一节");
+        assert_eq!(items[1].excerpt, "一节 正文 小节 内容");
+        // 有块引用时用它作标识，移动段落后仍能对应
+        assert_eq!(summary[2], ("dir/n.md#^abc", 11, "block"));
+        assert_eq!(items[2].excerpt, "第一行 第二行");
+        assert_eq!((summary[3].1, items[3].excerpt.as_str()), (14, "条目 续行"));
+    }
+}
@@ -0,0 +1,360 @@
+// 代码块执行：用配置好的解释器在子进程中运行围栏代码块，输出通过事件实时推送
+// 子进程在临时目录、精简环境变量下运行并受超时限制，但这不是安全沙箱，只应运行自己信任的代码
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, BufReader, Read};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::error::{MobiError, MutexExt};
+use crate::{markdown, settings};
+
+// 单次运行最多回传的输出字节数
+const MAX_OUTPUT: usize = 256 * 1024;
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct Interpreter {
+    command: String,
+    // {file} 会替换为代码文件路径
+    args: Vec<String>,
+    extension: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct RunnerSettings {
+    timeout_secs: u64,
+    interpreters: HashMap<String, Interpreter>,
+}
+
+impl Default for RunnerSettings {
+    fn default() -> Self {
+        let interpreter = |command: &str, args: &[&str], extension: &str| Interpreter {
+            command: command.into(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+            extension: extension.into(),
+        };
+        let mut interpreters = HashMap::new();
+        interpreters.insert("python".into(), interpreter("python3", &["{file}"], "py"));
+        interpreters.insert("node".into(), interpreter("node", &["{file}"], "js"));
+        interpreters.insert("bash".into(), interpreter("bash", &["{file}"], "sh"));
+        interpreters.insert(
+            "rust".into(),
+            interpreter(
+                "sh",
+                &[
+                    "-c",
+                    "rustc --edition 2021 -o main \"$0\" && ./main",
+                    "{file}",
+                ],
+                "rs",
+            ),
+        );
+        RunnerSettings {
+            timeout_secs: 30,
+            interpreters,
+        }
+    }
+}
+
+// 代码块语言名的常见别名
+fn canonical_language(lang: &str) -> String {
+    match lang.to_ascii_lowercase().as_str() {
+        "py" | "python3" => "python".into(),
+        "js" | "javascript" => "node".into(),
+        "sh" | "shell" | "zsh" => "bash".into(),
+        "rs" => "rust".into(),
+        other => other.into(),
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OutputEvent {
+    run_id: String,
+    stream: &'static str,
+    data: String,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExitEvent {
+    run_id: String,
+    code: Option<i32>,
+    timed_out: bool,
+    cancelled: bool,
+    duration_ms: u128,
+    // 合并后的输出，便于插入到代码块下方
+    output: String,
+}
+
+struct Run {
+    child: Arc<Mutex<Child>>,
+    cancelled: Arc<Mutex<bool>>,
+}
+
+#[derive(Default)]
+pub struct RunnerState {
+    runs: Mutex<HashMap<String, Run>>,
+}
+
+fn scratch_dir() -> Result<PathBuf, String> {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    let dir = std::env::temp_dir().join(format!("mobi-run-{}-{}", std::process::id(), nanos));
+    fs::create_dir_all(&dir).map_err(|e| format!("创建临时目录失败: {}", e))?;
+    Ok(dir)
+}
+
+fn pipe_output(
+    app: AppHandle,
+    run_id: String,
+    stream: &'static str,
+    reader: impl Read + Send + 'static,
+    collected: Arc<Mutex<String>>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut reader = BufReader::new(reader);
+        let mut line = String::new();
+        while reader.read_line(&mut line).unwrap_or(0) > 0 {
+            {
+                let mut all = collected.locked();
+                if all.len() >= MAX_OUTPUT {
+                    line.clear();
+                    continue;
+                }
+                all.push_str(&line);
+            }
+            let _ = app.emit(
+                "code-run-output",
+                OutputEvent {
+                    run_id: run_id.clone(),
+                    stream,
+                    data: line.clone(),
+                },
+            );
+            line.clear();
+        }
+    })
+}
+
+// 结束子进程及其启动的所有进程
+fn kill_tree(child: &mut Child) {
+    #[cfg(unix)]
+    unsafe {
+        libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+    }
+    #[cfg(windows)]
+    {
+        let _ = Command::new("taskkill")
+            .args(["/T", "/F", "/PID", &child.id().to_string()])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+    }
+    let _ = child.kill();
+}
+
+#[tauri::command]
+pub fn run_code_block(
+    app: AppHandle,
+    state: State<RunnerState>,
+    run_id: String,
+    language: String,
+    code: String,
+) -> Result<(), MobiError> {
+    let config: RunnerSettings = settings::load(&app, "runner");
+    let language = canonical_language(&language);
+    let interpreter = config
+        .interpreters
+        .get(&language)
+        .cloned()
+        .ok_or_else(|| format!("没有为 {} 配置解释器", language))?;
+
+    let dir = scratch_dir()?;
+    let file = dir.join(format!("main.{}", interpreter.extension));
+    fs::write(&file, &code).map_err(|e| format!("写入代码文件失败: {}", e))?;
+    let file = file.to_string_lossy().to_string();
+
+    // 只保留运行解释器所需的环境变量
+    let mut command = Command::new(&interpreter.command);
+    command
+        .args(interpreter.args.iter().map(|a| a.replace("{file}", &file)))
+        .current_dir(&dir)
+        .env_clear()
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    for key in ["PATH", "HOME", "LANG", "TMPDIR"] {
+        if let Ok(value) = std::env::var(key) {
+            command.env(key, value);
+        }
+    }
+    // 解释器可能再启动子进程（如 sh -c "rustc … && ./main"），放进单独的进程组以便一起结束
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    let mut child = command.spawn().map_err(|e| {
+        let _ = fs::remove_dir_all(&dir);
+        format!("启动 {} 失败: {}", interpreter.command, e)
+    })?;
+
+    let collected = Arc::new(Mutex::new(String::new()));
+    let stdout = child.stdout.take().map(|out| {
+        pipe_output(
+            app.clone(),
+            run_id.clone(),
+            "stdout",
+            out,
+            collected.clone(),
+        )
+    });
+    let stderr = child.stderr.take().map(|err| {
+        pipe_output(
+            app.clone(),
+            run_id.clone(),
+            "stderr",
+            err,
+            collected.clone(),
+        )
+    });
+
+    let child = Arc::new(Mutex::new(child));
+    let cancelled = Arc::new(Mutex::new(false));
+    state.runs.locked().insert(
+        run_id.clone(),
+        Run {
+            child: child.clone(),
+            cancelled: cancelled.clone(),
+        },
+    );
+
+    let timeout = Duration::from_secs(config.timeout_secs.max(1));
+    thread::spawn(move || {
+        let started = Instant::now();
+        let mut timed_out = false;
+        let status = loop {
+            if let Ok(Some(status)) = child.locked().try_wait() {
+                break Some(status);
+            }
+            if started.elapsed() >= timeout {
+                timed_out = true;
+                kill_tree(&mut child.locked());
+            }
+            if timed_out || *cancelled.locked() {
+                break child.locked().wait().ok();
+            }
+            thread::sleep(Duration::from_millis(50));
+        };
+
+        // 被结束时仍可能有没结束的孙进程占着管道，不再等待读取线程
+        if !timed_out && !*cancelled.locked() {
+            for reader in [stdout, stderr].into_iter().flatten() {
+                let _ = reader.join();
+            }
+        }
+        let _ = fs::remove_dir_all(&dir);
+        app.state::<RunnerState>().runs.locked().remove(&run_id);
+
+        let output = collected.locked().clone();
+        let _ = app.emit(
+            "code-run-exit",
+            ExitEvent {
+                run_id,
+                code: status.and_then(|s| s.code()),
+                timed_out,
+                cancelled: *cancelled.locked(),
+                duration_ms: started.elapsed().as_millis(),
+                output,
+            },
+        );
+    });
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn cancel_code_run(state: State<RunnerState>, run_id: String) {
+    if let Some(run) = state.runs.locked().get(&run_id) {
+        *run.cancelled.locked() = true;
+        kill_tree(&mut run.child.locked());
+    }
+}
+
+// 把运行结果作为 ```output 代码块插到第 block_index 个代码块下方，已有的结果块会被替换
+#[tauri::command]
+pub fn insert_code_output(
+    content: String,
+    block_index: usize,
+    output: String,
+) -> Result<String, MobiError> {
+    let blocks = markdown::fenced_blocks(&content);
+    let block = blocks.get(block_index).ok_or("找不到对应的代码块")?;
+
+    let lines: Vec<&str> = content.lines().collect();
+    let mut resume = block.end_line + 1;
+    if let Some(next) = blocks.get(block_index + 1) {
+        let only_blank_between = lines[block.end_line + 1..next.start_line]
+            .iter()
+            .all(|l| l.trim().is_empty());
+        if next.lang == "output" && only_blank_between {
+            resume = next.end_line + 1;
+        }
+    }
+
+    let mut result: Vec<String> = lines[..=block.end_line]
+        .iter()
+        .map(|l| l.to_string())
+        .collect();
+    // 围栏比输出中最长的连续反引号多一个，输出中的 ``` 不会提前结束代码块
+    let longest = output.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest.max(2) + 1);
+    result.push(String::new());
+    result.push(format!("{}output", fence));
+    result.extend(output.trim_end().lines().map(|l| l.to_string()));
+    result.push(fence);
+    result.extend(
+        lines[resume.min(lines.len())..]
+            .iter()
+            .map(|l| l.to_string()),
+    );
+
+    let mut text = result.join("\n");
+    if content.ends_with('\n') {
+        text.push('\n');
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 输出中的 ``` 不会提前结束结果块，再次运行时整块替换
+    #[test]
+    fn fences_output_longer_than_its_backticks() {
+        let content = "```python\nprint(x)\n```\n\n正文\n";
+        let once = insert_code_output(content.into(), 0, "a\n```\nb\n".into()).unwrap();
+        assert_eq!(
+            once,
+            "```python\nprint(x)\n```\n\n````output\na\n```\nb\n````\n\n正文\n"
+        );
+        let twice = insert_code_output(once, 0, "c".into()).unwrap();
+        assert_eq!(
+            twice,
+            "```python\nprint(x)\n```\n\n```output\nc\n```\n\n正文\n"
+        );
+    }
+}
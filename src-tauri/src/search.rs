@@ -0,0 +1,624 @@
+// 全文搜索、反向链接和标签索引：持久化在工作区索引库中，重新打开工作区时只重新解析修改时间或大小变了、且内容哈希也变了的笔记
+// 打开工作区时在后台预热，之后由工作区监视按变化的路径增量更新，完成后发出 search-index-updated；正文用 FTS5 trigram 分词，中文不需要额外分词
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+
+use pulldown_cmark::{Event, LinkType, Parser, Tag, TagEnd, TextMergeStream};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Emitter};
+
+use crate::aliases::{self, Names};
+use crate::error::{self, MobiError, MutexExt};
+use crate::{encryption, index, markdown, stats, workspace};
+
+// 预热和命令同时更新同一个索引时排队
+static LOCK: Mutex<()> = Mutex::new(());
+
+const DEFAULT_LIMIT: usize = 50;
+// 摘要在命中位置前后各取的字数
+const SNIPPET_CHARS: usize = 40;
+
+#[derive(Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexSummary {
+    pub workspace: String,
+    pub notes: usize,
+    // 重新解析的笔记数
+    pub updated: usize,
+    pub removed: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    pub path: String,
+    pub title: String,
+    pub snippet: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagCount {
+    pub tag: String,
+    pub count: usize,
+}
+
+struct Stored {
+    id: i64,
+    mtime: i64,
+    size: i64,
+    hash: String,
+}
+
+struct NoteLink {
+    line: usize,
+    // Markdown 链接为解析后相对工作区的路径，wiki 链接为 [[ ]] 中的原文
+    target: String,
+    wiki: bool,
+}
+
+#[derive(Default)]
+struct ParsedNote {
+    title: String,
+    body: String,
+    links: Vec<NoteLink>,
+    tags: Vec<String>,
+}
+
+fn ensure_schema(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS search_notes (
+            id INTEGER PRIMARY KEY,
+            path TEXT NOT NULL UNIQUE,
+            mtime INTEGER NOT NULL,
+            size INTEGER NOT NULL,
+            hash TEXT NOT NULL,
+            title TEXT NOT NULL
+        );
+        CREATE VIRTUAL TABLE IF NOT EXISTS search_fts USING fts5(
+            title, body, tokenize = 'trigram'
+        );
+        CREATE TABLE IF NOT EXISTS search_links (
+            path TEXT NOT NULL,
+            line INTEGER NOT NULL,
+            target TEXT NOT NULL,
+            wiki INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_search_links_path ON search_links(path);
+        CREATE INDEX IF NOT EXISTS idx_search_links_target ON search_links(target);
+        CREATE TABLE IF NOT EXISTS search_tags (
+            path TEXT NOT NULL,
+            tag TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_search_tags_path ON search_tags(path);",
+    )
+    .map_err(|e| format!("初始化搜索索引失败: {}", e))
+}
+
+// 标题取 front matter 的 title，没有时用文件名；加密笔记只索引标题
+fn parse(root: &Path, file: &Path, content: &str) -> ParsedNote {
+    let title = markdown::front_matter_field(content, "title")
+        .filter(|t| !t.is_empty())
+        .or_else(|| file.file_stem().map(|s| s.to_string_lossy().to_string()))
+        .unwrap_or_default();
+    if encryption::is_envelope(content) {
+        return ParsedNote {
+            title,
+            ..Default::default()
+        };
+    }
+    let body = markdown::strip_front_matter(content);
+    let start = content.len() - body.len();
+
+    let mut links = Vec::new();
+    let mut line = content[..start].matches('\n').count();
+    let mut counted = 0;
+    for (event, range) in Parser::new_ext(body, aliases::wiki_options()).into_offset_iter() {
+        let (Event::Start(Tag::Link {
+            link_type,
+            dest_url,
+            ..
+        })
+        | Event::Start(Tag::Image {
+            link_type,
+            dest_url,
+            ..
+        })) = event
+        else {
+            continue;
+        };
+        line += body[counted..range.start].matches('\n').count();
+        counted = range.start;
+        if matches!(link_type, LinkType::WikiLink { .. }) {
+            links.push(NoteLink {
+                line,
+                target: dest_url.to_string(),
+                wiki: true,
+            });
+        } else if let Some(target) = workspace::resolve_note_link(root, file, &dest_url) {
+            links.push(NoteLink {
+                line,
+                target: workspace::relative_path(root, &target),
+                wiki: false,
+            });
+        }
+    }
+
+    let mut tags = stats::front_matter_tags(content);
+    let mut in_code_block = false;
+    for event in TextMergeStream::new(Parser::new_ext(body, aliases::wiki_options())) {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+            Event::End(TagEnd::CodeBlock) => in_code_block = false,
+            Event::Text(text) if !in_code_block => stats::inline_tags(&text, &mut tags),
+            _ => {}
+        }
+    }
+    let mut seen = HashSet::new();
+    tags.retain(|t| !t.is_empty() && seen.insert(t.to_lowercase()));
+
+    ParsedNote {
+        title,
+        body: markdown::to_plain_text(body),
+        links,
+        tags,
+    }
+}
+
+fn remove_rows(conn: &Connection, id: i64, relative: &str) -> Result<(), String> {
+    conn.execute("DELETE FROM search_fts WHERE rowid = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM search_links WHERE path = ?1", [relative])
+        .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM search_tags WHERE path = ?1", [relative])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn write_note(
+    conn: &Connection,
+    stored: Option<&Stored>,
+    relative: &str,
+    (mtime, size, hash): (i64, i64, &str),
+    note: &ParsedNote,
+) -> Result<(), String> {
+    let id = match stored {
+        Some(stored) => {
+            remove_rows(conn, stored.id, relative)?;
+            conn.execute(
+                "UPDATE search_notes SET mtime = ?2, size = ?3, hash = ?4, title = ?5 WHERE id = ?1",
+                params![stored.id, mtime, size, hash, note.title],
+            )
+            .map_err(|e| e.to_string())?;
+            stored.id
+        }
+        None => {
+            conn.execute(
+                "INSERT INTO search_notes (path, mtime, size, hash, title) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![relative, mtime, size, hash, note.title],
+            )
+            .map_err(|e| e.to_string())?;
+            conn.last_insert_rowid()
+        }
+    };
+    conn.execute(
+        "INSERT INTO search_fts (rowid, title, body) VALUES (?1, ?2, ?3)",
+        params![id, note.title, note.body],
+    )
+    .map_err(|e| e.to_string())?;
+    for link in &note.links {
+        conn.execute(
+            "INSERT INTO search_links (path, line, target, wiki) VALUES (?1, ?2, ?3, ?4)",
+            params![relative, link.line, link.target, link.wiki],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    for tag in &note.tags {
+        conn.execute(
+            "INSERT INTO search_tags (path, tag) VALUES (?1, ?2)",
+            params![relative, tag],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+// 与索引中的记录对比：修改时间和大小都没变时跳过；变了但内容哈希相同（如同步盘重写）时只更新记录；返回是否重新解析
+fn reconcile(
+    conn: &Connection,
+    root: &Path,
+    file: &Path,
+    relative: &str,
+    stored: Option<&Stored>,
+) -> Result<bool, String> {
+    let mtime = workspace::modified_secs(file);
+    let size = fs::metadata(file).map(|m| m.len() as i64).unwrap_or(0);
+    if stored.is_some_and(|s| s.mtime == mtime && s.size == size) {
+        return Ok(false);
+    }
+    let bytes = fs::read(file).unwrap_or_default();
+    let hash = format!("{:x}", Sha256::digest(&bytes));
+    if let Some(stored) = stored.filter(|s| s.hash == hash) {
+        conn.execute(
+            "UPDATE search_notes SET mtime = ?2, size = ?3 WHERE id = ?1",
+            params![stored.id, mtime, size],
+        )
+        .map_err(|e| e.to_string())?;
+        return Ok(false);
+    }
+    let note = parse(root, file, &String::from_utf8_lossy(&bytes));
+    write_note(conn, stored, relative, (mtime, size, &hash), &note)?;
+    Ok(true)
+}
+
+// 查询直接读取索引，不扫描工作区：打开工作区时对齐一次，之后由监视器按变化的路径更新
+fn open(root: &Path) -> Result<Connection, String> {
+    let conn = index::open(root)?;
+    ensure_schema(&conn)?;
+    Ok(conn)
+}
+
+fn stored(conn: &Connection, relative: &str) -> Option<Stored> {
+    conn.query_row(
+        "SELECT id, mtime, size, hash FROM search_notes WHERE path = ?1",
+        [relative],
+        |r| {
+            Ok(Stored {
+                id: r.get(0)?,
+                mtime: r.get(1)?,
+                size: r.get(2)?,
+                hash: r.get(3)?,
+            })
+        },
+    )
+    .ok()
+}
+
+fn delete_note(conn: &Connection, id: i64, relative: &str) -> Result<(), String> {
+    remove_rows(conn, id, relative)?;
+    conn.execute("DELETE FROM search_notes WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// 删除 relative 本身以及以它为目录的所有笔记，返回删除的篇数
+fn delete_under(conn: &Connection, relative: &str) -> Result<usize, String> {
+    let pattern = format!(
+        "{}/%",
+        relative
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_")
+    );
+    let rows: Vec<(i64, String)> = {
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, path FROM search_notes WHERE path = ?1 OR path LIKE ?2 ESCAPE '\\'",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![relative, pattern], |r| Ok((r.get(0)?, r.get(1)?)))
+            .map_err(|e| e.to_string())?;
+        rows.flatten().collect()
+    };
+    for (id, path) in &rows {
+        delete_note(conn, *id, path)?;
+    }
+    Ok(rows.len())
+}
+
+// 增量更新索引，返回打开的连接和本次的统计
+pub fn refresh(root: &Path) -> Result<(Connection, IndexSummary), String> {
+    let _guard = LOCK.locked();
+    let conn = open(root)?;
+
+    let mut existing: HashMap<String, Stored> = HashMap::new();
+    {
+        let mut stmt = conn
+            .prepare("SELECT path, id, mtime, size, hash FROM search_notes")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |r| {
+                Ok((
+                    r.get(0)?,
+                    Stored {
+                        id: r.get(1)?,
+                        mtime: r.get(2)?,
+                        size: r.get(3)?,
+                        hash: r.get(4)?,
+                    },
+                ))
+            })
+            .map_err(|e| e.to_string())?;
+        existing.extend(rows.flatten());
+    }
+
+    let mut summary = IndexSummary {
+        workspace: root.to_string_lossy().to_string(),
+        ..Default::default()
+    };
+    // 首次建索引时几千篇笔记的写入放在一个事务里
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    for file in workspace::note_files(root) {
+        let relative = workspace::relative_path(root, &file);
+        if reconcile(&tx, root, &file, &relative, existing.get(&relative))? {
+            summary.updated += 1;
+        }
+        existing.remove(&relative);
+        summary.notes += 1;
+    }
+    for (relative, stored) in &existing {
+        delete_note(&tx, stored.id, relative)?;
+        summary.removed += 1;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok((conn, summary))
+}
+
+// 打开工作区时在后台对齐索引
+pub fn warm(app: &AppHandle, root: PathBuf) {
+    let app = app.clone();
+    thread::spawn(move || {
+        if let Ok((_, summary)) = refresh(&root) {
+            let _ = app.emit("search-index-updated", summary);
+        }
+    });
+}
+
+// 监视器报告的路径（已按忽略规则过滤）：笔记文件重新对齐，已不存在的路径连同其下的笔记一起删除
+// 出现目录时（如整个文件夹移入）扫描一遍工作区，只对齐该目录下的笔记
+fn update_paths(root: &Path, paths: &[PathBuf]) -> Result<IndexSummary, String> {
+    let _guard = LOCK.locked();
+    let conn = open(root)?;
+    let mut summary = IndexSummary {
+        workspace: root.to_string_lossy().to_string(),
+        ..Default::default()
+    };
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let mut dirs = Vec::new();
+    for path in paths {
+        let relative = workspace::relative_path(root, path);
+        // 不在工作区中，或位于扫描时跳过的隐藏目录中
+        if !path.starts_with(root) || relative.split('/').any(|c| c.starts_with('.')) {
+            continue;
+        }
+        if path.is_dir() {
+            dirs.push(path);
+        } else if path.is_file() && workspace::is_note(path) {
+            if reconcile(&tx, root, path, &relative, stored(&tx, &relative).as_ref())? {
+                summary.updated += 1;
+            }
+        } else if !path.exists() {
+            summary.removed += delete_under(&tx, &relative)?;
+        }
+    }
+    if !dirs.is_empty() {
+        for file in workspace::note_files(root) {
+            if dirs.iter().any(|dir| file.starts_with(dir)) {
+                let relative = workspace::relative_path(root, &file);
+                if reconcile(&tx, root, &file, &relative, stored(&tx, &relative).as_ref())? {
+                    summary.updated += 1;
+                }
+            }
+        }
+    }
+    summary.notes = tx
+        .query_row("SELECT COUNT(*) FROM search_notes", [], |r| r.get(0))
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(summary)
+}
+
+// 由监视线程调用，有改动时同样发出 search-index-updated
+pub fn apply_changes(app: &AppHandle, root: &Path, paths: &[PathBuf]) {
+    match update_paths(root, paths) {
+        Ok(summary) if summary.updated + summary.removed > 0 => {
+            let _ = app.emit("search-index-updated", summary);
+        }
+        Ok(_) => {}
+        Err(e) => error::report(app, "更新搜索索引", e),
+    }
+}
+
+// 命中第一个词前后的一段正文
+fn snippet(body: &str, terms: &[String]) -> String {
+    let lower = body.to_lowercase();
+    let chars: Vec<char> = body.chars().collect();
+    // 转小写后长度变了时无法对应位置，取开头
+    let at = terms
+        .iter()
+        .find_map(|term| lower.find(term.as_str()))
+        .filter(|_| lower.len() == body.len())
+        .map_or(0, |byte| body[..byte].chars().count());
+    let start = at.saturating_sub(SNIPPET_CHARS);
+    let end = (at + SNIPPET_CHARS * 2).min(chars.len());
+    let mut text: String = chars[start..end]
+        .iter()
+        .map(|&c| if c.is_whitespace() { ' ' } else { c })
+        .collect();
+    if start > 0 {
+        text.insert(0, '…');
+    }
+    if end < chars.len() {
+        text.push('…');
+    }
+    text
+}
+
+// 空格分隔的词都要出现；每个词都不少于 3 个字时走 FTS5，否则逐条 LIKE 匹配；已归档的笔记按工作区设置隐藏
+#[tauri::command]
+pub async fn search_notes(
+    workspace: String,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<SearchHit>, MobiError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = PathBuf::from(&workspace);
+        let terms: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+        let conn = open(&root)?;
+        let config = workspace::settings(&root);
+
+        let (sql, args) = if terms.iter().all(|t| t.chars().count() >= 3) {
+            let phrase = terms
+                .iter()
+                .map(|t| format!("\"{}\"", t.replace('"', "\"\"")))
+                .collect::<Vec<_>>()
+                .join(" ");
+            (
+                "SELECT n.path, n.title, f.body FROM search_fts f JOIN search_notes n ON n.id = f.rowid
+                 WHERE search_fts MATCH ?1 ORDER BY bm25(search_fts, 5.0, 1.0)"
+                    .to_string(),
+                vec![phrase],
+            )
+        } else {
+            let conditions = (1..=terms.len())
+                .map(|i| format!("(f.title || ' ' || f.body) LIKE ?{} ESCAPE '\\'", i))
+                .collect::<Vec<_>>()
+                .join(" AND ");
+            let patterns = terms
+                .iter()
+                .map(|t| {
+                    let escaped = t.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+                    format!("%{}%", escaped)
+                })
+                .collect();
+            (
+                format!(
+                    "SELECT n.path, n.title, f.body FROM search_fts f JOIN search_notes n ON n.id = f.rowid
+                     WHERE {} ORDER BY n.path",
+                    conditions
+                ),
+                patterns,
+            )
+        };
+        let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(rusqlite::params_from_iter(&args), |r| {
+                Ok((
+                    r.get::<_, String>(0)?,
+                    r.get::<_, String>(1)?,
+                    r.get::<_, String>(2)?,
+                ))
+            })
+            .map_err(|e| format!("搜索失败: {}", e))?;
+        Ok(rows
+            .flatten()
+            .filter(|(relative, _, _)| !config.hides(relative))
+            .take(limit.unwrap_or(DEFAULT_LIMIT))
+            .map(|(relative, title, body)| SearchHit {
+                path: root.join(&relative).to_string_lossy().to_string(),
+                title,
+                snippet: snippet(&body, &terms),
+            })
+            .collect())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// 链接到 target（相对路径）的笔记和行号：Markdown 链接按解析后的路径比较，wiki 链接按当前的文件名和别名解析
+pub fn backlinks(root: &Path, names: &Names, target: &str) -> Result<Vec<(String, usize)>, String> {
+    let (conn, _) = refresh(root)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT path, line, target, wiki FROM search_links
+             WHERE wiki = 1 OR target = ?1 ORDER BY path, line",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([target], |r| {
+            Ok((
+                r.get::<_, String>(0)?,
+                r.get::<_, usize>(1)?,
+                r.get::<_, String>(2)?,
+                r.get::<_, bool>(3)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+    let target_path = root.join(target);
+    let mut seen = HashSet::new();
+    Ok(rows
+        .flatten()
+        .filter(|(source, _, link, wiki)| {
+            source != target
+                && (!*wiki
+                    || names.resolve_link(link, Some(&root.join(source))).as_ref()
+                        == Some(&target_path))
+        })
+        // 同一行多次链接到同一篇笔记只记一次
+        .filter(|(source, line, _, _)| seen.insert((source.clone(), *line)))
+        .map(|(source, line, _, _)| (source, line))
+        .collect())
+}
+
+// 工作区中的标签及使用它的笔记数，大小写不同的算同一个，按笔记数从多到少
+#[tauri::command]
+pub async fn list_tags(workspace: String) -> Result<Vec<TagCount>, MobiError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let conn = open(Path::new(&workspace))?;
+        let mut stmt = conn
+            .prepare("SELECT tag, COUNT(*) FROM search_tags GROUP BY tag")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |r| Ok((r.get::<_, String>(0)?, r.get::<_, usize>(1)?)))
+            .map_err(|e| e.to_string())?;
+        let mut merged: HashMap<String, TagCount> = HashMap::new();
+        for (tag, count) in rows.flatten() {
+            merged
+                .entry(tag.to_lowercase())
+                .or_insert_with(|| TagCount { tag, count: 0 })
+                .count += count;
+        }
+        let mut tags: Vec<TagCount> = merged.into_values().collect();
+        tags.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.tag.cmp(&b.tag)));
+        Ok(tags)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// 带某个标签的笔记（绝对路径），tag 为 a 时 a/b 这样的子标签也算
+#[tauri::command]
+pub async fn notes_with_tag(workspace: String, tag: String) -> Result<Vec<String>, MobiError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = PathBuf::from(&workspace);
+        let conn = open(&root)?;
+        let tag = tag.trim().trim_start_matches('#').to_lowercase();
+        let prefix = format!("{}/", tag);
+        let mut stmt = conn
+            .prepare("SELECT path, tag FROM search_tags ORDER BY path")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |r| Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?)))
+            .map_err(|e| e.to_string())?;
+        let mut paths: Vec<String> = rows
+            .flatten()
+            .filter(|(_, t)| {
+                let t = t.to_lowercase();
+                t == tag || t.starts_with(&prefix)
+            })
+            .map(|(relative, _)| root.join(relative).to_string_lossy().to_string())
+            .collect();
+        paths.dedup();
+        Ok(paths)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// 手动对齐索引，例如在工作区外批量改动了笔记之后
+#[tauri::command]
+pub async fn refresh_search_index(workspace: String) -> Result<IndexSummary, MobiError> {
+    tauri::async_runtime::spawn_blocking(move || Ok(refresh(Path::new(&workspace))?.1))
+        .await
+        .map_err(|e| e.to_string())?
+}
@@ -0,0 +1,59 @@
+// 凭据存储：API Key、令牌等统一保存在系统钥匙串（Keychain / 凭据管理器 / Secret Service），不写入配置文件
+// 钥匙串条目的账户名为 "{service}-{key}"，如 AI 模块的 "ai-api-key"
+
+use serde_json::Value;
+use tauri::AppHandle;
+
+use crate::error::MobiError;
+use crate::settings;
+
+const KEYCHAIN_SERVICE: &str = "com.mobi.editor";
+
+fn entry(service: &str, key: &str) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(KEYCHAIN_SERVICE, &format!("{}-{}", service, key))
+        .map_err(|e| format!("无法访问钥匙串: {}", e))
+}
+
+// 读取凭据，未设置时返回 None
+pub fn get_secret(service: &str, key: &str) -> Result<Option<String>, String> {
+    match entry(service, key)?.get_password() {
+        Ok(value) => Ok(Some(value)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!("读取凭据失败: {}", e)),
+    }
+}
+
+// 旧版本把凭据明文存在 settings.json 的某个字段里，读取时迁移到钥匙串并从配置中删除
+pub fn migrate_setting(app: &AppHandle, section: &str, field: &str, service: &str, key: &str) {
+    let Value::Object(mut values) = settings::get_setting(app.clone(), section.to_string()) else {
+        return;
+    };
+    let Some(Value::String(value)) = values.remove(field) else {
+        return;
+    };
+    if !value.is_empty() && set_secret(service.into(), key.into(), value).is_err() {
+        return;
+    }
+    let _ = settings::save(app, section, &values);
+}
+
+// value 为空时删除该凭据
+#[tauri::command]
+pub fn set_secret(service: String, key: String, value: String) -> Result<(), MobiError> {
+    let entry = entry(&service, &key)?;
+    if value.is_empty() {
+        return match entry.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(format!("删除凭据失败: {}", e).into()),
+        };
+    }
+    entry
+        .set_password(&value)
+        .map_err(|e| format!("保存凭据失败: {}", e).into())
+}
+
+// 凭据本身只在后端使用，前端只能知道是否已设置
+#[tauri::command]
+pub fn has_secret(service: String, key: String) -> bool {
+    matches!(get_secret(&service, &key), Ok(Some(_)))
+}
@@ -0,0 +1,356 @@
+// 语义搜索：笔记分块后用本地嵌入模型向量化，向量存入工作区索引库
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::ai::ollama;
+use crate::error::MobiError;
+use crate::{encryption, index, workspace};
+
+// 单个分块的大致字符数
+const CHUNK_CHARS: usize = 800;
+// 每次请求嵌入模型的分块数
+const EMBED_BATCH: usize = 32;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexStats {
+    total: usize,
+    indexed: usize,
+    removed: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SemanticHit {
+    pub path: String,
+    pub heading: String,
+    pub snippet: String,
+    pub score: f32,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelatedNote {
+    path: String,
+    heading: String,
+    score: f32,
+}
+
+// 发给前端的 semantic-index-progress 事件
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct IndexProgress {
+    done: usize,
+    total: usize,
+    path: String,
+}
+
+struct Chunk {
+    heading: String,
+    text: String,
+}
+
+fn ensure_schema(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS embedding_notes (
+            path TEXT PRIMARY KEY,
+            mtime INTEGER NOT NULL,
+            model TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS embedding_chunks (
+            id INTEGER PRIMARY KEY,
+            path TEXT NOT NULL,
+            heading TEXT NOT NULL,
+            text TEXT NOT NULL,
+            vector BLOB NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_embedding_chunks_path ON embedding_chunks(path);",
+    )
+    .map_err(|e| format!("初始化语义索引失败: {}", e))
+}
+
+// 按标题和段落切分，过长的段落单独成块
+fn chunk_note(markdown: &str) -> Vec<Chunk> {
+    let mut chunks = Vec::new();
+    let mut heading = String::new();
+    let mut current = String::new();
+    let mut in_fence = false;
+
+    let mut flush = |heading: &str, current: &mut String| {
+        let text = current.trim();
+        if !text.is_empty() {
+            chunks.push(Chunk {
+                heading: heading.to_string(),
+                text: text.to_string(),
+            });
+        }
+        current.clear();
+    };
+
+    for line in markdown.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        }
+        if !in_fence && trimmed.starts_with('#') {
+            let title = trimmed.trim_start_matches('#');
+            if title.is_empty() || title.starts_with(' ') {
+                flush(&heading, &mut current);
+                heading = title.trim().to_string();
+                continue;
+            }
+        }
+        if line.trim().is_empty() && current.chars().count() >= CHUNK_CHARS {
+            flush(&heading, &mut current);
+            continue;
+        }
+        current.push_str(line);
+        current.push('\n');
+    }
+    flush(&heading, &mut current);
+
+    chunks
+}
+
+fn normalize(mut vector: Vec<f32>) -> Vec<f32> {
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|v| *v /= norm);
+    }
+    vector
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn to_blob(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+fn from_blob(blob: &[u8]) -> Vec<f32> {
+    blob.chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect()
+}
+
+fn snippet(text: &str) -> String {
+    let snippet: String = text.chars().take(160).collect();
+    snippet.replace('\n', " ")
+}
+
+// 增量构建：只重新向量化修改过的笔记，或嵌入模型变了的笔记
+#[tauri::command]
+pub async fn build_semantic_index(
+    app: AppHandle,
+    workspace: String,
+) -> Result<IndexStats, MobiError> {
+    let root = PathBuf::from(&workspace);
+    let model = ollama::embedding_model(&app);
+    let files = workspace::note_files(&root);
+
+    let conn = index::open(&root)?;
+    ensure_schema(&conn)?;
+
+    let mut existing: HashMap<String, (i64, String)> = HashMap::new();
+    {
+        let mut stmt = conn
+            .prepare("SELECT path, mtime, model FROM embedding_notes")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |r| Ok((r.get(0)?, (r.get(1)?, r.get(2)?))))
+            .map_err(|e| e.to_string())?;
+        existing.extend(rows.flatten());
+    }
+
+    let current: HashSet<String> = files
+        .iter()
+        .map(|f| workspace::relative_path(&root, f))
+        .collect();
+    let removed: Vec<String> = existing
+        .keys()
+        .filter(|p| !current.contains(*p))
+        .cloned()
+        .collect();
+    for path in &removed {
+        conn.execute("DELETE FROM embedding_chunks WHERE path = ?1", [path])
+            .map_err(|e| e.to_string())?;
+        conn.execute("DELETE FROM embedding_notes WHERE path = ?1", [path])
+            .map_err(|e| e.to_string())?;
+    }
+
+    let stale: Vec<(PathBuf, String, i64)> = files
+        .into_iter()
+        .filter_map(|file| {
+            let relative = workspace::relative_path(&root, &file);
+            let mtime = workspace::modified_secs(&file);
+            match existing.get(&relative) {
+                Some((m, stored_model)) if *m == mtime && *stored_model == model => None,
+                _ => Some((file, relative, mtime)),
+            }
+        })
+        .collect();
+
+    for (done, (file, relative, mtime)) in stale.iter().enumerate() {
+        let _ = app.emit(
+            "semantic-index-progress",
+            IndexProgress {
+                done,
+                total: stale.len(),
+                path: relative.clone(),
+            },
+        );
+
+        let content = fs::read_to_string(file).unwrap_or_default();
+        // 加密笔记不进入语义索引
+        let chunks = if encryption::is_envelope(&content) {
+            Vec::new()
+        } else {
+            chunk_note(&content)
+        };
+        let mut vectors = Vec::with_capacity(chunks.len());
+        for batch in chunks.chunks(EMBED_BATCH) {
+            let inputs: Vec<String> = batch
+                .iter()
+                .map(|c| format!("{}\n{}", c.heading, c.text))
+                .collect();
+            vectors.extend(ollama::embed(&app, &inputs).await?);
+        }
+
+        conn.execute("DELETE FROM embedding_chunks WHERE path = ?1", [relative])
+            .map_err(|e| e.to_string())?;
+        for (chunk, vector) in chunks.iter().zip(vectors) {
+            conn.execute(
+                "INSERT INTO embedding_chunks (path, heading, text, vector) VALUES (?1, ?2, ?3, ?4)",
+                params![relative, chunk.heading, chunk.text, to_blob(&normalize(vector))],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        conn.execute(
+            "INSERT OR REPLACE INTO embedding_notes (path, mtime, model) VALUES (?1, ?2, ?3)",
+            params![relative, mtime, model],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    Ok(IndexStats {
+        total: current.len(),
+        indexed: stale.len(),
+        removed: removed.len(),
+    })
+}
+
+struct StoredChunk {
+    path: String,
+    heading: String,
+    text: String,
+    vector: Vec<f32>,
+}
+
+fn load_chunks(conn: &Connection) -> Result<Vec<StoredChunk>, String> {
+    let mut stmt = conn
+        .prepare("SELECT path, heading, text, vector FROM embedding_chunks")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |r| {
+            Ok(StoredChunk {
+                path: r.get(0)?,
+                heading: r.get(1)?,
+                text: r.get(2)?,
+                vector: from_blob(&r.get::<_, Vec<u8>>(3)?),
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(rows.flatten().collect())
+}
+
+#[tauri::command]
+pub async fn semantic_search(
+    app: AppHandle,
+    workspace: String,
+    query: String,
+    k: Option<usize>,
+) -> Result<Vec<SemanticHit>, MobiError> {
+    let query_vector = ollama::embed(&app, &[query])
+        .await?
+        .pop()
+        .map(normalize)
+        .unwrap_or_default();
+
+    let root = PathBuf::from(&workspace);
+    let conn = index::open(&root)?;
+    ensure_schema(&conn)?;
+    let config = workspace::settings(&root);
+    let mut hits: Vec<SemanticHit> = load_chunks(&conn)?
+        .into_iter()
+        .filter(|c| !config.hides(&c.path))
+        .map(|c| SemanticHit {
+            score: dot(&query_vector, &c.vector),
+            snippet: snippet(&c.text),
+            path: root.join(&c.path).to_string_lossy().to_string(),
+            heading: c.heading,
+        })
+        .collect();
+
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    hits.truncate(k.unwrap_or(10));
+    Ok(hits)
+}
+
+// 相关笔记：以当前笔记各分块的平均向量为查询，按其他笔记中最相近的分块打分
+#[tauri::command]
+pub async fn related_notes(
+    workspace: String,
+    path: String,
+    k: Option<usize>,
+) -> Result<Vec<RelatedNote>, MobiError> {
+    let root = PathBuf::from(&workspace);
+    let relative = workspace::relative_path(&root, Path::new(&path));
+
+    let conn = index::open(&root)?;
+    ensure_schema(&conn)?;
+    let chunks = load_chunks(&conn)?;
+
+    let own: Vec<&StoredChunk> = chunks.iter().filter(|c| c.path == relative).collect();
+    let Some(first) = own.first() else {
+        return Ok(Vec::new());
+    };
+    let mut centroid = vec![0.0; first.vector.len()];
+    for chunk in &own {
+        for (c, v) in centroid.iter_mut().zip(&chunk.vector) {
+            *c += v;
+        }
+    }
+    let centroid = normalize(centroid);
+
+    let mut best: HashMap<&str, (f32, &str)> = HashMap::new();
+    let config = workspace::settings(&root);
+    for chunk in chunks
+        .iter()
+        .filter(|c| c.path != relative && !config.hides(&c.path))
+    {
+        let score = dot(&centroid, &chunk.vector);
+        let entry = best.entry(&chunk.path).or_insert((f32::MIN, ""));
+        if score > entry.0 {
+            *entry = (score, &chunk.heading);
+        }
+    }
+
+    let mut related: Vec<RelatedNote> = best
+        .into_iter()
+        .map(|(path, (score, heading))| RelatedNote {
+            path: root.join(path).to_string_lossy().to_string(),
+            heading: heading.to_string(),
+            score,
+        })
+        .collect();
+    related.sort_by(|a, b| b.score.total_cmp(&a.score));
+    related.truncate(k.unwrap_or(5));
+    Ok(related)
+}
@@ -0,0 +1,68 @@
+// 后端设置：保存在应用配置目录的 settings.json 中，按模块分节存放
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tauri::{AppHandle, Manager};
+
+use crate::error::{MobiError, MutexExt};
+
+// 串行化读写，避免多个命令同时写坏文件
+static LOCK: Mutex<()> = Mutex::new(());
+
+fn settings_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_config_dir()
+        .map_err(|e| format!("无法获取配置目录: {}", e))?;
+    Ok(dir.join("settings.json"))
+}
+
+fn read_all(app: &AppHandle) -> Map<String, Value> {
+    settings_path(app)
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+fn write_all(app: &AppHandle, all: &Map<String, Value>) -> Result<(), String> {
+    let path = settings_path(app)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("创建配置目录失败: {}", e))?;
+    }
+    let text = serde_json::to_string_pretty(all).map_err(|e| e.to_string())?;
+    fs::write(&path, text).map_err(|e| format!("保存设置失败: {}", e))
+}
+
+// 读取某一节设置，缺失或格式不对时使用默认值
+pub fn load<T: DeserializeOwned + Default>(app: &AppHandle, section: &str) -> T {
+    let _guard = LOCK.locked();
+    read_all(app)
+        .remove(section)
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default()
+}
+
+pub fn save<T: Serialize>(app: &AppHandle, section: &str, value: &T) -> Result<(), String> {
+    let value = serde_json::to_value(value).map_err(|e| e.to_string())?;
+    let _guard = LOCK.locked();
+    let mut all = read_all(app);
+    all.insert(section.to_string(), value);
+    write_all(app, &all)
+}
+
+#[tauri::command]
+pub fn get_setting(app: AppHandle, section: String) -> Value {
+    let _guard = LOCK.locked();
+    read_all(&app).remove(&section).unwrap_or(Value::Null)
+}
+
+#[tauri::command]
+pub fn set_setting(app: AppHandle, section: String, value: Value) -> Result<(), MobiError> {
+    Ok(save(&app, &section, &value)?)
+}
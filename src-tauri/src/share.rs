@@ -0,0 +1,187 @@
+// 局域网分享：为单篇笔记临时开一个 HTTP 端口，提供分享时渲染好的页面和笔记引用的资源，到期自动关闭
+// 地址中带随机令牌，只有拿到链接（或扫二维码）的人能访问；只提供分享时笔记中链接到的文件，同目录和工作区中的其他笔记与附件一律 404
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::OsRng;
+use qrcode::render::svg;
+use qrcode::QrCode;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tiny_http::Server;
+
+use crate::error::{MobiError, MutexExt};
+use crate::export;
+use crate::{markdown, preview, workspace};
+
+// 默认 10 分钟，最长 24 小时
+const DEFAULT_TTL_SECS: u64 = 10 * 60;
+const MAX_TTL_SECS: u64 = 24 * 60 * 60;
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LanShare {
+    id: String,
+    path: String,
+    url: String,
+    // 扫码打开的二维码（SVG）
+    qr_svg: String,
+    expires_at: i64,
+}
+
+struct ActiveShare {
+    info: LanShare,
+    server: Arc<Server>,
+}
+
+#[derive(Default)]
+pub struct ShareState {
+    shares: Mutex<HashMap<String, ActiveShare>>,
+}
+
+pub fn random_token() -> String {
+    let mut bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn qr_svg(url: &str) -> Result<String, String> {
+    let code = QrCode::new(url).map_err(|e| format!("生成二维码失败: {}", e))?;
+    Ok(code.render::<svg::Color>().min_dimensions(240, 240).build())
+}
+
+// 笔记中链接和图片指向的本地文件（规范化后的路径），不含其他笔记
+fn linked_files(content: &str, note: &Path, workspace: Option<&Path>) -> HashSet<PathBuf> {
+    let Some(root) = workspace.or_else(|| note.parent()) else {
+        return HashSet::new();
+    };
+    let mut files = HashSet::new();
+    markdown::rewrite_link_destinations(content, |destination| {
+        let file = workspace::resolve_note_link(root, note, destination)
+            .and_then(|path| fs::canonicalize(path).ok())
+            .filter(|path| path.is_file() && !workspace::is_note(path));
+        files.extend(file);
+        None
+    });
+    files
+}
+
+fn serve(
+    server: Arc<Server>,
+    token: String,
+    html: String,
+    roots: Vec<PathBuf>,
+    workspace: Option<PathBuf>,
+    allowed: HashSet<PathBuf>,
+) {
+    let prefix = format!("/{}/", token);
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let url = request.url().to_string();
+            // 令牌不对一律 404，不暴露分享是否存在
+            let Some(rest) = url.strip_prefix(&prefix) else {
+                preview::respond_status(request, 404, "Not Found");
+                continue;
+            };
+            if rest.is_empty() || rest.starts_with('?') {
+                preview::respond_html(request, html.clone());
+                continue;
+            }
+            match preview::resolve_asset(rest, &roots, workspace.as_deref()) {
+                Some(file) if allowed.contains(&file) => preview::serve_file(request, &file),
+                _ => preview::respond_status(request, 404, "Not Found"),
+            }
+        }
+    });
+}
+
+fn stop(app: &AppHandle, id: &str) {
+    let removed = app.state::<ShareState>().shares.locked().remove(id);
+    if let Some(share) = removed {
+        share.server.unblock();
+        let _ = app.emit("lan-share-expired", id);
+    }
+}
+
+// ttl 为有效秒数；返回的链接和二维码在到期后失效
+#[tauri::command]
+pub async fn share_note_on_lan(
+    app: AppHandle,
+    state: State<'_, ShareState>,
+    path: String,
+    workspace: Option<String>,
+    ttl: Option<u64>,
+) -> Result<LanShare, MobiError> {
+    let ip = preview::lan_address().ok_or("未连接局域网")?;
+    let note = PathBuf::from(&path);
+    let workspace = workspace.filter(|w| !w.is_empty()).map(PathBuf::from);
+    let (content, options) = preview::read_note(&note, workspace.as_deref())?;
+    let allowed = linked_files(&content, &note, workspace.as_deref());
+    let html = export::render_html(app.clone(), content, Some(options)).await?;
+
+    let server = Server::http(("0.0.0.0", 0)).map_err(|e| format!("启动分享服务失败: {}", e))?;
+    let port = server
+        .server_addr()
+        .to_ip()
+        .map(|addr| addr.port())
+        .ok_or("启动分享服务失败")?;
+    let server = Arc::new(server);
+    let token = random_token();
+    let url = format!("http://{}:{}/{}/", ip, port, token);
+    let ttl = ttl.unwrap_or(DEFAULT_TTL_SECS).clamp(1, MAX_TTL_SECS);
+
+    let info = LanShare {
+        id: token.clone(),
+        path,
+        qr_svg: qr_svg(&url)?,
+        url,
+        expires_at: chrono::Local::now().timestamp() + ttl as i64,
+    };
+    serve(
+        server.clone(),
+        token.clone(),
+        html,
+        preview::asset_roots(&note, workspace.as_deref()),
+        workspace,
+        allowed,
+    );
+    state.shares.locked().insert(
+        token.clone(),
+        ActiveShare {
+            info: info.clone(),
+            server,
+        },
+    );
+
+    let handle = app.clone();
+    thread::spawn(move || {
+        thread::sleep(Duration::from_secs(ttl));
+        stop(&handle, &token);
+    });
+    Ok(info)
+}
+
+#[tauri::command]
+pub fn stop_lan_share(app: AppHandle, id: String) {
+    stop(&app, &id);
+}
+
+#[tauri::command]
+pub fn list_lan_shares(state: State<ShareState>, path: Option<String>) -> Vec<LanShare> {
+    state
+        .shares
+        .locked()
+        .values()
+        .filter(|s| {
+            path.as_ref()
+                .is_none_or(|p| Path::new(p) == Path::new(&s.info.path))
+        })
+        .map(|s| s.info.clone())
+        .collect()
+}
@@ -0,0 +1,224 @@
+// 粉碎笔记：先用随机数据覆盖文件内容并刷盘，改成随机文件名后再删除，同时清理撤销历史、共享缓冲区、回收站中的旧副本和索引库中的记录
+// 只粉碎用户指定的文件：同步冲突副本只列出来，由用户确认后逐个粉碎；符号链接不粉碎，免得只删掉链接而留下明文目标
+// 尽力而为：SSD 的磨损均衡、APFS / Btrfs 等写时复制文件系统、系统快照（时间机器、卷影复制）和同步服务的历史版本都可能保留旧数据，无法从应用内清除
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::OsRng;
+use rusqlite::params;
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::error::MobiError;
+use crate::{bookmarks, buffers, conflicts, index, trash, undo, workspace};
+
+// 覆盖时每次写入的字节数
+const BLOCK: usize = 64 * 1024;
+
+// 按笔记路径清理的索引表：(表名, 条件)，?1 为相对工作区的路径
+const INDEX_TABLES: &[(&str, &str)] = &[
+    ("task_notes", "path = ?1"),
+    ("tasks", "path = ?1"),
+    ("minhash_notes", "path = ?1"),
+    ("embedding_notes", "path = ?1"),
+    ("embedding_chunks", "path = ?1"),
+    ("note_aliases", "path = ?1"),
+    ("note_stats", "path = ?1"),
+    ("review_notes", "path = ?1"),
+    ("review_items", "path = ?1"),
+    // 全文索引按 search_notes 的 id 关联，要在它之前清理
+    (
+        "search_fts",
+        "rowid IN (SELECT id FROM search_notes WHERE path = ?1)",
+    ),
+    ("search_notes", "path = ?1"),
+    ("search_links", "path = ?1"),
+    ("search_tags", "path = ?1"),
+    // 块 id 为 path#...
+    (
+        "review_schedule",
+        "substr(id, 1, length(?1) + 1) = ?1 || '#'",
+    ),
+    // 提醒键以路径和换行开头
+    (
+        "reminder_state",
+        "substr(key, 1, length(?1) + 1) = ?1 || char(10)",
+    ),
+];
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShredReport {
+    // 覆盖的字节数
+    overwritten: u64,
+    // 一并粉碎的其他文件（回收站副本、撤销历史）
+    removed: Vec<String>,
+    // 疑似该笔记的同步冲突副本，没有粉碎
+    conflict_copies: Vec<String>,
+    // 删除的索引记录数
+    index_rows: usize,
+    warnings: Vec<String>,
+}
+
+// 用随机数据覆盖后删除文件，返回覆盖的字节数
+pub fn shred_file(path: &Path) -> Result<u64, String> {
+    let meta = fs::symlink_metadata(path).map_err(|e| format!("文件不存在: {}", e))?;
+    if meta.file_type().is_symlink() {
+        return Err("这是一个符号链接，请直接粉碎它指向的文件".into());
+    }
+    let size = meta.len();
+    let mut file = OpenOptions::new()
+        .write(true)
+        .open(path)
+        .map_err(|e| format!("打开文件失败: {}", e))?;
+    let mut block = vec![0u8; BLOCK];
+    let mut written = 0u64;
+    while written < size {
+        let len = (size - written).min(BLOCK as u64) as usize;
+        OsRng.fill_bytes(&mut block[..len]);
+        file.write_all(&block[..len])
+            .map_err(|e| format!("覆盖文件失败: {}", e))?;
+        written += len as u64;
+    }
+    file.sync_all()
+        .map_err(|e| format!("写入磁盘失败: {}", e))?;
+    file.set_len(0)
+        .map_err(|e| format!("截断文件失败: {}", e))?;
+    drop(file);
+
+    // 改名后再删除，目录项中不留原文件名
+    let mut name = [0u8; 12];
+    OsRng.fill_bytes(&mut name);
+    let hidden = path.with_file_name(
+        name.iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>(),
+    );
+    let target = if fs::rename(path, &hidden).is_ok() {
+        hidden
+    } else {
+        path.to_path_buf()
+    };
+    fs::remove_file(&target).map_err(|e| format!("删除文件失败: {}", e))?;
+    Ok(size)
+}
+
+// 粉碎目录中的所有文件后删除目录
+fn shred_dir(dir: &Path, removed: &mut Vec<String>, warnings: &mut Vec<String>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() && !path.is_symlink() {
+            shred_dir(&path, removed, warnings);
+        } else {
+            match shred_file(&path) {
+                Ok(_) => removed.push(path.to_string_lossy().to_string()),
+                Err(e) => warnings.push(format!("{}: {}", path.display(), e)),
+            }
+        }
+    }
+    let _ = fs::remove_dir_all(dir);
+}
+
+// 删除索引库中该笔记的记录；开启 secure_delete 让释放的页面被清零，再截断 WAL 文件
+pub fn purge_index(root: &Path, relative: &str) -> Result<usize, String> {
+    let conn = index::open(root)?;
+    conn.pragma_update(None, "secure_delete", "ON")
+        .map_err(|e| e.to_string())?;
+    let mut rows = 0;
+    for (table, condition) in INDEX_TABLES {
+        let exists: bool = conn
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1)",
+                [table],
+                |r| r.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        if exists {
+            rows += conn
+                .execute(
+                    &format!("DELETE FROM {} WHERE {}", table, condition),
+                    params![relative],
+                )
+                .map_err(|e| format!("清理索引失败: {}", e))?;
+        }
+    }
+    conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))
+        .map_err(|e| format!("清理索引失败: {}", e))?;
+    Ok(rows)
+}
+
+// 永久删除笔记并清理应用中的副本；workspace 为空时只覆盖删除文件和撤销历史
+#[tauri::command]
+pub async fn shred_note(
+    app: AppHandle,
+    workspace: Option<String>,
+    path: String,
+) -> Result<ShredReport, MobiError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let note = PathBuf::from(&path);
+        if note.is_symlink() {
+            return Err(MobiError::InvalidInput(
+                "这是一个符号链接，请直接粉碎它指向的文件".into(),
+            ));
+        }
+        if !note.is_file() {
+            return Err("只能粉碎单个文件".into());
+        }
+        let mut report = ShredReport {
+            overwritten: 0,
+            removed: Vec::new(),
+            conflict_copies: Vec::new(),
+            index_rows: 0,
+            warnings: Vec::new(),
+        };
+
+        // 缓冲区和撤销日志按规范化后的路径定位，需在删除文件前处理
+        buffers::forget(&app, &path);
+        if let Some(log) = undo::log_path(&app, &path).filter(|log| log.exists()) {
+            match shred_file(&log) {
+                Ok(_) => report.removed.push(log.to_string_lossy().to_string()),
+                Err(e) => report.warnings.push(format!("撤销历史: {}", e)),
+            }
+        }
+
+        let root = workspace.filter(|w| !w.is_empty()).map(PathBuf::from);
+        if let Some(root) = &root {
+            // 同步服务产生的冲突副本：按文件名猜测，可能误认，只列出不粉碎
+            report.conflict_copies = fs::read_dir(note.parent().unwrap_or(root))
+                .map(|entries| entries.flatten().map(|e| e.path()).collect::<Vec<_>>())
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|copy| {
+                    conflicts::detect(root, copy).is_some_and(|c| Path::new(&c.original) == note)
+                })
+                .map(|copy| copy.to_string_lossy().to_string())
+                .collect();
+        }
+
+        report.overwritten = shred_file(&note)?;
+
+        if let Some(root) = &root {
+            let relative = workspace::relative_path(root, &note);
+            // 之前删除到回收站的同名笔记
+            for slot in trash::take(root, &relative)? {
+                shred_dir(&slot, &mut report.removed, &mut report.warnings);
+            }
+            match purge_index(root, &relative) {
+                Ok(rows) => report.index_rows = rows,
+                Err(e) => report.warnings.push(e),
+            }
+            if let Err(e) = bookmarks::removed(&root.to_string_lossy(), &relative) {
+                report.warnings.push(e);
+            }
+        }
+        Ok(report)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
@@ -0,0 +1,291 @@
+// 片段展开：全局片段存在设置的 snippets 节，工作区片段存在 .mobi/snippets.json，随工作区同步
+// 片段正文支持 $1 / ${1:默认值} / $0 制表位，以及 $DATE、$CLIPBOARD、$FILENAME 等动态变量
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::error::{MobiError, MutexExt};
+use crate::templates::{format_date, utf16_offset};
+use crate::{settings, workspace};
+
+// 串行化工作区片段文件的读写
+static LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Snippet {
+    trigger: String,
+    #[serde(default)]
+    description: String,
+    body: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetInfo {
+    #[serde(flatten)]
+    snippet: Snippet,
+    scope: &'static str,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SnippetContext {
+    workspace: Option<String>,
+    file_path: Option<String>,
+    clipboard: Option<String>,
+    selection: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TabStop {
+    index: u32,
+    // UTF-16 偏移
+    start: usize,
+    end: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Expansion {
+    text: String,
+    // 按 index 排序，同一 index 出现多次时需同步编辑
+    tab_stops: Vec<TabStop>,
+    // $0 的位置，没有则在末尾
+    cursor: usize,
+}
+
+fn workspace_file(workspace: &str) -> Result<PathBuf, String> {
+    Ok(workspace::data_dir(Path::new(workspace))?.join("snippets.json"))
+}
+
+fn load_workspace(workspace: &str) -> Vec<Snippet> {
+    workspace_file(workspace)
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+fn save_workspace(workspace: &str, snippets: &[Snippet]) -> Result<(), String> {
+    let text = serde_json::to_string_pretty(snippets).map_err(|e| e.to_string())?;
+    fs::write(workspace_file(workspace)?, text).map_err(|e| format!("保存片段失败: {}", e))
+}
+
+// 工作区片段覆盖同名的全局片段
+fn all_snippets(app: &AppHandle, workspace: Option<&str>) -> Vec<SnippetInfo> {
+    let mut snippets: Vec<SnippetInfo> = settings::load::<Vec<Snippet>>(app, "snippets")
+        .into_iter()
+        .map(|snippet| SnippetInfo {
+            snippet,
+            scope: "global",
+        })
+        .collect();
+    if let Some(workspace) = workspace {
+        let _guard = LOCK.locked();
+        for snippet in load_workspace(workspace) {
+            snippets.retain(|s| s.snippet.trigger != snippet.trigger);
+            snippets.push(SnippetInfo {
+                snippet,
+                scope: "workspace",
+            });
+        }
+    }
+    snippets.sort_by(|a, b| a.snippet.trigger.cmp(&b.snippet.trigger));
+    snippets
+}
+
+fn variable(name: &str, context: &SnippetContext) -> Option<String> {
+    let now = Local::now();
+    let file = context.file_path.as_deref().map(Path::new);
+    let value = match name {
+        "DATE" => format_date(&now, "%Y-%m-%d"),
+        "TIME" => format_date(&now, "%H:%M"),
+        "DATETIME" => format_date(&now, "%Y-%m-%d %H:%M"),
+        "YEAR" => format_date(&now, "%Y"),
+        "MONTH" => format_date(&now, "%m"),
+        "DAY" => format_date(&now, "%d"),
+        "CLIPBOARD" => context.clipboard.clone().unwrap_or_default(),
+        "SELECTION" => context.selection.clone().unwrap_or_default(),
+        "FILENAME" => file?.file_name()?.to_string_lossy().to_string(),
+        "FILENAME_BASE" => file?.file_stem()?.to_string_lossy().to_string(),
+        "WORKSPACE_NAME" => Path::new(context.workspace.as_deref()?)
+            .file_name()?
+            .to_string_lossy()
+            .to_string(),
+        _ => return None,
+    };
+    Some(value)
+}
+
+// 展开片段正文，制表位的位置先按字节记录
+fn expand(body: &str, context: &SnippetContext) -> Expansion {
+    let mut text = String::with_capacity(body.len());
+    let mut stops: Vec<(u32, usize, usize)> = Vec::new();
+    let mut final_cursor = None;
+    let mut chars = body.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        if c == '\\' && matches!(chars.peek(), Some((_, '$' | '\\' | '}'))) {
+            let (_, escaped) = chars.next().unwrap();
+            text.push(escaped);
+            continue;
+        }
+        if c != '$' {
+            text.push(c);
+            continue;
+        }
+        let rest = &body[i + 1..];
+        // ${...}
+        if let Some(inner) = rest
+            .strip_prefix('{')
+            .and_then(|r| r.find('}').map(|e| &r[..e]))
+        {
+            let (name, placeholder) = inner.split_once(':').unwrap_or((inner, ""));
+            let consumed = inner.len() + 2;
+            if let Ok(index) = name.parse::<u32>() {
+                push_stop(&mut text, &mut stops, &mut final_cursor, index, placeholder);
+            } else if let Some(value) = variable(name, context) {
+                text.push_str(&value);
+            } else {
+                text.push_str(&body[i..i + 1 + consumed]);
+            }
+            for _ in 0..inner.chars().count() + 2 {
+                chars.next();
+            }
+            continue;
+        }
+        // $1 / $NAME
+        let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+        if !digits.is_empty() {
+            let index = digits.parse().unwrap_or(0);
+            push_stop(&mut text, &mut stops, &mut final_cursor, index, "");
+            for _ in 0..digits.len() {
+                chars.next();
+            }
+            continue;
+        }
+        let name: String = rest
+            .chars()
+            .take_while(|c| c.is_ascii_uppercase() || *c == '_')
+            .collect();
+        match variable(&name, context) {
+            Some(value) if !name.is_empty() => {
+                text.push_str(&value);
+                for _ in 0..name.len() {
+                    chars.next();
+                }
+            }
+            _ => text.push('$'),
+        }
+    }
+
+    // 同一制表位再次出现时沿用第一次的默认值
+    let mut tab_stops: Vec<TabStop> = stops
+        .into_iter()
+        .map(|(index, start, end)| TabStop {
+            index,
+            start: utf16_offset(&text, start),
+            end: utf16_offset(&text, end),
+        })
+        .collect();
+    tab_stops.sort_by_key(|s| (s.index, s.start));
+    Expansion {
+        cursor: utf16_offset(&text, final_cursor.unwrap_or(text.len())),
+        tab_stops,
+        text,
+    }
+}
+
+fn push_stop(
+    text: &mut String,
+    stops: &mut Vec<(u32, usize, usize)>,
+    final_cursor: &mut Option<usize>,
+    index: u32,
+    placeholder: &str,
+) {
+    if index == 0 {
+        final_cursor.get_or_insert(text.len());
+        return;
+    }
+    let placeholder = match stops.iter().find(|(i, _, _)| *i == index) {
+        Some((_, start, end)) if placeholder.is_empty() => text[*start..*end].to_string(),
+        _ => placeholder.to_string(),
+    };
+    let start = text.len();
+    text.push_str(&placeholder);
+    stops.push((index, start, text.len()));
+}
+
+#[tauri::command]
+pub fn list_snippets(app: AppHandle, workspace: Option<String>) -> Vec<SnippetInfo> {
+    all_snippets(&app, workspace.as_deref())
+}
+
+#[tauri::command]
+pub fn expand_snippet(
+    app: AppHandle,
+    trigger: String,
+    context: Option<SnippetContext>,
+) -> Result<Expansion, MobiError> {
+    let context = context.unwrap_or_default();
+    let snippet = all_snippets(&app, context.workspace.as_deref())
+        .into_iter()
+        .find(|s| s.snippet.trigger == trigger)
+        .ok_or_else(|| format!("找不到片段: {}", trigger))?;
+    Ok(expand(&snippet.snippet.body, &context))
+}
+
+// workspace 为空时保存为全局片段，同名片段会被替换
+#[tauri::command]
+pub fn save_snippet(
+    app: AppHandle,
+    workspace: Option<String>,
+    snippet: Snippet,
+) -> Result<(), MobiError> {
+    if snippet.trigger.trim().is_empty() {
+        return Err("触发词不能为空".into());
+    }
+    match workspace {
+        Some(workspace) => {
+            let _guard = LOCK.locked();
+            let mut snippets = load_workspace(&workspace);
+            snippets.retain(|s| s.trigger != snippet.trigger);
+            snippets.push(snippet);
+            Ok(save_workspace(&workspace, &snippets)?)
+        }
+        None => {
+            let mut snippets: Vec<Snippet> = settings::load(&app, "snippets");
+            snippets.retain(|s| s.trigger != snippet.trigger);
+            snippets.push(snippet);
+            Ok(settings::save(&app, "snippets", &snippets)?)
+        }
+    }
+}
+
+#[tauri::command]
+pub fn delete_snippet(
+    app: AppHandle,
+    workspace: Option<String>,
+    trigger: String,
+) -> Result<(), MobiError> {
+    match workspace {
+        Some(workspace) => {
+            let _guard = LOCK.locked();
+            let mut snippets = load_workspace(&workspace);
+            snippets.retain(|s| s.trigger != trigger);
+            Ok(save_workspace(&workspace, &snippets)?)
+        }
+        None => {
+            let mut snippets: Vec<Snippet> = settings::load(&app, "snippets");
+            snippets.retain(|s| s.trigger != trigger);
+            Ok(settings::save(&app, "snippets", &snippets)?)
+        }
+    }
+}
@@ -0,0 +1,265 @@
+// 朗读：使用系统 TTS（AVSpeechSynthesizer / SAPI / speech-dispatcher）校听草稿
+
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+use tts::Tts;
+
+use crate::error::{MobiError, MutexExt};
+use crate::markdown;
+
+#[derive(Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Idle,
+    Playing,
+    Paused,
+}
+
+// 一次朗读任务：文本按句切分后依次排队
+struct Session {
+    segments: Vec<String>,
+    // 本轮排队的起始片段（暂停后从这里继续）
+    start: usize,
+    begun: usize,
+    ended: usize,
+    status: Status,
+}
+
+impl Session {
+    fn new() -> Self {
+        Session {
+            segments: Vec::new(),
+            start: 0,
+            begun: 0,
+            ended: 0,
+            status: Status::Idle,
+        }
+    }
+
+    fn progress(&self, index: usize) -> SpeechProgress {
+        SpeechProgress {
+            status: self.status,
+            index,
+            total: self.segments.len(),
+            text: self.segments.get(index).cloned(),
+        }
+    }
+}
+
+// 发给前端的 speech-progress 事件
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SpeechProgress {
+    status: Status,
+    index: usize,
+    total: usize,
+    text: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VoiceInfo {
+    id: String,
+    name: String,
+    language: String,
+}
+
+pub struct SpeechState {
+    tts: Mutex<Option<Tts>>,
+    session: Arc<Mutex<Session>>,
+}
+
+impl Default for SpeechState {
+    fn default() -> Self {
+        SpeechState {
+            tts: Mutex::new(None),
+            session: Arc::new(Mutex::new(Session::new())),
+        }
+    }
+}
+
+impl SpeechState {
+    // 首次使用时初始化 TTS 引擎并注册回调
+    fn engine(&self, app: &AppHandle) -> Result<Tts, String> {
+        let mut engine = self.tts.locked();
+        if let Some(tts) = engine.as_ref() {
+            return Ok(tts.clone());
+        }
+
+        let tts = Tts::default().map_err(|e| format!("初始化语音引擎失败: {}", e))?;
+        if tts.supported_features().utterance_callbacks {
+            let session = self.session.clone();
+            let handle = app.clone();
+            let _ = tts.on_utterance_begin(Some(Box::new(move |_| {
+                let mut s = session.locked();
+                if s.status != Status::Playing {
+                    return;
+                }
+                let index = s.start + s.begun;
+                s.begun += 1;
+                let _ = handle.emit("speech-progress", s.progress(index));
+            })));
+
+            let session = self.session.clone();
+            let handle = app.clone();
+            let _ = tts.on_utterance_end(Some(Box::new(move |_| {
+                let mut s = session.locked();
+                if s.status != Status::Playing {
+                    return;
+                }
+                s.ended += 1;
+                if s.start + s.ended >= s.segments.len() {
+                    s.status = Status::Idle;
+                    let index = s.segments.len();
+                    let _ = handle.emit("speech-progress", s.progress(index));
+                }
+            })));
+        }
+
+        *engine = Some(tts.clone());
+        Ok(tts)
+    }
+}
+
+// 按句切分，避免单条语音过长导致无法暂停/续读
+fn split_segments(text: &str) -> Vec<String> {
+    let mut segments = Vec::new();
+    let mut current = String::new();
+
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        current.push(c);
+        // 英文句点后需跟空白，避免把 3.14 之类的数字拆开
+        let is_end = match c {
+            '。' | '！' | '？' | '；' | '!' | '?' | ';' | '\n' => true,
+            '.' => chars.peek().is_none_or(|n| n.is_whitespace()),
+            _ => false,
+        };
+        if is_end {
+            let segment = current.trim();
+            if !segment.is_empty() {
+                segments.push(segment.to_string());
+            }
+            current.clear();
+        }
+    }
+    let segment = current.trim();
+    if !segment.is_empty() {
+        segments.push(segment.to_string());
+    }
+
+    segments
+}
+
+fn enqueue(tts: &mut Tts, segments: &[String]) -> Result<(), String> {
+    for (i, segment) in segments.iter().enumerate() {
+        tts.speak(segment.as_str(), i == 0)
+            .map_err(|e| format!("朗读失败: {}", e))?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_voices(app: AppHandle, state: State<SpeechState>) -> Result<Vec<VoiceInfo>, MobiError> {
+    let tts = state.engine(&app)?;
+    let voices = tts
+        .voices()
+        .map_err(|e| format!("获取语音列表失败: {}", e))?;
+    Ok(voices
+        .into_iter()
+        .map(|v| VoiceInfo {
+            id: v.id(),
+            name: v.name(),
+            language: v.language().to_string(),
+        })
+        .collect())
+}
+
+// rate 为相对语速，1.0 为系统默认
+#[tauri::command]
+pub fn speak(
+    app: AppHandle,
+    state: State<SpeechState>,
+    text: String,
+    voice: Option<String>,
+    rate: Option<f32>,
+) -> Result<(), MobiError> {
+    let mut tts = state.engine(&app)?;
+
+    if let Some(voice) = voice {
+        let voices = tts
+            .voices()
+            .map_err(|e| format!("获取语音列表失败: {}", e))?;
+        if let Some(v) = voices.iter().find(|v| v.id() == voice || v.name() == voice) {
+            tts.set_voice(v)
+                .map_err(|e| format!("设置语音失败: {}", e))?;
+        }
+    }
+    if let Some(rate) = rate {
+        let value = (tts.normal_rate() * rate).clamp(tts.min_rate(), tts.max_rate());
+        tts.set_rate(value)
+            .map_err(|e| format!("设置语速失败: {}", e))?;
+    }
+
+    let segments = split_segments(&markdown::to_plain_text(&text));
+    if segments.is_empty() {
+        return Ok(());
+    }
+
+    {
+        let mut s = state.session.locked();
+        *s = Session::new();
+        s.segments = segments.clone();
+        s.status = Status::Playing;
+    }
+    Ok(enqueue(&mut tts, &segments)?)
+}
+
+#[tauri::command]
+pub fn pause_speech(app: AppHandle, state: State<SpeechState>) -> Result<(), MobiError> {
+    let mut tts = state.engine(&app)?;
+    {
+        let mut s = state.session.locked();
+        if s.status != Status::Playing {
+            return Ok(());
+        }
+        // 从尚未读完的片段继续
+        s.start += s.ended;
+        s.begun = 0;
+        s.ended = 0;
+        s.status = Status::Paused;
+        let _ = app.emit("speech-progress", s.progress(s.start));
+    }
+    tts.stop().map_err(|e| format!("暂停失败: {}", e))?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn resume_speech(app: AppHandle, state: State<SpeechState>) -> Result<(), MobiError> {
+    let mut tts = state.engine(&app)?;
+    let remaining = {
+        let mut s = state.session.locked();
+        if s.status != Status::Paused {
+            return Ok(());
+        }
+        s.status = Status::Playing;
+        s.segments[s.start..].to_vec()
+    };
+    Ok(enqueue(&mut tts, &remaining)?)
+}
+
+#[tauri::command]
+pub fn stop_speech(app: AppHandle, state: State<SpeechState>) -> Result<(), MobiError> {
+    let mut tts = state.engine(&app)?;
+    {
+        let mut s = state.session.locked();
+        let index = s.start + s.ended;
+        s.status = Status::Idle;
+        let _ = app.emit("speech-progress", s.progress(index));
+        *s = Session::new();
+    }
+    tts.stop().map_err(|e| format!("停止朗读失败: {}", e))?;
+    Ok(())
+}
@@ -0,0 +1,349 @@
+// 工作区统计：笔记数、总字数、最长和最久未改的笔记、孤立笔记、标签分布和按月的增长，供前端绘制知识库概况
+// 每篇笔记的字数、链接和标签缓存在工作区索引库中，按修改时间增量更新；链接在统计时才解析，笔记改名后结果仍然准确
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use chrono::{Local, TimeZone};
+use pulldown_cmark::{Event, LinkType, Parser, Tag, TagEnd, TextMergeStream};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::aliases::{self, Names};
+use crate::error::MobiError;
+use crate::{duplicates, encryption, index, markdown, workspace};
+
+// 最长、最久未改的笔记各列出的篇数
+const TOP_NOTES: usize = 10;
+// 缓存内容的格式变化时递增
+const STATS_VERSION: i64 = 1;
+
+#[derive(Serialize, Deserialize, Default)]
+struct NoteLinks {
+    // Markdown 链接和图片的目标（原文）
+    links: Vec<String>,
+    // [[ ]] 中的目标
+    wiki: Vec<String>,
+}
+
+struct NoteRow {
+    relative: String,
+    mtime: i64,
+    created: i64,
+    words: usize,
+    links: NoteLinks,
+    tags: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NoteSummary {
+    path: String,
+    words: usize,
+    // 秒
+    modified: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagCount {
+    tag: String,
+    count: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonthGrowth {
+    // YYYY-MM
+    month: String,
+    // 当月新建的笔记
+    added: usize,
+    // 截至当月的笔记总数
+    total: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceStats {
+    notes: usize,
+    words: usize,
+    largest: Vec<NoteSummary>,
+    stalest: Vec<NoteSummary>,
+    // 没有链接出去也没有被链接的笔记
+    orphans: Vec<String>,
+    tags: Vec<TagCount>,
+    growth: Vec<MonthGrowth>,
+}
+
+fn ensure_schema(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS note_stats (
+            path TEXT PRIMARY KEY,
+            mtime INTEGER NOT NULL,
+            version INTEGER NOT NULL,
+            created INTEGER NOT NULL,
+            words INTEGER NOT NULL,
+            links TEXT NOT NULL,
+            tags TEXT NOT NULL
+        );",
+    )
+    .map_err(|e| format!("初始化统计索引失败: {}", e))
+}
+
+// 行内 #标签：# 前为行首或空白，后面不是空白或 #，纯数字（如 #1）不算
+pub fn inline_tags(text: &str, tags: &mut Vec<String>) {
+    let mut prev = ' ';
+    for (i, c) in text.char_indices() {
+        if c == '#' && prev.is_whitespace() {
+            let tag: String = text[i + 1..]
+                .chars()
+                .take_while(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '/'))
+                .collect();
+            let tag = tag.trim_end_matches('/');
+            if !tag.is_empty() && !tag.chars().all(|c| c.is_ascii_digit()) {
+                tags.push(tag.to_string());
+            }
+        }
+        prev = c;
+    }
+}
+
+// front matter 中的 tags：a, b 或 tags: a b 这样写在一行的也拆开
+pub fn front_matter_tags(content: &str) -> Vec<String> {
+    markdown::front_matter_list(content, "tags")
+        .iter()
+        .flat_map(|t| t.split([',', ' ']))
+        .map(|t| t.trim().trim_start_matches('#').to_string())
+        .collect()
+}
+
+// 解析一篇笔记的链接、标签和字数；加密笔记只记为 0 字
+fn analyze(content: &str) -> (usize, NoteLinks, Vec<String>) {
+    if encryption::is_envelope(content) {
+        return (0, NoteLinks::default(), Vec::new());
+    }
+    let body = markdown::strip_front_matter(content);
+    let words = duplicates::words(&markdown::to_plain_text(body)).len();
+
+    let mut links = NoteLinks::default();
+    let mut tags = front_matter_tags(content);
+    let mut in_code_block = false;
+    // 合并相邻的文本事件，避免 #my_tag 之类被拆开
+    for event in TextMergeStream::new(Parser::new_ext(body, aliases::wiki_options())) {
+        match event {
+            Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                ..
+            })
+            | Event::Start(Tag::Image {
+                link_type,
+                dest_url,
+                ..
+            }) => match link_type {
+                LinkType::WikiLink { .. } => links.wiki.push(dest_url.to_string()),
+                _ => links.links.push(dest_url.to_string()),
+            },
+            Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+            Event::End(TagEnd::CodeBlock) => in_code_block = false,
+            Event::Text(text) if !in_code_block => inline_tags(&text, &mut tags),
+            _ => {}
+        }
+    }
+    let mut seen = HashSet::new();
+    tags.retain(|t| !t.is_empty() && seen.insert(t.to_lowercase()));
+    (words, links, tags)
+}
+
+fn created_secs(path: &Path) -> i64 {
+    fs::metadata(path)
+        .and_then(|m| m.created())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_else(|| workspace::modified_secs(path))
+}
+
+// 增量更新缓存，返回当前所有笔记的统计
+fn refresh(root: &Path) -> Result<Vec<NoteRow>, String> {
+    let conn = index::open(root)?;
+    ensure_schema(&conn)?;
+
+    let mut existing: HashMap<String, NoteRow> = HashMap::new();
+    {
+        let mut stmt = conn
+            .prepare("SELECT path, mtime, created, words, links, tags FROM note_stats WHERE version = ?1")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([STATS_VERSION], |r| {
+                Ok(NoteRow {
+                    relative: r.get(0)?,
+                    mtime: r.get(1)?,
+                    created: r.get(2)?,
+                    words: r.get(3)?,
+                    links: serde_json::from_str(&r.get::<_, String>(4)?).unwrap_or_default(),
+                    tags: serde_json::from_str(&r.get::<_, String>(5)?).unwrap_or_default(),
+                })
+            })
+            .map_err(|e| e.to_string())?;
+        existing.extend(rows.flatten().map(|row| (row.relative.clone(), row)));
+    }
+
+    let mut notes = Vec::new();
+    let mut current = HashSet::new();
+    for file in workspace::note_files(root) {
+        let relative = workspace::relative_path(root, &file);
+        let mtime = workspace::modified_secs(&file);
+        current.insert(relative.clone());
+        if let Some(row) = existing.remove(&relative).filter(|row| row.mtime == mtime) {
+            notes.push(row);
+            continue;
+        }
+        let (words, links, tags) = analyze(&fs::read_to_string(&file).unwrap_or_default());
+        let created = created_secs(&file);
+        conn.execute(
+            "INSERT OR REPLACE INTO note_stats (path, mtime, version, created, words, links, tags)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                relative,
+                mtime,
+                STATS_VERSION,
+                created,
+                words,
+                serde_json::to_string(&links).map_err(|e| e.to_string())?,
+                serde_json::to_string(&tags).map_err(|e| e.to_string())?
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+        notes.push(NoteRow {
+            relative,
+            mtime,
+            created,
+            words,
+            links,
+            tags,
+        });
+    }
+    conn.execute(
+        "DELETE FROM note_stats WHERE version != ?1",
+        [STATS_VERSION],
+    )
+    .map_err(|e| e.to_string())?;
+    for path in existing.keys().filter(|p| !current.contains(*p)) {
+        conn.execute("DELETE FROM note_stats WHERE path = ?1", [path])
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(notes)
+}
+
+fn month(secs: i64) -> String {
+    Local
+        .timestamp_opt(secs, 0)
+        .single()
+        .map(|d| d.format("%Y-%m").to_string())
+        .unwrap_or_default()
+}
+
+fn summarize(root: &Path, notes: &[NoteRow]) -> Result<WorkspaceStats, String> {
+    let names = Names::load(root)?;
+    let config = workspace::settings(root);
+    let absolute = |relative: &str| root.join(relative).to_string_lossy().to_string();
+
+    // 链接到其他笔记的出入度，指向自身的不算
+    let known: HashSet<PathBuf> = notes.iter().map(|n| root.join(&n.relative)).collect();
+    let mut linked: HashSet<PathBuf> = HashSet::new();
+    for note in notes {
+        let path = root.join(&note.relative);
+        let targets = note
+            .links
+            .links
+            .iter()
+            .filter_map(|link| workspace::resolve_note_link(root, &path, link))
+            .chain(
+                note.links
+                    .wiki
+                    .iter()
+                    .filter_map(|link| names.resolve_link(link, Some(&path))),
+            )
+            .filter(|target| *target != path && known.contains(target))
+            .collect::<Vec<_>>();
+        if !targets.is_empty() {
+            linked.insert(path);
+            linked.extend(targets);
+        }
+    }
+
+    let summary = |n: &NoteRow| NoteSummary {
+        path: absolute(&n.relative),
+        words: n.words,
+        modified: n.mtime,
+    };
+    let mut largest: Vec<&NoteRow> = notes.iter().collect();
+    largest.sort_by_key(|n| std::cmp::Reverse(n.words));
+    // 最久未改和孤立笔记不算已归档的
+    let mut stalest: Vec<&NoteRow> = notes
+        .iter()
+        .filter(|n| !config.is_archived(&n.relative))
+        .collect();
+    stalest.sort_by_key(|n| n.mtime);
+    let mut orphans: Vec<String> = notes
+        .iter()
+        .filter(|n| !config.is_archived(&n.relative) && !linked.contains(&root.join(&n.relative)))
+        .map(|n| absolute(&n.relative))
+        .collect();
+    orphans.sort();
+
+    // 标签按出现的笔记数计，大小写不同的视为同一个，显示最先遇到的写法
+    let mut tag_counts: HashMap<String, (String, usize)> = HashMap::new();
+    for tag in notes.iter().flat_map(|n| &n.tags) {
+        tag_counts
+            .entry(tag.to_lowercase())
+            .or_insert_with(|| (tag.clone(), 0))
+            .1 += 1;
+    }
+    let mut tags: Vec<TagCount> = tag_counts
+        .into_values()
+        .map(|(tag, count)| TagCount { tag, count })
+        .collect();
+    tags.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.tag.cmp(&b.tag)));
+
+    let mut by_month: BTreeMap<String, usize> = BTreeMap::new();
+    for note in notes {
+        *by_month.entry(month(note.created)).or_default() += 1;
+    }
+    let mut total = 0;
+    let growth = by_month
+        .into_iter()
+        .map(|(month, added)| {
+            total += added;
+            MonthGrowth {
+                month,
+                added,
+                total,
+            }
+        })
+        .collect();
+
+    Ok(WorkspaceStats {
+        notes: notes.len(),
+        words: notes.iter().map(|n| n.words).sum(),
+        largest: largest.into_iter().take(TOP_NOTES).map(summary).collect(),
+        stalest: stalest.into_iter().take(TOP_NOTES).map(summary).collect(),
+        orphans,
+        tags,
+        growth,
+    })
+}
+
+#[tauri::command]
+pub async fn get_workspace_stats(workspace: String) -> Result<WorkspaceStats, MobiError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = PathBuf::from(&workspace);
+        let notes = refresh(&root)?;
+        Ok(summarize(&root, &notes)?)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
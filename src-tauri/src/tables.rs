@@ -0,0 +1,666 @@
+// 表格转换：CSV / TSV 转为对齐的 Markdown 表格，以及把选中的 Markdown 表格转回 CSV
+// 导入时自动识别编码（BOM、UTF-8，否则按 GB18030，Excel 中文版默认如此）和分隔符，引号内的分隔符与换行按 RFC 4180 处理
+// 从 Excel、Google 表格、WPS 复制的区域优先按剪贴板中的 HTML 转换（可处理合并单元格），没有 HTML 时按 TSV
+
+use std::collections::HashMap;
+use std::fs;
+
+use encoding_rs::{Encoding, GB18030, UTF_16BE, UTF_16LE};
+use serde::Deserialize;
+use tauri::AppHandle;
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+use crate::error::MobiError;
+use crate::unfurl::{attributes, decode_entities, is_tag};
+
+// 按优先级排列，计数相同时取靠前的
+const DELIMITERS: &[char] = &[',', '\t', ';', '|'];
+// 识别分隔符时查看的行数
+const SNIFF_ROWS: usize = 20;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CsvOptions {
+    // 为空时自动识别
+    delimiter: Option<char>,
+    // 第一行是否为表头，否则生成"列 1"、"列 2"……
+    header: bool,
+    // 编码名称（如 gbk、shift_jis），为空时自动识别
+    encoding: String,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        CsvOptions {
+            delimiter: None,
+            header: true,
+            encoding: String::new(),
+        }
+    }
+}
+
+// 合并单元格占用的其余格子
+#[derive(Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum MergedCells {
+    // 留空
+    #[default]
+    Empty,
+    // 重复合并单元格的内容
+    Repeat,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum NumberFormat {
+    // 与表格中显示的一致（千分位、货币符号、百分号）
+    #[default]
+    Display,
+    // 单元格的原始数值（Excel 的 x:num、Google 表格的 data-sheets-value），没有时同显示值
+    Raw,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PasteOptions {
+    header: bool,
+    merged: MergedCells,
+    numbers: NumberFormat,
+}
+
+impl Default for PasteOptions {
+    fn default() -> Self {
+        PasteOptions {
+            header: true,
+            merged: MergedCells::Empty,
+            numbers: NumberFormat::Display,
+        }
+    }
+}
+
+// 合并单元格的跨度上限，防止异常的 colspan 撑出巨大的表格
+const MAX_SPAN: usize = 64;
+
+struct HtmlCell {
+    text: String,
+    raw: Option<String>,
+    colspan: usize,
+    rowspan: usize,
+}
+
+fn decode(bytes: &[u8], label: &str) -> Result<String, String> {
+    if !label.is_empty() {
+        let encoding = Encoding::for_label(label.as_bytes())
+            .ok_or_else(|| format!("不支持的编码: {}", label))?;
+        return Ok(encoding.decode(bytes).0.into_owned());
+    }
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        return Ok(encoding.decode(bytes).0.into_owned());
+    }
+    // 没有 BOM 的 UTF-16 文本每隔一个字节是 0
+    let zeros = |offset: usize| {
+        bytes
+            .iter()
+            .skip(offset)
+            .step_by(2)
+            .take(256)
+            .filter(|b| **b == 0)
+            .count()
+    };
+    if bytes.len() >= 4 && zeros(1) > 64.min(bytes.len() / 4) {
+        return Ok(UTF_16LE.decode(bytes).0.into_owned());
+    }
+    if bytes.len() >= 4 && zeros(0) > 64.min(bytes.len() / 4) {
+        return Ok(UTF_16BE.decode(bytes).0.into_owned());
+    }
+    Ok(match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => GB18030.decode(bytes).0.into_owned(),
+    })
+}
+
+// 按 RFC 4180 解析：双引号包围的字段可以包含分隔符和换行，"" 表示一个引号
+fn parse(text: &str, delimiter: char, limit: Option<usize>) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => quoted = false,
+                c => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => quoted = true,
+            c if c == delimiter => row.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' | '\r' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+                if limit.is_some_and(|limit| rows.len() >= limit) {
+                    return rows;
+                }
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    // 去掉完全空白的行
+    rows.retain(|row| row.iter().any(|cell| !cell.trim().is_empty()));
+    rows
+}
+
+// 选每行字段数最一致、且多于一列的分隔符
+fn sniff(text: &str) -> char {
+    let mut best = (',', 0usize);
+    for &delimiter in DELIMITERS {
+        let rows = parse(text, delimiter, Some(SNIFF_ROWS));
+        let Some(first) = rows.first().map(Vec::len).filter(|n| *n > 1) else {
+            continue;
+        };
+        let consistent = rows.iter().filter(|row| row.len() == first).count();
+        // 一致的行多者优先，其次列数多者
+        let score = consistent * 1000 + first;
+        if score > best.1 {
+            best = (delimiter, score);
+        }
+    }
+    best.0
+}
+
+// 显示宽度：中日韩全角字符占两格，用于对齐表格
+pub fn display_width(text: &str) -> usize {
+    text.chars()
+        .map(|c| match c as u32 {
+            0x1100..=0x115F
+            | 0x2E80..=0xA4CF
+            | 0xAC00..=0xD7A3
+            | 0xF900..=0xFAFF
+            | 0xFE30..=0xFE4F
+            | 0xFF00..=0xFF60
+            | 0xFFE0..=0xFFE6
+            | 0x1F300..=0x1FAFF
+            | 0x20000..=0x3FFFD => 2,
+            _ => 1,
+        })
+        .sum()
+}
+
+fn escape_cell(cell: &str) -> String {
+    cell.trim()
+        .replace('|', "\\|")
+        .replace("\r\n", "<br>")
+        .replace(['\n', '\r'], "<br>")
+}
+
+fn is_number(cell: &str) -> bool {
+    let cell = cell
+        .trim()
+        .trim_start_matches(['-', '+', '¥', '$', '€', '£'])
+        .trim_end_matches('%')
+        .replace(',', "");
+    !cell.is_empty() && cell.parse::<f64>().is_ok()
+}
+
+// 对齐的 Markdown 表格，全部为数字的列右对齐
+pub fn to_markdown(rows: &[Vec<String>], header: bool) -> String {
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    if columns == 0 {
+        return String::new();
+    }
+    let mut cells: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| {
+            (0..columns)
+                .map(|i| row.get(i).map(|c| escape_cell(c)).unwrap_or_default())
+                .collect()
+        })
+        .collect();
+    if !header {
+        cells.insert(0, (1..=columns).map(|i| format!("列 {}", i)).collect());
+    }
+    let numeric: Vec<bool> = (0..columns)
+        .map(|i| {
+            let mut body = cells[1..]
+                .iter()
+                .map(|row| &row[i])
+                .filter(|c| !c.is_empty());
+            let first = body.next();
+            first.is_some_and(|c| is_number(c)) && body.all(|c| is_number(c))
+        })
+        .collect();
+    let widths: Vec<usize> = (0..columns)
+        .map(|i| {
+            cells
+                .iter()
+                .map(|row| display_width(&row[i]))
+                .max()
+                .unwrap_or(0)
+                .max(3)
+        })
+        .collect();
+
+    let pad = |text: &str, i: usize| {
+        let fill = " ".repeat(widths[i] - display_width(text));
+        if numeric[i] {
+            format!("{}{}", fill, text)
+        } else {
+            format!("{}{}", text, fill)
+        }
+    };
+    let line = |row: &[String]| {
+        let cells: Vec<String> = row.iter().enumerate().map(|(i, c)| pad(c, i)).collect();
+        format!("| {} |", cells.join(" | "))
+    };
+    let separator: Vec<String> = (0..columns)
+        .map(|i| {
+            if numeric[i] {
+                format!("{}:", "-".repeat(widths[i] - 1))
+            } else {
+                "-".repeat(widths[i])
+            }
+        })
+        .collect();
+
+    let mut out = vec![line(&cells[0]), format!("| {} |", separator.join(" | "))];
+    out.extend(cells[1..].iter().map(|row| line(row)));
+    out.join("\n")
+}
+
+// 拆分表格行，\| 和 `代码` 中的 | 不算分隔
+fn split_row(line: &str) -> Vec<String> {
+    let line = line.trim();
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = line
+        .strip_suffix('|')
+        .filter(|l| !l.ends_with('\\'))
+        .unwrap_or(line);
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut in_code = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'|') => {
+                cell.push('|');
+                chars.next();
+            }
+            '`' => {
+                in_code = !in_code;
+                cell.push(c);
+            }
+            '|' if !in_code => cells.push(std::mem::take(&mut cell)),
+            c => cell.push(c),
+        }
+    }
+    cells.push(cell);
+    cells
+        .into_iter()
+        .map(|c| c.trim().replace("<br>", "\n").replace("<br/>", "\n"))
+        .collect()
+}
+
+fn is_separator_row(cells: &[String]) -> bool {
+    cells.iter().all(|c| {
+        let c = c.trim();
+        !c.is_empty() && c.trim_matches(':').chars().all(|ch| ch == '-')
+    })
+}
+
+fn csv_field(cell: &str, delimiter: char) -> String {
+    if cell.contains([delimiter, '"', '\n', '\r']) || cell.starts_with(' ') || cell.ends_with(' ') {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+// 单元格的原始数值：Excel 为 x:num="1234.5"，Google 表格为 data-sheets-value='{"1":3,"3":1234.5}'
+fn raw_value(attrs: &HashMap<String, String>) -> Option<String> {
+    if let Some(num) = attrs.get("x:num").filter(|v| !v.is_empty()) {
+        return Some(num.clone());
+    }
+    let value: serde_json::Value =
+        serde_json::from_str(&decode_entities(attrs.get("data-sheets-value")?)).ok()?;
+    value.get("3").map(|n| n.to_string())
+}
+
+// 单元格文字：HTML 中的空白合并为一个空格，<br> 和段落换行保留为换行
+fn cell_text(parts: &str) -> String {
+    decode_entities(parts)
+        .replace(['\u{a0}', '\u{2009}', '\u{202f}'], " ")
+        .split('\n')
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// 剪贴板 HTML 中的第一个表格，按行列出单元格；不含表格时返回 None
+fn parse_html_table(html: &str) -> Option<Vec<Vec<HtmlCell>>> {
+    // 与原文等长的小写副本，用来定位标签
+    let lower = html.to_ascii_lowercase();
+    let start = lower.find("<table")?;
+    let end = lower[start..]
+        .find("</table")
+        .map_or(html.len(), |i| start + i);
+
+    let mut rows: Vec<Vec<HtmlCell>> = Vec::new();
+    let mut row: Option<Vec<HtmlCell>> = None;
+    // 正在读取的单元格及其文字（HTML 原文，标签之间的文本，换行用 \n 标记）
+    let mut cell: Option<(HtmlCell, String)> = None;
+    let finish_cell = |cell: &mut Option<(HtmlCell, String)>, row: &mut Option<Vec<HtmlCell>>| {
+        if let Some((mut c, text)) = cell.take() {
+            c.text = cell_text(&text);
+            row.get_or_insert_with(Vec::new).push(c);
+        }
+    };
+
+    let mut pos = start;
+    while let Some(offset) = lower[pos..end].find('<') {
+        let open = pos + offset;
+        if let Some((_, text)) = cell.as_mut() {
+            text.push_str(&html[pos..open].replace(['\r', '\n'], " "));
+        }
+        let Some(close) = lower[open..end].find('>').map(|i| open + i) else {
+            break;
+        };
+        let tag = &html[open + 1..close];
+        let tag_lower = &lower[open + 1..close];
+        pos = close + 1;
+        if tag_lower.starts_with("!--") {
+            pos = lower[open..end].find("-->").map_or(end, |i| open + i + 3);
+        } else if is_tag(tag_lower, "style") || is_tag(tag_lower, "script") {
+            let name = if is_tag(tag_lower, "style") {
+                "</style"
+            } else {
+                "</script"
+            };
+            pos = lower[pos..end].find(name).map_or(end, |i| pos + i);
+        } else if is_tag(tag_lower, "tr") || is_tag(tag_lower, "/tr") {
+            finish_cell(&mut cell, &mut row);
+            rows.extend(row.take());
+        } else if is_tag(tag_lower, "td") || is_tag(tag_lower, "th") {
+            finish_cell(&mut cell, &mut row);
+            let attrs = attributes(tag);
+            let span = |name: &str| {
+                attrs
+                    .get(name)
+                    .and_then(|v| v.trim().parse::<usize>().ok())
+                    .unwrap_or(1)
+                    .clamp(1, MAX_SPAN)
+            };
+            cell = Some((
+                HtmlCell {
+                    text: String::new(),
+                    raw: raw_value(&attrs),
+                    colspan: span("colspan"),
+                    rowspan: span("rowspan"),
+                },
+                String::new(),
+            ));
+        } else if is_tag(tag_lower, "/td") || is_tag(tag_lower, "/th") {
+            finish_cell(&mut cell, &mut row);
+        } else if let Some((_, text)) = cell.as_mut() {
+            if is_tag(tag_lower, "br") || is_tag(tag_lower, "/p") || is_tag(tag_lower, "/div") {
+                text.push('\n');
+            }
+        }
+    }
+    finish_cell(&mut cell, &mut row);
+    rows.extend(row.take());
+    rows.retain(|row| !row.is_empty());
+    (!rows.is_empty()).then_some(rows)
+}
+
+// 按 rowspan / colspan 展开为规整的行列
+fn expand_cells(rows: Vec<Vec<HtmlCell>>, options: &PasteOptions) -> Vec<Vec<String>> {
+    let mut grid: Vec<Vec<Option<String>>> = Vec::new();
+    for (r, row) in rows.into_iter().enumerate() {
+        let mut c = 0;
+        for cell in row {
+            if grid.len() <= r {
+                grid.resize_with(r + 1, Vec::new);
+            }
+            // 跳过上面的合并单元格占用的格子
+            while grid[r].get(c).is_some_and(Option::is_some) {
+                c += 1;
+            }
+            let value = match (options.numbers, cell.raw) {
+                (NumberFormat::Raw, Some(raw)) => raw,
+                _ => cell.text,
+            };
+            for dr in 0..cell.rowspan {
+                if grid.len() <= r + dr {
+                    grid.resize_with(r + dr + 1, Vec::new);
+                }
+                for dc in 0..cell.colspan {
+                    let target = &mut grid[r + dr];
+                    if target.len() <= c + dc {
+                        target.resize(c + dc + 1, None);
+                    }
+                    target[c + dc] = Some(
+                        if (dr == 0 && dc == 0) || options.merged == MergedCells::Repeat {
+                            value.clone()
+                        } else {
+                            String::new()
+                        },
+                    );
+                }
+            }
+            c += cell.colspan;
+        }
+    }
+    grid.into_iter()
+        .map(|row| row.into_iter().map(Option::unwrap_or_default).collect())
+        .collect()
+}
+
+// 去掉全空的行和列（选区边缘常带空白格）
+fn trim_empty(mut rows: Vec<Vec<String>>) -> Vec<Vec<String>> {
+    rows.retain(|row| row.iter().any(|cell| !cell.trim().is_empty()));
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let keep: Vec<bool> = (0..columns)
+        .map(|i| {
+            rows.iter()
+                .any(|row| row.get(i).is_some_and(|c| !c.trim().is_empty()))
+        })
+        .collect();
+    rows.into_iter()
+        .map(|row| {
+            row.into_iter()
+                .enumerate()
+                .filter(|(i, _)| keep[*i])
+                .map(|(_, c)| c)
+                .collect()
+        })
+        .collect()
+}
+
+// path 与 text 都为空时读取剪贴板
+#[tauri::command]
+pub fn import_csv(
+    app: AppHandle,
+    path: Option<String>,
+    text: Option<String>,
+    options: Option<CsvOptions>,
+) -> Result<String, MobiError> {
+    let options = options.unwrap_or_default();
+    let text = match (path.filter(|p| !p.is_empty()), text) {
+        (Some(path), _) => {
+            let bytes = fs::read(&path).map_err(|e| format!("读取文件失败: {}", e))?;
+            decode(&bytes, &options.encoding)?
+        }
+        (None, Some(text)) => text,
+        (None, None) => app
+            .clipboard()
+            .read_text()
+            .map_err(|_| "剪贴板中没有文本".to_string())?,
+    };
+    let text = text.trim_start_matches('\u{feff}');
+    let delimiter = options.delimiter.unwrap_or_else(|| sniff(text));
+    let rows = parse(text, delimiter, None);
+    if rows.is_empty() {
+        return Err("没有可以导入的数据".into());
+    }
+    Ok(to_markdown(&rows, options.header))
+}
+
+// selection 为编辑器中选中的 Markdown 表格；output 不为空时写入文件（带 BOM，Excel 能正确识别 UTF-8），否则返回 CSV 文本
+#[tauri::command]
+pub fn export_table_csv(
+    selection: String,
+    delimiter: Option<char>,
+    output: Option<String>,
+) -> Result<String, MobiError> {
+    let delimiter = delimiter.unwrap_or(',');
+    let rows: Vec<Vec<String>> = selection
+        .lines()
+        .filter(|line| line.trim_start().starts_with('|') || line.contains(" | "))
+        .map(split_row)
+        .filter(|cells| !is_separator_row(cells))
+        .collect();
+    if rows.is_empty() {
+        return Err("选中的内容不是 Markdown 表格".into());
+    }
+    let csv = rows
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|cell| csv_field(cell, delimiter))
+                .collect::<Vec<_>>()
+                .join(&delimiter.to_string())
+        })
+        .collect::<Vec<_>>()
+        .join("\r\n")
+        + "\r\n";
+    if let Some(output) = output.filter(|o| !o.is_empty()) {
+        fs::write(&output, format!("\u{feff}{}", csv))
+            .map_err(|e| format!("保存文件失败: {}", e))?;
+    }
+    Ok(csv)
+}
+
+// html / text 为粘贴事件中的 text/html 与 text/plain；不是表格时返回 None，由编辑器按普通文本粘贴
+#[tauri::command]
+pub fn paste_as_table(
+    html: Option<String>,
+    text: Option<String>,
+    options: Option<PasteOptions>,
+) -> Result<Option<String>, MobiError> {
+    let options = options.unwrap_or_default();
+    let rows = match html.as_deref().and_then(parse_html_table) {
+        Some(cells) => expand_cells(cells, &options),
+        None => {
+            let text = text.unwrap_or_default();
+            let text = text.trim_start_matches('\u{feff}');
+            if !text.contains('\t') {
+                return Ok(None);
+            }
+            let rows = parse(text, '\t', None);
+            // 用 Tab 缩进的代码不是表格：要求各行列数一致，且不是每行都以 Tab 开头
+            let columns = rows.first().map_or(0, Vec::len);
+            if columns < 2
+                || rows.iter().any(|row| row.len() != columns)
+                || rows.iter().all(|row| row[0].is_empty())
+            {
+                return Ok(None);
+            }
+            rows
+        }
+    };
+    let rows = trim_empty(rows);
+    // 单个单元格按普通文本粘贴更自然
+    if rows.len() < 2 && rows.first().is_none_or(|row| row.len() < 2) {
+        return Ok(None);
+    }
+    Ok(Some(to_markdown(&rows, options.header)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_quoted_fields() {
+        assert_eq!(
+            parse(
+                "a,\"b,c\",\"say \"\"hi\"\"\"\r\n\"two\nlines\",x,\n\n",
+                ',',
+                None
+            ),
+            vec![vec!["a", "b,c", "say \"hi\""], vec!["two\nlines", "x", ""],]
+        );
+    }
+
+    // 引号内的逗号不计入，分号才是分隔符
+    #[test]
+    fn sniffs_delimiter() {
+        assert_eq!(sniff("名称;价格\n\"苹果,红\";3\n梨;2\n"), ';');
+        assert_eq!(sniff("a\tb\tc\n1\t2\t3\n"), '\t');
+        assert_eq!(sniff("only one column\n"), ',');
+    }
+
+    // 全角字符按两格对齐，数字列右对齐
+    #[test]
+    fn aligns_markdown_table() {
+        let rows = vec![
+            vec!["名称".to_string(), "数量".to_string()],
+            vec!["苹果".to_string(), "3".to_string()],
+            vec!["pear".to_string(), "1,200".to_string()],
+            vec!["a|b".to_string(), String::new()],
+        ];
+        assert_eq!(
+            to_markdown(&rows, true),
+            "| 名称 |  数量 |\n\
+             | ---- | ----: |\n\
+             | 苹果 |     3 |\n\
+             | pear | 1,200 |\n\
+             | a\\|b |       |"
+        );
+        assert_eq!(
+            to_markdown(&rows[1..2], false),
+            "| 列 1 | 列 2 |\n| ---- | ---: |\n| 苹果 |    3 |"
+        );
+    }
+
+    #[test]
+    fn exports_markdown_table_as_csv() {
+        let selection = "| a | b |\n|:--|--:|\n| x\\|y | `p|q` |\n| \"q\" | one<br>two |\n";
+        assert_eq!(
+            export_table_csv(selection.to_string(), None, None).ok(),
+            Some("a,b\r\nx|y,`p|q`\r\n\"\"\"q\"\"\",\"one\ntwo\"\r\n".to_string())
+        );
+    }
+
+    // 用 Tab 缩进的代码、列数不一致的文本不按表格粘贴
+    #[test]
+    fn pastes_only_real_tsv_as_table() {
+        let paste = |text: &str| {
+            paste_as_table(None, Some(text.to_string()), None)
+                .ok()
+                .flatten()
+        };
+        assert_eq!(
+            paste("a\tb\nx\t2\n"),
+            Some("| a   |   b |\n| --- | --: |\n| x   |   2 |".to_string())
+        );
+        assert_eq!(paste("\tfoo();\n\tbar();\n"), None);
+        assert_eq!(paste("a\tb\nc\n"), None);
+        assert_eq!(
+            paste("a\tb\n"),
+            Some("| a   | b   |\n| --- | --- |".to_string())
+        );
+    }
+}
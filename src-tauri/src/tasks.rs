@@ -0,0 +1,309 @@
+// 任务汇总：解析所有笔记中的 - [ ] / - [x] 复选框（可带 📅 截止日期），增量维护在工作区索引库中
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::Local;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::error::MobiError;
+use crate::{document, index, markdown, workspace};
+
+const DUE_MARKER: &str = "📅";
+const DONE_MARKER: &str = "✅";
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Task {
+    pub path: String,
+    // 行号从 0 开始
+    pub line: usize,
+    pub text: String,
+    pub done: bool,
+    // YYYY-MM-DD
+    pub due: Option<String>,
+    // HH:MM
+    pub due_time: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TaskFilter {
+    // open / done / all，默认 open
+    status: Option<String>,
+    due_before: Option<String>,
+    due_after: Option<String>,
+    // 只看某个笔记或目录（绝对路径）
+    path: Option<String>,
+    query: Option<String>,
+}
+
+struct ParsedTask {
+    line: usize,
+    text: String,
+    done: bool,
+    due: Option<String>,
+    due_time: Option<String>,
+}
+
+fn ensure_schema(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS task_notes (
+            path TEXT PRIMARY KEY,
+            mtime INTEGER NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS tasks (
+            path TEXT NOT NULL,
+            line INTEGER NOT NULL,
+            text TEXT NOT NULL,
+            done INTEGER NOT NULL,
+            due TEXT,
+            due_time TEXT,
+            PRIMARY KEY (path, line)
+        );
+        CREATE INDEX IF NOT EXISTS idx_tasks_due ON tasks(due);",
+    )
+    .map_err(|e| format!("初始化任务索引失败: {}", e))
+}
+
+// 复选框行拆成 (缩进和列表标记, 是否完成, 正文)
+fn split_checkbox(line: &str) -> Option<(&str, bool, &str)> {
+    let trimmed = line.trim_start();
+    let indent = line.len() - trimmed.len();
+    let marker_len =
+        if trimmed.starts_with("- ") || trimmed.starts_with("* ") || trimmed.starts_with("+ ") {
+            2
+        } else {
+            let digits = trimmed.chars().take_while(|c| c.is_ascii_digit()).count();
+            let rest = &trimmed[digits..];
+            if digits == 0 || !(rest.starts_with(". ") || rest.starts_with(") ")) {
+                return None;
+            }
+            digits + 2
+        };
+    let rest = &trimmed[marker_len..];
+    let done = match rest.get(..4) {
+        Some("[ ] ") => false,
+        Some("[x] ") | Some("[X] ") => true,
+        _ => return None,
+    };
+    Some((&line[..indent + marker_len], done, &rest[4..]))
+}
+
+// 📅 2025-02-01 或 📅 2025-02-01 14:30
+fn parse_due(text: &str) -> (String, Option<String>, Option<String>) {
+    let Some(pos) = text.find(DUE_MARKER) else {
+        return (text.trim().to_string(), None, None);
+    };
+    let after = text[pos + DUE_MARKER.len()..].trim_start();
+    let date: String = after.chars().take(10).collect();
+    if chrono::NaiveDate::parse_from_str(&date, "%Y-%m-%d").is_err() {
+        return (text.trim().to_string(), None, None);
+    }
+    let mut consumed = text.len() - after.len() + date.len();
+    let rest = &after[date.len()..];
+    let time = rest
+        .strip_prefix(' ')
+        .and_then(|r| r.get(..5))
+        .filter(|t| chrono::NaiveTime::parse_from_str(t, "%H:%M").is_ok())
+        .map(|t| t.to_string());
+    if time.is_some() {
+        consumed += 6;
+    }
+    let stripped = format!("{}{}", &text[..pos], &text[consumed..]);
+    (
+        stripped.split_whitespace().collect::<Vec<_>>().join(" "),
+        Some(date),
+        time,
+    )
+}
+
+fn parse_tasks(content: &str) -> Vec<ParsedTask> {
+    let code: HashSet<usize> = markdown::fenced_blocks(content)
+        .iter()
+        .flat_map(|b| b.start_line..=b.end_line)
+        .collect();
+    content
+        .lines()
+        .enumerate()
+        .filter(|(i, _)| !code.contains(i))
+        .filter_map(|(i, line)| {
+            let (_, done, body) = split_checkbox(line)?;
+            let body = match body.find(DONE_MARKER) {
+                Some(pos) => &body[..pos],
+                None => body,
+            };
+            let (text, due, due_time) = parse_due(body);
+            Some(ParsedTask {
+                line: i,
+                text,
+                done,
+                due,
+                due_time,
+            })
+        })
+        .collect()
+}
+
+fn index_note(conn: &Connection, relative: &str, content: &str, mtime: i64) -> Result<(), String> {
+    conn.execute("DELETE FROM tasks WHERE path = ?1", [relative])
+        .map_err(|e| e.to_string())?;
+    for task in parse_tasks(content) {
+        conn.execute(
+            "INSERT INTO tasks (path, line, text, done, due, due_time) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![relative, task.line, task.text, task.done, task.due, task.due_time],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    conn.execute(
+        "INSERT OR REPLACE INTO task_notes (path, mtime) VALUES (?1, ?2)",
+        params![relative, mtime],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// 只重新解析修改过的笔记，并清理已删除笔记的任务
+pub fn refresh(root: &Path) -> Result<Connection, String> {
+    let conn = index::open(root)?;
+    ensure_schema(&conn)?;
+
+    let mut existing: HashMap<String, i64> = HashMap::new();
+    {
+        let mut stmt = conn
+            .prepare("SELECT path, mtime FROM task_notes")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
+            .map_err(|e| e.to_string())?;
+        existing.extend(rows.flatten());
+    }
+
+    let mut current = HashSet::new();
+    for file in workspace::note_files(root) {
+        let relative = workspace::relative_path(root, &file);
+        let mtime = workspace::modified_secs(&file);
+        if existing.get(&relative) != Some(&mtime) {
+            let content = fs::read_to_string(&file).unwrap_or_default();
+            index_note(&conn, &relative, &content, mtime)?;
+        }
+        current.insert(relative);
+    }
+    for path in existing.keys().filter(|p| !current.contains(*p)) {
+        conn.execute("DELETE FROM tasks WHERE path = ?1", [path])
+            .map_err(|e| e.to_string())?;
+        conn.execute("DELETE FROM task_notes WHERE path = ?1", [path])
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(conn)
+}
+
+pub fn query(root: &Path, conn: &Connection, filter: &TaskFilter) -> Result<Vec<Task>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT path, line, text, done, due, due_time FROM tasks
+             ORDER BY due IS NULL, due, due_time, path, line",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |r| {
+            Ok(Task {
+                path: root
+                    .join(r.get::<_, String>(0)?)
+                    .to_string_lossy()
+                    .to_string(),
+                line: r.get(1)?,
+                text: r.get(2)?,
+                done: r.get(3)?,
+                due: r.get(4)?,
+                due_time: r.get(5)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    let query = filter.query.as_deref().map(|q| q.to_lowercase());
+    let tasks = rows
+        .flatten()
+        .filter(|t| match filter.status.as_deref().unwrap_or("open") {
+            "done" => t.done,
+            "all" => true,
+            _ => !t.done,
+        })
+        .filter(|t| match (&filter.due_before, &t.due) {
+            (Some(before), Some(due)) => due <= before,
+            (Some(_), None) => false,
+            _ => true,
+        })
+        .filter(|t| match (&filter.due_after, &t.due) {
+            (Some(after), Some(due)) => due >= after,
+            (Some(_), None) => false,
+            _ => true,
+        })
+        .filter(|t| {
+            filter
+                .path
+                .as_deref()
+                .is_none_or(|p| Path::new(&t.path).starts_with(p))
+        })
+        .filter(|t| {
+            query
+                .as_deref()
+                .is_none_or(|q| t.text.to_lowercase().contains(q))
+        })
+        .collect();
+    Ok(tasks)
+}
+
+#[tauri::command]
+pub fn get_tasks(workspace: String, filter: Option<TaskFilter>) -> Result<Vec<Task>, MobiError> {
+    let root = PathBuf::from(&workspace);
+    let conn = refresh(&root)?;
+    Ok(query(&root, &conn, &filter.unwrap_or_default())?)
+}
+
+// 切换第 line 行复选框的状态，完成时追加 ✅ 日期，取消时去掉；返回文件新内容
+#[tauri::command]
+pub fn toggle_task(workspace: String, path: String, line: usize) -> Result<String, MobiError> {
+    let content = fs::read_to_string(&path).map_err(|e| format!("读取文件失败: {}", e))?;
+    let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+    let target = lines.get(line).ok_or("行号超出范围")?;
+    let (prefix, done, body) = split_checkbox(target).ok_or("该行不是任务")?;
+
+    let toggled = if done {
+        let body = match body.find(DONE_MARKER) {
+            Some(pos) => body[..pos].trim_end(),
+            None => body,
+        };
+        format!("{}[ ] {}", prefix, body)
+    } else {
+        let today = Local::now().format("%Y-%m-%d");
+        format!(
+            "{}[x] {} {} {}",
+            prefix,
+            body.trim_end(),
+            DONE_MARKER,
+            today
+        )
+    };
+    lines[line] = toggled;
+
+    let mut text = lines.join("\n");
+    if content.ends_with('\n') {
+        text.push('\n');
+    }
+    document::write_note(Path::new(&path), &text)?;
+
+    let root = PathBuf::from(&workspace);
+    let conn = index::open(&root)?;
+    ensure_schema(&conn)?;
+    let relative = workspace::relative_path(&root, Path::new(&path));
+    index_note(
+        &conn,
+        &relative,
+        &text,
+        workspace::modified_secs(Path::new(&path)),
+    )?;
+    Ok(text)
+}
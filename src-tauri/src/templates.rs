@@ -0,0 +1,230 @@
+// 笔记模板：内置模板 + 全局模板目录 + 工作区 .mobi/templates，新建笔记时替换 {{变量}}
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::error::MobiError;
+use crate::workspace;
+
+// 同名模板的优先级：工作区 > 全局 > 内置
+const BUILTIN: &[(&str, &str)] = &[
+    (
+        "会议记录",
+        "# {{title}}\n\n- 时间：{{date}} {{time}}\n- 参会人：{{参会人}}\n\n## 议题\n\n{{cursor}}\n\n## 结论\n\n## 待办\n\n- [ ] \n",
+    ),
+    (
+        "周回顾",
+        "# {{date:%Y 年第 %V 周}}回顾\n\n## 本周完成\n\n{{cursor}}\n\n## 遇到的问题\n\n## 下周计划\n\n- [ ] \n",
+    ),
+    (
+        "博客文章",
+        "---\ntitle: {{title}}\ndate: {{date}}\ntags: []\n---\n\n# {{title}}\n\n{{cursor}}\n",
+    ),
+];
+
+// 内置变量，其余 {{name}} 视为需要用户填写的自定义变量
+const BUILTIN_VARIABLES: &[&str] = &["title", "date", "time", "datetime", "cursor"];
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateInfo {
+    name: String,
+    source: &'static str,
+    // 需要用户填写的自定义变量
+    prompts: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedNote {
+    path: String,
+    content: String,
+    // {{cursor}} 所在位置（UTF-16 偏移），没有则为 None
+    cursor: Option<usize>,
+}
+
+fn global_dir(app: &AppHandle) -> Option<PathBuf> {
+    Some(app.path().app_config_dir().ok()?.join("templates"))
+}
+
+fn workspace_dir(workspace: &str) -> PathBuf {
+    Path::new(workspace).join(".mobi").join("templates")
+}
+
+fn read_dir_templates(dir: &Path) -> Vec<(String, String)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| workspace::is_note(p))
+        .filter_map(|p| {
+            let name = p.file_stem()?.to_string_lossy().to_string();
+            Some((name, fs::read_to_string(&p).ok()?))
+        })
+        .collect()
+}
+
+// (名称, 来源, 内容)，按优先级去重
+fn all_templates(app: &AppHandle, workspace: Option<&str>) -> Vec<(String, &'static str, String)> {
+    let mut templates: Vec<(String, &'static str, String)> = Vec::new();
+    let mut add = |name: String, source: &'static str, body: String| match templates
+        .iter_mut()
+        .find(|(n, _, _)| *n == name)
+    {
+        Some(existing) => *existing = (name, source, body),
+        None => templates.push((name, source, body)),
+    };
+    for (name, body) in BUILTIN {
+        add(name.to_string(), "builtin", body.to_string());
+    }
+    if let Some(dir) = global_dir(app) {
+        for (name, body) in read_dir_templates(&dir) {
+            add(name, "global", body);
+        }
+    }
+    if let Some(workspace) = workspace {
+        for (name, body) in read_dir_templates(&workspace_dir(workspace)) {
+            add(name, "workspace", body);
+        }
+    }
+    templates
+}
+
+pub fn find_template(app: &AppHandle, workspace: Option<&str>, name: &str) -> Option<String> {
+    all_templates(app, workspace)
+        .into_iter()
+        .find(|(n, _, _)| n == name)
+        .map(|(_, _, body)| body)
+}
+
+// 模板中出现的 {{name}} / {{name:参数}}，按出现顺序去重
+fn variables(body: &str) -> Vec<(String, Option<String>)> {
+    let mut found: Vec<(String, Option<String>)> = Vec::new();
+    let mut rest = body;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let inner = rest[start + 2..start + 2 + len].trim();
+        let (name, arg) = match inner.split_once(':') {
+            Some((name, arg)) => (name.trim().to_string(), Some(arg.to_string())),
+            None => (inner.to_string(), None),
+        };
+        if !name.is_empty() && !found.iter().any(|(n, a)| *n == name && *a == arg) {
+            found.push((name, arg));
+        }
+        rest = &rest[start + 2 + len + 2..];
+    }
+    found
+}
+
+fn prompts(body: &str) -> Vec<String> {
+    let mut prompts: Vec<String> = Vec::new();
+    for (name, _) in variables(body) {
+        if !BUILTIN_VARIABLES.contains(&name.as_str()) && !prompts.contains(&name) {
+            prompts.push(name);
+        }
+    }
+    prompts
+}
+
+// 格式串无效时原样返回，chrono 的 to_string 遇到无效格式会 panic
+pub fn format_date<Tz: chrono::TimeZone>(date: &chrono::DateTime<Tz>, format: &str) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    let mut text = String::new();
+    match write!(text, "{}", date.format(format)) {
+        Ok(()) => text,
+        Err(_) => format.to_string(),
+    }
+}
+
+// 替换变量，日期类变量取 now，返回结果和 {{cursor}} 的字节位置
+pub fn render(
+    body: &str,
+    values: &HashMap<String, String>,
+    now: &DateTime<Local>,
+) -> (String, Option<usize>) {
+    let mut output = String::with_capacity(body.len());
+    let mut cursor = None;
+    let mut rest = body;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        output.push_str(&rest[..start]);
+        let inner = rest[start + 2..start + 2 + len].trim();
+        let (name, arg) = match inner.split_once(':') {
+            Some((name, arg)) => (name.trim(), Some(arg)),
+            None => (inner, None),
+        };
+        // 日期类变量支持 chrono 格式参数，如 {{date:%Y年%m月%d日}}
+        let formatted = |default: &str| format_date(now, arg.unwrap_or(default));
+        match name {
+            "cursor" => cursor = cursor.or(Some(output.len())),
+            "date" => output.push_str(&formatted("%Y-%m-%d")),
+            "time" => output.push_str(&formatted("%H:%M")),
+            "datetime" => output.push_str(&formatted("%Y-%m-%d %H:%M")),
+            other => output.push_str(values.get(other).map(|v| v.as_str()).unwrap_or_default()),
+        }
+        rest = &rest[start + 2 + len + 2..];
+    }
+    output.push_str(rest);
+    (output, cursor)
+}
+
+pub fn utf16_offset(text: &str, byte: usize) -> usize {
+    text[..byte].encode_utf16().count()
+}
+
+#[tauri::command]
+pub fn list_templates(app: AppHandle, workspace: Option<String>) -> Vec<TemplateInfo> {
+    all_templates(&app, workspace.as_deref())
+        .into_iter()
+        .map(|(name, source, body)| TemplateInfo {
+            prompts: prompts(&body),
+            name,
+            source,
+        })
+        .collect()
+}
+
+// 在 directory 下用模板新建笔记，文件名取自标题（没有标题时用日期），不会覆盖已有文件
+#[tauri::command]
+pub fn create_from_template(
+    app: AppHandle,
+    workspace: Option<String>,
+    name: String,
+    directory: String,
+    variables: Option<HashMap<String, String>>,
+) -> Result<CreatedNote, MobiError> {
+    let body = find_template(&app, workspace.as_deref(), &name)
+        .ok_or_else(|| format!("找不到模板: {}", name))?;
+    let values = variables.unwrap_or_default();
+    let (content, cursor) = render(&body, &values, &Local::now());
+
+    let title = values.get("title").map(|t| t.trim()).unwrap_or_default();
+    let stem = match workspace::sanitize_file_name(title) {
+        stem if stem.is_empty() => Local::now().format("%Y-%m-%d").to_string(),
+        stem => stem,
+    };
+    let dir = PathBuf::from(&directory);
+    fs::create_dir_all(&dir).map_err(|e| format!("创建目录失败: {}", e))?;
+    let path = workspace::unique_path(&dir, &stem, "md");
+    fs::write(&path, &content).map_err(|e| format!("创建笔记失败: {}", e))?;
+
+    Ok(CreatedNote {
+        path: path.to_string_lossy().to_string(),
+        cursor: cursor.map(|c| utf16_offset(&content, c)),
+        content,
+    })
+}
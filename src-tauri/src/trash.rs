@@ -0,0 +1,328 @@
+// 工作区回收站：应用内删除的笔记和文件夹移到 {workspace}/.mobi/trash/{id}/，可原样恢复，链接到它的笔记不受影响
+// 只被这篇笔记引用的本地图片和附件一起移入回收站；超过工作区设置 trashDays 天的条目自动清除
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{MobiError, MutexExt};
+use crate::{encryption, markdown, workspace};
+
+// 串行化回收站索引的读写
+static LOCK: Mutex<()> = Mutex::new(());
+
+const DAY_MILLIS: i64 = 24 * 60 * 60 * 1000;
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashEntry {
+    id: String,
+    // 原来相对工作区的路径，用 / 分隔
+    path: String,
+    is_directory: bool,
+    // 一起移入的附件（原相对路径）
+    #[serde(default)]
+    assets: Vec<String>,
+    // 毫秒时间戳
+    deleted: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashedInfo {
+    #[serde(flatten)]
+    entry: TrashEntry,
+    // 字节，文件夹为其中文件的总大小
+    size: u64,
+    // 自动清除的时间（毫秒），不自动清除时为空
+    expires: Option<i64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreResult {
+    // 恢复后的绝对路径
+    path: String,
+    // 原位置已被占用，恢复为另一个名字
+    renamed: bool,
+    assets: Vec<String>,
+}
+
+fn trash_dir(root: &Path) -> Result<PathBuf, MobiError> {
+    let dir = workspace::data_dir(root)?.join("trash");
+    fs::create_dir_all(&dir).map_err(|e| MobiError::io("创建回收站目录失败", e))?;
+    Ok(dir)
+}
+
+fn load(root: &Path) -> Vec<TrashEntry> {
+    trash_dir(root)
+        .ok()
+        .and_then(|dir| fs::read_to_string(dir.join("index.json")).ok())
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+fn save(root: &Path, entries: &[TrashEntry]) -> Result<(), MobiError> {
+    let text = serde_json::to_string_pretty(entries).map_err(|e| e.to_string())?;
+    fs::write(trash_dir(root)?.join("index.json"), text)
+        .map_err(|e| MobiError::io("保存回收站索引失败", e))
+}
+
+fn size_of(path: &Path) -> u64 {
+    match fs::symlink_metadata(path) {
+        Ok(meta) if meta.is_dir() => fs::read_dir(path)
+            .map(|entries| entries.flatten().map(|e| size_of(&e.path())).sum())
+            .unwrap_or(0),
+        Ok(meta) => meta.len(),
+        Err(_) => 0,
+    }
+}
+
+// 不与现有文件重名的路径：name.png、name 2.png ...
+fn free_path(path: &Path) -> PathBuf {
+    if !path.exists() {
+        return path.to_path_buf();
+    }
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    workspace::unique_path(dir, &stem, &extension)
+}
+
+fn move_path(from: &Path, to: &Path) -> Result<(), MobiError> {
+    if let Some(dir) = to.parent() {
+        fs::create_dir_all(dir).map_err(|e| MobiError::io("创建目录失败", e))?;
+    }
+    fs::rename(from, to).map_err(|e| MobiError::io("移动文件失败", e))
+}
+
+// 笔记引用的工作区内本地文件（不含笔记），且没有被其他笔记引用
+fn owned_assets(root: &Path, note: &Path, content: &str) -> Vec<PathBuf> {
+    let mut targets: Vec<PathBuf> = Vec::new();
+    markdown::rewrite_link_destinations(content, |destination| {
+        if let Some(target) = workspace::resolve_note_link(root, note, destination) {
+            if target.starts_with(root)
+                && target.is_file()
+                && !workspace::is_note(&target)
+                && !targets.contains(&target)
+            {
+                targets.push(target);
+            }
+        }
+        None
+    });
+    if targets.is_empty() {
+        return targets;
+    }
+    let mut shared: HashSet<PathBuf> = HashSet::new();
+    for other in workspace::note_files(root) {
+        if other == note {
+            continue;
+        }
+        let Ok(text) = fs::read_to_string(&other) else {
+            continue;
+        };
+        markdown::rewrite_link_destinations(&text, |destination| {
+            if let Some(target) = workspace::resolve_note_link(root, &other, destination) {
+                shared.insert(target);
+            }
+            None
+        });
+    }
+    targets.retain(|t| !shared.contains(t));
+    targets
+}
+
+// 删除超过保留天数的条目，返回剩下的
+fn purge_expired(root: &Path, entries: Vec<TrashEntry>) -> Result<Vec<TrashEntry>, String> {
+    let days = workspace::settings(root).trash_days;
+    if days == 0 {
+        return Ok(entries);
+    }
+    let cutoff = Utc::now().timestamp_millis() - days as i64 * DAY_MILLIS;
+    let dir = trash_dir(root)?;
+    let (expired, kept): (Vec<_>, Vec<_>) = entries.into_iter().partition(|e| e.deleted < cutoff);
+    if !expired.is_empty() {
+        for entry in &expired {
+            let _ = fs::remove_dir_all(dir.join(&entry.id));
+        }
+        save(root, &kept)?;
+    }
+    Ok(kept)
+}
+
+// 从索引中移除原路径为 relative 的条目，返回它们在回收站中的目录，由调用方负责删除（粉碎笔记时用）
+pub fn take(root: &Path, relative: &str) -> Result<Vec<PathBuf>, String> {
+    let _guard = LOCK.locked();
+    let dir = trash_dir(root)?;
+    let (taken, kept): (Vec<_>, Vec<_>) = load(root).into_iter().partition(|e| e.path == relative);
+    if !taken.is_empty() {
+        save(root, &kept)?;
+    }
+    Ok(taken.iter().map(|e| dir.join(&e.id)).collect())
+}
+
+// 把笔记或文件夹移入回收站，代替直接删除
+#[tauri::command]
+pub fn trash_item(workspace: String, path: String) -> Result<TrashEntry, MobiError> {
+    // 比较前先规范化工作区和所在目录，"{工作区}/../别处" 这类路径不能绕过检查；文件本身不解析，符号链接按链接本身移入
+    let root = fs::canonicalize(&workspace).map_err(|e| MobiError::io("打开工作区失败", e))?;
+    let requested = PathBuf::from(&path);
+    let source = match (requested.parent(), requested.file_name()) {
+        (Some(parent), Some(name)) => fs::canonicalize(parent)
+            .map_err(|e| MobiError::io("删除失败", e))?
+            .join(name),
+        _ => return Err("无效的路径".into()),
+    };
+    if !source.starts_with(&root) || source == root {
+        return Err("只能删除工作区内的文件".into());
+    }
+    if source.starts_with(root.join(".mobi")) {
+        return Err("不能删除工作区数据目录".into());
+    }
+    let meta = fs::symlink_metadata(&source).map_err(|e| MobiError::io("删除失败", e))?;
+    let is_directory = meta.is_dir();
+
+    let _guard = LOCK.locked();
+    let mut entries = purge_expired(&root, load(&root))?;
+    let id = workspace::new_id(|id| entries.iter().any(|e| e.id == id));
+    let slot = trash_dir(&root)?.join(&id);
+    let name = source
+        .file_name()
+        .map(|n| n.to_os_string())
+        .ok_or("无效的路径")?;
+
+    let assets = if !is_directory && workspace::is_note(&source) {
+        let content = fs::read_to_string(&source).unwrap_or_default();
+        if encryption::is_envelope(&content) {
+            Vec::new()
+        } else {
+            owned_assets(&root, &source, &content)
+        }
+    } else {
+        Vec::new()
+    };
+
+    move_path(&source, &slot.join(&name))?;
+    let mut moved = Vec::new();
+    for asset in assets {
+        let relative = workspace::relative_path(&root, &asset);
+        if move_path(&asset, &slot.join("assets").join(&relative)).is_ok() {
+            moved.push(relative);
+        }
+    }
+
+    let entry = TrashEntry {
+        id,
+        path: workspace::relative_path(&root, &source),
+        is_directory,
+        assets: moved,
+        deleted: Utc::now().timestamp_millis(),
+    };
+    entries.push(entry.clone());
+    save(&root, &entries)?;
+    Ok(entry)
+}
+
+// 最近删除的在前
+#[tauri::command]
+pub fn list_trashed(workspace: String) -> Result<Vec<TrashedInfo>, MobiError> {
+    let root = PathBuf::from(&workspace);
+    let _guard = LOCK.locked();
+    let entries = purge_expired(&root, load(&root))?;
+    let dir = trash_dir(&root)?;
+    let days = workspace::settings(&root).trash_days;
+    let mut list: Vec<TrashedInfo> = entries
+        .into_iter()
+        .map(|entry| TrashedInfo {
+            size: size_of(&dir.join(&entry.id)),
+            expires: (days > 0).then(|| entry.deleted + days as i64 * DAY_MILLIS),
+            entry,
+        })
+        .collect();
+    list.sort_by_key(|info| std::cmp::Reverse(info.entry.deleted));
+    Ok(list)
+}
+
+// 恢复到原位置；原位置已有同名文件时加序号，附件被占用时改名并改写笔记中的链接
+#[tauri::command]
+pub fn restore_trashed(workspace: String, id: String) -> Result<RestoreResult, MobiError> {
+    let root = PathBuf::from(&workspace);
+    let _guard = LOCK.locked();
+    let mut entries = load(&root);
+    let index = entries
+        .iter()
+        .position(|e| e.id == id)
+        .ok_or("回收站中没有该条目")?;
+    let entry = entries[index].clone();
+    let slot = trash_dir(&root)?.join(&entry.id);
+    let original = root.join(&entry.path);
+    let name = original.file_name().ok_or("无效的路径")?;
+
+    let target = free_path(&original);
+    move_path(&slot.join(name), &target)?;
+
+    let mut restored = Vec::new();
+    let mut renamed_assets: Vec<(PathBuf, PathBuf)> = Vec::new();
+    for relative in &entry.assets {
+        let original_asset = root.join(relative);
+        let asset_target = free_path(&original_asset);
+        if move_path(&slot.join("assets").join(relative), &asset_target).is_ok() {
+            if asset_target != original_asset {
+                renamed_assets.push((original_asset, asset_target.clone()));
+            }
+            restored.push(asset_target.to_string_lossy().to_string());
+        }
+    }
+    if !renamed_assets.is_empty() {
+        let content = fs::read_to_string(&target).map_err(|e| MobiError::io("读取笔记失败", e))?;
+        let dir = target.parent().unwrap_or(&root).to_path_buf();
+        let rewritten = markdown::rewrite_link_destinations(&content, |destination| {
+            let resolved = workspace::resolve_note_link(&root, &original, destination)?;
+            let (_, to) = renamed_assets.iter().find(|(from, _)| *from == resolved)?;
+            let fragment = destination.find('#').map_or("", |i| &destination[i..]);
+            Some(format!(
+                "{}{}",
+                markdown::link_destination(&workspace::relative_to(&dir, to)),
+                fragment
+            ))
+        });
+        fs::write(&target, rewritten).map_err(|e| MobiError::io("更新链接失败", e))?;
+    }
+
+    let _ = fs::remove_dir_all(&slot);
+    entries.remove(index);
+    save(&root, &entries)?;
+    Ok(RestoreResult {
+        renamed: target != original,
+        path: target.to_string_lossy().to_string(),
+        assets: restored,
+    })
+}
+
+// 永久删除一个条目，id 为空时清空回收站
+#[tauri::command]
+pub fn purge_trashed(workspace: String, id: Option<String>) -> Result<(), MobiError> {
+    let root = PathBuf::from(&workspace);
+    let _guard = LOCK.locked();
+    let dir = trash_dir(&root)?;
+    let mut entries = load(&root);
+    entries.retain(|entry| {
+        let remove = id.as_ref().is_none_or(|id| *id == entry.id);
+        if remove {
+            let _ = fs::remove_dir_all(dir.join(&entry.id));
+        }
+        !remove
+    });
+    save(&root, &entries)
+}
@@ -0,0 +1,301 @@
+// 撤销历史持久化：编辑器的撤销栈以操作日志的形式按文件保存在应用数据目录，关闭后重新打开文档仍可撤销
+// 每次保存追加一条带内容摘要的标记，加载时回放到与磁盘内容一致的最后一个标记，之后未保存的操作和被外部修改过的历史都会丢弃
+// 操作中是明文，加密笔记不保存撤销历史，笔记被加密时粉碎已有的日志
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Manager};
+
+use crate::error::MobiError;
+use crate::{encryption, settings, shred};
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct UndoSettings {
+    // 超过这些天的操作丢弃，日志文件超过这些天未修改则删除
+    max_age_days: i64,
+    // 单个文件的日志超过此大小（KB）时压缩
+    max_kb: u64,
+}
+
+impl Default for UndoSettings {
+    fn default() -> Self {
+        UndoSettings {
+            max_age_days: 30,
+            max_kb: 1024,
+        }
+    }
+}
+
+// 一次编辑：在 UTF-16 偏移 at 处删除 del、插入 ins
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Edit {
+    at: usize,
+    #[serde(default)]
+    del: String,
+    #[serde(default)]
+    ins: String,
+    // 秒级时间戳
+    time: i64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase",
+    tag = "type"
+)]
+pub enum UndoOp {
+    Edit(Edit),
+    Undo { time: i64 },
+    Redo { time: i64 },
+}
+
+// 日志中的一行：前端提交的操作，或保存时写入的标记
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum Entry {
+    Saved { saved: String },
+    Op(UndoOp),
+}
+
+#[derive(Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoHistory {
+    // 由旧到新，最后一项最先撤销
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+}
+
+fn digest(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
+fn log_dir(app: &AppHandle) -> Option<PathBuf> {
+    Some(app.path().app_data_dir().ok()?.join("undo"))
+}
+
+// 日志文件名取自文档绝对路径的摘要
+pub fn log_path(app: &AppHandle, path: &str) -> Option<PathBuf> {
+    let path = fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path));
+    Some(log_dir(app)?.join(format!("{}.jsonl", digest(&path.to_string_lossy()))))
+}
+
+fn read_entries(log: &Path) -> Vec<Entry> {
+    fs::read_to_string(log)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+fn append(log: &Path, entries: &[Entry]) -> Result<(), String> {
+    if let Some(dir) = log.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("创建撤销历史目录失败: {}", e))?;
+    }
+    let mut text = String::new();
+    for entry in entries {
+        text.push_str(&serde_json::to_string(entry).map_err(|e| e.to_string())?);
+        text.push('\n');
+    }
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log)
+        .and_then(|mut file| file.write_all(text.as_bytes()))
+        .map_err(|e| format!("写入撤销历史失败: {}", e))
+}
+
+fn replay<'a>(entries: impl IntoIterator<Item = &'a Entry>) -> UndoHistory {
+    let mut history = UndoHistory::default();
+    for entry in entries {
+        match entry {
+            Entry::Op(UndoOp::Edit(edit)) => {
+                history.undo.push(edit.clone());
+                history.redo.clear();
+            }
+            Entry::Op(UndoOp::Undo { .. }) => {
+                if let Some(edit) = history.undo.pop() {
+                    history.redo.push(edit);
+                }
+            }
+            Entry::Op(UndoOp::Redo { .. }) => {
+                if let Some(edit) = history.redo.pop() {
+                    history.undo.push(edit);
+                }
+            }
+            Entry::Saved { .. } => {}
+        }
+    }
+    history
+}
+
+// 最旧的操作在最前面，过期的从头部丢弃
+fn drop_expired(history: &mut UndoHistory, max_age_days: i64) {
+    let cutoff = chrono::Local::now().timestamp() - max_age_days * 24 * 3600;
+    let expired = history.undo.iter().take_while(|e| e.time < cutoff).count();
+    history.undo.drain(..expired);
+}
+
+// 把回放结果重新写成等价的日志：撤销栈逐条写入，重做栈写入后再撤销掉
+fn entries_of(history: UndoHistory) -> Vec<Entry> {
+    let redo_count = history.redo.len();
+    let mut entries: Vec<Entry> = history
+        .undo
+        .into_iter()
+        .chain(history.redo.into_iter().rev())
+        .map(|edit| Entry::Op(UndoOp::Edit(edit)))
+        .collect();
+    let time = chrono::Local::now().timestamp();
+    entries.extend((0..redo_count).map(|_| Entry::Op(UndoOp::Undo { time })));
+    entries
+}
+
+fn edit_size(edit: &Edit) -> u64 {
+    (edit.del.len() + edit.ins.len() + 48) as u64
+}
+
+// 压缩日志：回放到最后一个保存标记，丢弃过期和超出大小的旧操作，标记之后的操作原样保留
+fn compact(log: &Path, config: &UndoSettings) {
+    let mut entries = read_entries(log);
+    let Some(last_saved) = entries
+        .iter()
+        .rposition(|e| matches!(e, Entry::Saved { .. }))
+    else {
+        // 从未保存过的历史无法再对应磁盘内容
+        let _ = fs::remove_file(log);
+        return;
+    };
+    let mut history = replay(&entries[..last_saved]);
+    drop_expired(&mut history, config.max_age_days);
+    // 压缩到上限的四分之三，避免每次追加都重写
+    let budget = config.max_kb * 1024 * 3 / 4;
+    let mut total: u64 = history
+        .undo
+        .iter()
+        .chain(&history.redo)
+        .map(edit_size)
+        .sum();
+    let mut excess = 0;
+    while total > budget && excess < history.undo.len() {
+        total -= edit_size(&history.undo[excess]);
+        excess += 1;
+    }
+    history.undo.drain(..excess);
+
+    let tail = entries.split_off(last_saved);
+    let mut compacted = entries_of(history);
+    compacted.extend(tail);
+    let _ = fs::remove_file(log);
+    let _ = append(log, &compacted);
+}
+
+// 粉碎该文档的撤销历史
+pub fn purge(app: &AppHandle, path: &str) {
+    if let Some(log) = log_path(app, path).filter(|log| log.exists()) {
+        let _ = shred::shred_file(&log);
+    }
+}
+
+// 保存文档后调用，记录此时的内容摘要
+pub fn saved(app: &AppHandle, path: &str, content: &str) {
+    if encryption::is_envelope(content) {
+        return purge(app, path);
+    }
+    if let Some(log) = log_path(app, path) {
+        if log.exists() {
+            let _ = append(
+                &log,
+                &[Entry::Saved {
+                    saved: digest(content),
+                }],
+            );
+        }
+    }
+}
+
+// 启动时在后台删除长期未使用的日志，压缩超出大小的日志
+pub fn start(app: AppHandle) {
+    thread::spawn(move || {
+        let config: UndoSettings = settings::load(&app, "undo");
+        let Some(entries) = log_dir(&app).and_then(|dir| fs::read_dir(dir).ok()) else {
+            return;
+        };
+        let max_age = std::time::Duration::from_secs(config.max_age_days.max(0) as u64 * 24 * 3600);
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let stale = metadata
+                .modified()
+                .ok()
+                .and_then(|modified| modified.elapsed().ok())
+                .is_some_and(|age| age > max_age);
+            if stale {
+                let _ = fs::remove_file(&path);
+            } else if metadata.len() > config.max_kb * 1024 {
+                compact(&path, &config);
+            }
+        }
+    });
+}
+
+// 打开文档时调用；磁盘内容与任何保存标记都对不上时（在别处修改过）历史作废
+#[tauri::command]
+pub fn load_undo_history(app: AppHandle, path: String) -> UndoHistory {
+    if encryption::is_envelope_file(Path::new(&path)) {
+        purge(&app, &path);
+        return UndoHistory::default();
+    }
+    let Some(log) = log_path(&app, &path) else {
+        return UndoHistory::default();
+    };
+    let entries = read_entries(&log);
+    let current = fs::read_to_string(&path).map(|content| digest(&content));
+    let matched = entries.iter().rposition(|e| match (e, &current) {
+        (Entry::Saved { saved }, Ok(current)) => saved == current,
+        _ => false,
+    });
+    let Some(matched) = matched else {
+        let _ = fs::remove_file(&log);
+        // 以当前内容作为起点，之后的操作才能在保存后对上
+        if let Ok(current) = current {
+            let _ = append(&log, &[Entry::Saved { saved: current }]);
+        }
+        return UndoHistory::default();
+    };
+
+    let config: UndoSettings = settings::load(&app, "undo");
+    let mut history = replay(&entries[..matched]);
+    drop_expired(&mut history, config.max_age_days);
+    // 丢弃匹配标记之后未保存的操作
+    if matched + 1 < entries.len() {
+        let _ = fs::remove_file(&log);
+        let mut kept = entries;
+        kept.truncate(matched + 1);
+        let _ = append(&log, &kept);
+    }
+    history
+}
+
+#[tauri::command]
+pub fn append_undo_ops(app: AppHandle, path: String, ops: Vec<UndoOp>) -> Result<(), MobiError> {
+    if encryption::is_envelope_file(Path::new(&path)) {
+        return Ok(());
+    }
+    let log = log_path(&app, &path).ok_or("无法定位撤销历史目录")?;
+    let entries: Vec<Entry> = ops.into_iter().map(Entry::Op).collect();
+    append(&log, &entries)?;
+    let config: UndoSettings = settings::load(&app, "undo");
+    if fs::metadata(&log).is_ok_and(|m| m.len() > config.max_kb * 1024) {
+        compact(&log, &config);
+    }
+    Ok(())
+}
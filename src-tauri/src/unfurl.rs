@@ -0,0 +1,342 @@
+// 链接信息：抓取网页的标题、简介和图标，粘贴裸链接时据此生成 [标题](地址)
+// 只读取页面开头的 <head>，结果按地址缓存在应用缓存目录中，同一链接短期内不重复请求
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use encoding_rs::{Encoding, UTF_8};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::redirect::Policy;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Manager};
+
+use crate::error::MobiError;
+use crate::network;
+
+const TIMEOUT: Duration = Duration::from_secs(8);
+const MAX_REDIRECTS: usize = 5;
+// 标题等信息都在页面开头，读到这么多就停止下载
+const MAX_BYTES: usize = 512 * 1024;
+// 缓存有效期（秒）
+const CACHE_TTL: i64 = 7 * 24 * 3600;
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkMetadata {
+    // 跟随重定向后的最终地址
+    url: String,
+    title: Option<String>,
+    description: Option<String>,
+    // 图标的绝对地址，页面没有声明时为站点根目录的 favicon.ico
+    favicon: Option<String>,
+    site_name: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CacheEntry {
+    fetched_at: i64,
+    metadata: LinkMetadata,
+}
+
+fn cache_path(app: &AppHandle, url: &str) -> Option<PathBuf> {
+    let dir = app.path().app_cache_dir().ok()?.join("links");
+    fs::create_dir_all(&dir).ok()?;
+    Some(dir.join(format!("{:x}.json", Sha256::digest(url.as_bytes()))))
+}
+
+fn read_cache(path: &PathBuf) -> Option<LinkMetadata> {
+    let entry: CacheEntry = serde_json::from_str(&fs::read_to_string(path).ok()?).ok()?;
+    (chrono::Utc::now().timestamp() - entry.fetched_at < CACHE_TTL).then_some(entry.metadata)
+}
+
+pub fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest.find(';').filter(|&end| end <= 10).and_then(|end| {
+            let name = &rest[1..end];
+            let c = match name {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                _ => name
+                    .strip_prefix("#x")
+                    .or_else(|| name.strip_prefix("#X"))
+                    .map(|hex| u32::from_str_radix(hex, 16))
+                    .or_else(|| name.strip_prefix('#').map(str::parse))
+                    .and_then(Result::ok)
+                    .and_then(char::from_u32),
+            };
+            c.map(|c| (c, end + 1))
+        });
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+// 标题中常见的换行和连续空白压成一个空格
+fn clean(text: &str) -> Option<String> {
+    let text = decode_entities(text)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    (!text.is_empty()).then_some(text)
+}
+
+// 解析标签属性，如 <meta property="og:title" content="..."> 中的键值；属性名转为小写
+pub fn attributes(tag: &str) -> HashMap<String, String> {
+    let mut attrs = HashMap::new();
+    let bytes = tag.as_bytes();
+    // 跳过标签名
+    let mut i = tag
+        .find(|c: char| c.is_ascii_whitespace())
+        .unwrap_or(tag.len());
+    while i < bytes.len() {
+        while i < bytes.len() && (bytes[i].is_ascii_whitespace() || bytes[i] == b'/') {
+            i += 1;
+        }
+        let name_start = i;
+        while i < bytes.len() && !bytes[i].is_ascii_whitespace() && !b"=/>".contains(&bytes[i]) {
+            i += 1;
+        }
+        if name_start == i {
+            break;
+        }
+        let name = tag[name_start..i].to_ascii_lowercase();
+        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        let mut value = String::new();
+        if i < bytes.len() && bytes[i] == b'=' {
+            i += 1;
+            while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+                i += 1;
+            }
+            if i < bytes.len() && (bytes[i] == b'"' || bytes[i] == b'\'') {
+                let quote = bytes[i];
+                let start = i + 1;
+                i = start;
+                while i < bytes.len() && bytes[i] != quote {
+                    i += 1;
+                }
+                value = tag[start..i.min(bytes.len())].to_string();
+                i += 1;
+            } else {
+                let start = i;
+                while i < bytes.len() && !bytes[i].is_ascii_whitespace() && bytes[i] != b'>' {
+                    i += 1;
+                }
+                value = tag[start..i].to_string();
+            }
+        }
+        attrs.entry(name).or_insert(value);
+    }
+    attrs
+}
+
+// 标签名后面紧跟空白或结束，避免 <link> 匹配到 <linkx>
+pub fn is_tag(tag: &str, name: &str) -> bool {
+    tag.strip_prefix(name).is_some_and(|rest| {
+        rest.is_empty() || rest.starts_with(|c: char| c.is_ascii_whitespace() || c == '/')
+    })
+}
+
+fn parse_head(html: &str, base: &Url) -> LinkMetadata {
+    // 与原文等长的小写副本，用来定位标签
+    let lower = html.to_ascii_lowercase();
+    let end = lower
+        .find("</head")
+        .or_else(|| lower.find("<body"))
+        .unwrap_or(html.len());
+    let mut meta: HashMap<String, String> = HashMap::new();
+    let mut title = None;
+    let mut icon: Option<(u8, String)> = None;
+
+    let mut pos = 0;
+    while let Some(offset) = lower[pos..end].find('<') {
+        let start = pos + offset;
+        let Some(close) = lower[start..].find('>').map(|i| start + i) else {
+            break;
+        };
+        let tag = &html[start + 1..close];
+        let tag_lower = &lower[start + 1..close];
+        pos = close + 1;
+        if is_tag(tag_lower, "title") && title.is_none() {
+            let text_end = lower[pos..].find("</title").map_or(end, |i| pos + i);
+            title = clean(&html[pos..text_end]);
+            pos = text_end;
+        } else if is_tag(tag_lower, "meta") {
+            let attrs = attributes(tag);
+            let key = attrs
+                .get("property")
+                .or_else(|| attrs.get("name"))
+                .map(|k| k.to_ascii_lowercase());
+            if let (Some(key), Some(content)) = (key, attrs.get("content")) {
+                meta.entry(key).or_insert_with(|| content.clone());
+            }
+        } else if is_tag(tag_lower, "link") {
+            let attrs = attributes(tag);
+            let rel = attrs
+                .get("rel")
+                .map(|r| r.to_ascii_lowercase())
+                .unwrap_or_default();
+            // 优先 icon，其次 apple-touch-icon
+            let rank = if rel.split_whitespace().any(|r| r == "icon") {
+                2
+            } else if rel.contains("apple-touch-icon") {
+                1
+            } else {
+                0
+            };
+            if let Some(href) = attrs.get("href").filter(|_| rank > 0) {
+                if icon.as_ref().is_none_or(|(best, _)| rank > *best) {
+                    icon = Some((rank, decode_entities(href)));
+                }
+            }
+        } else if let Some(name) = ["script", "style"]
+            .into_iter()
+            .find(|n| is_tag(tag_lower, n))
+        {
+            // 跳过脚本和样式内容，其中的 < 不是标签
+            pos = lower[pos..]
+                .find(&format!("</{}", name))
+                .map_or(end, |i| pos + i);
+        }
+        if pos >= end {
+            break;
+        }
+    }
+
+    let pick = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|k| meta.get(*k).and_then(|v| clean(v)))
+    };
+    let favicon = match icon {
+        Some((_, href)) => base.join(&href).ok(),
+        None => base.join("/favicon.ico").ok(),
+    };
+    LinkMetadata {
+        url: base.to_string(),
+        title: pick(&["og:title", "twitter:title"]).or(title),
+        description: pick(&["og:description", "description", "twitter:description"]),
+        favicon: favicon.map(|u| u.to_string()),
+        site_name: pick(&["og:site_name", "application-name"]),
+    }
+}
+
+// 编码优先取响应头，其次页面开头的 <meta charset>，都没有时按 UTF-8
+fn decode(bytes: &[u8], content_type: Option<&str>) -> String {
+    let label = |text: &str| {
+        let lower = text.to_ascii_lowercase();
+        let start = lower.find("charset=")? + "charset=".len();
+        let value: String = lower[start..]
+            .trim_start_matches(['"', '\''])
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+            .collect();
+        Encoding::for_label(value.as_bytes())
+    };
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(2048)]);
+    let encoding = content_type
+        .and_then(label)
+        .or_else(|| label(&head))
+        .unwrap_or(UTF_8);
+    encoding.decode(bytes).0.into_owned()
+}
+
+async fn fetch(app: &AppHandle, url: &Url) -> Result<LinkMetadata, MobiError> {
+    let client = network::client_builder(app)?
+        .timeout(TIMEOUT)
+        .redirect(Policy::limited(MAX_REDIRECTS))
+        .user_agent(concat!("Mobi/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let mut response = client
+        .get(url.clone())
+        .header("Accept", "text/html,application/xhtml+xml;q=0.9,*/*;q=0.1")
+        .send()
+        .await
+        .map_err(|e| MobiError::Network(format!("获取网页失败: {}", e)))?;
+    if !response.status().is_success() {
+        return Err(MobiError::Network(format!(
+            "网页返回错误 {}",
+            response.status()
+        )));
+    }
+    let final_url = response.url().clone();
+    let content_type = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(String::from);
+    // 不是网页（PDF、图片等）时只返回地址，由调用方用文件名作标题
+    if content_type.as_deref().is_some_and(|t| !t.contains("html")) {
+        return Ok(LinkMetadata {
+            url: final_url.to_string(),
+            ..Default::default()
+        });
+    }
+
+    let mut body = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| MobiError::Network(format!("读取网页失败: {}", e)))?
+    {
+        // 只在新读到的部分（连同上一块的结尾）中查找 </head>
+        let from = body.len().saturating_sub(6);
+        body.extend_from_slice(&chunk);
+        let head_done = body[from..]
+            .windows(7)
+            .any(|w| w.eq_ignore_ascii_case(b"</head>"));
+        if head_done || body.len() >= MAX_BYTES {
+            break;
+        }
+    }
+    let html = decode(&body, content_type.as_deref());
+    Ok(parse_head(&html, &final_url))
+}
+
+#[tauri::command]
+pub async fn fetch_link_metadata(app: AppHandle, url: String) -> Result<LinkMetadata, MobiError> {
+    let parsed =
+        Url::parse(url.trim()).map_err(|_| MobiError::InvalidInput("不是有效的网址".into()))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(MobiError::Unsupported("只支持 http 和 https 链接".into()));
+    }
+    let cache = cache_path(&app, parsed.as_str());
+    if let Some(metadata) = cache.as_ref().and_then(read_cache) {
+        return Ok(metadata);
+    }
+    let metadata = fetch(&app, &parsed).await?;
+    if let Some(path) = cache {
+        let entry = CacheEntry {
+            fetched_at: chrono::Utc::now().timestamp(),
+            metadata: metadata.clone(),
+        };
+        if let Ok(json) = serde_json::to_string(&entry) {
+            let _ = fs::write(path, json);
+        }
+    }
+    Ok(metadata)
+}
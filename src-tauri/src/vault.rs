@@ -0,0 +1,575 @@
+// 加密仓库：整个工作区存为一个目录下的加密数据块，文件名、目录结构和搜索索引都不以明文落盘，适合放在第三方网盘的同步文件夹中
+// vault.json 记录 Argon2id 参数、盐值和口令校验；manifest.bin 为加密的文件清单，index.bin 为加密的倒排索引，blobs/ 下每篇笔记一个随机命名的数据块；解锁后清单和索引只在内存中解密
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+use zeroize::Zeroizing;
+
+use crate::error::{MobiError, MutexExt};
+use crate::{encryption, workspace};
+
+const HEADER: &str = "vault.json";
+const MANIFEST: &str = "manifest.bin";
+const INDEX: &str = "index.bin";
+const BLOBS: &str = "blobs";
+// 口令校验数据块的明文
+const CHECK: &[u8] = b"mobi-vault";
+const NONCE_LEN: usize = 24;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VaultHeader {
+    version: u32,
+    memory: u32,
+    iterations: u32,
+    parallelism: u32,
+    salt: String,
+    // 加密的 CHECK，base64
+    check: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultEntry {
+    path: String,
+    // 数据块文件名
+    blob: String,
+    size: u64,
+    // 毫秒时间戳
+    modified: i64,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct Manifest {
+    // 相对路径 -> 条目
+    files: BTreeMap<String, VaultEntry>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct SearchIndex {
+    // 词 -> 含有该词的笔记路径
+    terms: HashMap<String, BTreeSet<String>>,
+}
+
+struct OpenVault {
+    key: Zeroizing<[u8; 32]>,
+    manifest: Manifest,
+    index: SearchIndex,
+    last_used: Instant,
+}
+
+#[derive(Default)]
+pub struct VaultState {
+    // 仓库目录 -> 已解锁的仓库
+    open: Mutex<HashMap<PathBuf, OpenVault>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultHit {
+    path: String,
+    // 行号从 0 开始
+    line: usize,
+    text: String,
+}
+
+fn blob_name() -> String {
+    let mut bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// nonce 在前、密文在后；name 作为附加数据参与认证，数据块不能被换成另一个
+fn encrypt(key: &[u8; 32], name: &str, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = XChaCha20Poly1305::new(key.into())
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: plaintext,
+                aad: name.as_bytes(),
+            },
+        )
+        .map_err(|_| "加密失败")?;
+    let mut out = nonce.to_vec();
+    out.extend(ciphertext);
+    Ok(out)
+}
+
+fn decrypt(key: &[u8; 32], name: &str, data: &[u8]) -> Result<Zeroizing<Vec<u8>>, String> {
+    if data.len() < NONCE_LEN {
+        return Err("仓库数据已损坏".into());
+    }
+    let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+    XChaCha20Poly1305::new(key.into())
+        .decrypt(
+            XNonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: name.as_bytes(),
+            },
+        )
+        .map(Zeroizing::new)
+        .map_err(|_| "口令错误或仓库数据已损坏".into())
+}
+
+// 先写同目录下的临时文件并落盘，再改名覆盖：中途崩溃或磁盘写满时原文件保持完整，同步服务也不会拿到写了一半的文件
+// 加密笔记的信封也用它写入
+pub fn write_atomic(path: &Path, data: &[u8]) -> io::Result<()> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp = path.with_file_name(format!(".{}.tmp", name));
+    let written = File::create(&temp).and_then(|mut file| {
+        file.write_all(data)?;
+        // 沿用原文件的权限
+        if let Ok(metadata) = fs::metadata(path) {
+            file.set_permissions(metadata.permissions())?;
+        }
+        file.sync_all()
+    });
+    let result = written.and_then(|_| fs::rename(&temp, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+fn read_header(dir: &Path) -> Result<VaultHeader, String> {
+    let text = fs::read_to_string(dir.join(HEADER)).map_err(|_| "不是加密仓库")?;
+    serde_json::from_str(&text).map_err(|e| format!("仓库已损坏: {}", e))
+}
+
+fn load_encrypted<T: serde::de::DeserializeOwned + Default>(
+    dir: &Path,
+    key: &[u8; 32],
+    name: &str,
+) -> Result<T, String> {
+    match fs::read(dir.join(name)) {
+        Ok(data) => serde_json::from_slice(&decrypt(key, name, &data)?)
+            .map_err(|e| format!("仓库已损坏: {}", e)),
+        Err(_) => Ok(T::default()),
+    }
+}
+
+fn save_encrypted<T: Serialize>(
+    dir: &Path,
+    key: &[u8; 32],
+    name: &str,
+    value: &T,
+) -> Result<(), String> {
+    let plaintext = Zeroizing::new(serde_json::to_vec(value).map_err(|e| e.to_string())?);
+    write_atomic(&dir.join(name), &encrypt(key, name, &plaintext)?)
+        .map_err(|e| format!("写入仓库失败: {}", e))
+}
+
+// 仓库内的相对路径：统一为 / 分隔，不允许跳出仓库
+fn normalize(path: &str) -> Result<String, String> {
+    let parts: Vec<&str> = path
+        .split(['/', '\\'])
+        .filter(|p| !p.is_empty() && *p != ".")
+        .collect();
+    if parts.is_empty() || parts.contains(&"..") {
+        return Err("无效的笔记路径".into());
+    }
+    Ok(parts.join("/"))
+}
+
+// 索引词：英文和数字按词，小写；中日韩文字按相邻两个字，单独一个字时按字
+fn terms(text: &str) -> BTreeSet<String> {
+    let is_cjk = |c: char| matches!(c as u32, 0x3040..=0x30ff | 0x3400..=0x4dbf | 0x4e00..=0x9fff | 0xac00..=0xd7af);
+    let mut terms = BTreeSet::new();
+    let mut word = String::new();
+    // 当前这段中日韩文字
+    let mut run: Vec<char> = Vec::new();
+    for c in text.chars().chain(std::iter::once(' ')) {
+        if is_cjk(c) {
+            if let Some(previous) = run.last() {
+                terms.insert(format!("{}{}", previous, c));
+            }
+            run.push(c);
+        } else if run.len() == 1 {
+            terms.insert(run[0].to_string());
+        }
+        if !is_cjk(c) {
+            run.clear();
+        }
+        if c.is_alphanumeric() && !is_cjk(c) {
+            word.extend(c.to_lowercase());
+        } else if !word.is_empty() {
+            terms.insert(std::mem::take(&mut word));
+        }
+    }
+    terms
+}
+
+fn reindex(index: &mut SearchIndex, path: &str, content: Option<&str>) {
+    index.terms.retain(|_, paths| {
+        paths.remove(path);
+        !paths.is_empty()
+    });
+    if let Some(content) = content {
+        for term in terms(content) {
+            index
+                .terms
+                .entry(term)
+                .or_default()
+                .insert(path.to_string());
+        }
+    }
+}
+
+// 对已解锁的仓库执行操作，并刷新最近使用时间
+fn with_vault<T>(
+    state: &VaultState,
+    dir: &Path,
+    f: impl FnOnce(&mut OpenVault) -> Result<T, MobiError>,
+) -> Result<T, MobiError> {
+    let mut open = state.open.locked();
+    let vault = open
+        .get_mut(dir)
+        .ok_or_else(|| MobiError::NeedsUnlock("仓库已锁定，请先解锁".into()))?;
+    vault.last_used = Instant::now();
+    f(vault)
+}
+
+fn read_entry(dir: &Path, vault: &OpenVault, path: &str) -> Result<Zeroizing<Vec<u8>>, String> {
+    let entry = vault.manifest.files.get(path).ok_or("仓库中没有该笔记")?;
+    let data =
+        fs::read(dir.join(BLOBS).join(&entry.blob)).map_err(|e| format!("读取仓库失败: {}", e))?;
+    decrypt(&vault.key, &entry.blob, &data)
+}
+
+// 写入新数据块并更新内存中的清单和索引，返回被替换的旧条目
+fn put_entry(
+    dir: &Path,
+    vault: &mut OpenVault,
+    path: &str,
+    content: &str,
+) -> Result<(VaultEntry, Option<VaultEntry>), String> {
+    let blob = blob_name();
+    let blobs = dir.join(BLOBS);
+    fs::create_dir_all(&blobs).map_err(|e| format!("写入仓库失败: {}", e))?;
+    write_atomic(
+        &blobs.join(&blob),
+        &encrypt(&vault.key, &blob, content.as_bytes())?,
+    )
+    .map_err(|e| format!("写入仓库失败: {}", e))?;
+    let entry = VaultEntry {
+        path: path.to_string(),
+        blob,
+        size: content.len() as u64,
+        modified: Utc::now().timestamp_millis(),
+    };
+    let old = vault.manifest.files.insert(path.to_string(), entry.clone());
+    reindex(&mut vault.index, path, Some(content));
+    Ok((entry, old))
+}
+
+// 写入一篇笔记：新数据块落盘后再保存清单和索引，最后删除旧数据块
+fn write_entry(
+    dir: &Path,
+    vault: &mut OpenVault,
+    path: &str,
+    content: &str,
+) -> Result<VaultEntry, String> {
+    let (entry, old) = put_entry(dir, vault, path, content)?;
+    save_encrypted(dir, &vault.key, MANIFEST, &vault.manifest)?;
+    save_encrypted(dir, &vault.key, INDEX, &vault.index)?;
+    if let Some(old) = old {
+        let _ = fs::remove_file(dir.join(BLOBS).join(old.blob));
+    }
+    Ok(entry)
+}
+
+// 锁定超时未使用的仓库，由加密笔记的自动锁定线程调用
+pub fn lock_idle(app: &AppHandle, timeout: Duration) {
+    let Some(state) = app.try_state::<VaultState>() else {
+        return;
+    };
+    let mut open = state.open.locked();
+    let before = open.len();
+    open.retain(|_, vault| vault.last_used.elapsed() < timeout);
+    if open.len() != before {
+        let _ = app.emit("vault-locked", ());
+    }
+}
+
+// 在空目录中创建仓库；source 为已有工作区时把其中的笔记加密导入，原文件保持不动
+#[tauri::command]
+pub async fn create_vault(
+    state: State<'_, VaultState>,
+    dir: String,
+    passphrase: String,
+    source: Option<String>,
+) -> Result<usize, MobiError> {
+    if passphrase.is_empty() {
+        return Err("口令不能为空".into());
+    }
+    let dir = PathBuf::from(&dir);
+    if dir.exists() && fs::read_dir(&dir).is_ok_and(|mut d| d.next().is_some()) {
+        return Err("仓库目录必须为空".into());
+    }
+    let (vault, count) = tauri::async_runtime::spawn_blocking({
+        let dir = dir.clone();
+        move || -> Result<(OpenVault, usize), String> {
+            fs::create_dir_all(dir.join(BLOBS)).map_err(|e| format!("创建仓库失败: {}", e))?;
+            let salt = encryption::new_salt();
+            let key = encryption::derive_key(
+                &passphrase,
+                &encryption::decode(&salt)?,
+                encryption::MEMORY_KIB,
+                encryption::ITERATIONS,
+                encryption::PARALLELISM,
+            )?;
+            let header = VaultHeader {
+                version: 1,
+                memory: encryption::MEMORY_KIB,
+                iterations: encryption::ITERATIONS,
+                parallelism: encryption::PARALLELISM,
+                salt,
+                check: BASE64.encode(encrypt(&key, HEADER, CHECK)?),
+            };
+            let text = serde_json::to_string_pretty(&header).map_err(|e| e.to_string())?;
+            write_atomic(&dir.join(HEADER), text.as_bytes())
+                .map_err(|e| format!("写入仓库失败: {}", e))?;
+
+            let mut vault = OpenVault {
+                key,
+                manifest: Manifest::default(),
+                index: SearchIndex::default(),
+                last_used: Instant::now(),
+            };
+            let mut count = 0;
+            if let Some(source) = source.filter(|s| !s.is_empty()) {
+                let root = PathBuf::from(source);
+                for note in workspace::note_files(&root) {
+                    let Ok(content) = fs::read_to_string(&note) else {
+                        continue;
+                    };
+                    if encryption::is_envelope(&content) {
+                        continue;
+                    }
+                    let path = workspace::relative_path(&root, &note);
+                    put_entry(&dir, &mut vault, &path, &content)?;
+                    count += 1;
+                }
+            }
+            save_encrypted(&dir, &vault.key, MANIFEST, &vault.manifest)?;
+            save_encrypted(&dir, &vault.key, INDEX, &vault.index)?;
+            Ok((vault, count))
+        }
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    state.open.locked().insert(dir, vault);
+    Ok(count)
+}
+
+#[tauri::command]
+pub fn is_vault(dir: String) -> bool {
+    read_header(Path::new(&dir)).is_ok()
+}
+
+// 校验口令并在内存中解密清单和索引
+#[tauri::command]
+pub async fn unlock_vault(
+    state: State<'_, VaultState>,
+    dir: String,
+    passphrase: String,
+) -> Result<Vec<VaultEntry>, MobiError> {
+    let dir = PathBuf::from(&dir);
+    let vault = tauri::async_runtime::spawn_blocking({
+        let dir = dir.clone();
+        move || -> Result<OpenVault, MobiError> {
+            let header = read_header(&dir)?;
+            // vault.json 随云盘同步，派生前先检查参数，避免被改大后耗尽内存
+            encryption::check_params(header.memory, header.iterations, header.parallelism)?;
+            let key = encryption::derive_key(
+                &passphrase,
+                &encryption::decode(&header.salt)?,
+                header.memory,
+                header.iterations,
+                header.parallelism,
+            )?;
+            decrypt(&key, HEADER, &encryption::decode(&header.check)?)
+                .map_err(|_| MobiError::WrongPassphrase("口令错误".into()))?;
+            Ok(OpenVault {
+                manifest: load_encrypted(&dir, &key, MANIFEST)?,
+                index: load_encrypted(&dir, &key, INDEX)?,
+                key,
+                last_used: Instant::now(),
+            })
+        }
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    let entries = vault.manifest.files.values().cloned().collect();
+    state.open.locked().insert(dir, vault);
+    Ok(entries)
+}
+
+// 锁定仓库，dir 为空时锁定全部；内存中的密钥、清单和索引随之丢弃
+#[tauri::command]
+pub fn lock_vault(app: AppHandle, state: State<VaultState>, dir: Option<String>) {
+    let mut open = state.open.locked();
+    match dir {
+        Some(dir) => {
+            open.remove(Path::new(&dir));
+        }
+        None => open.clear(),
+    }
+    let _ = app.emit("vault-locked", ());
+}
+
+#[tauri::command]
+pub fn list_vault(state: State<VaultState>, dir: String) -> Result<Vec<VaultEntry>, MobiError> {
+    with_vault(&state, Path::new(&dir), |vault| {
+        Ok(vault.manifest.files.values().cloned().collect())
+    })
+}
+
+#[tauri::command]
+pub fn read_vault_note(
+    state: State<VaultState>,
+    dir: String,
+    path: String,
+) -> Result<String, MobiError> {
+    let dir = PathBuf::from(&dir);
+    let path = normalize(&path)?;
+    with_vault(&state, &dir, |vault| {
+        let plaintext = read_entry(&dir, vault, &path)?;
+        Ok(String::from_utf8(plaintext.to_vec()).map_err(|e| e.to_string())?)
+    })
+}
+
+// 新建或覆盖一篇笔记
+#[tauri::command]
+pub fn write_vault_note(
+    state: State<VaultState>,
+    dir: String,
+    path: String,
+    content: String,
+) -> Result<VaultEntry, MobiError> {
+    let dir = PathBuf::from(&dir);
+    let path = normalize(&path)?;
+    with_vault(&state, &dir, |vault| {
+        Ok(write_entry(&dir, vault, &path, &content)?)
+    })
+}
+
+// 改名或移动，to 已存在时报错
+#[tauri::command]
+pub fn rename_vault_note(
+    state: State<VaultState>,
+    dir: String,
+    from: String,
+    to: String,
+) -> Result<VaultEntry, MobiError> {
+    let dir = PathBuf::from(&dir);
+    let (from, to) = (normalize(&from)?, normalize(&to)?);
+    with_vault(&state, &dir, |vault| {
+        if vault.manifest.files.contains_key(&to) {
+            return Err("目标位置已有同名笔记".into());
+        }
+        let plaintext = read_entry(&dir, vault, &from)?;
+        let content = String::from_utf8(plaintext.to_vec()).map_err(|e| e.to_string())?;
+        let mut entry = vault
+            .manifest
+            .files
+            .remove(&from)
+            .ok_or("仓库中没有该笔记")?;
+        reindex(&mut vault.index, &from, None);
+        reindex(&mut vault.index, &to, Some(&content));
+        entry.path = to.clone();
+        vault.manifest.files.insert(to, entry.clone());
+        save_encrypted(&dir, &vault.key, MANIFEST, &vault.manifest)?;
+        save_encrypted(&dir, &vault.key, INDEX, &vault.index)?;
+        Ok(entry)
+    })
+}
+
+#[tauri::command]
+pub fn delete_vault_note(
+    state: State<VaultState>,
+    dir: String,
+    path: String,
+) -> Result<(), MobiError> {
+    let dir = PathBuf::from(&dir);
+    let path = normalize(&path)?;
+    with_vault(&state, &dir, |vault| {
+        let entry = vault
+            .manifest
+            .files
+            .remove(&path)
+            .ok_or("仓库中没有该笔记")?;
+        reindex(&mut vault.index, &path, None);
+        save_encrypted(&dir, &vault.key, MANIFEST, &vault.manifest)?;
+        save_encrypted(&dir, &vault.key, INDEX, &vault.index)?;
+        let _ = fs::remove_file(dir.join(BLOBS).join(entry.blob));
+        Ok(())
+    })
+}
+
+// 用内存中的索引找出含有全部查询词的笔记，再解密这些笔记取出匹配的行
+#[tauri::command]
+pub fn search_vault(
+    state: State<VaultState>,
+    dir: String,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<VaultHit>, MobiError> {
+    let dir = PathBuf::from(&dir);
+    let wanted = terms(&query);
+    if wanted.is_empty() {
+        return Ok(Vec::new());
+    }
+    let limit = limit.unwrap_or(50);
+    with_vault(&state, &dir, |vault| {
+        let mut candidates: Option<BTreeSet<String>> = None;
+        for term in &wanted {
+            let paths = vault.index.terms.get(term).cloned().unwrap_or_default();
+            candidates = Some(match candidates {
+                Some(current) => current.intersection(&paths).cloned().collect(),
+                None => paths,
+            });
+        }
+        let mut hits = Vec::new();
+        for path in candidates.unwrap_or_default() {
+            let Ok(plaintext) = read_entry(&dir, vault, &path) else {
+                continue;
+            };
+            let content = String::from_utf8_lossy(&plaintext);
+            let matched: Vec<VaultHit> = content
+                .lines()
+                .enumerate()
+                .filter(|(_, line)| {
+                    let line_terms = terms(line);
+                    wanted.iter().any(|t| line_terms.contains(t))
+                })
+                .map(|(line, text)| VaultHit {
+                    path: path.clone(),
+                    line,
+                    text: text.chars().take(200).collect(),
+                })
+                .collect();
+            hits.extend(matched);
+            if hits.len() >= limit {
+                hits.truncate(limit);
+                break;
+            }
+        }
+        Ok(hits)
+    })
+}
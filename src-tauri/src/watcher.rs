@@ -0,0 +1,153 @@
+// 工作区监视：监听工作区文件变化，按忽略规则过滤并合并短时间内的事件后通过 workspace-changed 推送给前端
+// 同步盘生成的冲突副本会额外发出 sync-conflict 事件；指向工作区外的目录链接单独监视，事件路径换回链接路径
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Component, Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use notify::{Config, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State, Window};
+
+use crate::error::{MobiError, MutexExt};
+use crate::{buffers, conflicts, search, workspace};
+
+// 合并事件的时间窗口
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct WorkspaceChanged {
+    workspace: String,
+    paths: Vec<String>,
+}
+
+#[derive(Default)]
+pub struct WatcherState {
+    // 按窗口标签区分，每个窗口监视自己的工作区；替换或窗口关闭时旧的监视器随之释放
+    active: Mutex<HashMap<String, RecommendedWatcher>>,
+}
+
+// .mobi 数据目录和编辑锁文件的变化不通知前端
+fn is_internal(path: &Path) -> bool {
+    path.components()
+        .any(|c| matches!(c, Component::Normal(name) if name == ".mobi"))
+        || path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().ends_with(".mobi-lock"))
+}
+
+// 链接目标下的路径换成经过链接的路径
+fn through_link(path: PathBuf, links: &[(PathBuf, PathBuf)]) -> PathBuf {
+    links
+        .iter()
+        .find_map(|(link, target)| path.strip_prefix(target).ok().map(|rest| link.join(rest)))
+        .unwrap_or(path)
+}
+
+fn forward(
+    app: AppHandle,
+    label: String,
+    workspace: String,
+    links: Vec<(PathBuf, PathBuf)>,
+    rx: mpsc::Receiver<Vec<PathBuf>>,
+) {
+    thread::spawn(move || {
+        let mut ignore = workspace::IgnoreRules::load(Path::new(&workspace));
+        // 发送端随监视器释放后 recv 返回错误，线程退出
+        while let Ok(first) = rx.recv() {
+            let mut paths: BTreeSet<PathBuf> = BTreeSet::new();
+            let mut batch = first;
+            loop {
+                paths.extend(batch.into_iter().map(|p| through_link(p, &links)));
+                match rx.recv_timeout(DEBOUNCE) {
+                    Ok(more) => batch = more,
+                    Err(_) => break,
+                }
+            }
+            if paths.iter().any(|p| p.ends_with(".mobiignore")) {
+                ignore = workspace::IgnoreRules::load(Path::new(&workspace));
+            }
+            paths.retain(|p| !is_internal(p) && !ignore.is_ignored(p, p.is_dir()));
+            if paths.is_empty() {
+                continue;
+            }
+
+            let changed: Vec<PathBuf> = paths.iter().cloned().collect();
+            buffers::disk_changed(&app, &changed);
+            search::apply_changes(&app, Path::new(&workspace), &changed);
+            for path in &paths {
+                if let Some(conflict) = conflicts::detect(Path::new(&workspace), path) {
+                    let _ = app.emit_to(label.as_str(), "sync-conflict", conflict);
+                }
+            }
+            let _ = app.emit_to(
+                label.as_str(),
+                "workspace-changed",
+                WorkspaceChanged {
+                    workspace: workspace.clone(),
+                    paths: paths
+                        .iter()
+                        .map(|p| p.to_string_lossy().to_string())
+                        .collect(),
+                },
+            );
+        }
+    });
+}
+
+// 窗口关闭时停止它的监视
+pub fn forget_window(app: &AppHandle, label: &str) {
+    if let Some(state) = app.try_state::<WatcherState>() {
+        state.active.locked().remove(label);
+    }
+}
+
+// workspace 为 None 时停止监视；事件只发给调用的窗口
+#[tauri::command]
+pub fn watch_workspace(
+    app: AppHandle,
+    window: Window,
+    state: State<WatcherState>,
+    workspace: Option<String>,
+) -> Result<(), MobiError> {
+    let label = window.label().to_string();
+    let mut active = state.active.locked();
+    active.remove(&label);
+    let Some(workspace) = workspace else {
+        return Ok(());
+    };
+
+    let root = Path::new(&workspace);
+    let follow = workspace::settings(root).symlinks != workspace::SymlinkPolicy::Ignore;
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = RecommendedWatcher::new(
+        move |res: notify::Result<notify::Event>| {
+            if let Ok(event) = res {
+                if !matches!(event.kind, EventKind::Access(_)) {
+                    let _ = tx.send(event.paths);
+                }
+            }
+        },
+        Config::default().with_follow_symlinks(follow),
+    )
+    .map_err(|e| format!("创建文件监视失败: {}", e))?;
+    watcher
+        .watch(root, RecursiveMode::Recursive)
+        .map_err(|e| format!("监视工作区失败: {}", e))?;
+
+    // 部分平台（如 macOS FSEvents）不会跟进目录链接，链接目标单独监视
+    let links = workspace::linked_dirs(root);
+    for (_, target) in &links {
+        let _ = watcher.watch(target, RecursiveMode::Recursive);
+    }
+
+    // 搜索索引在后台与磁盘对齐一次，之后随事件增量更新
+    search::warm(&app, root.to_path_buf());
+    forward(app, label.clone(), workspace, links, rx);
+    active.insert(label, watcher);
+    Ok(())
+}
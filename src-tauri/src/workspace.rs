@@ -0,0 +1,511 @@
+// 工作区：遍历笔记文件、定位工作区内的 .mobi 数据目录、读写工作区设置
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::error::MobiError;
+use crate::markdown;
+use crate::onsave::OnSaveSettings;
+
+// 作为笔记参与索引的扩展名
+const NOTE_EXTENSIONS: &[&str] = &["md", "markdown"];
+
+// 内置的忽略规则（gitignore 语法），可在工作区设置中关闭
+const DEFAULT_IGNORES: &[&str] = &["node_modules/", ".git/", ".obsidian/"];
+
+pub fn is_note(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| NOTE_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+// 符号链接的跟随策略
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum SymlinkPolicy {
+    #[default]
+    Follow,
+    // 只跟随指向工作区内部的链接
+    InsideWorkspace,
+    Ignore,
+}
+
+// 工作区设置：{workspace}/.mobi/workspace.json，随工作区一起同步
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WorkspaceSettings {
+    pub symlinks: SymlinkPolicy,
+    pub default_ignores: bool,
+    // 在 .mobiignore 之外追加的忽略规则
+    pub ignore_patterns: Vec<String>,
+    // 归档目录（相对工作区根目录）
+    pub archive_dir: String,
+    // 搜索、相关笔记和重复检测是否包含已归档的笔记
+    pub search_archived: bool,
+    // 保存前执行的步骤
+    pub on_save: OnSaveSettings,
+    // 回收站中的条目保留的天数，0 为不自动清除
+    pub trash_days: u32,
+    // 笔记附件目录（相对笔记所在目录），{note} 为笔记文件名
+    pub attachment_dir: String,
+}
+
+impl Default for WorkspaceSettings {
+    fn default() -> Self {
+        WorkspaceSettings {
+            symlinks: SymlinkPolicy::Follow,
+            default_ignores: true,
+            ignore_patterns: Vec::new(),
+            archive_dir: "archive".into(),
+            search_archived: false,
+            on_save: OnSaveSettings::default(),
+            trash_days: 30,
+            attachment_dir: "attachments/{note}".into(),
+        }
+    }
+}
+
+impl WorkspaceSettings {
+    // relative 为相对工作区的路径（/ 分隔），位于归档目录中时返回 true
+    pub fn is_archived(&self, relative: &str) -> bool {
+        let dir = self.archive_dir.trim_matches('/');
+        !dir.is_empty()
+            && relative
+                .strip_prefix(dir)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    }
+
+    // 默认的搜索结果中是否隐藏该笔记
+    pub fn hides(&self, relative: &str) -> bool {
+        !self.search_archived && self.is_archived(relative)
+    }
+}
+
+pub fn settings(root: &Path) -> WorkspaceSettings {
+    fs::read_to_string(root.join(".mobi").join("workspace.json"))
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+// 忽略规则：内置规则、工作区设置中的规则和工作区根目录的 .mobiignore，后者优先
+pub struct IgnoreRules {
+    root: PathBuf,
+    rules: Gitignore,
+}
+
+impl IgnoreRules {
+    pub fn load(root: &Path) -> Self {
+        let config = settings(root);
+        let mut builder = GitignoreBuilder::new(root);
+        if config.default_ignores {
+            for pattern in DEFAULT_IGNORES {
+                let _ = builder.add_line(None, pattern);
+            }
+        }
+        for pattern in &config.ignore_patterns {
+            let _ = builder.add_line(None, pattern);
+        }
+        let _ = builder.add(root.join(".mobiignore"));
+        IgnoreRules {
+            root: root.to_path_buf(),
+            rules: builder.build().unwrap_or_else(|_| Gitignore::empty()),
+        }
+    }
+
+    // path 为工作区内的路径（经过链接时用链接路径），所在目录被忽略时也算忽略
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        path.starts_with(&self.root)
+            && self
+                .rules
+                .matched_path_or_any_parents(path, is_dir)
+                .is_ignore()
+    }
+}
+
+fn follows(policy: SymlinkPolicy, root: &Path, target: &Path) -> bool {
+    match policy {
+        SymlinkPolicy::Follow => true,
+        SymlinkPolicy::InsideWorkspace => target.starts_with(root),
+        SymlinkPolicy::Ignore => false,
+    }
+}
+
+struct Scan {
+    ignore: IgnoreRules,
+    root: PathBuf,
+    policy: SymlinkPolicy,
+    // 已进入的目录和已收录的文件（真实路径），用于检测环和去重
+    visited: HashSet<PathBuf>,
+    files: HashSet<PathBuf>,
+    notes: Vec<PathBuf>,
+    // 所有文件（含附件等非笔记文件）
+    all: Vec<PathBuf>,
+    linked_dirs: Vec<(PathBuf, PathBuf)>,
+    // 待处理的符号链接
+    pending: Vec<PathBuf>,
+}
+
+impl Scan {
+    fn new(root: &Path) -> Self {
+        let canonical = fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+        let mut scan = Scan {
+            ignore: IgnoreRules::load(root),
+            root: canonical.clone(),
+            policy: settings(root).symlinks,
+            visited: HashSet::new(),
+            files: HashSet::new(),
+            notes: Vec::new(),
+            all: Vec::new(),
+            linked_dirs: Vec::new(),
+            pending: Vec::new(),
+        };
+        scan.visited.insert(canonical.clone());
+        scan.collect(root, &canonical);
+        scan.follow_links();
+        scan
+    }
+
+    // dir 为遍历用的路径（可能经过链接），canonical 为其真实路径
+    fn collect(&mut self, dir: &Path, canonical: &Path) {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with('.') {
+                continue;
+            }
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_symlink() {
+                self.pending.push(path);
+            } else {
+                self.visit(path, canonical.join(&name), file_type.is_dir(), false);
+            }
+        }
+    }
+
+    fn visit(&mut self, path: PathBuf, target: PathBuf, is_dir: bool, is_link: bool) {
+        // 父目录已被剪枝，这里只需检查自身
+        if self.ignore.rules.matched(&path, is_dir).is_ignore() {
+            return;
+        }
+        if is_dir {
+            // 链接回祖先目录会形成环，经由多条路径到达的目录也只进入一次
+            if !self.visited.insert(target.clone()) {
+                return;
+            }
+            if is_link && !target.starts_with(&self.root) {
+                self.linked_dirs.push((path.clone(), target.clone()));
+            }
+            self.collect(&path, &target);
+        } else if self.files.insert(target) {
+            if is_note(&path) {
+                self.notes.push(path.clone());
+            }
+            self.all.push(path);
+        }
+    }
+
+    // 先走完真实目录再处理链接，同一文件优先以真实路径收录
+    fn follow_links(&mut self) {
+        while !self.pending.is_empty() {
+            for path in std::mem::take(&mut self.pending) {
+                // 悬空链接直接跳过
+                let Ok(target) = fs::canonicalize(&path) else {
+                    continue;
+                };
+                if follows(self.policy, &self.root, &target) {
+                    let is_dir = target.is_dir();
+                    self.visit(path, target, is_dir, true);
+                }
+            }
+        }
+    }
+}
+
+// 递归列出工作区内的所有笔记，跳过隐藏目录（含 .mobi）和被忽略的路径，按工作区设置跟随符号链接
+pub fn note_files(root: &Path) -> Vec<PathBuf> {
+    let mut files = Scan::new(root).notes;
+    files.sort();
+    files
+}
+
+// 与 note_files 相同的遍历规则，但包含图片、附件等所有文件
+pub fn all_files(root: &Path) -> Vec<PathBuf> {
+    let mut files = Scan::new(root).all;
+    files.sort();
+    files
+}
+
+// 跟随的、指向工作区外的目录链接：(链接路径, 真实路径)，监视器需要单独监视这些目录
+pub fn linked_dirs(root: &Path) -> Vec<(PathBuf, PathBuf)> {
+    Scan::new(root).linked_dirs
+}
+
+// 写入时使用的真实路径：经过符号链接时写到链接目标，保留链接本身
+pub fn resolve_link(path: &Path) -> PathBuf {
+    match fs::symlink_metadata(path) {
+        Ok(meta) if meta.file_type().is_symlink() => {
+            fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+        }
+        _ => path.to_path_buf(),
+    }
+}
+
+// 工作区私有数据目录：{workspace}/.mobi
+pub fn data_dir(root: &Path) -> Result<PathBuf, String> {
+    let dir = root.join(".mobi");
+    fs::create_dir_all(&dir).map_err(|e| format!("创建 .mobi 目录失败: {}", e))?;
+    Ok(dir)
+}
+
+// 文件修改时间（秒），取不到时为 0
+pub fn modified_secs(path: &Path) -> i64 {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+// 相对工作区的路径，统一使用 / 分隔
+pub fn relative_path(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+// 从 base 目录到 path 的相对路径（可含 ..），/ 分隔；不在同一个盘符下时返回绝对路径
+pub fn relative_to(base: &Path, path: &Path) -> String {
+    let base: Vec<_> = base.components().collect();
+    let target: Vec<_> = path.components().collect();
+    let common = base.iter().zip(&target).take_while(|(a, b)| a == b).count();
+    if common == 0 {
+        return path.to_string_lossy().replace('\\', "/");
+    }
+    std::iter::repeat_n("..".to_string(), base.len() - common)
+        .chain(
+            target[common..]
+                .iter()
+                .map(|c| c.as_os_str().to_string_lossy().to_string()),
+        )
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+// 笔记 note 中的链接目标（Markdown 原文，可含 %20 和 #锚点）指向的本地文件，/ 开头时相对工作区根目录；外部链接返回 None
+pub fn resolve_note_link(root: &Path, note: &Path, destination: &str) -> Option<PathBuf> {
+    let path = destination.split('#').next()?;
+    if path.is_empty() || path.contains("://") || path.starts_with("mailto:") {
+        return None;
+    }
+    let path = urlencoding::decode(path).ok()?;
+    let joined = match path.strip_prefix('/') {
+        Some(rooted) => root.join(rooted),
+        None => note.parent()?.join(path.as_ref()),
+    };
+    // 只做字面上的规范化，目标文件不存在时也能比较
+    let mut resolved = PathBuf::new();
+    for component in joined.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            other => resolved.push(other),
+        }
+    }
+    Some(resolved)
+}
+
+// 把其他笔记中指向 from 的链接改为指向 to（保留 #锚点），skip 为不处理的笔记；返回改写了链接的笔记
+pub fn retarget_links(
+    root: &Path,
+    from: &Path,
+    to: &Path,
+    skip: Option<&Path>,
+) -> Result<Vec<String>, String> {
+    let mut updated = Vec::new();
+    for note in note_files(root) {
+        if Some(note.as_path()) == skip {
+            continue;
+        }
+        let Ok(content) = fs::read_to_string(&note) else {
+            continue;
+        };
+        let note_dir = note.parent().unwrap_or(root).to_path_buf();
+        let rewritten = markdown::rewrite_link_destinations(&content, |destination| {
+            if resolve_note_link(root, &note, destination)? != from {
+                return None;
+            }
+            let fragment = destination.find('#').map_or("", |i| &destination[i..]);
+            Some(format!(
+                "{}{}",
+                markdown::link_destination(&relative_to(&note_dir, to)),
+                fragment
+            ))
+        });
+        if rewritten != content {
+            fs::write(resolve_link(&note), rewritten)
+                .map_err(|e| format!("更新链接失败: {}", e))?;
+            updated.push(note.to_string_lossy().to_string());
+        }
+    }
+    Ok(updated)
+}
+
+// 原本位于 from 的笔记正文移到 to 时，相对链接和图片按新位置重写，/ 开头（相对工作区根目录）的不变
+pub fn rebase_links(root: &Path, content: &str, from: &Path, to: &Path) -> String {
+    let dir = to.parent().unwrap_or(root);
+    markdown::rewrite_link_destinations(content, |destination| {
+        if destination.starts_with('/') || destination.starts_with('#') {
+            return None;
+        }
+        let mut target = resolve_note_link(root, from, destination)?;
+        if target == from {
+            target = to.to_path_buf();
+        }
+        let fragment = destination.find('#').map_or("", |i| &destination[i..]);
+        Some(format!(
+            "{}{}",
+            markdown::link_destination(&relative_to(dir, &target)),
+            fragment
+        ))
+    })
+}
+
+// 书签、回收站、工作区列表等的条目 id：毫秒时间戳的 36 进制，已被占用（同一毫秒内添加多个）时顺延
+pub fn new_id(taken: impl Fn(&str) -> bool) -> String {
+    const DIGITS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+    let mut n = chrono::Utc::now().timestamp_millis().max(0) as u64;
+    loop {
+        let mut digits = Vec::new();
+        let mut rest = n;
+        loop {
+            digits.push(DIGITS[(rest % 36) as usize]);
+            rest /= 36;
+            if rest == 0 {
+                break;
+            }
+        }
+        digits.reverse();
+        let id = String::from_utf8_lossy(&digits).into_owned();
+        if !taken(&id) {
+            return id;
+        }
+        n += 1;
+    }
+}
+
+// 去掉文件名中各平台不允许的字符
+pub fn sanitize_file_name(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '-',
+            c if c.is_control() => '-',
+            c => c,
+        })
+        .collect();
+    cleaned.trim().trim_matches('.').to_string()
+}
+
+// 目录中不与现有文件重名的路径：name.md、name 2.md、name 3.md ...，extension 为空时为 name、name 2 ...
+pub fn unique_path(dir: &Path, stem: &str, extension: &str) -> PathBuf {
+    let name = |stem: &str| match extension {
+        "" => stem.to_string(),
+        ext => format!("{}.{}", stem, ext),
+    };
+    let mut path = dir.join(name(stem));
+    let mut n = 2;
+    while path.exists() {
+        path = dir.join(name(&format!("{} {}", stem, n)));
+        n += 1;
+    }
+    path
+}
+
+#[tauri::command]
+pub fn get_workspace_settings(workspace: String) -> WorkspaceSettings {
+    settings(Path::new(&workspace))
+}
+
+#[tauri::command]
+pub fn set_workspace_settings(
+    workspace: String,
+    settings: WorkspaceSettings,
+) -> Result<(), MobiError> {
+    let text = serde_json::to_string_pretty(&settings).map_err(|e| e.to_string())?;
+    fs::write(
+        data_dir(Path::new(&workspace))?.join("workspace.json"),
+        text,
+    )
+    .map_err(|e| MobiError::io("保存工作区设置失败", e))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DirEntry {
+    name: String,
+    path: String,
+    is_directory: bool,
+}
+
+// 文件树用的目录列表：跳过隐藏文件、被忽略的路径和按策略不跟随的链接
+#[tauri::command]
+pub fn list_workspace_dir(
+    workspace: Option<String>,
+    dir: String,
+) -> Result<Vec<DirEntry>, MobiError> {
+    let root = PathBuf::from(workspace.unwrap_or_else(|| dir.clone()));
+    let policy = settings(&root).symlinks;
+    let canonical_root = fs::canonicalize(&root).unwrap_or_else(|_| root.clone());
+    let ignore = IgnoreRules::load(&root);
+
+    let entries = fs::read_dir(&dir).map_err(|e| MobiError::io("读取目录失败", e))?;
+    let mut list = Vec::new();
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if name.starts_with('.') {
+            continue;
+        }
+        let path = entry.path();
+        let is_directory = if file_type.is_symlink() {
+            let Ok(target) = fs::canonicalize(&path) else {
+                continue;
+            };
+            if !follows(policy, &canonical_root, &target) {
+                continue;
+            }
+            target.is_dir()
+        } else {
+            file_type.is_dir()
+        };
+        if ignore.is_ignored(&path, is_directory) {
+            continue;
+        }
+        list.push(DirEntry {
+            name,
+            path: path.to_string_lossy().to_string(),
+            is_directory,
+        });
+    }
+    Ok(list)
+}
@@ -0,0 +1,252 @@
+// 工作区列表：记住用户选择加入的工作区（名称、路径、图标、上次打开时间），保存在设置的 workspaces 节，用于切换工作区和在新窗口中同时打开多个
+// 每个工作区的索引和设置本来就在各自的 .mobi 目录中；工作区窗口的标签为 workspace-{id}，前端据此取回自己的工作区并使用独立的本地存储
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder, Window};
+use tauri_plugin_dialog::DialogExt;
+
+use crate::error::{MobiError, MutexExt};
+use crate::{settings, workspace};
+
+// 串行化工作区列表的读改写
+static LOCK: Mutex<()> = Mutex::new(());
+
+const WINDOW_PREFIX: &str = "workspace-";
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceEntry {
+    id: String,
+    name: String,
+    path: String,
+    // emoji 或空
+    #[serde(default)]
+    icon: String,
+    // 毫秒时间戳
+    #[serde(default)]
+    last_opened: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceInfo {
+    #[serde(flatten)]
+    entry: WorkspaceEntry,
+    // 目录已被删除或所在的磁盘未连接
+    missing: bool,
+    // 已在某个窗口中打开（主窗口不计）
+    window: Option<String>,
+}
+
+fn load(app: &AppHandle) -> Vec<WorkspaceEntry> {
+    settings::load(app, "workspaces")
+}
+
+fn save(app: &AppHandle, entries: &[WorkspaceEntry]) -> Result<(), String> {
+    settings::save(app, "workspaces", &entries)
+}
+
+// 比较路径时去掉末尾的分隔符
+fn normalize(path: &str) -> String {
+    let trimmed = path.trim_end_matches(['/', '\\']);
+    if trimmed.is_empty() {
+        path.to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+// 资源协议和前端文件读写只对登记过的工作区开放
+pub fn is_registered(app: &AppHandle, path: &str) -> bool {
+    let path = normalize(path);
+    load(app).iter().any(|e| e.path == path)
+}
+
+// path 需已规范化：是否位于某个登记过的工作区中
+pub fn in_registered(app: &AppHandle, path: &Path) -> bool {
+    load(app).iter().any(|e| {
+        Path::new(&e.path)
+            .canonicalize()
+            .is_ok_and(|root| path.starts_with(root))
+    })
+}
+
+fn default_name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string())
+}
+
+fn window_label(id: &str) -> String {
+    format!("{}{}", WINDOW_PREFIX, id)
+}
+
+// 不存在时加入列表，返回该工作区
+fn upsert(
+    entries: &mut Vec<WorkspaceEntry>,
+    path: &str,
+    name: Option<String>,
+    icon: Option<String>,
+) -> WorkspaceEntry {
+    let path = normalize(path);
+    let index = match entries.iter().position(|e| e.path == path) {
+        Some(index) => index,
+        None => {
+            let entry = WorkspaceEntry {
+                id: workspace::new_id(|id| entries.iter().any(|e| e.id == id)),
+                name: default_name(&path),
+                path,
+                icon: String::new(),
+                last_opened: 0,
+            };
+            entries.push(entry);
+            entries.len() - 1
+        }
+    };
+    let entry = &mut entries[index];
+    if let Some(name) = name.map(|n| n.trim().to_string()).filter(|n| !n.is_empty()) {
+        entry.name = name;
+    }
+    if let Some(icon) = icon {
+        entry.icon = icon.trim().to_string();
+    }
+    entry.clone()
+}
+
+// 最近打开的在前
+#[tauri::command]
+pub fn list_workspaces(app: AppHandle) -> Vec<WorkspaceInfo> {
+    let mut entries = load(&app);
+    entries.sort_by_key(|e| std::cmp::Reverse(e.last_opened));
+    entries
+        .into_iter()
+        .map(|entry| {
+            let label = window_label(&entry.id);
+            WorkspaceInfo {
+                missing: !PathBuf::from(&entry.path).is_dir(),
+                window: app.get_webview_window(&label).map(|_| label),
+                entry,
+            }
+        })
+        .collect()
+}
+
+// 由用户在系统对话框中选择目录后加入工作区，已存在时更新名称和图标；取消选择时返回 None
+// 工作区只能经由这里登记，前端不能直接传入路径
+#[tauri::command]
+pub async fn add_workspace(
+    app: AppHandle,
+    window: Window,
+    name: Option<String>,
+    icon: Option<String>,
+) -> Result<Option<WorkspaceEntry>, MobiError> {
+    let Some(picked) = app
+        .dialog()
+        .file()
+        .set_parent(&window)
+        .set_title("选择工作目录")
+        .blocking_pick_folder()
+    else {
+        return Ok(None);
+    };
+    let path = picked
+        .into_path()
+        .map_err(|e| format!("无法使用所选目录: {}", e))?;
+    let path = path.to_string_lossy().to_string();
+    if !Path::new(&path).is_dir() {
+        return Err("工作区目录不存在".into());
+    }
+    let _guard = LOCK.locked();
+    let mut entries = load(&app);
+    let entry = upsert(&mut entries, &path, name, icon);
+    save(&app, &entries)?;
+    Ok(Some(entry))
+}
+
+#[tauri::command]
+pub fn update_workspace(
+    app: AppHandle,
+    id: String,
+    name: Option<String>,
+    icon: Option<String>,
+) -> Result<WorkspaceEntry, MobiError> {
+    let _guard = LOCK.locked();
+    let mut entries = load(&app);
+    let path = entries
+        .iter()
+        .find(|e| e.id == id)
+        .map(|e| e.path.clone())
+        .ok_or("没有该工作区")?;
+    let entry = upsert(&mut entries, &path, name, icon);
+    save(&app, &entries)?;
+    Ok(entry)
+}
+
+// 只从列表中移除，不删除文件
+#[tauri::command]
+pub fn remove_workspace(app: AppHandle, id: String) -> Result<(), MobiError> {
+    let _guard = LOCK.locked();
+    let mut entries = load(&app);
+    entries.retain(|e| e.id != id);
+    Ok(save(&app, &entries)?)
+}
+
+// 前端切换到某个工作区时调用：记下打开时间；不在列表中的目录不会因此加入
+#[tauri::command]
+pub fn mark_workspace_opened(app: AppHandle, path: String) -> Result<WorkspaceEntry, MobiError> {
+    let _guard = LOCK.locked();
+    let mut entries = load(&app);
+    let path = normalize(&path);
+    let entry = entries
+        .iter_mut()
+        .find(|e| e.path == path)
+        .ok_or("工作区未登记")?;
+    entry.last_opened = Utc::now().timestamp_millis();
+    let entry = entry.clone();
+    save(&app, &entries)?;
+    Ok(entry)
+}
+
+// 在新窗口中打开工作区，已打开时切到该窗口；返回窗口标签
+#[tauri::command]
+pub fn open_workspace_window(app: AppHandle, id: String) -> Result<String, MobiError> {
+    let entry = load(&app)
+        .into_iter()
+        .find(|e| e.id == id)
+        .ok_or("没有该工作区")?;
+    if !Path::new(&entry.path).is_dir() {
+        return Err("工作区目录不存在".into());
+    }
+    let label = window_label(&entry.id);
+    if let Some(window) = app.get_webview_window(&label) {
+        let _ = window.unminimize();
+        window
+            .set_focus()
+            .map_err(|e| format!("切换窗口失败: {}", e))?;
+        return Ok(label);
+    }
+    let title = if entry.icon.is_empty() {
+        format!("{} - 墨笔", entry.name)
+    } else {
+        format!("{} {} - 墨笔", entry.icon, entry.name)
+    };
+    WebviewWindowBuilder::new(&app, &label, WebviewUrl::App("index.html".into()))
+        .title(title)
+        .inner_size(1200.0, 800.0)
+        .min_inner_size(800.0, 600.0)
+        .build()
+        .map_err(|e| format!("打开工作区窗口失败: {}", e))?;
+    Ok(label)
+}
+
+// 工作区窗口启动时取回自己的工作区，主窗口返回 None
+#[tauri::command]
+pub fn get_window_workspace(app: AppHandle, window: Window) -> Option<WorkspaceEntry> {
+    let id = window.label().strip_prefix(WINDOW_PREFIX)?;
+    load(&app).into_iter().find(|e| e.id == id)
+}
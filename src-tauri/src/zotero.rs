@@ -0,0 +1,178 @@
+// Zotero 集成：通过 Better BibTeX 的 JSON-RPC 接口检索文献，并把笔记中引用到的条目同步为工作区文献库
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::AppHandle;
+
+use crate::error::{self, MobiError};
+use crate::{citations, settings, workspace};
+
+// 写在同步生成的文献库开头，避免覆盖用户手写的 .bib
+const SYNC_MARKER: &str = "@comment{Mobi: synced from Zotero}";
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct ZoteroSettings {
+    url: String,
+    translator: String,
+    // 导出前自动同步引用到的条目
+    auto_sync: bool,
+}
+
+impl Default for ZoteroSettings {
+    fn default() -> Self {
+        ZoteroSettings {
+            url: "http://127.0.0.1:23119".into(),
+            translator: "Better BibLaTeX".into(),
+            auto_sync: false,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ZoteroItem {
+    citekey: String,
+    title: String,
+    authors: Vec<String>,
+    year: Option<i64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncResult {
+    path: String,
+    count: usize,
+}
+
+async fn rpc(config: &ZoteroSettings, method: &str, params: Value) -> Result<Value, String> {
+    let client = reqwest::Client::builder()
+        .no_proxy()
+        .build()
+        .map_err(|e| e.to_string())?;
+    let response = client
+        .post(format!(
+            "{}/better-bibtex/json-rpc",
+            config.url.trim_end_matches('/')
+        ))
+        .json(&json!({ "jsonrpc": "2.0", "method": method, "params": params, "id": 1 }))
+        .send()
+        .await
+        .map_err(|e| {
+            format!(
+                "无法连接 Zotero，请确认 Zotero 已启动并安装 Better BibTeX: {}",
+                e
+            )
+        })?;
+    if !response.status().is_success() {
+        return Err(format!("Zotero 请求失败: {}", response.status()));
+    }
+    let mut body: Value = response
+        .json()
+        .await
+        .map_err(|e| format!("解析 Zotero 响应失败: {}", e))?;
+    if let Some(message) = body["error"]["message"].as_str() {
+        return Err(format!("Zotero 返回错误: {}", message));
+    }
+    Ok(body["result"].take())
+}
+
+fn item_from_csl(value: &Value) -> Option<ZoteroItem> {
+    let citekey = value["citekey"]
+        .as_str()
+        .or_else(|| value["citation-key"].as_str())?;
+    let authors = value["author"]
+        .as_array()
+        .map(|list| {
+            list.iter()
+                .map(|a| match (a["given"].as_str(), a["family"].as_str()) {
+                    (Some(given), Some(family)) => format!("{} {}", given, family),
+                    (None, Some(family)) => family.to_string(),
+                    _ => a["literal"].as_str().unwrap_or_default().to_string(),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    Some(ZoteroItem {
+        citekey: citekey.to_string(),
+        title: value["title"].as_str().unwrap_or_default().to_string(),
+        authors,
+        year: value["issued"]["date-parts"][0][0].as_i64(),
+    })
+}
+
+#[tauri::command]
+pub async fn zotero_search(app: AppHandle, query: String) -> Result<Vec<ZoteroItem>, MobiError> {
+    let config: ZoteroSettings = settings::load(&app, "zotero");
+    let result = rpc(&config, "item.search", json!([query])).await?;
+    Ok(result
+        .as_array()
+        .map(|items| items.iter().filter_map(item_from_csl).collect())
+        .unwrap_or_default())
+}
+
+// 把工作区笔记中引用到的条目（以及上次同步过的条目）从 Zotero 导出到文献库
+pub async fn sync(app: &AppHandle, root: &Path) -> Result<SyncResult, String> {
+    let config: ZoteroSettings = settings::load(app, "zotero");
+    let target =
+        citations::bibliography_path(app, root).unwrap_or_else(|| root.join("references.bib"));
+
+    let mut keys = BTreeSet::new();
+    if let Ok(existing) = fs::read_to_string(&target) {
+        if !existing.starts_with(SYNC_MARKER) {
+            return Err(format!(
+                "{} 不是由 Zotero 同步生成的，请在引用设置中指定其他文献库路径",
+                target.display()
+            ));
+        }
+        keys.extend(citations::bib_keys(&existing));
+    }
+    for file in workspace::note_files(root) {
+        if let Ok(content) = fs::read_to_string(&file) {
+            keys.extend(citations::cited_keys(&content));
+        }
+    }
+
+    let keys: Vec<String> = keys.into_iter().collect();
+    let bibliography = if keys.is_empty() {
+        String::new()
+    } else {
+        let result = rpc(&config, "item.export", json!([keys, config.translator])).await?;
+        // 旧版 Better BibTeX 返回 [状态码, 类型, 内容]
+        match result {
+            Value::String(text) => text,
+            Value::Array(parts) => parts
+                .get(2)
+                .and_then(|p| p.as_str())
+                .unwrap_or_default()
+                .to_string(),
+            _ => return Err("Zotero 导出结果格式不正确".into()),
+        }
+    };
+
+    fs::write(&target, format!("{}\n\n{}", SYNC_MARKER, bibliography))
+        .map_err(|e| format!("写入文献库失败: {}", e))?;
+    Ok(SyncResult {
+        path: target.to_string_lossy().to_string(),
+        count: keys.len(),
+    })
+}
+
+// 导出前按设置自动同步；Zotero 未启动等失败不影响导出
+pub async fn auto_sync(app: &AppHandle, root: &Path) {
+    let config: ZoteroSettings = settings::load(app, "zotero");
+    if config.auto_sync {
+        if let Err(e) = sync(app, root).await {
+            error::report(app, "Zotero 同步失败", e);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn zotero_sync(app: AppHandle, workspace: String) -> Result<SyncResult, MobiError> {
+    Ok(sync(&app, &PathBuf::from(&workspace)).await?)
+}
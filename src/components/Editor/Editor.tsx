@@ -1,11 +1,13 @@
 import React, { useCallback, useEffect, useMemo, useRef, useState } from 'react';
 import MDEditor from '@uiw/react-md-editor';
-import { convertFileSrc } from '@tauri-apps/api/core';
+import { convertFileSrc, invoke } from '@tauri-apps/api/core';
 import { openPath } from '@tauri-apps/plugin-opener';
 import { useEditorStore } from '../../stores/editorStore';
 import { useSettingsStore } from '../../stores/settingsStore';
 import { MermaidRenderer } from './MermaidRenderer';
 import { useFilePaste } from '../../hooks/useFilePaste';
+import { recordUndoEdit } from '../../hooks/useUndoHistory';
+import { errorMessage } from '../../errors';
 
 // 支持的文件扩展名
 const IMAGE_EXTENSIONS = ['png', 'jpg', 'jpeg', 'gif', 'webp', 'bmp', 'svg'];
@@ -83,8 +85,8 @@ const CustomImage: React.FC<React.ImgHTMLAttributes<HTMLImageElement> & { baseDi
       return;
     }
 
-    // 如果已经是 asset:// 协议，直接使用
-    if (src.startsWith('asset://')) {
+    // 如果已经是 mobi-asset:// 协议，直接使用
+    if (src.startsWith('mobi-asset://') || src.startsWith('http://mobi-asset.localhost/')) {
       setImageSrc(src);
       setHasError(false);
       return;
@@ -98,9 +100,9 @@ const CustomImage: React.FC<React.ImgHTMLAttributes<HTMLImageElement> & { baseDi
       fullPath = `${baseDir}/${src}`;
     }
 
-    // 使用 convertFileSrc 转换为 Tauri 可访问的 URL
+    // 通过 mobi-asset 协议读取，后端只提供工作区和当前文档目录中的文件
     try {
-      const tauriSrc = convertFileSrc(fullPath);
+      const tauriSrc = convertFileSrc(fullPath, 'mobi-asset');
       console.log('图片路径转换:', { src, baseDir, fullPath, tauriSrc });
       setImageSrc(tauriSrc);
       setHasError(false);
@@ -171,7 +173,7 @@ const CustomLink: React.FC<React.AnchorHTMLAttributes<HTMLAnchorElement> & { bas
         await openPath(fullPath);
       } catch (error) {
         console.error('打开文件失败:', error);
-        alert('无法打开文件: ' + (error instanceof Error ? error.message : String(error)));
+        alert('无法打开文件: ' + errorMessage(error));
       }
     }
   };
@@ -207,7 +209,7 @@ const CustomLink: React.FC<React.AnchorHTMLAttributes<HTMLAnchorElement> & { bas
 };
 
 export const Editor: React.FC = () => {
-  const { content, setContent, editorMode, currentFilePath, workspaceDir, pendingImageMarkdown, setPendingImageMarkdown } = useEditorStore();
+  const { content, setContent, editorMode, currentFilePath, workspaceDir, pendingImageMarkdown, setPendingImageMarkdown, readOnly } = useEditorStore();
   const { fontSize } = useSettingsStore();
   const { handlePaste } = useFilePaste();
   const editorRef = useRef<HTMLDivElement>(null);
@@ -232,6 +234,7 @@ export const Editor: React.FC = () => {
   }, [baseDir]);
 
   const handleChange = useCallback((value?: string) => {
+    recordUndoEdit(value || '');
     setContent(value || '');
   }, [setContent]);
 
@@ -263,10 +266,40 @@ export const Editor: React.FC = () => {
     }
   }, [pendingImageMarkdown, insertMarkdownImage, setPendingImageMarkdown]);
 
+  // 粘贴裸链接：先原样插入，取到网页标题后替换为 [标题](地址)；选中文字时保留浏览器默认行为
+  const pasteLink = useCallback((event: ClipboardEvent, url: string) => {
+    const textarea = editorRef.current?.querySelector('textarea');
+    if (!textarea || textarea.selectionStart !== textarea.selectionEnd) return;
+    event.preventDefault();
+    const start = textarea.selectionStart;
+    insertMarkdownImage(url);
+    invoke<{ title: string | null }>('fetch_link_metadata', { url })
+      .then(({ title }) => {
+        if (!title) return;
+        const { content, setContent } = useEditorStore.getState();
+        // 等待期间链接被改动或移走时不再替换
+        if (content.slice(start, start + url.length) !== url) return;
+        const link = `[${title.replace(/([[\]])/g, '\\$1')}](${url})`;
+        setContent(content.slice(0, start) + link + content.slice(start + url.length));
+      })
+      .catch(error => console.warn('获取网页标题失败:', error));
+  }, [insertMarkdownImage]);
+
   // 处理粘贴事件
   const onPaste = useCallback(async (event: ClipboardEvent) => {
     console.log('粘贴事件触发');
 
+    // 从 Excel / Google 表格 / WPS 复制的区域转为 Markdown 表格；Excel 同时放入的区域截图不按图片粘贴
+    const html = event.clipboardData?.getData('text/html') || '';
+    const plain = event.clipboardData?.getData('text/plain') || '';
+    if (/<table[\s>]/i.test(html) || (!html && plain.includes('\t') && plain.trim().includes('\n'))) {
+      event.preventDefault();
+      const table = await invoke<string | null>('paste_as_table', { html, text: plain })
+        .catch(() => null);
+      insertMarkdownImage(table ? `\n${table}\n` : plain);
+      return;
+    }
+
     // 检查是否有图片数据
     const items = event.clipboardData?.items;
     if (!items) {
@@ -285,6 +318,10 @@ export const Editor: React.FC = () => {
 
     if (!hasImage) {
       console.log('剪贴板中没有图片');
+      const text = event.clipboardData?.getData('text/plain').trim() || '';
+      if (/^https?:\/\/\S+$/i.test(text) && useSettingsStore.getState().fetchLinkTitles) {
+        pasteLink(event, text);
+      }
       return;
     }
 
@@ -299,7 +336,7 @@ export const Editor: React.FC = () => {
     if (markdownImage) {
       insertMarkdownImage(markdownImage);
     }
-  }, [currentFilePath, workspaceDir, handlePaste, insertMarkdownImage]);
+  }, [currentFilePath, workspaceDir, handlePaste, insertMarkdownImage, pasteLink]);
 
   // 添加粘贴事件监听
   useEffect(() => {
@@ -308,11 +345,25 @@ export const Editor: React.FC = () => {
       const pasteHandler = (event: Event) => {
         onPaste(event as ClipboardEvent);
       };
+      // 在编辑器中复制、剪切的文字记入剪贴板历史（需在设置中开启）
+      const copyHandler = (event: Event) => {
+        const target = event.target;
+        const text = target instanceof HTMLTextAreaElement
+          ? target.value.slice(target.selectionStart, target.selectionEnd)
+          : window.getSelection()?.toString() || '';
+        if (text) {
+          invoke('record_clipboard_copy', { text }).catch(() => {});
+        }
+      };
 
       editorElement.addEventListener('paste', pasteHandler);
+      editorElement.addEventListener('copy', copyHandler);
+      editorElement.addEventListener('cut', copyHandler);
 
       return () => {
         editorElement.removeEventListener('paste', pasteHandler);
+        editorElement.removeEventListener('copy', copyHandler);
+        editorElement.removeEventListener('cut', copyHandler);
       };
     }
   }, [onPaste]);
@@ -347,6 +398,7 @@ export const Editor: React.FC = () => {
         enableScroll={true}
         textareaProps={{
           placeholder: '开始编写你的 Markdown 文档...',
+          readOnly,
         }}
         previewOptions={{
           components: {
@@ -1,8 +1,25 @@
-import React from 'react';
+import React, { useEffect, useState } from 'react';
+import { listen } from '@tauri-apps/api/event';
 import { useEditorStore } from '../../stores/editorStore';
 
+interface BackgroundError {
+  context: string;
+  message: string;
+}
+
 export const StatusBar: React.FC = () => {
   const { content, currentFilePath, isModified } = useEditorStore();
+  // 后台任务（提醒检查、自动同步、录音等）的最近一次错误，点击后清除
+  const [backgroundError, setBackgroundError] = useState<BackgroundError | null>(null);
+
+  useEffect(() => {
+    const unlisten = listen<BackgroundError>('background-error', (event) => {
+      setBackgroundError(event.payload);
+    });
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
 
   // 统计字数
   const wordCount = content.length;
@@ -17,6 +34,15 @@ export const StatusBar: React.FC = () => {
         <span>行数: {lineCount}</span>
       </div>
       <div className="flex items-center gap-4">
+        {backgroundError && (
+          <button
+            className="truncate max-w-xs text-red-500"
+            title={`${backgroundError.context}: ${backgroundError.message}`}
+            onClick={() => setBackgroundError(null)}
+          >
+            {backgroundError.context}
+          </button>
+        )}
         {currentFilePath && (
           <span className="truncate max-w-md" title={currentFilePath}>
             {currentFilePath}
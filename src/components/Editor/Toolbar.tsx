@@ -3,6 +3,7 @@ import { useEditorStore, EditorMode } from '../../stores/editorStore';
 import { useSettingsStore, Theme } from '../../stores/settingsStore';
 import { useFileSystem } from '../../hooks/useFileSystem';
 import { useExport } from '../../hooks/useExport';
+import { errorMessage } from '../../errors';
 
 interface ToolbarProps {
   onToggleSidebar: () => void;
@@ -11,7 +12,7 @@ interface ToolbarProps {
 
 export const Toolbar: React.FC<ToolbarProps> = ({ onToggleSidebar, sidebarVisible }) => {
   const { editorMode, setEditorMode, currentFileName, isModified } = useEditorStore();
-  const { theme, setTheme, saveImagesToSubfolder, imageSubfolderName, setSaveImagesToSubfolder, setImageSubfolderName } = useSettingsStore();
+  const { theme, setTheme, saveImagesToSubfolder, imageSubfolderName, setSaveImagesToSubfolder, setImageSubfolderName, fetchLinkTitles, setFetchLinkTitles } = useSettingsStore();
   const { newFile, openFile, saveFile, saveFileAs } = useFileSystem();
   const { exportToHtml } = useExport();
   const [showExportMenu, setShowExportMenu] = useState(false);
@@ -37,6 +38,7 @@ export const Toolbar: React.FC<ToolbarProps> = ({ onToggleSidebar, sidebarVisibl
       await exportToHtml();
     } catch (error) {
       console.error('导出失败:', error);
+      alert('导出失败: ' + errorMessage(error));
     }
   };
 
@@ -223,6 +225,15 @@ export const Toolbar: React.FC<ToolbarProps> = ({ onToggleSidebar, sidebarVisibl
                     )}
                   </div>
                 )}
+                <label className="flex items-center gap-2 mt-2 text-sm text-gray-600 dark:text-gray-300 cursor-pointer">
+                  <input
+                    type="checkbox"
+                    checked={fetchLinkTitles}
+                    onChange={(e) => setFetchLinkTitles(e.target.checked)}
+                    className="rounded border-gray-300 dark:border-gray-600"
+                  />
+                  粘贴链接时获取网页标题
+                </label>
               </div>
             </>
           )}
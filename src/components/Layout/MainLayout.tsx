@@ -4,10 +4,12 @@ import { FileTree } from '../Sidebar';
 import { useFileSystem } from '../../hooks/useFileSystem';
 import { useSettingsStore, initTheme } from '../../stores/settingsStore';
 import { useEditorStore } from '../../stores/editorStore';
-import { useFilePaste } from '../../hooks/useFilePaste';
+import { useSharedBuffer } from '../../hooks/useSharedBuffer';
+import { useUndoHistory } from '../../hooks/useUndoHistory';
 import { getCurrentWindow } from '@tauri-apps/api/window';
 import { listen } from '@tauri-apps/api/event';
 import { invoke } from '@tauri-apps/api/core';
+import { errorMessage } from '../../errors';
 
 // 支持的文件扩展名列表
 const IMAGE_EXTENSIONS = ['png', 'jpg', 'jpeg', 'gif', 'webp', 'bmp', 'svg'];
@@ -30,7 +32,8 @@ export const MainLayout: React.FC = () => {
   const [isDraggingFile, setIsDraggingFile] = useState(false);
   const { openFile, saveFile, newFile } = useFileSystem();
   const { currentFilePath, workspaceDir, setPendingImageMarkdown } = useEditorStore();
-  const { handleTauriDrop } = useFilePaste();
+  useSharedBuffer();
+  useUndoHistory();
 
   // 使用 ref 保存最新的函数引用
   const openFileRef = useRef(openFile);
@@ -45,9 +48,58 @@ export const MainLayout: React.FC = () => {
     initTheme();
   }, []);
 
+  // 监视当前工作区的文件变化（含同步冲突副本）
+  useEffect(() => {
+    invoke('watch_workspace', { workspace: workspaceDir || null }).catch(error => {
+      console.error('监视工作区失败:', error);
+    });
+  }, [workspaceDir]);
+
+  // 收件箱文件夹中放入的文件自动导入当前工作区
+  useEffect(() => {
+    invoke('watch_inbox', { workspace: workspaceDir || null }).catch(error => {
+      console.error('监视收件箱失败:', error);
+    });
+  }, [workspaceDir]);
+
+  // 告诉预览服务当前文档，外部浏览器中的预览随之切换
+  useEffect(() => {
+    invoke('set_preview_document', {
+      path: currentFilePath || null,
+      workspace: workspaceDir || null,
+    }).catch(() => {});
+  }, [currentFilePath, workspaceDir]);
+
+  // 工作区窗口启动时打开自己的工作区
+  useEffect(() => {
+    invoke<{ path: string } | null>('get_window_workspace')
+      .then(entry => {
+        if (entry && entry.path !== useEditorStore.getState().workspaceDir) {
+          useEditorStore.getState().setWorkspaceDir(entry.path);
+        }
+      })
+      .catch(() => {});
+  }, []);
+
+  // 记入工作区列表，供切换工作区时按最近打开排序
+  useEffect(() => {
+    if (!workspaceDir) return;
+    invoke('mark_workspace_opened', { path: workspaceDir }).catch(() => {});
+  }, [workspaceDir]);
+
+  // 预览中的本地图片只能读取当前工作区和文档所在目录
+  useEffect(() => {
+    invoke('set_asset_scope', {
+      workspace: workspaceDir || null,
+      document: currentFilePath || null,
+    }).catch(() => {});
+  }, [currentFilePath, workspaceDir]);
+
   // 启动时检查是否有待打开的文件（通过"打开方式"启动）
   useEffect(() => {
     const checkOpenedFile = async () => {
+      // 由"打开方式"传入的文件只在主窗口打开
+      if (getCurrentWindow().label !== 'main') return;
       try {
         const filePath = await invoke<string | null>('get_opened_file');
         if (filePath && filePath.match(/\.(md|markdown|txt)$/i)) {
@@ -83,24 +135,30 @@ export const MainLayout: React.FC = () => {
             setIsDraggingFile(false);
           } else if (event.payload.type === 'drop' && event.payload.paths.length > 0) {
             setIsDraggingFile(false);
-            const filePath = event.payload.paths[0];
-            const ext = filePath.split('.').pop()?.toLowerCase() || '';
-
-            // 如果是支持的附件文件，插入到编辑器
-            if (SUPPORTED_FILE_EXTENSIONS.includes(ext)) {
-              // 检查是否有打开的文件或工作目录
-              if (!currentFilePath && !workspaceDir) {
-                alert('请先保存文件或打开一个目录后再拖拽文件');
-                return;
-              }
-              const markdownLink = await handleTauriDrop(event.payload.paths);
-              if (markdownLink) {
+            // 由后端按设置中的策略处理：Markdown 打开或插入链接，图片和其他附件放入附件目录后插入链接
+            const { saveImagesToSubfolder, imageSubfolderName } = useSettingsStore.getState();
+            const noteName = currentFilePath?.split('/').pop()?.replace(/\.(md|markdown|txt)$/i, '');
+            const assetDir = saveImagesToSubfolder && imageSubfolderName
+              ? (noteName ? `${imageSubfolderName}/${noteName}` : imageSubfolderName)
+              : '';
+            try {
+              const result = await invoke<{ open: string[]; markdown: string }>('handle_dropped_paths', {
+                paths: event.payload.paths,
+                mode: null,
+                document: currentFilePath,
+                workspace: workspaceDir,
+                assetDir,
+              });
+              if (result.markdown) {
                 // 通过 store 设置待插入的内容，让 Editor 组件插入到光标位置
-                setPendingImageMarkdown(markdownLink);
+                setPendingImageMarkdown(result.markdown);
               }
-            } else if (filePath.match(/\.(md|markdown|txt)$/i)) {
-              // 从外部拖拽打开，设置工作目录为文件所在目录
-              await openFileRef.current(filePath, true);
+              if (result.open.length > 0) {
+                // 从外部拖拽打开，设置工作目录为文件所在目录
+                await openFileRef.current(result.open[0], true);
+              }
+            } catch (error) {
+              alert('处理拖拽文件失败: ' + errorMessage(error));
             }
           }
         });
@@ -114,7 +172,7 @@ export const MainLayout: React.FC = () => {
     return () => {
       cleanup?.then(unlisten => unlisten?.());
     };
-  }, [currentFilePath, workspaceDir, handleTauriDrop, setPendingImageMarkdown]);
+  }, [currentFilePath, workspaceDir, setPendingImageMarkdown]);
 
   // 监听从系统双击打开的文件 (通过命令行参数)
   useEffect(() => {
@@ -126,7 +184,14 @@ export const MainLayout: React.FC = () => {
           await openFileRef.current(filePath, true);
         }
       });
-      return unlisten;
+      // 二进制或过大的文件：openFile 会再次检查并询问打开方式
+      const unlistenWarning = await listen<{ path: string }>('open-file-warning', async (event) => {
+        await openFileRef.current(event.payload.path, true);
+      });
+      return () => {
+        unlisten();
+        unlistenWarning();
+      };
     };
 
     const cleanup = setupOpenFileListener();
@@ -135,10 +200,46 @@ export const MainLayout: React.FC = () => {
     };
   }, []);
 
+  // 命令面板、菜单和 mobi://action 链接经后端动作注册表发来的界面动作
+  useEffect(() => {
+    const unlisten = listen<{ id: string; args: Record<string, unknown> }>('run-action', (event) => {
+      switch (event.payload.id) {
+        case 'file.new':
+          newFileRef.current();
+          break;
+        case 'file.open':
+          openFileRef.current();
+          break;
+        case 'file.save':
+          saveFileRef.current();
+          break;
+        case 'file.saveAs':
+          saveFileRef.current(true);
+          break;
+        case 'file.print': {
+          const { content, currentFilePath, workspaceDir } = useEditorStore.getState();
+          invoke('print_document', {
+            content,
+            path: currentFilePath,
+            workspace: workspaceDir,
+          }).catch(error => console.error('打印失败:', error));
+          break;
+        }
+        case 'view.toggleSidebar':
+          setSidebarVisible(v => !v);
+          break;
+      }
+    });
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
   // 侧边栏文件选择处理 - 不改变工作目录
   const handleFileSelect = useCallback((path: string) => {
     openFileRef.current(path, false).catch(error => {
       console.error('打开文件失败:', error);
+      alert('打开文件失败: ' + errorMessage(error));
     });
   }, []);
 
@@ -170,6 +271,17 @@ export const MainLayout: React.FC = () => {
             e.preventDefault();
             setSidebarVisible(v => !v);
             break;
+          case 'p': {
+            // 由后端渲染打印版页面，替代 webview 自带的打印
+            e.preventDefault();
+            const { content, currentFilePath, workspaceDir } = useEditorStore.getState();
+            invoke('print_document', {
+              content,
+              path: currentFilePath,
+              workspace: workspaceDir,
+            }).catch(error => console.error('打印失败:', error));
+            break;
+          }
         }
       }
     };
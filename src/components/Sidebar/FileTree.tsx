@@ -1,6 +1,5 @@
 import React, { useState, useEffect } from 'react';
-import { open } from '@tauri-apps/plugin-dialog';
-import { readDir } from '@tauri-apps/plugin-fs';
+import { invoke } from '@tauri-apps/api/core';
 import { useEditorStore } from '../../stores/editorStore';
 
 interface FileInfo {
@@ -13,21 +12,14 @@ interface FileTreeProps {
   onFileSelect: (path: string) => void;
 }
 
-// 读取目录内容
+// 读取目录内容（后端按 .mobiignore 和符号链接策略过滤）
 async function loadDirContents(dirPath: string): Promise<FileInfo[]> {
   try {
-    const entries = await readDir(dirPath);
-    const files: FileInfo[] = [];
-
-    for (const entry of entries) {
-      if (entry.name.startsWith('.')) continue;
-
-      files.push({
-        name: entry.name,
-        path: `${dirPath}/${entry.name}`,
-        isDirectory: entry.isDirectory || false,
-      });
-    }
+    const { workspaceDir } = useEditorStore.getState();
+    const files = await invoke<FileInfo[]>('list_workspace_dir', {
+      workspace: workspaceDir || null,
+      dir: dirPath,
+    });
 
     return files.sort((a, b) => {
       if (a.isDirectory && !b.isDirectory) return -1;
@@ -115,16 +107,15 @@ export const FileTree: React.FC<FileTreeProps> = ({ onFileSelect }) => {
   const [files, setFiles] = useState<FileInfo[]>([]);
   const [loading, setLoading] = useState(false);
 
-  // 选择工作目录
+  // 选择工作目录：由后端弹出对话框并登记到工作区列表，只有登记过的工作区才能读写
   const selectWorkspace = async () => {
-    const selected = await open({
-      directory: true,
-      multiple: false,
-      title: '选择工作目录'
-    });
-
-    if (selected) {
-      setWorkspaceDir(selected as string);
+    try {
+      const entry = await invoke<{ path: string } | null>('add_workspace');
+      if (entry) {
+        setWorkspaceDir(entry.path);
+      }
+    } catch (error) {
+      console.error('选择工作目录失败:', error);
     }
   };
 
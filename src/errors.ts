@@ -0,0 +1,43 @@
+// 后端命令返回的错误：{ code, message }，code 与 src-tauri/src/error.rs 中的 MobiError 对应
+export type ErrorCode =
+  | 'notFound'
+  | 'alreadyExists'
+  | 'permissionDenied'
+  | 'needsElevation'
+  | 'readOnly'
+  | 'locked'
+  | 'diskFull'
+  | 'invalidInput'
+  | 'wrongPassphrase'
+  | 'needsUnlock'
+  | 'network'
+  | 'database'
+  | 'unsupported'
+  | 'failed';
+
+export interface MobiError {
+  code: ErrorCode;
+  message: string;
+}
+
+export const isMobiError = (error: unknown): error is MobiError =>
+  typeof error === 'object' && error !== null && 'code' in error && 'message' in error;
+
+// 可以由用户处理的错误附带的建议
+const HINTS: Partial<Record<ErrorCode, string>> = {
+  locked: '请关闭正在使用该文件的程序后重试',
+  readOnly: '可以另存到其他位置',
+  permissionDenied: '可以另存到其他位置，或检查文件权限',
+  diskFull: '请清理磁盘空间后重试',
+  network: '请检查网络连接或代理设置',
+  needsUnlock: '请先输入密码解锁',
+};
+
+// 显示给用户的错误文字，兼容插件抛出的字符串和 Error
+export const errorMessage = (error: unknown): string => {
+  if (isMobiError(error)) {
+    const hint = HINTS[error.code];
+    return hint ? `${error.message}。${hint}` : error.message;
+  }
+  return error instanceof Error ? error.message : String(error);
+};
@@ -1,4 +1,5 @@
 import { useCallback } from 'react';
+import { invoke } from '@tauri-apps/api/core';
 import { save } from '@tauri-apps/plugin-dialog';
 import { writeTextFile } from '@tauri-apps/plugin-fs';
 import { marked } from 'marked';
@@ -7,118 +8,15 @@ import { useEditorStore } from '../stores/editorStore';
 export type ExportFormat = 'html' | 'md';
 
 export const useExport = () => {
-  const { content, currentFileName } = useEditorStore();
+  const { content, currentFileName, workspaceDir } = useEditorStore();
 
-  // 生成 HTML 内容
+  // 生成 HTML 内容（由后端渲染，代码高亮以内联样式写入，引用按工作区文献库解析）
   const generateHtml = useCallback(async (markdown: string, title: string): Promise<string> => {
-    const bodyHtml = await marked(markdown);
-
-    return `<!DOCTYPE html>
-<html lang="zh-CN">
-<head>
-  <meta charset="UTF-8">
-  <meta name="viewport" content="width=device-width, initial-scale=1.0">
-  <title>${title}</title>
-  <style>
-    * {
-      box-sizing: border-box;
-    }
-    body {
-      max-width: 800px;
-      margin: 0 auto;
-      padding: 40px 20px;
-      font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, 'Helvetica Neue', Arial, sans-serif;
-      font-size: 16px;
-      line-height: 1.6;
-      color: #333;
-      background: #fff;
-    }
-    h1, h2, h3, h4, h5, h6 {
-      margin-top: 24px;
-      margin-bottom: 16px;
-      font-weight: 600;
-      line-height: 1.25;
-    }
-    h1 { font-size: 2em; border-bottom: 1px solid #eee; padding-bottom: 0.3em; }
-    h2 { font-size: 1.5em; border-bottom: 1px solid #eee; padding-bottom: 0.3em; }
-    h3 { font-size: 1.25em; }
-    p { margin-top: 0; margin-bottom: 16px; }
-    a { color: #0366d6; text-decoration: none; }
-    a:hover { text-decoration: underline; }
-    code {
-      padding: 0.2em 0.4em;
-      margin: 0;
-      font-size: 85%;
-      background-color: #f6f8fa;
-      border-radius: 3px;
-      font-family: 'SF Mono', Monaco, Menlo, Consolas, monospace;
-    }
-    pre {
-      padding: 16px;
-      overflow: auto;
-      font-size: 85%;
-      line-height: 1.45;
-      background-color: #f6f8fa;
-      border-radius: 6px;
-    }
-    pre code {
-      padding: 0;
-      background: transparent;
-    }
-    blockquote {
-      margin: 0 0 16px;
-      padding: 0 1em;
-      color: #6a737d;
-      border-left: 4px solid #dfe2e5;
-    }
-    ul, ol {
-      padding-left: 2em;
-      margin-top: 0;
-      margin-bottom: 16px;
-    }
-    li + li {
-      margin-top: 0.25em;
-    }
-    table {
-      border-collapse: collapse;
-      width: 100%;
-      margin-bottom: 16px;
-    }
-    table th, table td {
-      padding: 8px 13px;
-      border: 1px solid #dfe2e5;
-    }
-    table th {
-      font-weight: 600;
-      background-color: #f6f8fa;
-    }
-    table tr:nth-child(2n) {
-      background-color: #f6f8fa;
-    }
-    img {
-      max-width: 100%;
-      height: auto;
-    }
-    hr {
-      height: 0.25em;
-      padding: 0;
-      margin: 24px 0;
-      background-color: #e1e4e8;
-      border: 0;
-    }
-    .task-list-item {
-      list-style-type: none;
-    }
-    .task-list-item input {
-      margin-right: 0.5em;
-    }
-  </style>
-</head>
-<body>
-${bodyHtml}
-</body>
-</html>`;
-  }, []);
+    return invoke<string>('render_html', {
+      content: markdown,
+      options: { title, workspace: workspaceDir ?? '' },
+    });
+  }, [workspaceDir]);
 
   // 导出为 HTML
   const exportToHtml = useCallback(async () => {
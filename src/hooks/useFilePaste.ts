@@ -1,7 +1,8 @@
 import { useCallback } from 'react';
-import { writeFile, exists, mkdir, readFile } from '@tauri-apps/plugin-fs';
+import { invoke } from '@tauri-apps/api/core';
 import { useEditorStore } from '../stores/editorStore';
 import { useSettingsStore } from '../stores/settingsStore';
+import { errorMessage } from '../errors';
 
 // 图片扩展名列表
 const IMAGE_EXTENSIONS = ['png', 'jpg', 'jpeg', 'gif', 'webp', 'bmp', 'svg'];
@@ -82,9 +83,9 @@ export const useFilePaste = () => {
     }
 
     // 确保目录存在
-    const dirExists = await exists(targetDir);
+    const dirExists = await invoke<boolean>('path_exists', { path: targetDir });
     if (!dirExists) {
-      await mkdir(targetDir, { recursive: true });
+      await invoke('create_dir', { path: targetDir, recursive: true });
     }
 
     return { targetDir, relativePath };
@@ -171,7 +172,9 @@ export const useFilePaste = () => {
       console.log('保存文件到:', filePath);
 
       // 写入文件
-      await writeFile(filePath, new Uint8Array(fileData));
+      await invoke('write_binary_file', new Uint8Array(fileData), {
+        headers: { path: encodeURIComponent(filePath) },
+      });
 
       console.log('文件保存成功');
 
@@ -180,7 +183,7 @@ export const useFilePaste = () => {
       return { relativePath, fileName };
     } catch (error) {
       console.error('保存文件失败:', error);
-      alert('保存文件失败: ' + (error instanceof Error ? error.message : String(error)));
+      alert('保存文件失败: ' + errorMessage(error));
       return null;
     }
   }, [getAttachmentDir, generateFileName, isImageExtension]);
@@ -260,72 +263,9 @@ export const useFilePaste = () => {
     return null;
   }, [saveFile, getExtensionFromMime, generateMarkdownLink]);
 
-  // 处理 Tauri 拖拽事件（接收文件路径数组）
-  const handleTauriDrop = useCallback(async (paths: string[]): Promise<string | null> => {
-    if (!paths || paths.length === 0) {
-      console.log('没有拖拽的文件路径');
-      return null;
-    }
-
-    const attachmentDir = await getAttachmentDir();
-    if (!attachmentDir) {
-      console.warn('无法保存文件：没有打开的文件或工作目录');
-      alert('请先保存文件或打开一个目录后再拖拽文件');
-      return null;
-    }
-
-    console.log('Tauri 拖拽文件路径:', paths);
-
-    for (const filePath of paths) {
-      // 获取文件扩展名和原始文件名
-      const ext = filePath.split('.').pop()?.toLowerCase() || '';
-      const originalFileName = filePath.split('/').pop() || '';
-
-      if (SUPPORTED_EXTENSIONS.includes(ext)) {
-        try {
-          // 读取源文件
-          const fileData = await readFile(filePath);
-
-          const { targetDir, relativePath: basePath } = attachmentDir;
-
-          // 保留原始文件名，检查是否存在同名文件
-          let fileName = originalFileName;
-          let targetPath = `${targetDir}/${fileName}`;
-
-          // 如果文件已存在，添加时间戳后缀
-          if (await exists(targetPath)) {
-            const timestamp = Date.now();
-            const nameWithoutExt = originalFileName.replace(/\.[^.]+$/, '');
-            fileName = `${nameWithoutExt}_${timestamp}.${ext}`;
-            targetPath = `${targetDir}/${fileName}`;
-          }
-
-          console.log('复制文件到:', targetPath);
-
-          // 写入目标文件
-          await writeFile(targetPath, fileData);
-
-          console.log('文件复制成功');
-
-          // 返回 Markdown 链接
-          const relativePath = basePath ? `${basePath}/${fileName}` : fileName;
-
-          // 对于链接显示，使用不带后缀的原始文件名作为显示名称
-          const displayName = originalFileName.replace(/\.[^.]+$/, '');
-          return generateMarkdownLink(relativePath, displayName, ext);
-        } catch (error) {
-          console.error('处理拖拽文件失败:', error);
-          alert('处理拖拽文件失败: ' + (error instanceof Error ? error.message : String(error)));
-        }
-      }
-    }
-    return null;
-  }, [getAttachmentDir, generateMarkdownLink]);
-
   return {
     handlePaste,
     handleDrop,
-    handleTauriDrop,
     saveFile,
     getBaseDir,
   };
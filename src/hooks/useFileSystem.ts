@@ -1,7 +1,10 @@
 import { useCallback } from 'react';
-import { open, save, ask } from '@tauri-apps/plugin-dialog';
-import { readTextFile, writeTextFile, readDir, exists, mkdir, remove, rename } from '@tauri-apps/plugin-fs';
+import { open, save, ask, message } from '@tauri-apps/plugin-dialog';
+import { invoke } from '@tauri-apps/api/core';
 import { useEditorStore } from '../stores/editorStore';
+import { flushSharedBuffer } from './useSharedBuffer';
+import { flushUndoHistory } from './useUndoHistory';
+import { isMobiError, ErrorCode } from '../errors';
 
 export interface FileInfo {
   name: string;
@@ -10,6 +13,91 @@ export interface FileInfo {
   children?: FileInfo[];
 }
 
+// 后端 run_save_pipeline 返回的处理结果
+interface SaveReport {
+  content: string;
+  changed: boolean;
+  steps: { step: string; changed: boolean; detail: string }[];
+  skipped: string[];
+  errors: string[];
+}
+
+// 后端 check_open_file 返回的警告：二进制文件或超过大小上限的文件
+interface OpenWarning {
+  kind: 'binary' | 'tooLarge';
+  path: string;
+  size: number;
+  limit: number;
+  message: string;
+  choices: OpenChoice[];
+}
+
+type OpenChoice = 'readOnly' | 'chunked' | 'cancel';
+
+// 后端 read_file_chunk 返回的一段内容
+interface FileChunk {
+  text: string;
+  offset: number;
+  next: number;
+  size: number;
+  eof: boolean;
+}
+
+// 后端 acquire_lock / get_lock_status 返回的锁状态
+interface LockStatus {
+  held: boolean;
+  lockedByOther: boolean;
+  owner: { host: string; user: string; acquiredAt: number; heartbeat: number } | null;
+}
+
+// 切换文档时释放上一个文档的编辑锁
+const releaseCurrentLock = async () => {
+  const { currentFilePath } = useEditorStore.getState();
+  if (currentFilePath) {
+    await invoke('release_lock', { path: currentFilePath }).catch(() => {});
+  }
+};
+
+// 获取编辑锁；文件正在其他设备上编辑时询问是否仍要打开，返回 false 表示放弃
+const acquireLock = async (path: string): Promise<boolean> => {
+  const status = await invoke<LockStatus>('acquire_lock', { path, force: false });
+  if (!status.lockedByOther || !status.owner) return true;
+
+  const since = new Date(status.owner.acquiredAt * 1000).toLocaleString();
+  const force = await ask(
+    `该文件正在 ${status.owner.host}（${status.owner.user}）上编辑（自 ${since} 起），同时编辑可能互相覆盖。仍要打开吗？`,
+    { title: '文件已被锁定', kind: 'warning', okLabel: '仍要打开', cancelLabel: '取消' },
+  );
+  if (force) {
+    await invoke('acquire_lock', { path, force: true });
+  }
+  return force;
+};
+
+// 询问二进制或过大的文件如何打开
+const chooseOpenMode = async (warning: OpenWarning): Promise<OpenChoice> => {
+  if (!warning.choices.includes('chunked')) {
+    const readOnly = await ask(`${warning.message}。仍要以只读方式打开吗？`, {
+      title: '无法正常打开',
+      kind: 'warning',
+      okLabel: '只读打开',
+      cancelLabel: '取消',
+    });
+    return readOnly ? 'readOnly' : 'cancel';
+  }
+  const result = await message(warning.message, {
+    title: '文件过大',
+    kind: 'warning',
+    buttons: { yes: '分段打开', no: '只读打开', cancel: '取消' },
+  });
+  if (result === 'Yes' || result === '分段打开') return 'chunked';
+  if (result === 'No' || result === '只读打开') return 'readOnly';
+  return 'cancel';
+};
+
+// 保存失败时可以改为另存为的错误码；needsElevation 先提供管理员权限保存
+const SAVE_AS_CODES: ErrorCode[] = ['readOnly', 'permissionDenied', 'needsElevation', 'notFound'];
+
 export const useFileSystem = () => {
   const {
     setContent,
@@ -27,10 +115,13 @@ export const useFileSystem = () => {
   };
 
   // 保存文件 - 直接从 store 获取最新值
-  const saveFile = useCallback(async (forceSaveAs = false) => {
+  const saveFile = useCallback(async function saveDocument(forceSaveAs = false): Promise<string | null> {
     try {
+      await flushSharedBuffer();
+      await flushUndoHistory();
       // 直接获取最新的 store 状态
-      const { content, currentFilePath } = useEditorStore.getState();
+      const { currentFilePath, workspaceDir } = useEditorStore.getState();
+      let { content } = useEditorStore.getState();
 
       let targetPath = currentFilePath;
 
@@ -46,7 +137,59 @@ export const useFileSystem = () => {
 
       if (!targetPath) return null;
 
-      await writeTextFile(targetPath, content);
+      // 只读打开的内容可能不完整或有乱码，不覆盖原文件
+      if (useEditorStore.getState().readOnly && targetPath === currentFilePath) {
+        return saveDocument(true);
+      }
+
+      // 工作区配置了保存流水线时先处理内容，失败则按原内容保存
+      if (workspaceDir) {
+        const report = await invoke<SaveReport>('run_save_pipeline', {
+          workspace: workspaceDir,
+          path: targetPath,
+          content,
+        }).catch(() => null);
+        if (report?.changed) {
+          content = report.content;
+          useEditorStore.getState().setContent(content);
+        }
+        if (report?.errors.length) console.warn('保存流水线:', report.errors.join('\n'));
+      }
+
+      try {
+        await invoke('save_document', { path: targetPath, content });
+      } catch (error) {
+        if (!isMobiError(error) || !SAVE_AS_CODES.includes(error.code)) throw error;
+
+        // 无法写入：可用时先提供管理员权限保存，否则建议另存为
+        if (error.code === 'needsElevation') {
+          const elevate = await ask(`${error.message}，是否以管理员权限保存？`, {
+            title: '无法保存',
+            kind: 'warning',
+            okLabel: '输入密码保存',
+            cancelLabel: '另存为',
+          });
+          if (elevate) {
+            await invoke('save_document_elevated', { path: targetPath, content });
+            setCurrentFile(targetPath);
+            setIsModified(false);
+            return targetPath;
+          }
+        } else {
+          const saveAs = await ask(`${error.message}，是否另存为？`, {
+            title: '无法保存',
+            kind: 'warning',
+            okLabel: '另存为',
+            cancelLabel: '取消',
+          });
+          if (!saveAs) return null;
+        }
+        return saveDocument(true);
+      }
+      if (targetPath !== currentFilePath) {
+        await releaseCurrentLock();
+        await invoke('acquire_lock', { path: targetPath, force: false }).catch(() => {});
+      }
       setCurrentFile(targetPath);
       setIsModified(false);
 
@@ -78,6 +221,7 @@ export const useFileSystem = () => {
   // 新建文件
   const newFile = useCallback(async () => {
     await checkUnsavedChanges();
+    await releaseCurrentLock();
     resetEditor();
   }, [checkUnsavedChanges, resetEditor]);
 
@@ -101,10 +245,33 @@ export const useFileSystem = () => {
         targetPath = selected as string;
       }
 
-      const fileContent = await readTextFile(targetPath);
+      // 二进制或过大的文件先询问打开方式，只读和分段打开不获取编辑锁
+      const warning = await invoke<OpenWarning | null>('check_open_file', { path: targetPath });
+      const mode = warning ? await chooseOpenMode(warning) : null;
+      if (mode === 'cancel') return null;
+
+      const { currentFilePath } = useEditorStore.getState();
+      if (targetPath !== currentFilePath) {
+        if (!mode && !(await acquireLock(targetPath))) return null;
+        await releaseCurrentLock();
+      }
+
+      let fileContent: string;
+      if (mode === 'chunked') {
+        const chunk = await invoke<FileChunk>('read_file_chunk', { path: targetPath });
+        fileContent = chunk.eof
+          ? chunk.text
+          : `${chunk.text}\n\n<!-- 分段打开：已显示 ${chunk.next} / ${chunk.size} 字节 -->\n`;
+      } else if (mode === 'readOnly') {
+        const chunk = await invoke<FileChunk>('read_file_chunk', { path: targetPath, length: warning!.size });
+        fileContent = chunk.text;
+      } else {
+        fileContent = await invoke<string>('read_text_file', { path: targetPath });
+      }
       setContent(fileContent);
       setCurrentFile(targetPath);
       setIsModified(false);
+      useEditorStore.getState().setReadOnly(mode !== null);
 
       // 如果需要，同时设置工作目录为文件所在目录
       if (setWorkspace) {
@@ -124,25 +291,14 @@ export const useFileSystem = () => {
     return saveFile(true);
   }, [saveFile]);
 
-  // 读取目录
+  // 读取目录（后端按 .mobiignore 和符号链接策略过滤）
   const readDirectory = useCallback(async (dirPath: string): Promise<FileInfo[]> => {
     try {
-      const entries = await readDir(dirPath);
-      const files: FileInfo[] = [];
-
-      for (const entry of entries) {
-        // 跳过隐藏文件
-        if (entry.name.startsWith('.')) continue;
-
-        const fullPath = `${dirPath}/${entry.name}`;
-        const fileInfo: FileInfo = {
-          name: entry.name,
-          path: fullPath,
-          isDirectory: entry.isDirectory || false,
-        };
-
-        files.push(fileInfo);
-      }
+      const { workspaceDir } = useEditorStore.getState();
+      const files = await invoke<FileInfo[]>('list_workspace_dir', {
+        workspace: workspaceDir || null,
+        dir: dirPath,
+      });
 
       // 排序：文件夹在前，文件在后，按名称排序
       return files.sort((a, b) => {
@@ -160,11 +316,11 @@ export const useFileSystem = () => {
   const createFile = useCallback(async (dirPath: string, fileName: string) => {
     const filePath = `${dirPath}/${fileName}`;
     try {
-      const fileExists = await exists(filePath);
+      const fileExists = await invoke<boolean>('path_exists', { path: filePath });
       if (fileExists) {
         throw new Error('文件已存在');
       }
-      await writeTextFile(filePath, '');
+      await invoke('write_text_file', { path: filePath, contents: '' });
       return filePath;
     } catch (error) {
       console.error('创建文件失败:', error);
@@ -176,7 +332,7 @@ export const useFileSystem = () => {
   const createFolder = useCallback(async (dirPath: string, folderName: string) => {
     const folderPath = `${dirPath}/${folderName}`;
     try {
-      await mkdir(folderPath);
+      await invoke('create_dir', { path: folderPath });
       return folderPath;
     } catch (error) {
       console.error('创建文件夹失败:', error);
@@ -184,9 +340,22 @@ export const useFileSystem = () => {
     }
   }, []);
 
-  // 删除文件或文件夹
+  // 删除文件或文件夹；工作区内的移到工作区回收站，可以恢复
   const deleteItem = useCallback(async (itemPath: string) => {
     try {
+      const { workspaceDir } = useEditorStore.getState();
+      if (workspaceDir && itemPath.startsWith(`${workspaceDir}/`)) {
+        const confirmed = await ask(`确定要删除吗？删除的内容会移到工作区回收站，可以恢复。`, {
+          title: '确认删除',
+          kind: 'warning',
+          okLabel: '删除',
+          cancelLabel: '取消',
+        });
+        if (!confirmed) return false;
+        await invoke('trash_item', { workspace: workspaceDir, path: itemPath });
+        return true;
+      }
+
       const confirmed = await ask(`确定要删除吗？此操作无法撤销。`, {
         title: '确认删除',
         kind: 'warning',
@@ -195,7 +364,7 @@ export const useFileSystem = () => {
       });
 
       if (confirmed) {
-        await remove(itemPath, { recursive: true });
+        await invoke('remove_path', { path: itemPath });
         return true;
       }
       return false;
@@ -212,7 +381,7 @@ export const useFileSystem = () => {
       parts[parts.length - 1] = newName;
       const newPath = parts.join('/');
 
-      await rename(oldPath, newPath);
+      await invoke('rename_path', { from: oldPath, to: newPath });
       return newPath;
     } catch (error) {
       console.error('重命名失败:', error);
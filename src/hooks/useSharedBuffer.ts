@@ -0,0 +1,113 @@
+import { useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { getCurrentWindow } from '@tauri-apps/api/window';
+import { useEditorStore } from '../stores/editorStore';
+
+// 后端共享缓冲区返回的快照
+interface BufferSnapshot {
+  path: string;
+  content: string;
+  version: number;
+  saved: boolean;
+}
+
+interface BufferChanged extends BufferSnapshot {
+  source: string | null;
+}
+
+// 本窗口当前文档对应的缓冲区；synced 为最近一次与后端一致的内容
+let current: { filePath: string; key: string; version: number; synced: string } | null = null;
+let timer: ReturnType<typeof setTimeout> | null = null;
+
+// 编辑停顿多久后同步到后端
+const SYNC_DELAY = 300;
+
+const adopt = (snapshot: BufferSnapshot) => {
+  if (!current) return;
+  current.version = snapshot.version;
+  current.synced = snapshot.content;
+  if (useEditorStore.getState().content !== snapshot.content) {
+    useEditorStore.setState({ content: snapshot.content });
+  }
+  useEditorStore.setState({ isModified: !snapshot.saved });
+};
+
+const push = async () => {
+  timer = null;
+  const buffer = current;
+  const { content, currentFilePath } = useEditorStore.getState();
+  if (!buffer || buffer.filePath !== currentFilePath || content === buffer.synced) return;
+
+  const snapshot = await invoke<BufferSnapshot>('update_buffer', {
+    path: buffer.filePath,
+    baseVersion: buffer.version,
+    content,
+  });
+  if (current !== buffer) return;
+  if (useEditorStore.getState().content === content) {
+    adopt(snapshot);
+  } else if (snapshot.content === content) {
+    // 等待期间又有输入，保留本地内容，下次同步时再合并
+    buffer.version = snapshot.version;
+    buffer.synced = content;
+  }
+};
+
+// 保存前先把尚未同步的编辑交给后端，保存的是合并后的内容
+export const flushSharedBuffer = async () => {
+  if (timer) clearTimeout(timer);
+  await push().catch(error => console.error('同步文档失败:', error));
+};
+
+// 同一文件在多个窗口中打开时经由后端缓冲区保持内容一致
+export const useSharedBuffer = () => {
+  const currentFilePath = useEditorStore(state => state.currentFilePath);
+
+  useEffect(() => {
+    if (!currentFilePath) return;
+    let active = true;
+    invoke<BufferSnapshot>('open_buffer', { path: currentFilePath })
+      .then(snapshot => {
+        if (!active) return;
+        current = { filePath: currentFilePath, key: snapshot.path, version: snapshot.version, synced: snapshot.content };
+        adopt(snapshot);
+      })
+      .catch(error => console.error('打开共享文档失败:', error));
+
+    return () => {
+      active = false;
+      if (timer) clearTimeout(timer);
+      timer = null;
+      current = null;
+      invoke('close_buffer', { path: currentFilePath }).catch(() => {});
+    };
+  }, [currentFilePath]);
+
+  // 本地编辑停顿后同步
+  useEffect(() => {
+    return useEditorStore.subscribe((state, prev) => {
+      if (state.content === prev.content || !current || state.content === current.synced) return;
+      if (timer) clearTimeout(timer);
+      timer = setTimeout(() => {
+        push().catch(error => console.error('同步文档失败:', error));
+      }, SYNC_DELAY);
+    });
+  }, []);
+
+  // 其他窗口、快速记录或磁盘上的改动
+  useEffect(() => {
+    const label = getCurrentWindow().label;
+    const unlisten = listen<BufferChanged>('buffer-changed', event => {
+      const change = event.payload;
+      if (!current || change.path !== current.key || change.version < current.version) return;
+      if (change.source === label) return;
+      // 本窗口有未同步的输入时不覆盖，稍后同步时由后端合并
+      if (useEditorStore.getState().content !== current.synced) return;
+      adopt(change);
+    });
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+};
@@ -0,0 +1,171 @@
+import { useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { useEditorStore } from '../stores/editorStore';
+
+// 一次编辑：在 UTF-16 偏移 at 处删除 del、插入 ins，与后端日志格式一致
+interface Edit {
+  at: number;
+  del: string;
+  ins: string;
+  time: number;
+}
+
+type UndoOp = ({ type: 'edit' } & Edit) | { type: 'undo'; time: number } | { type: 'redo'; time: number };
+
+interface UndoHistory {
+  undo: Edit[];
+  redo: Edit[];
+}
+
+// 当前文档的撤销栈；open 为仍在合并连续输入的最后一次编辑，outbox 为尚未写入后端的操作
+let current: { path: string; undo: Edit[]; redo: Edit[]; open: Edit | null; outbox: UndoOp[] } | null = null;
+let timer: ReturnType<typeof setTimeout> | null = null;
+
+// 输入停顿多久后写入后端，同时作为连续输入合并的时间窗口
+const FLUSH_DELAY = 1000;
+
+const now = () => Math.floor(Date.now() / 1000);
+
+const diff = (before: string, after: string): Omit<Edit, 'time'> | null => {
+  if (before === after) return null;
+  let start = 0;
+  const max = Math.min(before.length, after.length);
+  while (start < max && before[start] === after[start]) start++;
+  let end = 0;
+  while (end < max - start && before[before.length - 1 - end] === after[after.length - 1 - end]) end++;
+  return {
+    at: start,
+    del: before.slice(start, before.length - end),
+    ins: after.slice(start, after.length - end),
+  };
+};
+
+// 连续输入同一个词、连续退格合并为一步撤销
+const coalesce = (open: Edit, edit: Omit<Edit, 'time'>) => {
+  if (!open.del && !edit.del && edit.at === open.at + open.ins.length && !/\s$/.test(open.ins)) {
+    open.ins += edit.ins;
+    return true;
+  }
+  if (!open.ins && !edit.ins && edit.at + edit.del.length === open.at) {
+    open.at = edit.at;
+    open.del = edit.del + open.del;
+    return true;
+  }
+  return false;
+};
+
+const close = () => {
+  if (!current?.open) return;
+  current.outbox.push({ type: 'edit', ...current.open });
+  current.open = null;
+};
+
+const send = async (history: NonNullable<typeof current>) => {
+  if (history.open) {
+    history.outbox.push({ type: 'edit', ...history.open });
+    history.open = null;
+  }
+  const ops = history.outbox.splice(0);
+  if (ops.length === 0) return;
+  await invoke('append_undo_ops', { path: history.path, ops });
+};
+
+const schedule = () => {
+  if (timer) clearTimeout(timer);
+  timer = setTimeout(() => {
+    timer = null;
+    if (current) send(current).catch(error => console.error('保存撤销历史失败:', error));
+  }, FLUSH_DELAY);
+};
+
+// 编辑器的输入变化，在写入 store 之前调用
+export const recordUndoEdit = (next: string) => {
+  if (!current) return;
+  const edit = diff(useEditorStore.getState().content, next);
+  if (!edit) return;
+  current.redo = [];
+  if (!current.open || !coalesce(current.open, edit)) {
+    close();
+    current.open = { ...edit, time: now() };
+    current.undo.push(current.open);
+  }
+  schedule();
+};
+
+// 保存前先把撤销操作写入后端，保存标记才会落在这些操作之后
+export const flushUndoHistory = async () => {
+  if (timer) clearTimeout(timer);
+  timer = null;
+  if (current) await send(current).catch(error => console.error('保存撤销历史失败:', error));
+};
+
+const placeCaret = (offset: number) => {
+  requestAnimationFrame(() => {
+    const textarea = document.querySelector<HTMLTextAreaElement>('.w-md-editor-text-input');
+    if (textarea) textarea.selectionStart = textarea.selectionEnd = offset;
+  });
+};
+
+// 撤销或重做一步；内容与记录对不上时（被程序修改过）放弃整个历史
+const step = (direction: 'undo' | 'redo') => {
+  if (!current) return;
+  close();
+  const from = direction === 'undo' ? current.undo : current.redo;
+  const to = direction === 'undo' ? current.redo : current.undo;
+  const edit = from.pop();
+  if (!edit) return;
+  const content = useEditorStore.getState().content;
+  const [remove, insert] = direction === 'undo' ? [edit.ins, edit.del] : [edit.del, edit.ins];
+  if (content.slice(edit.at, edit.at + remove.length) !== remove) {
+    current.undo = [];
+    current.redo = [];
+    return;
+  }
+  useEditorStore.getState().setContent(content.slice(0, edit.at) + insert + content.slice(edit.at + remove.length));
+  to.push(edit);
+  current.outbox.push({ type: direction, time: now() });
+  placeCaret(edit.at + insert.length);
+  schedule();
+};
+
+// 撤销栈按文件持久化，关闭后重新打开文档仍可撤销上次的编辑
+export const useUndoHistory = () => {
+  const currentFilePath = useEditorStore(state => state.currentFilePath);
+
+  useEffect(() => {
+    if (!currentFilePath) return;
+    let active = true;
+    invoke<UndoHistory>('load_undo_history', { path: currentFilePath })
+      .then(history => {
+        if (!active) return;
+        current = { path: currentFilePath, undo: history.undo, redo: history.redo, open: null, outbox: [] };
+      })
+      .catch(error => console.error('读取撤销历史失败:', error));
+
+    return () => {
+      active = false;
+      if (timer) clearTimeout(timer);
+      timer = null;
+      if (current) send(current).catch(error => console.error('保存撤销历史失败:', error));
+      current = null;
+    };
+  }, [currentFilePath]);
+
+  // 接管编辑区的撤销 / 重做快捷键
+  useEffect(() => {
+    const handleKeyDown = (e: KeyboardEvent) => {
+      if (!(e.metaKey || e.ctrlKey) || !current) return;
+      if (!(e.target instanceof HTMLElement) || !e.target.closest('.w-md-editor')) return;
+      const key = e.key.toLowerCase();
+      if (key === 'z') {
+        e.preventDefault();
+        step(e.shiftKey ? 'redo' : 'undo');
+      } else if (key === 'y') {
+        e.preventDefault();
+        step('redo');
+      }
+    };
+    window.addEventListener('keydown', handleKeyDown, true);
+    return () => window.removeEventListener('keydown', handleKeyDown, true);
+  }, []);
+};
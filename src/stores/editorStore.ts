@@ -1,5 +1,6 @@
 import { create } from 'zustand';
 import { persist } from 'zustand/middleware';
+import { getCurrentWindow } from '@tauri-apps/api/window';
 
 export type EditorMode = 'edit' | 'preview' | 'split';
 
@@ -20,6 +21,8 @@ interface EditorState {
   workspaceDir: string | null;
   // 待插入的图片 Markdown（用于从外部拖拽时插入）
   pendingImageMarkdown: string | null;
+  // 只读打开（二进制或分段打开的大文件），保存时改为另存为
+  readOnly: boolean;
 
   // Actions
   setContent: (content: string) => void;
@@ -30,8 +33,15 @@ interface EditorState {
   addRecentFile: (path: string) => void;
   resetEditor: () => void;
   setPendingImageMarkdown: (markdown: string | null) => void;
+  setReadOnly: (readOnly: boolean) => void;
 }
 
+// 工作区窗口（workspace-{id}）各自保存最近文件和工作目录，不与主窗口互相覆盖
+const storageName = (): string => {
+  const label = getCurrentWindow().label;
+  return label === 'main' ? 'mobi-editor-storage' : `mobi-editor-storage-${label}`;
+};
+
 const getFileName = (path: string | null): string => {
   if (!path) return '未命名';
   const parts = path.split('/');
@@ -49,6 +59,7 @@ export const useEditorStore = create<EditorState>()(
       recentFiles: [],
       workspaceDir: null,
       pendingImageMarkdown: null,
+      readOnly: false,
 
       setContent: (content) => {
         set({ content, isModified: true });
@@ -84,13 +95,16 @@ export const useEditorStore = create<EditorState>()(
           currentFilePath: null,
           currentFileName: '未命名',
           isModified: false,
+          readOnly: false,
         });
       },
 
       setPendingImageMarkdown: (markdown) => set({ pendingImageMarkdown: markdown }),
+
+      setReadOnly: (readOnly) => set({ readOnly }),
     }),
     {
-      name: 'mobi-editor-storage',
+      name: storageName(),
       partialize: (state) => ({
         editorMode: state.editorMode,
         recentFiles: state.recentFiles,
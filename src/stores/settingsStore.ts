@@ -13,6 +13,7 @@ interface SettingsState {
   sidebarWidth: number;
   saveImagesToSubfolder: boolean; // 是否将图片保存到子目录
   imageSubfolderName: string; // 图片子目录名称
+  fetchLinkTitles: boolean; // 粘贴裸链接时是否抓取网页标题
 
   setTheme: (theme: Theme) => void;
   setFontSize: (size: number) => void;
@@ -23,6 +24,7 @@ interface SettingsState {
   setSidebarWidth: (width: number) => void;
   setSaveImagesToSubfolder: (enabled: boolean) => void;
   setImageSubfolderName: (name: string) => void;
+  setFetchLinkTitles: (enabled: boolean) => void;
 }
 
 export const useSettingsStore = create<SettingsState>()(
@@ -37,6 +39,7 @@ export const useSettingsStore = create<SettingsState>()(
       sidebarWidth: 250,
       saveImagesToSubfolder: true,
       imageSubfolderName: 'assets',
+      fetchLinkTitles: true,
 
       setTheme: (theme) => {
         set({ theme });
@@ -50,6 +53,7 @@ export const useSettingsStore = create<SettingsState>()(
       setSidebarWidth: (sidebarWidth) => set({ sidebarWidth }),
       setSaveImagesToSubfolder: (saveImagesToSubfolder) => set({ saveImagesToSubfolder }),
       setImageSubfolderName: (imageSubfolderName) => set({ imageSubfolderName }),
+      setFetchLinkTitles: (fetchLinkTitles) => set({ fetchLinkTitles }),
     }),
     {
       name: 'mobi-settings-storage',